@@ -0,0 +1,160 @@
+//! `todo add --json-stdin`, for programs that add TODOs: one object, or
+//! an array of them, read from stdin, so that nothing has to be quoted
+//! for a shell:
+//!
+//! ```sh
+//! echo '{"title": "Pay rent", "date": "fri", "tags": ["bill"]}' | todo add --json-stdin
+//! ```
+//!
+//! Besides `title`, objects can have `date`, `start`, `repeat`,
+//! `estimate`, `assignee`, `url` or `urls`, `tags`, `notes` and `fields`,
+//! an object of custom fields, checked like the flags of `todo add` and
+//! `todo set`. Nothing is added unless all of them are valid.
+
+use crate::json::{self, Value};
+use crate::{fields, rules, tag_name, Todo, Todos};
+use seahorse::Context;
+use std::io::Read;
+use std::process::exit;
+
+const KEYS: &[&str] = &[
+    "title", "date", "start", "repeat", "estimate", "assignee", "url", "urls", "tags", "notes",
+    "fields",
+];
+
+pub fn add(c: &Context) {
+    let mut input = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+        eprintln!("Failed to read stdin: {}", e);
+        exit(1);
+    }
+    let value = match json::parse(&input) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Invalid JSON: {}", e);
+            exit(1);
+        }
+    };
+    let items = match &value {
+        Value::Array(items) => items.iter().collect(),
+        item => vec![item],
+    };
+    let mut new = vec![];
+    for (i, item) in items.iter().enumerate() {
+        match todo(item) {
+            Ok(todo) => new.push(todo),
+            Err(e) if items.len() == 1 => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+            Err(e) => {
+                eprintln!("TODO {} of {}: {}", i + 1, items.len(), e);
+                exit(1);
+            }
+        }
+    }
+
+    let mut todos = Todos::read();
+    let mut added = vec![];
+    for todo in new {
+        match todos.add(todo) {
+            Ok(todo) => added.push(todo),
+            Err(_) => {
+                eprintln!("Failed to add.");
+                exit(1);
+            }
+        }
+    }
+    todos.save().unwrap();
+    if c.bool_flag("json") {
+        let mut values: Vec<Value> = added.iter().map(Todo::to_json).collect();
+        match value {
+            Value::Array(_) => println!("{}", Value::Array(values)),
+            _ => println!("{}", values.remove(0)),
+        }
+        return;
+    }
+    for todo in &added {
+        println!("Added #{}: {}", todo.id, todo.title);
+    }
+}
+
+/// The TODO of `item`, with the defaults and rules of `todo add`.
+fn todo(item: &Value) -> Result<Todo, String> {
+    let Value::Object(pairs) = item else {
+        return Err("Expected an object with a title".to_string());
+    };
+    if let Some((key, _)) = pairs.iter().find(|(k, _)| !KEYS.contains(&k.as_str())) {
+        return Err(format!("Unknown key: {}", key));
+    }
+    let title = item.get("title").as_str().unwrap_or("").trim();
+    if title.is_empty() {
+        return Err("Please enter a title".to_string());
+    }
+    let mut todo = Todo::new(
+        "".to_string(),
+        "".to_string(),
+        title.to_string(),
+        vec![],
+        "".to_string(),
+    );
+    for field in ["date", "start", "repeat", "estimate", "assignee"] {
+        if let Some(value) = scalar(item.get(field), field)? {
+            let (field, value) = fields::check(field, &value)?;
+            fields::assign(&mut todo, &field, value);
+        }
+    }
+    let urls = match item.get("url") {
+        Value::Null => strings(item.get("urls"), "urls")?,
+        url => scalar(url, "url")?.into_iter().collect(),
+    };
+    for url in urls {
+        todo.urls.push(fields::check("url", &url)?.1);
+    }
+    todo.tags = strings(item.get("tags"), "tags")?
+        .iter()
+        .map(|t| tag_name(t))
+        .filter(|t| !t.is_empty())
+        .collect();
+    todo.notes = scalar(item.get("notes"), "notes")?.unwrap_or_default();
+    match item.get("fields") {
+        Value::Null => {}
+        Value::Object(pairs) => {
+            for (key, value) in pairs {
+                let value = scalar(value, key)?.unwrap_or_default();
+                let (key, value) =
+                    fields::parse_pair(&format!("{}={}", key, value)).map_err(|e| e.to_string())?;
+                fields::set(&mut todo, &key, &value);
+            }
+        }
+        _ => return Err("fields should be an object".to_string()),
+    }
+
+    rules::apply_to_new(&mut todo, item.get("assignee") != &Value::Null);
+    Ok(todo)
+}
+
+/// A string or number as text.
+fn scalar(value: &Value, key: &str) -> Result<Option<String>, String> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(s) => Ok(Some(s.clone())),
+        Value::Number(n) if n.fract() == 0.0 => Ok(Some((*n as i64).to_string())),
+        Value::Number(n) => Ok(Some(n.to_string())),
+        _ => Err(format!("{} should be a string", key)),
+    }
+}
+
+fn strings(value: &Value, key: &str) -> Result<Vec<String>, String> {
+    match value {
+        Value::Null => Ok(vec![]),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => Ok(s.clone()),
+                _ => Err(format!("{} should be strings", key)),
+            })
+            .collect(),
+        _ => Err(format!("{} should be an array", key)),
+    }
+}
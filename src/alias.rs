@@ -0,0 +1,184 @@
+//! Command shortcuts from the `[alias]` section of the config:
+//!
+//! ```toml
+//! [alias]
+//! t = "ls --where 'date<=today'"
+//! w = "t --tag work"              # aliases may use other aliases
+//! ```
+
+use crate::config;
+use seahorse::Command;
+use std::process::exit;
+use std::sync::OnceLock;
+
+/// Hops allowed when aliases expand to other aliases.
+const MAX_DEPTH: usize = 16;
+
+/// Names of the built-in commands, as given to `expand`.
+static COMMANDS: OnceLock<Vec<String>> = OnceLock::new();
+
+pub fn alias_command() -> Command {
+    Command::new("alias")
+        .description("Manage command shortcuts")
+        .usage("todo alias [list | set <name> <command>... | remove <name>]")
+        .action(|_| list())
+        .command(
+            Command::new("list")
+                .description("Show the aliases")
+                .usage("todo alias list")
+                .alias("ls")
+                .action(|_| list()),
+        )
+        .command(
+            Command::new("set")
+                .description("Add or change an alias")
+                .usage("todo alias set <name> <command>...")
+                .action(|c| {
+                    let (name, words) = match c.args.split_first() {
+                        Some((name, words)) if !words.is_empty() => (name, words),
+                        _ => {
+                            eprintln!("Please specify a name and the command");
+                            exit(1);
+                        }
+                    };
+                    if !is_valid_name(name) {
+                        eprintln!("Invalid alias name: {}", name);
+                        exit(1);
+                    }
+                    if COMMANDS.get().is_some_and(|c| c.contains(name)) {
+                        eprintln!("{} is a command, please choose another name", name);
+                        exit(1);
+                    }
+                    let expansion = words.iter().map(|w| quote(w)).collect::<Vec<_>>().join(" ");
+                    if let Err(e) = config::write_key("alias", name, Some(&expansion)) {
+                        eprintln!("Failed to write {}: {}", config::Config::path(), e);
+                        exit(1);
+                    }
+                    println!("todo {} = todo {}", name, expansion);
+                }),
+        )
+        .command(
+            Command::new("remove")
+                .description("Remove an alias")
+                .usage("todo alias remove <name>")
+                .alias("rm")
+                .action(|c| {
+                    let name = match c.args.as_slice() {
+                        [name] => name,
+                        _ => {
+                            eprintln!("Please specify one alias");
+                            exit(1);
+                        }
+                    };
+                    if config::get().string(&format!("alias.{}", name)).is_none() {
+                        eprintln!("No such alias: {}", name);
+                        exit(1);
+                    }
+                    if let Err(e) = config::write_key("alias", name, None) {
+                        eprintln!("Failed to write {}: {}", config::Config::path(), e);
+                        exit(1);
+                    }
+                    println!("Removed alias {}", name);
+                }),
+        )
+}
+
+fn list() {
+    let aliases = config::get().section("alias");
+    if aliases.is_empty() {
+        println!("No aliases");
+    }
+    let width = aliases.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+    for (name, expansion) in aliases {
+        println!("{:<width$}  {}", name, expansion, width = width);
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// `args` with a leading alias replaced by its expansion, as often as it
+/// takes. `commands` are the names that are not aliases, as built-in
+/// commands win over aliases of the same name.
+pub fn expand(mut args: Vec<String>, commands: Vec<String>) -> Vec<String> {
+    let commands = COMMANDS.get_or_init(|| commands);
+    let config = config::get();
+    let mut seen: Vec<String> = vec![];
+    while let Some(name) = args.get(1).cloned() {
+        if commands.contains(&name) {
+            break;
+        }
+        let Some(expansion) = config.string(&format!("alias.{}", name)) else {
+            break;
+        };
+        if seen.contains(&name) || seen.len() >= MAX_DEPTH {
+            seen.push(name);
+            eprintln!("Alias loop: {}", seen.join(" -> "));
+            exit(1);
+        }
+        seen.push(name);
+        let words = match split(expansion) {
+            Some(w) if !w.is_empty() => w,
+            _ => {
+                eprintln!("Invalid alias {}: {}", seen.last().unwrap(), expansion);
+                exit(1);
+            }
+        };
+        args.splice(1..2, words);
+    }
+    args
+}
+
+/// Split like a shell does: on whitespace, except in single or double
+/// quotes, and with `\` escaping the next character outside single
+/// quotes. `None` when a quote is left open.
+pub fn split(s: &str) -> Option<Vec<String>> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let w = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => w.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let w = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => w.push(chars.next()?),
+                        c => w.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).push(chars.next()?),
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Some(words)
+}
+
+/// `word` quoted for `split` if it needs to be.
+fn quote(word: &str) -> String {
+    if !word.is_empty()
+        && !word
+            .chars()
+            .any(|c| c.is_whitespace() || "'\"\\".contains(c))
+    {
+        return word.to_string();
+    }
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
@@ -0,0 +1,115 @@
+use crate::{dry_run, open, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+
+pub fn attach_command() -> Command {
+    Command::new("attach")
+        .description("Attach a file to a TODO")
+        .usage("todo attach <todo id> <file> [--copy]")
+        .flag(
+            Flag::new("copy", FlagType::Bool)
+                .alias("c")
+                .description("Copy the file into the attachments directory"),
+        )
+        .action(|c| {
+            let (id, file) = if c.args.len() == 2 {
+                (&c.args[0], &c.args[1])
+            } else {
+                eprintln!("Please specify an ID and a file");
+                exit(1);
+            };
+
+            let path = match fs::canonicalize(file) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("{}: {}", file, e);
+                    exit(1);
+                }
+            };
+
+            let mut todos = Todos::read();
+            let todo = match todos.find_mut(id) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
+
+            let path = if c.bool_flag("copy") {
+                match copy_to_managed_dir(&todo.id, &path) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        eprintln!("Failed to copy {}: {}", file, e);
+                        exit(1);
+                    }
+                }
+            } else {
+                path.to_string_lossy().to_string()
+            };
+
+            todo.attachments.push(path.clone());
+            println!(
+                "Attached {} to #{} as attachment {}",
+                path,
+                todo.id,
+                todo.attachments.len()
+            );
+            todos.save().unwrap();
+        })
+        .command(
+            Command::new("open")
+                .description("Open an attachment of a TODO")
+                .usage("todo attach open <todo id> <attachment number>")
+                .action(|c| {
+                    let (id, n) = if c.args.len() == 2 {
+                        (&c.args[0], &c.args[1])
+                    } else {
+                        eprintln!("Please specify an ID and an attachment number");
+                        exit(1);
+                    };
+
+                    let todos = Todos::read();
+                    let todo = match todos.records.iter().find(|r| &r.id == id) {
+                        Some(t) => t,
+                        None => {
+                            eprintln!("The specified ID does not exist");
+                            exit(1);
+                        }
+                    };
+                    let attachment = n
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| n.checked_sub(1))
+                        .and_then(|i| todo.attachments.get(i));
+                    let attachment = match attachment {
+                        Some(a) => a,
+                        None => {
+                            eprintln!("#{} has no attachment {}", todo.id, n);
+                            exit(1);
+                        }
+                    };
+
+                    if let Err(e) = open::open(attachment) {
+                        eprintln!("{}", e);
+                        exit(1);
+                    }
+                }),
+        )
+}
+
+/// Copy `file` to `<data file>.attachments/<id>/` and return the new path.
+fn copy_to_managed_dir(id: &str, file: &Path) -> std::io::Result<String> {
+    let dir = format!("{}.attachments/{}", Todos::todo_path(), id);
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    let dest = format!("{}/{}", dir, name);
+    if dry_run::is_enabled() {
+        println!("Dry run, {} would be copied to {}", file.display(), dest);
+        return Ok(dest);
+    }
+    fs::create_dir_all(&dir)?;
+    fs::copy(file, &dest)?;
+    Ok(dest)
+}
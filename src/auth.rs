@@ -0,0 +1,253 @@
+//! `todo auth`: the tokens and passwords of the integrations, kept in the
+//! system keyring, see `keyring`, rather than in the config:
+//!
+//! ```sh
+//! todo auth set jira           # asks for the API token of jira.email
+//! todo auth set github --account github.example.com
+//! todo auth status
+//! ```
+//!
+//! Each secret is stored as `todo-<service>` for an account, which is
+//! what the service's config names by default, such as the user or the
+//! host. An environment variable set for the service, like `$JIRA_API_TOKEN`,
+//! is used over the keyring, so that scripts and CI can pass their own.
+
+use crate::{config, http, keyring, term};
+use seahorse::{Command, Context, Flag, FlagType};
+use std::env;
+use std::process::exit;
+
+/// A service that needs a secret.
+struct Service {
+    name: &'static str,
+    /// What the secret is, for the prompt.
+    secret: &'static str,
+    /// The variables to take it from instead, in order.
+    env: &'static [&'static str],
+    /// The account it is for, unless given: what the config has.
+    account: fn() -> Option<String>,
+    /// What the account is, for asking to give one.
+    account_is: &'static str,
+}
+
+const SERVICES: &[Service] = &[
+    Service {
+        name: "github",
+        secret: "token",
+        env: &["GITHUB_TOKEN", "GH_TOKEN"],
+        account: || Some("github.com".to_string()),
+        account_is: "the host",
+    },
+    Service {
+        name: "jira",
+        secret: "API token",
+        env: &["JIRA_API_TOKEN"],
+        account: || {
+            env::var("JIRA_EMAIL")
+                .ok()
+                .or_else(|| setting("jira.email"))
+        },
+        account_is: "jira.email",
+    },
+    Service {
+        name: "homeassistant",
+        secret: "long-lived access token",
+        env: &["HA_TOKEN"],
+        account: || setting("homeassistant.url").map(|url| http::host(&url)),
+        account_is: "the host of homeassistant.url",
+    },
+    Service {
+        name: "relay",
+        secret: "key",
+        env: &["TODO_RELAY_KEY"],
+        account: || setting("relay.server").map(|url| http::host(&url)),
+        account_is: "the host of relay.server",
+    },
+    Service {
+        name: "webdav",
+        secret: "password",
+        env: &["WEBDAV_PASSWORD"],
+        account: || setting("webdav.user"),
+        account_is: "webdav.user",
+    },
+    Service {
+        name: "smtp",
+        secret: "password",
+        env: &["TODO_SMTP_PASSWORD"],
+        account: || setting("smtp.user"),
+        account_is: "smtp.user",
+    },
+    Service {
+        name: "telegram",
+        secret: "bot token",
+        env: &["TODO_TELEGRAM_TOKEN"],
+        account: || Some("bot".to_string()),
+        account_is: "the bot",
+    },
+    Service {
+        name: "webhook",
+        secret: "signing secret",
+        env: &["TODO_WEBHOOK_SECRET"],
+        account: || Some("webhooks".to_string()),
+        account_is: "the webhooks",
+    },
+    Service {
+        name: "s3",
+        secret: "secret access key",
+        env: &["AWS_SECRET_ACCESS_KEY"],
+        account: s3_access_key,
+        account_is: "s3.access_key",
+    },
+];
+
+fn setting(key: &str) -> Option<String> {
+    config::get().string(key).map(|s| s.to_string())
+}
+
+/// The access key ID that the S3 secret access key goes with.
+pub fn s3_access_key() -> Option<String> {
+    env::var("AWS_ACCESS_KEY_ID")
+        .ok()
+        .filter(|k| !k.is_empty())
+        .or_else(|| setting("s3.access_key"))
+}
+
+fn service(name: &str) -> &'static Service {
+    SERVICES
+        .iter()
+        .find(|s| s.name == name)
+        .expect("a service in SERVICES")
+}
+
+/// The secret of the service `name` for `account`, from its environment
+/// variables or the keyring.
+pub fn secret(name: &str, account: &str) -> Option<String> {
+    match set_variable(service(name)) {
+        Some(var) => env::var(var).ok(),
+        None => keyring::get(&format!("todo-{}", name), account),
+    }
+}
+
+/// The first of the service's variables that is set.
+fn set_variable(service: &Service) -> Option<&'static str> {
+    service
+        .env
+        .iter()
+        .find(|var| env::var(var).is_ok_and(|v| !v.is_empty()))
+        .copied()
+}
+
+pub fn auth_command() -> Command {
+    let account = || {
+        Flag::new("account", FlagType::String)
+            .alias("a")
+            .description("The account, if not the one in the config")
+    };
+    let services = SERVICES
+        .iter()
+        .map(|s| s.name)
+        .collect::<Vec<_>>()
+        .join(" | ");
+    Command::new("auth")
+        .description("Store the secrets of integrations in the system keyring")
+        .usage("todo auth [set <service> | remove <service> | status]")
+        .action(|c| c.help())
+        .command(
+            Command::new("set")
+                .description("Store the token or password of a service")
+                .usage(format!(
+                    "todo auth set <{}> [--account <account>]",
+                    services
+                ))
+                .flag(account())
+                .action(set_action),
+        )
+        .command(
+            Command::new("remove")
+                .description("Remove the token or password of a service")
+                .usage("todo auth remove <service> [--account <account>]")
+                .alias("rm")
+                .flag(account())
+                .action(remove_action),
+        )
+        .command(
+            Command::new("status")
+                .description("Show where each service's secret comes from")
+                .usage("todo auth status")
+                .action(|_| status()),
+        )
+}
+
+/// The service and account `c` is about.
+fn target(c: &Context) -> (&'static Service, String) {
+    let [name] = c.args.as_slice() else {
+        eprintln!("Please specify a service");
+        exit(1);
+    };
+    let Some(service) = SERVICES.iter().find(|s| s.name == name) else {
+        let names: Vec<_> = SERVICES.iter().map(|s| s.name).collect();
+        eprintln!("Unknown service {}, expected {}", name, names.join(", "));
+        exit(1);
+    };
+    let account = c.string_flag("account").ok().or_else(service.account);
+    let Some(account) = account else {
+        eprintln!(
+            "Please set {} in {} or specify --account",
+            service.account_is,
+            config::Config::path()
+        );
+        exit(1);
+    };
+    (service, account)
+}
+
+fn set_action(c: &Context) {
+    let (service, account) = target(c);
+    let prompt = format!("{} {} for {}: ", service.name, service.secret, account);
+    let secret = match term::read_secret(&prompt) {
+        Some(secret) if !secret.is_empty() => secret,
+        _ => exit(1),
+    };
+    if let Err(e) = keyring::set(&format!("todo-{}", service.name), &account, &secret) {
+        eprintln!("{}", e);
+        exit(1);
+    }
+    println!(
+        "Stored the {} {} for {}",
+        service.name, service.secret, account
+    );
+    if let Some(var) = set_variable(service) {
+        eprintln!("Note that ${} is set, and is used instead", var);
+    }
+}
+
+fn remove_action(c: &Context) {
+    let (service, account) = target(c);
+    if let Err(e) = keyring::delete(&format!("todo-{}", service.name), &account) {
+        eprintln!("{}", e);
+        exit(1);
+    }
+    println!(
+        "Removed the {} {} for {}",
+        service.name, service.secret, account
+    );
+}
+
+fn status() {
+    for service in SERVICES {
+        let account = (service.account)();
+        let from = match set_variable(service) {
+            Some(var) => format!("${}", var),
+            None => match &account {
+                Some(account)
+                    if keyring::get(&format!("todo-{}", service.name), account).is_some() =>
+                {
+                    format!("keyring, for {}", account)
+                }
+                Some(account) => format!("not set for {}", account),
+                None => "not set".to_string(),
+            },
+        };
+        println!("{:<14} {}", service.name, from);
+    }
+}
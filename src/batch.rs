@@ -0,0 +1,256 @@
+//! `todo batch`: many commands in one run, from stdin.
+//!
+//! ```text
+//! add 'buy milk' --tag home
+//! done 3
+//! ["edit", "4", "--title", "call the bank"]
+//! ```
+//!
+//! Each line is a command as it would follow `todo`, or a JSON array of
+//! its arguments. Blank lines and lines starting with `#` are skipped.
+//! The commands share one copy of the list and the files next to it, and
+//! those are written once at the end, so a command that fails leaves
+//! everything as it was.
+
+use crate::{alias, crdt, crypt, dry_run, integrity, json, storage, webhook, Todos};
+use seahorse::Command;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Read;
+use std::process::exit;
+use std::sync::Mutex;
+
+struct Session {
+    /// The list as the commands left it.
+    list: Option<Todos>,
+    /// Content to write to the other files, by path.
+    files: BTreeMap<String, String>,
+    /// Those of `files` that are other lists, as `todo move` writes, which
+    /// get a backup and a checksum like the list.
+    lists: BTreeSet<String>,
+    /// The line being run, for the message when a command exits.
+    line: Option<(usize, String)>,
+}
+
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+
+pub fn batch_command() -> Command {
+    Command::new("batch")
+        .description("Run commands read from stdin, one per line, and save once at the end")
+        .usage("todo batch < commands.txt")
+        .action(|_| {
+            let mut input = String::new();
+            if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+                eprintln!("Failed to read stdin: {}", e);
+                exit(1);
+            }
+            let commands = match parse(&input) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
+
+            *SESSION.lock().unwrap() = Some(Session {
+                list: None,
+                files: BTreeMap::new(),
+                lists: BTreeSet::new(),
+                line: None,
+            });
+            // Commands report errors by exiting, which ends the batch.
+            // SAFETY: on_exit is a plain function that lives as long as the
+            // program.
+            unsafe {
+                libc::atexit(on_exit);
+            }
+            for (number, line, args) in &commands {
+                if args.first().map(|a| a.as_str()) == Some("batch") {
+                    eprintln!("Line {}: batches can't be nested", number);
+                    exit(1);
+                }
+                set_line(Some((*number, line.clone())));
+                let mut args = args.clone();
+                args.insert(0, "todo".to_string());
+                crate::run(args);
+            }
+            set_line(None);
+
+            if let Err(e) = commit() {
+                eprintln!("Failed to save: {}", e);
+                exit(1);
+            }
+        })
+}
+
+/// The commands in `input` with their line numbers and text.
+fn parse(input: &str) -> anyhow::Result<Vec<(usize, String, Vec<String>)>> {
+    let mut commands = vec![];
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let args = if line.starts_with('[') {
+            let value = json::parse(line).map_err(|e| anyhow::anyhow!("Line {}: {}", i + 1, e))?;
+            value
+                .items()
+                .iter()
+                .map(|v| v.as_str().map(|s| s.to_string()))
+                .collect::<Option<Vec<String>>>()
+                .ok_or_else(|| anyhow::anyhow!("Line {}: expected an array of strings", i + 1))?
+        } else {
+            alias::split(line).ok_or_else(|| anyhow::anyhow!("Line {}: unclosed quote", i + 1))?
+        };
+        // Lines copied from a shell may start with the program name.
+        let args: Vec<String> = match args.split_first() {
+            Some((first, rest)) if first == "todo" => rest.to_vec(),
+            _ => args,
+        };
+        commands.push((i + 1, line.to_string(), args));
+    }
+    Ok(commands)
+}
+
+fn set_line(line: Option<(usize, String)>) {
+    if let Some(session) = SESSION.lock().unwrap().as_mut() {
+        session.line = line;
+    }
+}
+
+extern "C" fn on_exit() {
+    // The session is only locked briefly by the functions below, never
+    // while a command runs, but better no message than a deadlock.
+    let Ok(session) = SESSION.try_lock() else {
+        return;
+    };
+    if let Some((number, line)) = session.as_ref().and_then(|s| s.line.as_ref()) {
+        eprintln!("Stopped at line {}: {}, nothing was saved", number, line);
+    }
+}
+
+pub fn is_active() -> bool {
+    SESSION.lock().unwrap().is_some()
+}
+
+/// The list as the batch left it, once a command saved it.
+pub fn list() -> Option<Todos> {
+    SESSION.lock().unwrap().as_ref()?.list.clone()
+}
+
+/// Keep `todos` to write at the end of the batch.
+pub fn stage_list(todos: &Todos) {
+    if let Some(session) = SESSION.lock().unwrap().as_mut() {
+        session.list = Some(todos.clone());
+    }
+}
+
+/// `path` as the batch left it, or as it is on disk.
+pub fn read(path: &str) -> std::io::Result<String> {
+    let staged = SESSION
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|s| s.files.get(path).cloned());
+    match staged {
+        Some(content) => Ok(content),
+        None => fs::read_to_string(path),
+    }
+}
+
+/// Write `content` to `path`, or keep it for the end of the batch.
+pub fn write(path: &str, content: String) -> anyhow::Result<()> {
+    match SESSION.lock().unwrap().as_mut() {
+        Some(session) => {
+            session.files.insert(path.to_string(), content);
+            Ok(())
+        }
+        None => crate::write_file(path, &content, "save"),
+    }
+}
+
+/// Keep `content` to write to the other list at `path` at the end of
+/// the batch.
+pub fn stage_other_list(path: &str, content: String) {
+    if let Some(session) = SESSION.lock().unwrap().as_mut() {
+        session.files.insert(path.to_string(), content);
+        session.lists.insert(path.to_string());
+    }
+}
+
+/// Write what the batch changed, each file replaced at once so that
+/// nothing reads half of it.
+fn commit() -> anyhow::Result<()> {
+    let Some(session) = SESSION.lock().unwrap().take() else {
+        return Ok(());
+    };
+    let path = Todos::todo_path();
+    if dry_run::is_enabled() {
+        if let Some(todos) = &session.list {
+            let (saved, _) = Todos::parse(&Todos::read_content()?);
+            todos.print_changes(&saved);
+        }
+        return Ok(());
+    }
+    let saved = match (&session.list, webhook::is_configured()) {
+        (Some(_), true) => Some(Todos::parse(&Todos::read_content()?).0),
+        _ => None,
+    };
+    let mut files = session.files;
+    if let Some(todos) = &session.list {
+        let content = match crypt::recipient(&path) {
+            Some(recipient) => crypt::encrypt(&todos.content(), &recipient)?,
+            None => todos.content(),
+        };
+        files.insert(path.clone(), content);
+    }
+    // All or nothing: every file is written next to the one it replaces
+    // before any is replaced, and those replaced are put back when one
+    // can't be.
+    let mut replacements = vec![];
+    for (file, content) in &files {
+        match crate::Replacement::write(file, content, "batch") {
+            Ok(replacement) => replacements.push(replacement),
+            Err(e) => {
+                replacements
+                    .into_iter()
+                    .for_each(crate::Replacement::discard);
+                return Err(e.into());
+            }
+        }
+    }
+    let old: Vec<Option<String>> = files.keys().map(|f| fs::read_to_string(f).ok()).collect();
+    for file in files.keys() {
+        if *file == path || session.lists.contains(file) {
+            integrity::before_write(file);
+        }
+    }
+    let mut replacements = replacements.into_iter();
+    for (i, replacement) in replacements.by_ref().enumerate() {
+        if let Err(e) = replacement.finish() {
+            replacements.for_each(crate::Replacement::discard);
+            for (file, old) in files.keys().zip(&old).take(i) {
+                let _ = match old {
+                    Some(content) => crate::write_file(file, content, "batch"),
+                    None => fs::remove_file(file).map_err(Into::into),
+                };
+            }
+            return Err(e.into());
+        }
+    }
+    for (file, content) in &files {
+        if *file == path || session.lists.contains(file) {
+            integrity::record(file, content.as_bytes());
+        }
+    }
+    if let Some(content) = files.get(&path) {
+        storage::push(&path, content);
+    }
+    if let Some(todos) = &session.list {
+        crdt::saved(&path, todos);
+    }
+    if let (Some(saved), Some(todos)) = (saved, &session.list) {
+        webhook::emit(&saved, todos);
+    }
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use crate::json::Value;
+use crate::{auth, config, http, rules, someday, Todo, Todos};
+use anyhow::bail;
+use seahorse::Command;
+use std::process::exit;
+use std::thread;
+use std::time::Duration;
+
+/// Seconds Telegram holds a `getUpdates` request open waiting for messages.
+const POLL_TIMEOUT: u64 = 50;
+
+const HELP: &str =
+    "/add <text> - add a TODO\n/list - show open TODOs\n/done <id> - complete a TODO";
+
+pub fn bot_command() -> Command {
+    Command::new("bot")
+        .description("Manage TODOs from a chat app")
+        .usage("todo bot telegram")
+        .action(|c| c.help())
+        .command(
+            Command::new("telegram")
+                .description("Run a Telegram bot that adds, lists and completes TODOs")
+                .usage("todo bot telegram")
+                .action(|_| {
+                    let config = config::get();
+                    let token = match auth::secret("telegram", "bot")
+                        .or_else(|| config.string("telegram.token").map(|t| t.to_string()))
+                    {
+                        Some(t) => t,
+                        None => {
+                            eprintln!(
+                                "Please set $TODO_TELEGRAM_TOKEN or store the bot token with todo auth set telegram"
+                            );
+                            exit(1);
+                        }
+                    };
+                    let chats = config.list("telegram.chats");
+                    if chats.is_empty() {
+                        eprintln!(
+                            "No chats are allowed yet: messages will be answered with their chat ID to add to telegram.chats"
+                        );
+                    }
+                    Telegram { token, chats }.run();
+                }),
+        )
+}
+
+/// Answer one chat message. Each command reads and writes the TODO file
+/// itself, so the bot sees changes made from the terminal.
+fn handle(text: &str) -> String {
+    let (command, args) = text.trim().split_once(' ').unwrap_or((text.trim(), ""));
+    // In groups, commands are addressed as /add@SomeBot.
+    let command = command.split('@').next().unwrap_or(command);
+    let args = args.trim();
+
+    match command {
+        "/add" if !args.is_empty() => {
+            let mut todos = Todos::read();
+            let mut todo = Todo::new(
+                "".to_string(),
+                "".to_string(),
+                args.to_string(),
+                vec![],
+                "".to_string(),
+            );
+            rules::apply(&mut todo);
+            match todos.add(todo) {
+                Ok(todo) => {
+                    todos.save().unwrap();
+                    format!("Added #{}: {}", todo.id, todo.title)
+                }
+                Err(e) => e.to_string(),
+            }
+        }
+        "/add" => "Usage: /add <text>".to_string(),
+        "/list" => {
+            let todos = Todos::read();
+            let lines: Vec<String> = todos
+                .records
+                .iter()
+                .filter(|t| t.done.is_empty() && !someday::is_someday(t))
+                .map(|t| {
+                    if t.date.is_empty() {
+                        format!("#{} {}", t.id, t.title)
+                    } else {
+                        format!("#{} {} ({})", t.id, t.title, t.date)
+                    }
+                })
+                .collect();
+            if lines.is_empty() {
+                "Nothing to do".to_string()
+            } else {
+                lines.join("\n")
+            }
+        }
+        "/done" if !args.is_empty() => {
+            let mut todos = Todos::read();
+            let id = args.trim_start_matches('#');
+            match todos.done(id.to_string()) {
+                Ok(todo) => {
+                    todos.save().unwrap();
+                    format!("Completed #{}: {}", todo.id, todo.title)
+                }
+                Err(e) => e.to_string(),
+            }
+        }
+        "/done" => "Usage: /done <id>".to_string(),
+        _ => HELP.to_string(),
+    }
+}
+
+struct Telegram {
+    token: String,
+    /// Chat IDs allowed to use the bot.
+    chats: Vec<String>,
+}
+
+impl Telegram {
+    fn run(&self) {
+        match self.call("getMe", Value::object::<&str>(vec![])) {
+            Ok(me) => println!(
+                "Listening as @{} (Ctrl-C to stop)",
+                me.get("username").as_str().unwrap_or("?")
+            ),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+
+        let mut offset = 0.0;
+        loop {
+            let updates = match self.call(
+                "getUpdates",
+                Value::object(vec![
+                    ("offset", Value::Number(offset)),
+                    ("timeout", Value::Number(POLL_TIMEOUT as f64)),
+                ]),
+            ) {
+                Ok(u) => u,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    thread::sleep(Duration::from_secs(5));
+                    continue;
+                }
+            };
+
+            for update in updates.items() {
+                if let Some(id) = update.get("update_id").as_f64() {
+                    offset = offset.max(id + 1.0);
+                }
+                let message = update.get("message");
+                let (Some(chat), Some(text)) = (
+                    message.get("chat").get("id").as_f64(),
+                    message.get("text").as_str(),
+                ) else {
+                    continue;
+                };
+                let chat = format!("{}", chat as i64);
+
+                let reply = if self.chats.contains(&chat) {
+                    println!("{}: {}", chat, text);
+                    handle(text)
+                } else {
+                    eprintln!("Ignored a message from chat {}", chat);
+                    format!(
+                        "This chat is not allowed. Add \"{}\" to telegram.chats in {} to use it.",
+                        chat,
+                        config::Config::path()
+                    )
+                };
+                let sent = self.call(
+                    "sendMessage",
+                    Value::object(vec![
+                        ("chat_id", Value::Number(chat.parse().unwrap_or(0.0))),
+                        ("text", reply.as_str().into()),
+                    ]),
+                );
+                if let Err(e) = sent {
+                    eprintln!("{}", e);
+                }
+            }
+        }
+    }
+
+    /// Call a Bot API method and return its `result`.
+    fn call(&self, method: &str, body: Value) -> anyhow::Result<Value> {
+        let url = format!("https://api.telegram.org/bot{}/{}", self.token, method);
+        let response = http::request(
+            "POST",
+            &url,
+            &["Content-Type: application/json".to_string()],
+            Some(&body.to_string()),
+        )?;
+        let reply = response.json()?;
+        if reply.get("ok") != &Value::Bool(true) {
+            bail!(
+                "Telegram {}: {}",
+                method,
+                reply
+                    .get("description")
+                    .as_str()
+                    .unwrap_or("request failed")
+            );
+        }
+        Ok(reply.get("result").clone())
+    }
+}
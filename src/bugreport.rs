@@ -0,0 +1,195 @@
+//! `todo bugreport`: what is needed to look into a problem, as one blob
+//! to paste into an issue. It never holds the TODOs themselves: the
+//! config is shown with its values hidden, the list only by its shape,
+//! how many records and how much is in each field, and the recent log
+//! without the arguments of commands. Nothing is sent anywhere.
+
+use crate::config::{self, Value};
+use crate::{crypt, format, log, migrations, workspace, Todos};
+use seahorse::Command;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+/// How many lines of the log to include.
+const LOG_LINES: usize = 50;
+
+pub fn bugreport_command() -> Command {
+    Command::new("bugreport")
+        .description("Print a report to paste into an issue, without any TODOs")
+        .usage("todo bugreport")
+        .action(|_| print!("{}", report()))
+}
+
+fn report() -> String {
+    let mut out = String::from("todo bugreport (holds no TODOs, check before posting)\n");
+    out += "\n## Environment\n\n";
+    out += &format!("version: {}\n", env!("CARGO_PKG_VERSION"));
+    out += &format!("os: {} {}", env::consts::OS, env::consts::ARCH);
+    if let Some(kernel) = uname() {
+        out += &format!(" ({})", kernel);
+    }
+    out += "\n";
+    out += &format!(
+        "terminal: TERM={}{}\n",
+        env::var("TERM").unwrap_or_default(),
+        if env::var_os("NO_COLOR").is_some() {
+            ", NO_COLOR"
+        } else {
+            ""
+        }
+    );
+    out += &format!(
+        "workspace: {}\n",
+        if workspace::current().is_some() {
+            "selected"
+        } else {
+            "none"
+        }
+    );
+
+    out += "\n## Config (values hidden)\n\n";
+    let config = config::get();
+    let settings = config.all();
+    if settings.is_empty() {
+        out += "(empty)\n";
+    }
+    for (key, value) in settings {
+        out += &format!("{} = {}\n", key, hide(value));
+    }
+
+    out += "\n## List\n\n";
+    out += &describe_list(&Todos::todo_path());
+
+    out += &format!("\n## Recent log (last {} lines)\n\n", LOG_LINES);
+    out += &recent_log();
+    out
+}
+
+fn uname() -> Option<String> {
+    let output = process::Command::new("uname").arg("-sr").output().ok()?;
+    let kernel = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Some(kernel).filter(|k| output.status.success() && !k.is_empty())
+}
+
+/// A setting without what it is set to, beyond switches and numbers.
+fn hide(value: &Value) -> String {
+    match value {
+        Value::String(s) if s == "true" || s == "false" || s.parse::<f64>().is_ok() => s.clone(),
+        Value::String(s) => match s.split_once("://") {
+            Some((scheme, _)) => format!("<{}:// URL>", scheme),
+            None => format!("<{} characters>", s.chars().count()),
+        },
+        Value::List(items) => format!("<{} items>", items.len()),
+    }
+}
+
+/// `path` with the home directory as `~`, which often is a name.
+fn anonymize(path: &str) -> String {
+    match dirs::home_dir() {
+        Some(home) => path.replace(&*home.to_string_lossy(), "~"),
+        None => path.to_string(),
+    }
+}
+
+/// The shape of the list at `path` and the files kept next to it.
+fn describe_list(path: &str) -> String {
+    let Ok(metadata) = fs::metadata(path) else {
+        return format!("path: {} (missing)\n", anonymize(path));
+    };
+    let mut out = format!(
+        "path: {}\nsize: {} bytes\n",
+        anonymize(path),
+        metadata.len()
+    );
+    let raw = fs::read_to_string(path).unwrap_or_default();
+    let encrypted = crypt::is_encrypted(&raw);
+    out += &format!("encrypted: {}\n", if encrypted { "yes" } else { "no" });
+    match Todos::read_content() {
+        Ok(content) => out += &describe_table(&format::parse(&content)),
+        Err(e) => out += &format!("unreadable: {}\n", e),
+    }
+
+    let file = Path::new(path);
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let mut others: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let other = entry.file_name().to_string_lossy().to_string();
+            let suffix = other.strip_prefix(&*name)?.strip_prefix('.')?;
+            // ~/.todo.toml is the config, not the list's.
+            if suffix.starts_with("toml") {
+                return None;
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            Some(format!(".{} ({} bytes)", suffix, size))
+        })
+        .collect();
+    others.sort();
+    if !others.is_empty() {
+        out += &format!("next to it: {}\n", others.join(", "));
+    }
+    out
+}
+
+/// How many records `table` has and how full each field is.
+fn describe_table(table: &format::Table) -> String {
+    let rows: Vec<&format::Row> = table.rows.iter().filter(|r| !r.is_empty()).collect();
+    let mut out = format!(
+        "format version: {} (current {})\nrecords: {}\n",
+        table.version,
+        migrations::CURRENT_VERSION,
+        rows.len()
+    );
+    let width = rows.iter().map(|r| r.fields.len());
+    if let Some(max) = width.clone().max().filter(|&m| m != table.headers.len()) {
+        out += &format!(
+            "fields per record: {} to {}, {} headers\n",
+            width.min().unwrap_or(0),
+            max,
+            table.headers.len()
+        );
+    }
+    out += &format!(
+        "\n{:<14} {:>7} {:>9} {:>11}\n",
+        "field", "filled", "longest", "characters"
+    );
+    for (i, header) in table.headers.iter().enumerate() {
+        let lengths: Vec<usize> = rows
+            .iter()
+            .filter_map(|r| r.fields.get(i))
+            .map(|f| f.chars().count())
+            .filter(|&l| l > 0)
+            .collect();
+        out += &format!(
+            "{:<14} {:>7} {:>9} {:>11}\n",
+            header,
+            lengths.len(),
+            lengths.iter().max().unwrap_or(&0),
+            lengths.iter().sum::<usize>()
+        );
+    }
+    out
+}
+
+/// The end of the log file, leaving out lines with the arguments of
+/// commands, which may be TODOs.
+fn recent_log() -> String {
+    let Some(path) = log::path() else {
+        return "(no log file, set log.file to keep one)\n".to_string();
+    };
+    let Ok(content) = fs::read_to_string(path) else {
+        return format!("(nothing in {} yet)\n", anonymize(path));
+    };
+    let lines: Vec<String> = content
+        .lines()
+        .filter(|l| !l.contains(" args="))
+        .map(anonymize)
+        .collect();
+    let start = lines.len().saturating_sub(LOG_LINES);
+    lines[start..].iter().map(|l| format!("{}\n", l)).collect()
+}
@@ -0,0 +1,123 @@
+use crate::date::{self, days_in_month, Date};
+use crate::{someday, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::collections::HashMap;
+use std::process::exit;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Width of one day in the grid, e.g. ` 15(2)!*`.
+const CELL: usize = 8;
+
+pub fn cal_command() -> Command {
+    Command::new("cal")
+        .description("Show a month calendar with the number of TODOs due each day")
+        .usage("todo cal [YYYY-MM | MM] [--day YYYY-MM-DD]")
+        .flag(Flag::new("day", FlagType::String).description("List the TODOs due on a day"))
+        .action(|c| {
+            let todos = Todos::read();
+
+            if let Ok(day) = c.string_flag("day") {
+                let day = match Date::parse(&day) {
+                    Some(d) => d,
+                    None => {
+                        eprintln!("Invalid date: {}", day);
+                        exit(1);
+                    }
+                };
+                let mut todos = todos;
+                todos.records.retain(|r| Date::parse(&r.date) == Some(day));
+                todos.records.sort_by_key(|r| date::time_of(&r.date));
+                todos.print_list(true);
+                return;
+            }
+
+            let today = date::today();
+            let (year, month) = match c.args.first() {
+                None => (today.year, today.month),
+                Some(arg) => match parse_month(arg, today.year) {
+                    Some(ym) => ym,
+                    None => {
+                        eprintln!("Invalid month: {}", arg);
+                        exit(1);
+                    }
+                },
+            };
+            print!("{}", render(year, month, today, &todos.records));
+        })
+}
+
+/// `YYYY-MM`, or a month number in `year`.
+fn parse_month(s: &str, year: i32) -> Option<(i32, u32)> {
+    let (year, month) = match s.split_once('-') {
+        Some((y, m)) => (y.parse().ok()?, m.parse().ok()?),
+        None => (year, s.parse().ok()?),
+    };
+    if (1..=12).contains(&month) {
+        Some((year, month))
+    } else {
+        None
+    }
+}
+
+/// Open TODOs that count towards the calendar.
+fn is_pending(todo: &Todo) -> bool {
+    todo.done.is_empty() && !someday::is_someday(todo)
+}
+
+/// Render the month as a grid, each day followed by the number of
+/// pending TODOs due that day, `!` for overdue days and `*` for today.
+pub fn render(year: i32, month: u32, today: Date, todos: &[Todo]) -> String {
+    let mut due: HashMap<Date, usize> = HashMap::new();
+    for todo in todos.iter().filter(|t| is_pending(t)) {
+        if let Some(d) = Date::parse(&todo.date) {
+            *due.entry(d).or_default() += 1;
+        }
+    }
+
+    let title = format!("{} {}", MONTHS[month as usize - 1], year);
+    let width = CELL * 7;
+    let mut out = format!("{:^width$}\n", title, width = width);
+    for name in ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"] {
+        out += &format!("{:^width$}", name, width = CELL);
+    }
+    out += "\n";
+
+    let first = Date::new(year, month, 1).unwrap();
+    out += &" ".repeat(CELL * first.weekday() as usize);
+    for day in 1..=days_in_month(year, month) {
+        let d = Date::new(year, month, day).unwrap();
+        let mut cell = format!("{:>3}", day);
+        if let Some(n) = due.get(&d) {
+            cell += &format!("({})", n);
+            if d < today {
+                cell.push('!');
+            }
+        }
+        if d == today {
+            cell.push('*');
+        }
+        out += &format!("{:<width$}", cell, width = CELL);
+        if d.weekday() == 6 {
+            out += "\n";
+        }
+    }
+    if !out.ends_with('\n') {
+        out += "\n";
+    }
+    out += "\n(n) TODOs due  ! overdue  * today\n";
+    out
+}
@@ -0,0 +1,28 @@
+//! `todo capture`: a prompt for one line that becomes a TODO, made to be
+//! bound to a global hotkey that opens a small terminal, e.g. with
+//! `alacritty --class todo-capture -e todo capture`. The terminal closes
+//! once the TODO is added, or nothing is added when it is cancelled.
+
+use seahorse::Command;
+use std::process::exit;
+
+pub fn capture_command() -> Command {
+    Command::new("capture")
+        .description("Prompt for a line and add it as a TODO, for binding to a hotkey")
+        .usage("todo capture")
+        .action(|_| {
+            let Some(line) = crate::term::read_line("TODO: ") else {
+                exit(1);
+            };
+            let title = line.trim();
+            if title.is_empty() {
+                exit(1);
+            }
+            // Added as `todo add` would, so defaults and rules apply.
+            crate::run(vec![
+                "todo".to_string(),
+                "add".to_string(),
+                title.to_string(),
+            ]);
+        })
+}
@@ -0,0 +1,99 @@
+use crate::{Todo, Todos};
+use seahorse::Command;
+use std::process::exit;
+
+const CHECKED: &str = "[x] ";
+const UNCHECKED: &str = "[ ] ";
+
+pub fn check_command() -> Command {
+    Command::new("check")
+        .description("Manage the checklist of a TODO")
+        .usage("todo check [add <todo id> <text> | done <todo id> <item number>]")
+        .action(|c| c.help())
+        .command(
+            Command::new("add")
+                .description("Add an item to the checklist of a TODO")
+                .usage("todo check add <todo id> <text>")
+                .action(|c| {
+                    let (id, text) = match c.args.split_first() {
+                        Some((id, text)) if !text.is_empty() => (id, text.join(" ")),
+                        _ => {
+                            eprintln!("Please specify an ID and the item text");
+                            exit(1);
+                        }
+                    };
+
+                    let mut todos = Todos::read();
+                    let todo = find(&mut todos, id);
+                    todo.checklist.push(format!("{}{}", UNCHECKED, text));
+                    println!(
+                        "Added item {} to #{}: {}",
+                        todo.checklist.len(),
+                        todo.id,
+                        text
+                    );
+                    todos.save().unwrap();
+                }),
+        )
+        .command(
+            Command::new("done")
+                .description("Check off an item of a TODO's checklist")
+                .usage("todo check done <todo id> <item number>")
+                .action(|c| {
+                    let (id, n) = if c.args.len() == 2 {
+                        (&c.args[0], &c.args[1])
+                    } else {
+                        eprintln!("Please specify an ID and an item number");
+                        exit(1);
+                    };
+
+                    let mut todos = Todos::read();
+                    let todo = find(&mut todos, id);
+                    let item = n
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| n.checked_sub(1))
+                        .and_then(|i| todo.checklist.get_mut(i));
+                    let item = match item {
+                        Some(i) => i,
+                        None => {
+                            eprintln!("#{} has no checklist item {}", todo.id, n);
+                            exit(1);
+                        }
+                    };
+                    let text = item_text(item).to_string();
+                    *item = format!("{}{}", CHECKED, text);
+                    println!("Checked item {} of #{}: {}", n, todo.id, text);
+                    todos.save().unwrap();
+                }),
+        )
+}
+
+fn find<'a>(todos: &'a mut Todos, id: &str) -> &'a mut Todo {
+    match todos.find_mut(id) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+pub fn is_checked(item: &str) -> bool {
+    item.starts_with(CHECKED)
+}
+
+pub fn item_text(item: &str) -> &str {
+    item.strip_prefix(CHECKED)
+        .or_else(|| item.strip_prefix(UNCHECKED))
+        .unwrap_or(item)
+}
+
+/// `checked/total` for the list, or empty without a checklist.
+pub fn progress(todo: &Todo) -> String {
+    if todo.checklist.is_empty() {
+        return "".to_string();
+    }
+    let checked = todo.checklist.iter().filter(|i| is_checked(i)).count();
+    format!("{}/{}", checked, todo.checklist.len())
+}
@@ -0,0 +1,101 @@
+//! `todo check-clean`, for scripts that must not go on while something
+//! is left to do, like a release checklist in CI:
+//!
+//! ```sh
+//! todo check-clean --tag release && cargo publish
+//! ```
+//!
+//! It exits with 0 when no open TODO matches, and with 1 after listing
+//! those that do and how much of what matches is done. Someday and
+//! scheduled TODOs count: they are not done. With the tags to use set,
+//!
+//! ```toml
+//! [tags]
+//! known = ["release", "docs", "bug"]
+//! ```
+//!
+//! a matching TODO, open or done, with any other tag fails it too.
+
+use crate::{config, query, tag_name, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::process::exit;
+
+pub fn check_clean_command() -> Command {
+    Command::new("check-clean")
+        .description("Exit with 0 only when no matching TODO is open")
+        .usage("todo check-clean [--tag <tag>] [--where <filter>]")
+        .flag(Flag::new("tag", FlagType::String).description("Only TODOs with a tag"))
+        .flag(
+            Flag::new("where", FlagType::String)
+                .alias("w")
+                .description("Only TODOs matching a filter, like 'milestone=1.2'"),
+        )
+        .action(|c| {
+            let tag = c.string_flag("tag").ok().map(|t| tag_name(&t));
+            let query = c
+                .string_flag("where")
+                .ok()
+                .map(|w| match query::Query::parse(&w) {
+                    Ok(query) => query,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        exit(1);
+                    }
+                });
+            let todos = Todos::read();
+            let matching: Vec<&Todo> = todos
+                .records
+                .iter()
+                .filter(|r| tag.as_ref().is_none_or(|t| r.tags.contains(t)))
+                .filter(|r| query.as_ref().is_none_or(|q| q.matches(r)))
+                .collect();
+            let open: Vec<&&Todo> = matching.iter().filter(|r| r.done.is_empty()).collect();
+            let unknown = unknown_tags(&matching);
+            if open.is_empty() && unknown.is_empty() {
+                println!("Nothing left to do");
+                return;
+            }
+            for todo in &open {
+                println!("#{} {}", todo.id, todo.title);
+            }
+            if !open.is_empty() {
+                let done = matching.len() - open.len();
+                eprintln!(
+                    "{} of {} still open, {}% done",
+                    open.len(),
+                    matching.len(),
+                    done * 100 / matching.len()
+                );
+            }
+            for (todo, tags) in &unknown {
+                println!(
+                    "#{} {}: {} not in tags.known",
+                    todo.id,
+                    todo.title,
+                    tags.join(", ")
+                );
+            }
+            exit(1);
+        })
+}
+
+/// The TODOs of `todos` with tags other than those of `tags.known`, with
+/// those tags. Any tag is fine when it is not set.
+fn unknown_tags<'a>(todos: &[&'a Todo]) -> Vec<(&'a Todo, Vec<&'a str>)> {
+    let known: Vec<String> = config::get()
+        .list("tags.known")
+        .iter()
+        .map(|t| tag_name(t))
+        .collect();
+    if known.is_empty() {
+        return vec![];
+    }
+    todos
+        .iter()
+        .map(|todo| {
+            let tags = todo.tags.iter().filter(|t| !known.contains(t));
+            (*todo, tags.map(|t| t.as_str()).collect::<Vec<_>>())
+        })
+        .filter(|(_, tags)| !tags.is_empty())
+        .collect()
+}
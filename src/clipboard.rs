@@ -0,0 +1,50 @@
+use anyhow::bail;
+use std::env;
+use std::process::Command;
+
+/// The text on the system clipboard, read with the platform's tool:
+/// `pbpaste`, PowerShell's `Get-Clipboard`, `wl-paste` on Wayland, or
+/// `xclip` and then `xsel` on X11.
+pub fn read() -> anyhow::Result<String> {
+    let candidates: &[&[&str]] = if cfg!(target_os = "macos") {
+        &[&["pbpaste"]]
+    } else if cfg!(target_os = "windows") {
+        &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard"]]
+    } else if env::var_os("WAYLAND_DISPLAY").is_some() {
+        &[&["wl-paste", "--no-newline"]]
+    } else {
+        &[
+            &["xclip", "-selection", "clipboard", "-o"],
+            &["xsel", "--clipboard", "--output"],
+        ]
+    };
+
+    for args in candidates {
+        if let Ok(output) = Command::new(args[0]).args(&args[1..]).output() {
+            if output.status.success() {
+                return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+            }
+            bail!(
+                "Failed to read the clipboard: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+    }
+    bail!(
+        "Failed to read the clipboard: {} is not installed",
+        candidates
+            .iter()
+            .map(|c| c[0])
+            .collect::<Vec<_>>()
+            .join(" or ")
+    )
+}
+
+/// The first `http://` or `https://` URL in `text`, without punctuation
+/// that usually follows one in prose.
+pub fn find_url(text: &str) -> Option<&str> {
+    text.split(|c: char| c.is_whitespace() || c == '<' || c == '"')
+        .map(|word| word.trim_start_matches(['(', '[']))
+        .find(|word| word.starts_with("http://") || word.starts_with("https://"))
+        .map(|url| url.trim_end_matches(['.', ',', ')', ']', '>', ';', ':', '!', '?', '\'']))
+}
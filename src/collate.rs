@@ -0,0 +1,76 @@
+//! Ordering text the way people expect rather than by code point:
+//! case and accents only break ties (`apple`, `Éclair`, `eclairs`,
+//! `Zebra`), numbers compare by value (`step 2` before `step 10`), and
+//! katakana sorts with the hiragana it sounds like.
+
+use std::cmp::Ordering;
+
+/// Letters that compare like their base letters, and what those are.
+const FOLDS: &[(&str, &str)] = &[
+    ("àáâãäåāăą", "a"),
+    ("çćĉċč", "c"),
+    ("ďđ", "d"),
+    ("èéêëēĕėęě", "e"),
+    ("ĝğġģ", "g"),
+    ("ĥħ", "h"),
+    ("ìíîïĩīĭįı", "i"),
+    ("ĵ", "j"),
+    ("ķ", "k"),
+    ("ĺļľŀł", "l"),
+    ("ñńņň", "n"),
+    ("òóôõöøōŏő", "o"),
+    ("ŕŗř", "r"),
+    ("śŝşš", "s"),
+    ("ţťŧ", "t"),
+    ("ùúûüũūŭůűų", "u"),
+    ("ŵ", "w"),
+    ("ýÿŷ", "y"),
+    ("źżž", "z"),
+    ("ß", "ss"),
+    ("æ", "ae"),
+    ("œ", "oe"),
+    ("þ", "th"),
+];
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Element {
+    /// A run of digits, by value: the digits without leading zeros,
+    /// shorter ones first.
+    Number(usize, String),
+    Text(char),
+}
+
+/// Compare `a` and `b` by their base letters first, then with accents,
+/// then with case.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    key(a, true)
+        .cmp(&key(b, true))
+        .then_with(|| key(a, false).cmp(&key(b, false)))
+        .then_with(|| a.cmp(b))
+}
+
+fn key(s: &str, fold: bool) -> Vec<Element> {
+    let mut elements = vec![];
+    let mut chars = s.chars().flat_map(char::to_lowercase).peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() {
+            let mut digits = c.to_string();
+            while let Some(d) = chars.next_if(|d| d.is_ascii_digit()) {
+                digits.push(d);
+            }
+            let digits = digits.trim_start_matches('0').to_string();
+            elements.push(Element::Number(digits.len(), digits));
+            continue;
+        }
+        // Katakana to the matching hiragana.
+        let c = match c {
+            '\u{30a1}'..='\u{30f6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            c => c,
+        };
+        match FOLDS.iter().find(|(from, _)| fold && from.contains(c)) {
+            Some((_, to)) => elements.extend(to.chars().map(Element::Text)),
+            None => elements.push(Element::Text(c)),
+        }
+    }
+    elements
+}
@@ -0,0 +1,69 @@
+//! The columns of the list file, found by their header rather than their
+//! place, so that a file with its columns in another order reads the
+//! same. Columns this version doesn't know, as another tool or a newer
+//! version may add, are kept as they are and written back after the
+//! known ones.
+//!
+//! Which columns are used can be set in `[columns]`:
+//!
+//! ```toml
+//! [columns]
+//! require = ["id", "title", "done"] # refuse a file without them
+//! disable = ["time_log"]            # leave these alone
+//! ```
+//!
+//! A disabled column is treated like an unknown one: kept but not read,
+//! so what commands set in it is not saved, and not added to files that
+//! don't have it.
+
+use crate::{config, Todos};
+
+/// Where a column of a file goes.
+pub enum Column {
+    /// The field at this place in `Todos::default_headers`.
+    Known(usize),
+    /// Any other column, by header.
+    Other(String),
+}
+
+fn disabled() -> Vec<String> {
+    config::get().list("columns.disable")
+}
+
+/// Where each of `headers` goes.
+pub fn map(headers: &[String]) -> Vec<Column> {
+    let known = Todos::default_headers();
+    let disabled = disabled();
+    headers
+        .iter()
+        .map(|header| match known.iter().position(|k| k == header) {
+            Some(i) if !disabled.contains(header) => Column::Known(i),
+            _ => Column::Other(header.clone()),
+        })
+        .collect()
+}
+
+/// The headers to write the list with: the known ones in use, then the
+/// others of `headers` and then those only some records have.
+pub fn to_write<'a>(headers: &'a [String], others: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let disabled = disabled();
+    let mut columns: Vec<String> = Todos::default_headers()
+        .into_iter()
+        .filter(|h| !disabled.contains(h))
+        .collect();
+    for header in headers.iter().map(|h| h.as_str()).chain(others) {
+        if !columns.iter().any(|c| c == header) {
+            columns.push(header.to_string());
+        }
+    }
+    columns
+}
+
+/// The columns of `columns.require` that `headers` lack.
+pub fn missing(headers: &[String]) -> Vec<String> {
+    config::get()
+        .list("columns.require")
+        .into_iter()
+        .filter(|r| !headers.contains(r))
+        .collect()
+}
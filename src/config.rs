@@ -0,0 +1,403 @@
+use crate::{dry_run, workspace};
+use dirs::home_dir;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A value from the config file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    List(Vec<String>),
+}
+
+/// Settings from `~/.todo.toml`, overridden by those of the current
+/// workspace, see `workspace`, and then by any `.todo.toml` in the
+/// current directory or its parents below the home directory, nearest
+/// last. Like with `.editorconfig`, a file with `root = true` stops the
+/// search. A relative `storage.path` there is relative to the file.
+///
+/// Only the small subset of TOML the settings need is understood:
+/// `[section]` headers, `key = value` pairs with string, number, boolean
+/// or string-array values, and `#` comments. Keys are flattened, so
+/// `name` under `[user]` is looked up as `user.name`. `${NAME}` in a
+/// string is replaced by the environment variable `NAME`.
+#[derive(Debug, Default)]
+pub struct Config {
+    values: HashMap<String, Value>,
+}
+
+static CONFIG: Mutex<Option<&'static Config>> = Mutex::new(None);
+
+/// The config of this invocation, loaded on first use.
+pub fn get() -> &'static Config {
+    let mut config = CONFIG.lock().unwrap();
+    config.get_or_insert_with(|| Box::leak(Box::new(Config::load())))
+}
+
+/// Load the config again, for a command the daemon runs in the directory
+/// and with the environment of its client. What `get` returned before
+/// stays as it was, for whatever still holds it.
+pub fn reload() {
+    *CONFIG.lock().unwrap() = Some(Box::leak(Box::new(Config::load())));
+}
+
+impl Config {
+    pub fn path() -> String {
+        let home = home_dir().unwrap();
+        format!("{}/.todo.toml", home.to_str().unwrap())
+    }
+
+    fn load() -> Self {
+        let mut config = Self::read(&Self::path());
+        if let Some(name) = workspace::current() {
+            config.values.insert(
+                "storage.path".to_string(),
+                Value::String(workspace::list_path(name)),
+            );
+            let path = workspace::config_path(name);
+            config
+                .values
+                .extend(Self::read(&path.to_string_lossy()).values);
+        }
+        for path in directory_configs().iter().rev() {
+            let mut overrides = Self::read(&path.to_string_lossy());
+            if let Some(Value::String(storage)) = overrides.values.get_mut("storage.path") {
+                if !storage.starts_with(['/', '~']) {
+                    let dir = path.parent().unwrap_or(Path::new("/"));
+                    *storage = dir.join(&*storage).to_string_lossy().into_owned();
+                }
+            }
+            config.values.extend(overrides.values);
+        }
+        config
+    }
+
+    /// The settings of one file, without those of any other.
+    pub fn read(path: &str) -> Self {
+        match fs::read_to_string(path) {
+            Ok(s) => match Self::parse(&s) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("{}: {}", path, e);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let mut values = HashMap::new();
+        let mut section = String::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some(kv) => kv,
+                None => anyhow::bail!("line {}: expected `key = value`", i + 1),
+            };
+            let key = key.trim().trim_matches('"');
+            let key = if section.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", section, key)
+            };
+            let value = match parse_value(value.trim()) {
+                Some(v) => v,
+                None => anyhow::bail!("line {}: invalid value for `{}`", i + 1, key),
+            };
+            values.insert(key, value);
+        }
+
+        Ok(Self { values })
+    }
+
+    pub fn string(&self, key: &str) -> Option<&str> {
+        match self.values.get(key) {
+            Some(Value::String(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// All settings, by key, sorted.
+    pub fn all(&self) -> Vec<(&str, &Value)> {
+        let mut values: Vec<(&str, &Value)> =
+            self.values.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        values.sort_by_key(|(k, _)| *k);
+        values
+    }
+
+    /// String values of the keys in `[section]`, by key, sorted.
+    pub fn section(&self, section: &str) -> Vec<(&str, &str)> {
+        let prefix = format!("{}.", section);
+        let mut values: Vec<(&str, &str)> = self
+            .values
+            .iter()
+            .filter_map(|(k, v)| match v {
+                Value::String(s) => Some((k.strip_prefix(&prefix)?, s.as_str())),
+                Value::List(_) => None,
+            })
+            .filter(|(k, _)| !k.contains('.'))
+            .collect();
+        values.sort();
+        values
+    }
+
+    /// Names of the keys in `[section]`, sorted.
+    pub fn keys(&self, section: &str) -> Vec<&str> {
+        let prefix = format!("{}.", section);
+        let mut keys: Vec<&str> = self
+            .values
+            .keys()
+            .filter_map(|k| k.strip_prefix(&prefix))
+            .filter(|k| !k.contains('.'))
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Names of the `[section.<name>]` tables, sorted.
+    pub fn tables(&self, section: &str) -> Vec<&str> {
+        let prefix = format!("{}.", section);
+        let mut names: Vec<&str> = self
+            .values
+            .keys()
+            .filter_map(|k| k.strip_prefix(&prefix)?.split_once('.'))
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// A list of strings, where a single string counts as a list of one.
+    pub fn list(&self, key: &str) -> Vec<String> {
+        match self.values.get(key) {
+            Some(Value::String(s)) => vec![s.clone()],
+            Some(Value::List(items)) => items.clone(),
+            None => vec![],
+        }
+    }
+}
+
+/// The `.todo.toml` files that apply to the current directory, nearest
+/// first.
+fn directory_configs() -> Vec<PathBuf> {
+    let home = home_dir();
+    let Ok(cwd) = env::current_dir() else {
+        return vec![];
+    };
+    let mut paths = vec![];
+    for dir in cwd.ancestors() {
+        if home.as_deref() == Some(dir) {
+            break;
+        }
+        let path = dir.join(".todo.toml");
+        if path.is_file() {
+            let root = Config::read(&path.to_string_lossy()).string("root") == Some("true");
+            paths.push(path);
+            if root {
+                break;
+            }
+        }
+    }
+    paths
+}
+
+/// `path` with a leading `~` replaced by the home directory.
+pub fn expand_home(path: &str) -> String {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{}", home_dir().unwrap().to_str().unwrap(), rest)
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// Set `key` in `[section]` of the config file to the string `value`, or
+/// remove it when `value` is `None`. The rest of the file, comments
+/// included, is kept as it is. Takes effect on the next invocation.
+pub fn write_key(section: &str, key: &str, value: Option<&str>) -> anyhow::Result<()> {
+    let path = Config::path();
+    let content = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let entry = value.map(|v| format!("{} = {}", key, quote(v)));
+
+    let header = lines.iter().position(|l| {
+        strip_comment(l)
+            .trim()
+            .strip_prefix('[')
+            .and_then(|l| l.strip_suffix(']'))
+            .is_some_and(|name| name.trim() == section)
+    });
+    match header {
+        Some(header) => {
+            let end = lines[header + 1..]
+                .iter()
+                .position(|l| strip_comment(l).trim().starts_with('['))
+                .map_or(lines.len(), |i| header + 1 + i);
+            let existing = (header + 1..end).find(|&i| {
+                strip_comment(&lines[i])
+                    .split_once('=')
+                    .is_some_and(|(k, _)| k.trim().trim_matches('"') == key)
+            });
+            match (existing, entry) {
+                (Some(i), Some(entry)) => lines[i] = entry,
+                (Some(i), None) => {
+                    lines.remove(i);
+                }
+                (None, Some(entry)) => {
+                    // After the last setting, before any blank lines.
+                    let at = (header + 1..end)
+                        .rev()
+                        .find(|&i| !lines[i].trim().is_empty())
+                        .map_or(header + 1, |i| i + 1);
+                    lines.insert(at, entry);
+                }
+                (None, None) => {}
+            }
+        }
+        None => {
+            if let Some(entry) = entry {
+                if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                    lines.push("".to_string());
+                }
+                lines.push(format!("[{}]", section));
+                lines.push(entry);
+            }
+        }
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    if dry_run::is_enabled() {
+        match value {
+            Some(value) => println!(
+                "Dry run, {} would set {} in [{}] to {}",
+                path,
+                key,
+                section,
+                quote(value)
+            ),
+            None => println!("Dry run, {} would lose {} in [{}]", path, key, section),
+        }
+        return Ok(());
+    }
+    fs::write(&path, content)?;
+    Ok(())
+}
+
+/// `s` as a TOML basic string.
+fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_string(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next()? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                c => out.push(c),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    Some(interpolate(&out))
+}
+
+/// `s` with each `${NAME}` replaced by the environment variable, or by
+/// nothing when it is not set.
+fn interpolate(s: &str) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(&env::var(&rest[start + 2..start + 2 + len]).unwrap_or_default());
+        rest = &rest[start + 3 + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Split array items on commas outside of strings.
+fn split_items(s: &str) -> Vec<&str> {
+    let mut items = vec![];
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                items.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&s[start..]);
+    items
+}
+
+fn parse_value(s: &str) -> Option<Value> {
+    if let Some(inner) = s.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+        let items = split_items(inner)
+            .into_iter()
+            .map(|i| i.trim())
+            .filter(|i| !i.is_empty())
+            .map(parse_string)
+            .collect::<Option<Vec<String>>>()?;
+        return Some(Value::List(items));
+    }
+    if s.starts_with('"') {
+        return parse_string(s).map(Value::String);
+    }
+    if s == "true" || s == "false" || s.parse::<f64>().is_ok() {
+        return Some(Value::String(s.to_string()));
+    }
+    None
+}
@@ -0,0 +1,581 @@
+//! The list as a CRDT: a state that copies on several devices can be
+//! merged into in any order and as often as they like, always ending up
+//! the same and never in a conflict for the user to settle.
+//!
+//! - Each TODO has an ID of its own across devices, and each of its
+//!   fields is a last-writer-wins register.
+//! - Fields holding several things, like tags, links and comments, are
+//!   sets whose elements are added and removed each on their own, so
+//!   tags added on two devices are both kept.
+//! - A deleted TODO leaves a tombstone, which only an edit made after
+//!   the deletion outweighs.
+//!
+//! Changes are stamped when they are seen: when `todo` saves the list,
+//! or else at the next sync. Equal times go to the device with the
+//! greater name. The numeric ID of a TODO, and its links to services,
+//! which each device syncs on its own, stay local.
+
+use crate::json::{self, Value};
+use crate::{batch, crypt, format, Todo, Todos};
+use std::collections::{BTreeMap, HashMap};
+use std::io::ErrorKind;
+
+/// Fields that are sets rather than registers.
+const SETS: &[&str] = &[
+    "url",
+    "comments",
+    "attachments",
+    "time_log",
+    "completions",
+    "tags",
+];
+
+/// Fields that are not shared.
+const LOCAL: &[&str] = &["id", "remote_ids"];
+
+/// Milliseconds since the epoch.
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Register {
+    value: String,
+    at: u64,
+    node: String,
+}
+
+impl Register {
+    fn wins_over(&self, other: &Register) -> bool {
+        (self.at, &self.node, &self.value) > (other.at, &other.node, &other.value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Record {
+    registers: BTreeMap<String, Register>,
+    /// For each element, when it was last added and last removed.
+    sets: BTreeMap<String, BTreeMap<String, (u64, u64)>>,
+    deleted_at: u64,
+}
+
+impl Record {
+    fn changed_at(&self) -> u64 {
+        let registers = self.registers.values().map(|r| r.at);
+        let sets = self
+            .sets
+            .values()
+            .flat_map(|s| s.values().map(|(a, r)| *a.max(r)));
+        registers.chain(sets).max().unwrap_or(0)
+    }
+
+    fn is_live(&self) -> bool {
+        self.changed_at() > self.deleted_at
+    }
+
+    fn elements(&self, field: &str) -> Vec<&String> {
+        self.sets
+            .get(field)
+            .into_iter()
+            .flatten()
+            .filter(|(_, (added, removed))| added > removed)
+            .map(|(e, _)| e)
+            .collect()
+    }
+
+    /// Stamp the fields of `todo` that differ from those recorded.
+    fn observe(&mut self, todo: &Todo, at: u64, node: &str) {
+        // Brought back after being deleted: all of it is new again.
+        let revived = !self.is_live() && self.deleted_at > 0;
+        for (name, value) in Todos::default_headers().iter().zip(todo.to_fields()) {
+            if LOCAL.contains(&name.as_str()) {
+                continue;
+            }
+            if SETS.contains(&name.as_str()) {
+                let current = format::split_list(&value);
+                let set = self.sets.entry(name.clone()).or_default();
+                for element in &current {
+                    let (added, removed) = set.entry(element.clone()).or_insert((0, 0));
+                    if added <= removed || revived {
+                        *added = at;
+                    }
+                }
+                for (element, (added, removed)) in set.iter_mut() {
+                    if *added > *removed && !current.contains(element) {
+                        *removed = at;
+                    }
+                }
+            } else if revived || self.registers.get(name).is_none_or(|r| r.value != value) {
+                self.registers.insert(
+                    name.clone(),
+                    Register {
+                        value,
+                        at,
+                        node: node.to_string(),
+                    },
+                );
+            }
+        }
+    }
+
+    fn join(&mut self, other: &Record) {
+        for (name, theirs) in &other.registers {
+            match self.registers.get(name) {
+                Some(ours) if !theirs.wins_over(ours) => {}
+                _ => {
+                    self.registers.insert(name.clone(), theirs.clone());
+                }
+            }
+        }
+        for (name, theirs) in &other.sets {
+            let set = self.sets.entry(name.clone()).or_default();
+            for (element, (added, removed)) in theirs {
+                let entry = set.entry(element.clone()).or_insert((0, 0));
+                *entry = (entry.0.max(*added), entry.1.max(*removed));
+            }
+        }
+        self.deleted_at = self.deleted_at.max(other.deleted_at);
+    }
+
+    /// The fields of `local` made what this record says.
+    fn apply(&self, local: &Todo) -> Vec<String> {
+        let headers = Todos::default_headers();
+        let mut fields = local.to_fields();
+        for (i, name) in headers.iter().enumerate() {
+            if LOCAL.contains(&name.as_str()) {
+                continue;
+            }
+            if SETS.contains(&name.as_str()) {
+                let mut live = self.elements(name);
+                let mut local = format::split_list(&fields[i]);
+                local.sort();
+                let mut sorted = live.clone();
+                sorted.sort();
+                // Every device orders a changed set the same way.
+                if local.iter().ne(sorted) {
+                    let set = &self.sets[name];
+                    live.sort_by_key(|e| (set[*e].0, *e));
+                    let values: Vec<String> = live.into_iter().cloned().collect();
+                    fields[i] = format::join_list(&values);
+                }
+            } else if let Some(register) = self.registers.get(name) {
+                fields[i] = register.value.clone();
+            }
+        }
+        fields
+    }
+
+    fn to_json(&self) -> Value {
+        let registers = self
+            .registers
+            .iter()
+            .map(|(name, r)| {
+                let value = Value::Array(vec![
+                    r.value.as_str().into(),
+                    Value::Number(r.at as f64),
+                    r.node.as_str().into(),
+                ]);
+                (name.as_str(), value)
+            })
+            .collect();
+        let sets = self
+            .sets
+            .iter()
+            .map(|(name, set)| {
+                let elements = set
+                    .iter()
+                    .map(|(e, (a, r))| {
+                        let times = vec![Value::Number(*a as f64), Value::Number(*r as f64)];
+                        (e.as_str(), Value::Array(times))
+                    })
+                    .collect();
+                (name.as_str(), Value::object(elements))
+            })
+            .collect();
+        Value::object(vec![
+            ("fields", Value::object(registers)),
+            ("sets", Value::object(sets)),
+            ("deleted_at", Value::Number(self.deleted_at as f64)),
+        ])
+    }
+
+    fn from_json(value: &Value) -> Self {
+        let number = |v: &Value| v.as_f64().unwrap_or(0.0) as u64;
+        let mut record = Record {
+            deleted_at: number(value.get("deleted_at")),
+            ..Default::default()
+        };
+        for (name, r) in pairs(value.get("fields")) {
+            let [v, at, node] = r.items() else { continue };
+            record.registers.insert(
+                name.clone(),
+                Register {
+                    value: v.as_str().unwrap_or("").to_string(),
+                    at: number(at),
+                    node: node.as_str().unwrap_or("").to_string(),
+                },
+            );
+        }
+        for (name, set) in pairs(value.get("sets")) {
+            let elements = pairs(set)
+                .iter()
+                .filter_map(|(e, times)| match times.items() {
+                    [a, r] => Some((e.clone(), (number(a), number(r)))),
+                    _ => None,
+                })
+                .collect();
+            record.sets.insert(name.clone(), elements);
+        }
+        record
+    }
+}
+
+fn pairs(value: &Value) -> &[(String, Value)] {
+    match value {
+        Value::Object(pairs) => pairs,
+        _ => &[],
+    }
+}
+
+/// The TODOs by their IDs across devices.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct State {
+    records: BTreeMap<String, Record>,
+}
+
+impl State {
+    pub fn join(&mut self, other: &State) {
+        for (uid, theirs) in &other.records {
+            self.records.entry(uid.clone()).or_default().join(theirs);
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        Value::object(
+            self.records
+                .iter()
+                .map(|(uid, r)| (uid.as_str(), r.to_json()))
+                .collect(),
+        )
+    }
+
+    fn from_json(value: &Value) -> Self {
+        State {
+            records: pairs(value)
+                .iter()
+                .map(|(uid, r)| (uid.clone(), Record::from_json(r)))
+                .collect(),
+        }
+    }
+
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        Ok(Self::from_json(json::parse(content)?.get("records")))
+    }
+
+    pub fn content(&self) -> String {
+        Value::object(vec![
+            ("version", Value::Number(1.0)),
+            ("records", self.to_json()),
+        ])
+        .to_string()
+    }
+
+    /// The state in the file at `path`, empty when there is none.
+    pub fn read(path: &str) -> anyhow::Result<Self> {
+        match batch::read(path) {
+            Ok(content) => Self::parse(&crypt::unseal(content)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Replace the file at `path` with the state, at once or at the end of
+    /// the batch, encrypted when the list at `list` is.
+    pub fn write(&self, path: &str, list: &str) -> anyhow::Result<()> {
+        batch::write(path, crypt::seal(list, self.content())?)
+    }
+}
+
+/// What `Replica::apply` did to the list.
+#[derive(Default)]
+pub struct Changes {
+    pub added: usize,
+    pub updated: usize,
+    pub deleted: Vec<Todo>,
+}
+
+/// This device's copy of the state, kept next to the list as
+/// `<list>.crdt` with the local IDs of the TODOs.
+pub struct Replica {
+    node: String,
+    /// For making new IDs, `<node>.<n>`.
+    next: u64,
+    /// Local ID by ID across devices.
+    ids: BTreeMap<String, String>,
+    pub state: State,
+}
+
+impl Replica {
+    fn path(list: &str) -> String {
+        format!("{}.crdt", list)
+    }
+
+    /// The replica of the list at `list`, `None` if it was never synced.
+    pub fn load(list: &str) -> anyhow::Result<Option<Self>> {
+        let content = match batch::read(&Self::path(list)) {
+            Ok(c) => c,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let value = json::parse(&crypt::unseal(content)?)?;
+        Ok(Some(Replica {
+            node: value.get("node").as_str().unwrap_or("").to_string(),
+            next: value.get("next").as_f64().unwrap_or(0.0) as u64,
+            ids: pairs(value.get("ids"))
+                .iter()
+                .map(|(uid, id)| (uid.clone(), id.as_str().unwrap_or("").to_string()))
+                .collect(),
+            state: State::from_json(value.get("records")),
+        }))
+    }
+
+    pub fn new() -> Self {
+        // Unique enough to tell devices apart, and stable once saved.
+        let node = format!(
+            "{}-{:x}",
+            crate::current_user(),
+            now() ^ std::process::id() as u64
+        );
+        Replica {
+            node,
+            next: 1,
+            ids: BTreeMap::new(),
+            state: State::default(),
+        }
+    }
+
+    pub fn save(&self, list: &str) -> anyhow::Result<()> {
+        let ids = self
+            .ids
+            .iter()
+            .map(|(uid, id)| (uid.as_str(), id.as_str().into()))
+            .collect();
+        let content = Value::object(vec![
+            ("node", self.node.as_str().into()),
+            ("next", Value::Number(self.next as f64)),
+            ("ids", Value::object(ids)),
+            ("records", self.state.to_json()),
+        ]);
+        batch::write(&Self::path(list), crypt::seal(list, content.to_string())?)
+    }
+
+    /// Follow the TODOs of the list to the new local IDs `todo renumber`
+    /// gave them, as old and new, since `observe` finds them by ID.
+    pub fn renumber(&mut self, changes: &[(String, String, String)]) {
+        for id in self.ids.values_mut() {
+            if let Some((_, new, _)) = changes.iter().find(|(old, _, _)| old == id) {
+                *id = new.clone();
+            }
+        }
+    }
+
+    /// Stamp with `at` what changed in `todos` since they were last seen,
+    /// and tombstone the TODOs that are gone.
+    pub fn observe(&mut self, todos: &Todos, at: u64) {
+        let mut uids: HashMap<String, String> = self
+            .ids
+            .iter()
+            .map(|(uid, id)| (id.clone(), uid.clone()))
+            .collect();
+        let mut ids = BTreeMap::new();
+        for todo in &todos.records {
+            // An ID freed by a deletion may have been given to a new TODO.
+            let known = uids.remove(&todo.id).filter(|uid| {
+                self.state.records[uid]
+                    .registers
+                    .get("created_at")
+                    .is_none_or(|r| r.value == todo.created_at)
+            });
+            let uid = known.unwrap_or_else(|| {
+                self.next += 1;
+                format!("{}.{}", self.node, self.next - 1)
+            });
+            self.state
+                .records
+                .entry(uid.clone())
+                .or_default()
+                .observe(todo, at, &self.node);
+            ids.insert(uid, todo.id.clone());
+        }
+        for uid in self.ids.keys().filter(|uid| !ids.contains_key(*uid)) {
+            let record = self.state.records.get_mut(uid).unwrap();
+            if record.is_live() {
+                record.deleted_at = at;
+            }
+        }
+        self.ids = ids;
+    }
+
+    /// Make `todos` what the state says: new TODOs are added under the next
+    /// free IDs, and tombstoned ones are taken out.
+    pub fn apply(&mut self, todos: &mut Todos) -> Changes {
+        let mut changes = Changes::default();
+        for (uid, record) in &self.state.records {
+            let index = self
+                .ids
+                .get(uid)
+                .and_then(|id| todos.records.iter().position(|r| r.id == *id));
+            match (index, record.is_live()) {
+                (Some(i), true) => {
+                    let fields = record.apply(&todos.records[i]);
+                    if fields != todos.records[i].to_fields() {
+                        todos.records[i].set_fields(&fields);
+                        changes.updated += 1;
+                    }
+                }
+                (Some(i), false) => {
+                    changes.deleted.push(todos.records.remove(i));
+                    self.ids.remove(uid);
+                }
+                (None, true) => {
+                    let mut todo = Todo::from_fields(&record.apply(&Todo::from_fields(&[])));
+                    todo.id = todos.next_id().to_string();
+                    self.ids.insert(uid.clone(), todo.id.clone());
+                    todos.records.push(todo);
+                    changes.added += 1;
+                }
+                (None, false) => {}
+            }
+        }
+        changes
+    }
+}
+
+/// Stamp the changes of a save, for a list that is synced.
+pub fn saved(list: &str, todos: &Todos) {
+    if let Ok(Some(mut replica)) = Replica::load(list) {
+        replica.observe(todos, now());
+        let _ = replica.save(list);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todos(titles: &[&str]) -> Todos {
+        let records = titles
+            .iter()
+            .enumerate()
+            .map(|(i, title)| {
+                let mut todo = Todo::new(
+                    (i + 1).to_string(),
+                    "".to_string(),
+                    title.to_string(),
+                    vec![],
+                    "".to_string(),
+                );
+                todo.created_at = i.to_string();
+                todo
+            })
+            .collect();
+        Todos {
+            headers: Todos::default_headers(),
+            records,
+            skipped: vec![],
+        }
+    }
+
+    fn replica(node: &str) -> Replica {
+        Replica {
+            node: node.to_string(),
+            ..Replica::new()
+        }
+    }
+
+    /// Two devices that start from `a`'s list.
+    fn pair(titles: &[&str]) -> (Replica, Todos, Replica, Todos) {
+        let mut list_a = todos(titles);
+        let mut a = replica("a");
+        a.observe(&list_a, 1);
+        let mut b = replica("b");
+        let mut list_b = todos(&[]);
+        b.state.join(&a.state);
+        b.apply(&mut list_b);
+        a.apply(&mut list_a);
+        (a, list_a, b, list_b)
+    }
+
+    fn sync(a: &mut Replica, list_a: &mut Todos, b: &mut Replica, list_b: &mut Todos) {
+        let state = a.state.clone();
+        a.state.join(&b.state);
+        b.state.join(&state);
+        a.apply(list_a);
+        b.apply(list_b);
+    }
+
+    #[test]
+    fn joins_commutatively() {
+        let (mut a, mut list_a, mut b, mut list_b) = pair(&["one"]);
+        list_a.records[0].title = "from a".to_string();
+        list_b.records[0].tags.push("b".to_string());
+        a.observe(&list_a, 2);
+        b.observe(&list_b, 3);
+        let mut ab = a.state.clone();
+        ab.join(&b.state);
+        let mut ba = b.state.clone();
+        ba.join(&a.state);
+        assert_eq!(ab, ba);
+    }
+
+    #[test]
+    fn keeps_the_last_write_and_both_additions() {
+        let (mut a, mut list_a, mut b, mut list_b) = pair(&["one"]);
+        list_a.records[0].title = "older".to_string();
+        list_a.records[0].tags.push("a".to_string());
+        a.observe(&list_a, 2);
+        list_b.records[0].title = "newer".to_string();
+        list_b.records[0].tags.push("b".to_string());
+        b.observe(&list_b, 3);
+        sync(&mut a, &mut list_a, &mut b, &mut list_b);
+        for list in [&list_a, &list_b] {
+            assert_eq!(list.records[0].title, "newer");
+            assert_eq!(list.records[0].tags, ["a", "b"]);
+        }
+    }
+
+    #[test]
+    fn deletes_unless_edited_after() {
+        let (mut a, mut list_a, mut b, mut list_b) = pair(&["one", "two"]);
+        list_a.records.clear();
+        a.observe(&list_a, 2);
+        list_b.records[1].title = "edited".to_string();
+        b.observe(&list_b, 3);
+        sync(&mut a, &mut list_a, &mut b, &mut list_b);
+        for list in [&list_a, &list_b] {
+            let titles: Vec<&str> = list.records.iter().map(|r| r.title.as_str()).collect();
+            assert_eq!(titles, ["edited"]);
+        }
+    }
+
+    #[test]
+    fn follows_renumbered_todos() {
+        let (mut a, mut list_a, mut b, mut list_b) = pair(&["one", "two", "three"]);
+        list_a.records.remove(1);
+        a.observe(&list_a, 2);
+        list_a.records[1].id = "2".to_string();
+        a.renumber(&[("3".to_string(), "2".to_string(), "three".to_string())]);
+        a.observe(&list_a, 3);
+        // Still the same TODO, and not a new one in place of a deleted one.
+        assert_eq!(a.state.records.len(), 3);
+        list_b.records[2].title = "edited".to_string();
+        b.observe(&list_b, 4);
+        sync(&mut a, &mut list_a, &mut b, &mut list_b);
+        for list in [&list_a, &list_b] {
+            let titles: Vec<&str> = list.records.iter().map(|r| r.title.as_str()).collect();
+            assert_eq!(titles, ["one", "edited"]);
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! TODOs added on a schedule, for chores that come back:
+//!
+//! ```toml
+//! [cron]
+//! plants = "every monday 9:00 add 'water plants' #home"
+//! rent = "every month on 25 add 'pay rent'"
+//! standup = "every weekday at 9:30 add standup #work"
+//! ```
+//!
+//! A schedule is `every` followed by `day`, `weekday`, `weekend`, weekday
+//! names like `mon,thu`, or `month [on <day>]`, an optional time (00:00
+//! by default), and `add` with the title and `#tags`. `todo tick`, run
+//! from cron or a systemd timer, adds each TODO whose time has come once,
+//! as due that day, even when several times were missed. With
+//! `rollover.auto`, it also rolls over overdue TODOs, see `rollover`.
+
+use crate::date::{self, Date};
+use crate::{alias, batch, config, dry_run, format, rollover, rules, Todo, Todos};
+use anyhow::bail;
+use seahorse::Command;
+use std::collections::HashMap;
+use std::process::exit;
+
+const STATE_VERSION: u32 = 1;
+/// Days to look for an occurrence, enough for monthly ones.
+const SEARCH_DAYS: i64 = 62;
+
+pub fn cron_command() -> Command {
+    Command::new("cron")
+        .description("Show the scheduled TODOs from the config and when they come next")
+        .usage("todo cron")
+        .action(|_| {
+            let schedules = schedules();
+            if schedules.is_empty() {
+                println!(
+                    "No schedules, add them under [cron] in {}",
+                    config::Config::path()
+                );
+            }
+            let now = date::now();
+            let width = schedules.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+            for (name, schedule) in &schedules {
+                let next = match schedule.next(now) {
+                    Some(ts) => date::format_timestamp(ts),
+                    None => "never".to_string(),
+                };
+                println!(
+                    "{:<width$}  {}  {}",
+                    name,
+                    next,
+                    schedule.title(),
+                    width = width
+                );
+            }
+        })
+}
+
+pub fn tick_command() -> Command {
+    Command::new("tick")
+        .description("Add the scheduled TODOs that are due, meant to be run regularly")
+        .usage("todo tick")
+        .action(|_| {
+            if let Err(e) = tick() {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        })
+}
+
+fn tick() -> anyhow::Result<()> {
+    let schedules = schedules();
+    let mut todos = Todos::read();
+    let mut state = State::read();
+    let now = date::now();
+    let start_of_today = date::today().start_timestamp();
+
+    let mut added = 0;
+    let mut last_runs = HashMap::new();
+    for (name, schedule) in &schedules {
+        let due = schedule
+            .latest(now)
+            .filter(|&at| match state.last_runs.get(*name) {
+                Some(&last) => at > last,
+                // A new schedule starts with today's occurrence, if it passed.
+                None => at >= start_of_today,
+            });
+        if let Some(at) = due {
+            let mut todo = schedule.todo(Date::from_timestamp(at));
+            rules::apply(&mut todo);
+            let todo = todos.add(todo)?;
+            println!("Added #{}: {}", todo.id, todo.title);
+            added += 1;
+        }
+        last_runs.insert(name.to_string(), now);
+    }
+    let rolled = match rollover::is_auto() {
+        true => rollover::roll_over(&mut todos),
+        false => 0,
+    };
+    if added > 0 || rolled > 0 {
+        todos.save()?;
+    }
+    state.last_runs = last_runs;
+    state.save()
+}
+
+/// The valid schedules of the config, by name. Broken ones are reported
+/// and left out.
+fn schedules() -> Vec<(&'static str, Schedule)> {
+    config::get()
+        .section("cron")
+        .into_iter()
+        .filter_map(|(name, s)| match Schedule::parse(s) {
+            Ok(schedule) => Some((name, schedule)),
+            Err(e) => {
+                eprintln!("Ignoring cron {}: {}", name, e);
+                None
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Days {
+    Every,
+    /// 0 for Monday through 6 for Sunday.
+    Weekdays(Vec<u32>),
+    /// Day of the month, the last one in shorter months.
+    MonthDay(u32),
+}
+
+#[derive(Debug, Clone)]
+struct Schedule {
+    days: Days,
+    /// Minutes after midnight.
+    time: u32,
+    words: Vec<String>,
+}
+
+impl Schedule {
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        let Some(words) = alias::split(s) else {
+            bail!("unclosed quote");
+        };
+        let mut words = words.into_iter().peekable();
+        if words.next().as_deref() != Some("every") {
+            bail!("expected `every ...`");
+        }
+        let days = match words.next().map(|w| w.to_lowercase()).as_deref() {
+            Some("day") => Days::Every,
+            Some("weekday") => Days::Weekdays(vec![0, 1, 2, 3, 4]),
+            Some("weekend") => Days::Weekdays(vec![5, 6]),
+            Some("month") => {
+                if words.peek().map(|w| w.as_str()) == Some("on") {
+                    words.next();
+                }
+                let day = match words.peek().and_then(|w| w.parse::<u32>().ok()) {
+                    Some(day) if (1..=31).contains(&day) => {
+                        words.next();
+                        day
+                    }
+                    Some(_) => bail!("days of the month go from 1 to 31"),
+                    None => 1,
+                };
+                Days::MonthDay(day)
+            }
+            Some(names) => Days::Weekdays(
+                names
+                    .split(',')
+                    .map(|n| date::parse_weekday(n.trim()))
+                    .collect::<Option<Vec<u32>>>()
+                    .ok_or_else(|| anyhow::anyhow!("invalid day: {}", names))?,
+            ),
+            None => bail!("expected when after `every`"),
+        };
+        if words.peek().map(|w| w.as_str()) == Some("at") {
+            words.next();
+        }
+        let time = match words.peek().and_then(|w| w.split_once(':')) {
+            Some((h, m)) => {
+                let time = match (h.parse::<u32>(), m.parse::<u32>()) {
+                    (Ok(hours), Ok(minutes)) if hours < 24 && minutes < 60 && m.len() == 2 => {
+                        hours * 60 + minutes
+                    }
+                    _ => bail!("invalid time: {}", words.peek().unwrap()),
+                };
+                words.next();
+                time
+            }
+            None => 0,
+        };
+        if words.next().as_deref() != Some("add") {
+            bail!("expected `add <title>` after the time");
+        }
+        let words: Vec<String> = words.collect();
+        if words.iter().all(|w| w.starts_with('#')) {
+            bail!("no title to add");
+        }
+        Ok(Schedule { days, time, words })
+    }
+
+    fn title(&self) -> String {
+        self.words.join(" ")
+    }
+
+    fn is_on(&self, d: Date) -> bool {
+        match &self.days {
+            Days::Every => true,
+            Days::Weekdays(days) => days.contains(&d.weekday()),
+            Days::MonthDay(day) => d.day == (*day).min(date::days_in_month(d.year, d.month)),
+        }
+    }
+
+    fn at(&self, d: Date) -> u64 {
+        d.start_timestamp() + self.time as u64 * 60
+    }
+
+    /// The last time it came, up to `now`.
+    fn latest(&self, now: u64) -> Option<u64> {
+        let today = Date::from_timestamp(now);
+        (0..SEARCH_DAYS)
+            .map(|i| today.add_days(-i))
+            .filter(|d| self.is_on(*d))
+            .map(|d| self.at(d))
+            .find(|&at| at <= now)
+    }
+
+    /// The next time it comes after `now`.
+    fn next(&self, now: u64) -> Option<u64> {
+        let today = Date::from_timestamp(now);
+        (0..SEARCH_DAYS)
+            .map(|i| today.add_days(i))
+            .filter(|d| self.is_on(*d))
+            .map(|d| self.at(d))
+            .find(|&at| at > now)
+    }
+
+    fn todo(&self, due: Date) -> Todo {
+        let (tags, title): (Vec<&String>, Vec<&String>) =
+            self.words.iter().partition(|w| w.starts_with('#'));
+        let title = title
+            .iter()
+            .map(|w| w.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut todo = Todo::new(
+            "".to_string(),
+            due.to_string(),
+            title,
+            vec![],
+            "".to_string(),
+        );
+        todo.tags = tags.iter().map(|t| crate::tag_name(t)).collect();
+        todo
+    }
+}
+
+/// When `todo tick` last looked at each schedule, stored next to the
+/// data file.
+struct State {
+    last_runs: HashMap<String, u64>,
+}
+
+impl State {
+    fn path() -> String {
+        format!("{}.cron", Todos::todo_path())
+    }
+
+    fn read() -> Self {
+        let content = batch::read(&Self::path()).unwrap_or_default();
+        let last_runs = format::parse(&content)
+            .rows
+            .iter()
+            .filter(|r| r.fields.len() >= 2)
+            .filter_map(|r| Some((r.fields[0].clone(), r.fields[1].parse().ok()?)))
+            .collect();
+        State { last_runs }
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        if dry_run::is_enabled() {
+            return Ok(());
+        }
+        let headers = vec!["name".to_string(), "last_run".to_string()];
+        let mut rows: Vec<Vec<String>> = self
+            .last_runs
+            .iter()
+            .map(|(name, at)| vec![name.clone(), at.to_string()])
+            .collect();
+        rows.sort();
+        batch::write(&Self::path(), format::write(STATE_VERSION, &headers, &rows))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+//! Encryption of the TODO file with GnuPG, turned on by a `.gpg-id` file
+//! next to it that names the key to encrypt to, as `pass` does. gpg-agent
+//! asks for the passphrase when needed. The files next to it that hold
+//! its TODOs too, its trash, the sync state and a conflicting remote
+//! copy, are encrypted to the same key. Goals and attachments are not.
+
+use anyhow::{bail, Context};
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const ARMOR: &str = "-----BEGIN PGP MESSAGE-----";
+
+/// The key the file at `path` is encrypted to, if it is.
+pub fn recipient(path: &str) -> Option<String> {
+    let id = fs::read_to_string(format!("{}.gpg-id", path)).ok()?;
+    Some(id.trim().to_string()).filter(|id| !id.is_empty())
+}
+
+pub fn set_recipient(path: &str, key: &str) -> std::io::Result<()> {
+    fs::write(format!("{}.gpg-id", path), format!("{}\n", key))
+}
+
+pub fn is_encrypted(content: &str) -> bool {
+    content.trim_start().starts_with(ARMOR)
+}
+
+/// `content` for a file next to the list at `list` that holds its TODOs,
+/// encrypted when the list is.
+pub fn seal(list: &str, content: String) -> anyhow::Result<String> {
+    match recipient(list) {
+        Some(recipient) if !is_encrypted(&content) => encrypt(&content, &recipient),
+        _ => Ok(content),
+    }
+}
+
+/// `content` of a file that `seal` may have encrypted, decrypted.
+pub fn unseal(content: String) -> anyhow::Result<String> {
+    match is_encrypted(&content) {
+        true => decrypt(&content),
+        false => Ok(content),
+    }
+}
+
+pub fn decrypt(content: &str) -> anyhow::Result<String> {
+    gpg(&["--quiet", "--decrypt"], content)
+}
+
+pub fn encrypt(content: &str, recipient: &str) -> anyhow::Result<String> {
+    gpg(
+        &[
+            "--quiet",
+            "--batch",
+            "--yes",
+            "--armor",
+            "--encrypt",
+            "--recipient",
+            recipient,
+        ],
+        content,
+    )
+}
+
+/// Encrypt `content` with a passphrase rather than to a key, for devices
+/// that share a secret, as when syncing through a relay.
+pub fn encrypt_with_passphrase(content: &str, passphrase: &str) -> anyhow::Result<String> {
+    gpg(
+        &[
+            "--quiet",
+            "--batch",
+            "--yes",
+            "--armor",
+            "--symmetric",
+            "--cipher-algo",
+            "AES256",
+            "--pinentry-mode",
+            "loopback",
+            "--passphrase-fd",
+            "0",
+        ],
+        &format!("{}\n{}", passphrase, content),
+    )
+}
+
+pub fn decrypt_with_passphrase(content: &str, passphrase: &str) -> anyhow::Result<String> {
+    gpg(
+        &[
+            "--quiet",
+            "--batch",
+            "--decrypt",
+            "--pinentry-mode",
+            "loopback",
+            "--passphrase-fd",
+            "0",
+        ],
+        &format!("{}\n{}", passphrase, content),
+    )
+}
+
+fn gpg(args: &[&str], input: &str) -> anyhow::Result<String> {
+    let mut child = Command::new("gpg")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run gpg")?;
+    child.stdin.take().unwrap().write_all(input.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "gpg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
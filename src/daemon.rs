@@ -0,0 +1,392 @@
+//! A long-running `todo daemon` that keeps the list parsed, and decrypted,
+//! in memory, for `todo --client ...` to use instead of reading it each
+//! time, as in shell prompts.
+//!
+//! The daemon listens on `<list>.sock`, readable only by its user. A
+//! client passes it its arguments, environment, working directory and
+//! its stdin, stdout and stderr, and the daemon runs the command in a
+//! forked copy of itself that already has the list, so output, colors
+//! and prompts work as usual. Changes are saved to the file as always,
+//! and the daemon reads the file again when it changed. When no daemon
+//! runs for the list, `--client` runs the command itself.
+
+use crate::{config, dry_run, log, malformed, timings, Todos};
+use seahorse::Command;
+use std::env;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// The list as last read, with the modification time and size of the
+/// file then, to tell whether it is still current.
+struct Cache {
+    path: String,
+    stat: (SystemTime, u64),
+    todos: Todos,
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+pub fn daemon_command() -> Command {
+    Command::new("daemon")
+        .description("Keep the list in memory for commands run with --client")
+        .usage("todo daemon")
+        .action(|_| {
+            if let Err(e) = serve() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        })
+}
+
+fn socket_path() -> String {
+    format!("{}.sock", Todos::todo_path())
+}
+
+/// Take `--client` out of `args`, returning whether it was there.
+pub fn select_client(args: &mut Vec<String>) -> bool {
+    match args
+        .iter()
+        .take_while(|a| *a != "--")
+        .position(|a| a == "--client")
+    {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+fn stat(path: &str) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// The list at `path` as the daemon has it, when that is still what the
+/// file holds. Only ever set in the daemon, so `None` everywhere else.
+pub fn cached(path: &str) -> Option<Todos> {
+    let cache = CACHE.lock().unwrap();
+    let cache = cache.as_ref()?;
+    (cache.path == path && stat(path) == Some(cache.stat)).then(|| cache.todos.clone())
+}
+
+/// Read the list again if it changed since it was cached. Without the
+/// lock, which is for the commands to take, a write may be going on;
+/// the file then changes while it is read and is not cached.
+fn refresh(path: &str) {
+    let mut cache = CACHE.lock().unwrap();
+    let Some(before) = stat(path) else {
+        *cache = None;
+        return;
+    };
+    if cache.as_ref().is_some_and(|c| c.stat == before) {
+        return;
+    }
+    *cache = None;
+    let Ok(content) = Todos::read_content() else {
+        return;
+    };
+    let (todos, outdated) = Todos::parse(&content);
+    // Outdated lists are left for a command to upgrade and back up.
+    if outdated.is_none() && stat(path) == Some(before) {
+        *cache = Some(Cache {
+            path: path.to_string(),
+            stat: before,
+            todos,
+        });
+    }
+}
+
+/// Run `args` in the daemon, if one serves the list, and return the
+/// exit code of the command.
+#[cfg(unix)]
+pub fn forward(args: &[String]) -> Option<i32> {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    static CHILD: AtomicI32 = AtomicI32::new(0);
+    extern "C" fn on_interrupt(signal: libc::c_int) {
+        let pid = CHILD.load(Ordering::SeqCst);
+        if pid > 0 {
+            // SAFETY: kill is async-signal-safe.
+            unsafe { libc::kill(pid, signal) };
+        }
+    }
+
+    let mut stream = UnixStream::connect(socket_path()).ok()?;
+    log::debug("daemon", || "forwarding to the daemon".to_string());
+    let mut args = args.to_vec();
+    if dry_run::is_enabled() {
+        args.push("--dry-run".to_string());
+    }
+    if timings::is_enabled() {
+        args.push("--timings".to_string());
+    }
+    if malformed::is_strict() {
+        args.push("--strict".to_string());
+    }
+    let cwd = env::current_dir().ok()?.to_string_lossy().to_string();
+    let mut fields = vec![cwd, args.len().to_string()];
+    fields.extend(args);
+    fields.extend(env::vars().map(|(k, v)| format!("{}={}", k, v)));
+    let payload = fields.join("\0");
+
+    let sent = sys::send_fds(&stream, &[0, 1, 2], &(payload.len() as u32).to_le_bytes())
+        .and_then(|_| stream.write_all(payload.as_bytes()));
+    if let Err(e) = sent {
+        eprintln!("Failed to reach the daemon: {}", e);
+        return Some(1);
+    }
+
+    let mut pid = [0u8; 4];
+    let mut code = [0u8; 1];
+    if stream.read_exact(&mut pid).is_err() {
+        eprintln!("The daemon did not run the command");
+        return Some(1);
+    }
+    CHILD.store(i32::from_le_bytes(pid), Ordering::SeqCst);
+    // Ctrl-C reaches this process, not the command; pass it on.
+    // SAFETY: the handler only calls kill.
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+    match stream.read_exact(&mut code) {
+        Ok(_) => Some(code[0] as i32),
+        Err(_) => {
+            eprintln!("The daemon stopped before the command finished");
+            Some(1)
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn forward(_: &[String]) -> Option<i32> {
+    None
+}
+
+#[cfg(unix)]
+fn serve() -> anyhow::Result<()> {
+    use anyhow::bail;
+    use std::ffi::CString;
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::OnceLock;
+
+    static SOCKET: OnceLock<CString> = OnceLock::new();
+    extern "C" fn on_stop(_: libc::c_int) {
+        if let Some(socket) = SOCKET.get() {
+            // SAFETY: unlink and _exit are async-signal-safe.
+            unsafe { libc::unlink(socket.as_ptr()) };
+        }
+        unsafe { libc::_exit(0) };
+    }
+
+    let path = Todos::todo_path();
+    let socket = socket_path();
+    if UnixStream::connect(&socket).is_ok() {
+        bail!("A daemon already serves {}", path);
+    }
+    let _ = fs::remove_file(&socket);
+    // Whoever can connect runs commands as this user, so the socket is
+    // made for the user alone, not opened to others until a chmod.
+    // SAFETY: umask only changes the mask of this process, which has no
+    // other threads yet.
+    let umask = unsafe { libc::umask(0o077) };
+    let listener = UnixListener::bind(&socket);
+    unsafe { libc::umask(umask) };
+    let listener = listener?;
+    let _ = SOCKET.set(CString::new(socket.clone())?);
+    // SAFETY: the handler only unlinks the socket and exits.
+    unsafe {
+        let handler = on_stop as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+    println!("Serving {} on {} (Ctrl-C to stop)", path, socket);
+    log::info("daemon", || {
+        format!("serving path={} socket={}", path, socket)
+    });
+
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        let (fds, length) = match sys::recv_fds(&stream, 3) {
+            Ok(received) => received,
+            Err(e) => {
+                log::warn("daemon", || {
+                    format!("broken request error={:?}", e.to_string())
+                });
+                eprintln!("Ignoring a broken request: {}", e);
+                continue;
+            }
+        };
+        let mut request = vec![0u8; u32::from_le_bytes(length) as usize];
+        if fds.len() != 3 || stream.read_exact(&mut request).is_err() {
+            sys::close_all(&fds);
+            continue;
+        }
+        refresh(&path);
+
+        // SAFETY: the only other threads wait for children and write to
+        // their client, so nothing they hold is needed after the fork.
+        let pid = unsafe { libc::fork() };
+        if pid == 0 {
+            unsafe {
+                libc::signal(libc::SIGINT, libc::SIG_DFL);
+                libc::signal(libc::SIGTERM, libc::SIG_DFL);
+                libc::close(listener.as_raw_fd());
+                for (i, fd) in fds.iter().enumerate() {
+                    libc::dup2(*fd, i as libc::c_int);
+                }
+            }
+            sys::close_all(&fds);
+            drop(stream);
+            run(&String::from_utf8_lossy(&request));
+        }
+        sys::close_all(&fds);
+        if pid < 0 {
+            continue;
+        }
+        log::debug("daemon", || format!("forked pid={}", pid));
+        let _ = stream.write_all(&pid.to_le_bytes());
+        std::thread::spawn(move || {
+            let mut status = 0;
+            // SAFETY: waits for the child forked above.
+            unsafe { libc::waitpid(pid, &mut status, 0) };
+            let code = if libc::WIFEXITED(status) {
+                libc::WEXITSTATUS(status)
+            } else {
+                1
+            };
+            let _ = stream.write_all(&[code as u8]);
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve() -> anyhow::Result<()> {
+    anyhow::bail!("The daemon needs Unix sockets")
+}
+
+/// Run the command of a client request in the forked daemon, with the
+/// client's working directory and environment, and exit.
+#[cfg(unix)]
+fn run(request: &str) -> ! {
+    use std::io::Write;
+
+    let mut fields = request.split('\0');
+    let cwd = fields.next().unwrap_or("/");
+    let count: usize = fields.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+    let mut args: Vec<String> = fields.by_ref().take(count).map(|a| a.to_string()).collect();
+    for (key, _) in env::vars_os() {
+        env::remove_var(key);
+    }
+    for (key, value) in fields.filter_map(|f| f.split_once('=')) {
+        env::set_var(key, value);
+    }
+    if let Err(e) = env::set_current_dir(cwd) {
+        eprintln!("{}: {}", cwd, e);
+        std::process::exit(1);
+    }
+    config::reload();
+    dry_run::select(&mut args);
+    timings::select(&mut args);
+    malformed::select(&mut args);
+    crate::run(args);
+    let _ = std::io::stdout().flush();
+    std::process::exit(0);
+}
+
+/// Passing file descriptors over a Unix socket, which `std` has no API for.
+#[cfg(unix)]
+mod sys {
+    use std::io;
+    use std::mem::{size_of, zeroed};
+    use std::os::unix::io::{AsRawFd, RawFd};
+    use std::os::unix::net::UnixStream;
+    use std::ptr;
+
+    pub fn send_fds(stream: &UnixStream, fds: &[RawFd], data: &[u8; 4]) -> io::Result<()> {
+        let length = std::mem::size_of_val(fds) as u32;
+        // SAFETY: the message points into `data` and `control`, which
+        // outlive the call, and the control buffer has room for the
+        // header and the descriptors.
+        unsafe {
+            let mut iov = libc::iovec {
+                iov_base: data.as_ptr() as *mut libc::c_void,
+                iov_len: data.len(),
+            };
+            let mut control = vec![0u8; libc::CMSG_SPACE(length) as usize];
+            let mut msg: libc::msghdr = zeroed();
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+            msg.msg_controllen = control.len() as _;
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(length) as _;
+            ptr::copy_nonoverlapping(fds.as_ptr(), libc::CMSG_DATA(cmsg) as *mut RawFd, fds.len());
+            if libc::sendmsg(stream.as_raw_fd(), &msg, 0) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Up to `max` descriptors and the four bytes sent with them.
+    pub fn recv_fds(stream: &UnixStream, max: usize) -> io::Result<(Vec<RawFd>, [u8; 4])> {
+        let mut data = [0u8; 4];
+        let mut fds = vec![];
+        // SAFETY: as in send_fds; the descriptors are copied out of the
+        // control buffer only as far as the header says it holds them.
+        unsafe {
+            let mut iov = libc::iovec {
+                iov_base: data.as_mut_ptr() as *mut libc::c_void,
+                iov_len: data.len(),
+            };
+            let mut control =
+                vec![0u8; libc::CMSG_SPACE((max * size_of::<RawFd>()) as u32) as usize];
+            let mut msg: libc::msghdr = zeroed();
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+            msg.msg_controllen = control.len() as _;
+            let received = libc::recvmsg(stream.as_raw_fd(), &mut msg, 0);
+            if received < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+            while !cmsg.is_null() {
+                if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                    let bytes = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                    let data = libc::CMSG_DATA(cmsg) as *const RawFd;
+                    for i in 0..bytes / size_of::<RawFd>() {
+                        fds.push(ptr::read_unaligned(data.add(i)));
+                    }
+                }
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+            if received != 4 {
+                close_all(&fds);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "short request"));
+            }
+        }
+        Ok((fds, data))
+    }
+
+    pub fn close_all(fds: &[RawFd]) {
+        for fd in fds {
+            // SAFETY: the descriptors were received and are owned here.
+            unsafe { libc::close(*fd) };
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A calendar date in the proleptic Gregorian calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Self { year, month, day })
+    }
+
+    /// The local date of a Unix timestamp.
+    pub fn from_timestamp(ts: u64) -> Self {
+        Self::from_days(local_seconds(ts).div_euclid(86400))
+    }
+
+    /// Days since 1970-01-01.
+    pub fn to_days(self) -> i64 {
+        let y = if self.month <= 2 {
+            self.year - 1
+        } else {
+            self.year
+        } as i64;
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let m = self.month as i64;
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146097 + doe - 719468
+    }
+
+    /// Day of the week, 0 for Monday through 6 for Sunday.
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 was a Thursday.
+        (self.to_days() + 3).rem_euclid(7) as u32
+    }
+
+    pub fn from_days(days: i64) -> Self {
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        Self { year, month, day }
+    }
+
+    /// Parse a `YYYY-MM-DD` date, or the day of a `YYYY-MM-DD HH:MM` one.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = match s.trim().split_once(' ') {
+            Some((day, time)) => {
+                parse_time(time)?;
+                day
+            }
+            None => s.trim(),
+        };
+        let mut parts = s.splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        Self::new(year, month, day)
+    }
+
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_days(self.to_days() + days)
+    }
+
+    /// The same day `months` later, clamped to the end of shorter months.
+    pub fn add_months(self, months: i64) -> Self {
+        let index = self.year as i64 * 12 + self.month as i64 - 1 + months;
+        let year = index.div_euclid(12) as i32;
+        let month = index.rem_euclid(12) as u32 + 1;
+        let day = self.day.min(days_in_month(year, month));
+        Self { year, month, day }
+    }
+
+    /// Parse a date relative to `today`: `today`, `tomorrow`, `yesterday`,
+    /// an offset such as `+3d`, `-1w` or `+2m`, a weekday name for its next
+    /// occurrence (today included), or a `YYYY-MM-DD` date.
+    pub fn parse_expr(s: &str, today: Date) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        match s.as_str() {
+            "today" => return Some(today),
+            "tomorrow" => return Some(today.add_days(1)),
+            "yesterday" => return Some(today.add_days(-1)),
+            _ => {}
+        }
+        if let Some(weekday) = parse_weekday(&s) {
+            return Some(today.add_days((weekday as i64 - today.weekday() as i64).rem_euclid(7)));
+        }
+        if s.starts_with('+') || s.starts_with('-') {
+            let (n, unit) = s.split_at(s.len() - 1);
+            let (n, unit) = match n.parse::<i64>() {
+                Ok(n) => (n, unit),
+                Err(_) => (s.parse().ok()?, "d"),
+            };
+            return match unit {
+                "d" => Some(today.add_days(n)),
+                "w" => Some(today.add_days(n * 7)),
+                "m" => Some(today.add_months(n)),
+                _ => None,
+            };
+        }
+        Self::parse(&s)
+    }
+
+    /// Like `parse_expr`, but a weekday name means its last occurrence
+    /// (today included), as in `--since monday`.
+    pub fn parse_since(s: &str, today: Date) -> Option<Self> {
+        let d = Self::parse_expr(s, today)?;
+        if d > today && parse_weekday(&s.trim().to_lowercase()).is_some() {
+            Some(d.add_days(-7))
+        } else {
+            Some(d)
+        }
+    }
+
+    /// The Unix timestamp of local midnight at the start of this day.
+    pub fn start_timestamp(self) -> u64 {
+        let utc = self.to_days() * 86400;
+        (utc - utc_offset(utc.max(0) as u64)).max(0) as u64
+    }
+}
+
+/// A time of day as hours and minutes.
+pub type Time = (u32, u32);
+
+/// Parse a 24-hour `HH:MM` time.
+pub fn parse_time(s: &str) -> Option<Time> {
+    let (h, m) = s.trim().split_once(':')?;
+    let digits = |s: &str, len: std::ops::RangeInclusive<usize>| {
+        len.contains(&s.len()) && s.bytes().all(|b| b.is_ascii_digit())
+    };
+    if !digits(h, 1..=2) || !digits(m, 2..=2) {
+        return None;
+    }
+    let (h, m) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some((h, m))
+}
+
+/// The time of a stored `YYYY-MM-DD HH:MM` date.
+pub fn time_of(s: &str) -> Option<Time> {
+    let (day, time) = s.trim().split_once(' ')?;
+    Date::parse(day)?;
+    parse_time(time)
+}
+
+/// A stored date or date and time, with times normalized to `HH:MM`.
+pub fn format_due(day: Date, time: Option<Time>) -> String {
+    match time {
+        Some((h, m)) => format!("{} {:02}:{:02}", day, h, m),
+        None => day.to_string(),
+    }
+}
+
+/// Parse a date expression as `Date::parse_expr` does, optionally
+/// followed by a time, or a time alone for today: `tomorrow 9:30`,
+/// `fri 14:00`, `17:00`. Returns it as it is stored.
+pub fn parse_due(s: &str, today: Date) -> Option<String> {
+    let s = s.trim();
+    if let Some(time) = parse_time(s) {
+        return Some(format_due(today, Some(time)));
+    }
+    if let Some((day, time)) = s.rsplit_once(' ') {
+        if let Some(time) = parse_time(time) {
+            return Some(format_due(Date::parse_expr(day, today)?, Some(time)));
+        }
+    }
+    Date::parse_expr(s, today).map(|d| d.to_string())
+}
+
+/// Words that make the date expression after them one for sure, as in
+/// `by fri` or `due +3d`.
+const DATE_CUES: [&str; 6] = ["by", "on", "due", "until", "till", "before"];
+
+/// The first date mentioned in `text`, like `submit report by Friday`, as
+/// stored, and `text` without it and the word leading to it. Only words
+/// that can't mean anything else count on their own: `today`, `tomorrow`,
+/// weekday names and `YYYY-MM-DD` dates. Other expressions of
+/// `parse_expr`, like `fri` or `+3d`, count after one of `DATE_CUES`. A
+/// time can follow, as `at 14:00` or `14:00`. Past dates don't count.
+pub fn find_due(text: &str, today: Date) -> Option<(String, String)> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let bare = |w: &str| {
+        w.trim_end_matches(|c: char| ",.;:!?)".contains(c))
+            .to_lowercase()
+    };
+    for (i, word) in words.iter().enumerate() {
+        let word = bare(word);
+        let cued = i > 0 && DATE_CUES.contains(&bare(words[i - 1]).as_str());
+        let plain = matches!(word.as_str(), "today" | "tomorrow")
+            || WEEKDAYS.contains(&word.as_str())
+            || Date::parse(&word).is_some();
+        if !plain && !cued {
+            continue;
+        }
+        let Some(day) = Date::parse_expr(&word, today).filter(|d| *d >= today) else {
+            continue;
+        };
+        let mut end = i + 1;
+        let at = words.get(end).is_some_and(|w| bare(w) == "at");
+        let time = words
+            .get(end + at as usize)
+            .and_then(|w| parse_time(&bare(w)));
+        if time.is_some() {
+            end += 1 + at as usize;
+        }
+        let start = if cued { i - 1 } else { i };
+        let rest: Vec<&str> = words[..start]
+            .iter()
+            .chain(&words[end..])
+            .copied()
+            .collect();
+        return Some((format_due(day, time), rest.join(" ")));
+    }
+    None
+}
+
+/// When a stored date falls due: at its time, or at the end of its day.
+pub fn due_timestamp(s: &str) -> Option<u64> {
+    let day = Date::parse(s)?;
+    Some(match time_of(s) {
+        Some((h, m)) => day.start_timestamp() + (h as u64 * 60 + m as u64) * 60,
+        None => day.add_days(1).start_timestamp(),
+    })
+}
+
+/// Whether a stored date has passed, which for one with a time happens
+/// during its day.
+pub fn is_past(s: &str) -> bool {
+    due_timestamp(s).is_some_and(|at| at <= now())
+}
+
+/// A key that orders stored dates by day, and within a day those without
+/// a time first and the others by time.
+pub fn sort_key(s: &str) -> Option<(Date, Option<Time>)> {
+    Some((Date::parse(s)?, time_of(s)))
+}
+
+const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// 0 for Monday through 6 for Sunday; full names or three-letter forms.
+pub fn parse_weekday(s: &str) -> Option<u32> {
+    WEEKDAYS
+        .iter()
+        .position(|w| s == *w || (s.len() == 3 && w.starts_with(s)))
+        .map(|i| i as u32)
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// A stored `YYYY-MM-DD` date in the `display.date_format` of the config,
+/// where `YYYY`, `MM` and `DD` stand for the parts, followed by its time
+/// if it has one. Other text is shown as it is.
+pub fn display(s: &str) -> String {
+    let (Some(date), Some(format)) = (
+        Date::parse(s),
+        crate::config::get().string("display.date_format"),
+    ) else {
+        return s.to_string();
+    };
+    let day = format
+        .replace("YYYY", &format!("{:04}", date.year))
+        .replace("MM", &format!("{:02}", date.month))
+        .replace("DD", &format!("{:02}", date.day));
+    match time_of(s) {
+        Some((h, m)) => format!("{} {:02}:{:02}", day, h, m),
+        None => day,
+    }
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Seconds since the Unix epoch, as stored in timestamp fields.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn today() -> Date {
+    Date::from_timestamp(now())
+}
+
+/// A timestamp as local `YYYY-MM-DD HH:MM`.
+pub fn format_timestamp(ts: u64) -> String {
+    let minutes = local_seconds(ts).rem_euclid(86400) / 60;
+    format!(
+        "{} {:02}:{:02}",
+        Date::from_timestamp(ts),
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// A timestamp as an RFC 2822 date for mail headers, e.g.
+/// `Wed, 14 Oct 2026 09:30:00 +0900`.
+pub fn format_rfc2822(ts: u64) -> String {
+    const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let d = Date::from_timestamp(ts);
+    let secs = local_seconds(ts).rem_euclid(86400);
+    let offset = utc_offset(ts) / 60;
+    format!(
+        "{}, {} {} {} {:02}:{:02}:{:02} {}{:02}{:02}",
+        DAYS[d.weekday() as usize],
+        d.day,
+        MONTHS[d.month as usize - 1],
+        d.year,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// A timestamp as an RFC 3339 date for feeds, e.g.
+/// `2026-10-14T09:30:00+09:00`.
+pub fn format_rfc3339(ts: u64) -> String {
+    let secs = local_seconds(ts).rem_euclid(86400);
+    let offset = utc_offset(ts) / 60;
+    format!(
+        "{}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        Date::from_timestamp(ts),
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// A duration like `1h05m`, `25m` or `40s`.
+pub fn format_duration(secs: u64) -> String {
+    let minutes = secs / 60;
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if minutes >= 60 {
+        format!("{}h{:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+fn local_seconds(ts: u64) -> i64 {
+    ts as i64 + utc_offset(ts)
+}
+
+/// Offset of local time from UTC at `ts`, in seconds.
+fn utc_offset(ts: u64) -> i64 {
+    let t = ts as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers are valid for the duration of the call and
+    // localtime_r does not retain them.
+    let ok = unsafe { !libc::localtime_r(&t, &mut tm).is_null() };
+    if ok {
+        tm.tm_gmtoff as i64
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_due_dates_in_text() {
+        // A Wednesday.
+        let today = Date::new(2026, 10, 14).unwrap();
+        let found = |s: &str| find_due(s, today);
+        assert_eq!(
+            found("submit report by Friday"),
+            Some(("2026-10-16".to_string(), "submit report".to_string()))
+        );
+        assert_eq!(
+            found("call Kim tomorrow at 9:30, about rent"),
+            Some((
+                "2026-10-15 09:30".to_string(),
+                "call Kim about rent".to_string()
+            ))
+        );
+        assert_eq!(
+            found("renew passport due +2w"),
+            Some(("2026-10-28".to_string(), "renew passport".to_string()))
+        );
+        assert_eq!(found("sat exam prep"), None);
+        assert_eq!(found("notes from 2026-10-01"), None);
+        assert_eq!(found("fix off-by-one -1"), None);
+    }
+}
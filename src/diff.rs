@@ -0,0 +1,158 @@
+//! `todo diff`: what another list has that this one does not, such as
+//! the copy a sync or a colleague changed, before it is taken over; or,
+//! with `--since`, what changed in this list since a day, when the list
+//! is kept in a git repository. TODOs are told apart by their IDs and
+//! when they were added.
+
+use crate::date::{self, Date};
+use crate::{crypt, git, lists, workspace, Todo, Todos};
+use seahorse::{Command, Context, Flag, FlagType};
+use std::path::Path;
+use std::process::exit;
+
+pub fn diff_command() -> Command {
+    Command::new("diff")
+        .description("Show the TODOs another list added, removed or changed")
+        .usage("todo diff <workspace | file> | todo diff --since <date>")
+        .flag(
+            Flag::new("since", FlagType::String)
+                .description("Compare with the list as committed to git before a date"),
+        )
+        .action(diff_action)
+}
+
+fn diff_action(c: &Context) {
+    let todos = Todos::read();
+    let (old, new) = match (c.args.as_slice(), c.string_flag("since")) {
+        ([other], Err(_)) => (todos, other_list(other)),
+        ([], Ok(since)) => (committed(&since), todos),
+        _ => {
+            eprintln!("Please specify another list or --since <date>");
+            exit(1);
+        }
+    };
+    let changes = changes(&old, &new);
+    if changes.is_empty() {
+        println!("No differences");
+    }
+    for line in changes {
+        println!("{}", line);
+    }
+}
+
+fn other_list(other: &str) -> Todos {
+    let path = match workspace::lists()
+        .into_iter()
+        .find(|(name, _)| name == other)
+    {
+        Some((_, path)) => path,
+        None if Path::new(other).is_file() => other.to_string(),
+        None => {
+            eprintln!("No such workspace or file: {}", other);
+            exit(1);
+        }
+    };
+    match lists::read(&path) {
+        Ok(todos) => todos,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            exit(1);
+        }
+    }
+}
+
+/// The list as it was last committed before the start of `since`.
+fn committed(since: &str) -> Todos {
+    let Some(day) = Date::parse_expr(since, date::today()) else {
+        eprintln!("Invalid date: {}", since);
+        exit(1);
+    };
+    let path = Todos::todo_path();
+    let path = Path::new(&path);
+    let (dir, file) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(file)) => (dir.to_string_lossy(), file.to_string_lossy()),
+        _ => unreachable!(),
+    };
+    let git = |args: &[&str]| {
+        let mut all = vec!["-C", &dir];
+        all.extend(args);
+        git::run(&all)
+    };
+    if git(&["ls-files", "--error-unmatch", &file]).is_err() {
+        eprintln!(
+            "--since needs {} to be kept in a git repository",
+            path.display()
+        );
+        exit(1);
+    }
+    let before = format!("--before={} 00:00", day);
+    let commit = match git(&["log", "-1", "--format=%H", &before, "--", &file]) {
+        Ok(commit) if !commit.is_empty() => commit,
+        Ok(_) => {
+            eprintln!("{} was not committed before {}", path.display(), day);
+            exit(1);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    let content = git(&["show", &format!("{}:./{}", commit, file)]).and_then(|c| {
+        if crypt::is_encrypted(&c) {
+            crypt::decrypt(&c)
+        } else {
+            Ok(c)
+        }
+    });
+    let content = match content {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    Todos::parse(&content).0
+}
+
+/// `+` for each TODO only `new` has, `-` for each only `old` has, and
+/// `~` with the fields that differ for those in both.
+fn changes(old: &Todos, new: &Todos) -> Vec<String> {
+    let headers = Todos::default_headers();
+    let line = |mark: char, todo: &Todo| format!("{} #{} {}", mark, todo.id, todo.title);
+    let mut lines = vec![];
+    for todo in &old.records {
+        if !new.records.iter().any(|r| is_same(r, todo)) {
+            lines.push(line('-', todo));
+        }
+    }
+    for todo in &new.records {
+        let Some(before) = old.records.iter().find(|r| is_same(r, todo)) else {
+            lines.push(line('+', todo));
+            continue;
+        };
+        let (before, after) = (before.to_fields(), todo.to_fields());
+        if before == after {
+            continue;
+        }
+        lines.push(line('~', todo));
+        for ((name, old), new) in headers.iter().zip(&before).zip(&after) {
+            if old == new {
+                continue;
+            }
+            if !old.is_empty() {
+                lines.push(format!("    - {}: {}", name, old.replace('\n', ", ")));
+            }
+            if !new.is_empty() {
+                lines.push(format!("    + {}: {}", name, new.replace('\n', ", ")));
+            }
+        }
+    }
+    lines
+}
+
+/// Whether `a` and `b` are one TODO, and not one added under the ID of
+/// another that was deleted.
+fn is_same(a: &Todo, b: &Todo) -> bool {
+    a.id == b.id
+        && (a.created_at == b.created_at || a.created_at.is_empty() || b.created_at.is_empty())
+}
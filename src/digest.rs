@@ -0,0 +1,114 @@
+use crate::date::{self, Date};
+use crate::{config, mail, someday, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::process::exit;
+
+pub fn email_digest_command() -> Command {
+    Command::new("email-digest")
+        .description("Mail a digest of overdue TODOs and those due today and this week")
+        .usage("todo email-digest [--dry-run]")
+        .flag(
+            Flag::new("dry-run", FlagType::Bool)
+                .alias("n")
+                .description("Print the mail instead of sending it"),
+        )
+        .action(|c| {
+            let config = config::get();
+            let to = config.list("email.to");
+            let from = config
+                .string("email.from")
+                .map(|f| f.to_string())
+                .or_else(|| to.first().cloned());
+            let (from, to) = match from {
+                Some(from) if !to.is_empty() => (from, to),
+                _ => {
+                    eprintln!(
+                        "Please set email.to (and email.from) in {}",
+                        config::Config::path()
+                    );
+                    exit(1);
+                }
+            };
+
+            let todos = Todos::read();
+            let message = compose(&todos.records, date::today(), &from, &to);
+            if c.bool_flag("dry-run") {
+                print!("{}", message);
+                return;
+            }
+            if let Err(e) = mail::send(&message, &from, &to) {
+                eprintln!("{}", e);
+                exit(1);
+            }
+            println!("Sent the digest to {}", to.join(", "));
+        })
+}
+
+/// The digest mail, headers included.
+fn compose(todos: &[Todo], today: Date, from: &str, to: &[String]) -> String {
+    let week_end = today.add_days(6);
+    let mut sections: [(&str, Vec<(Date, &Todo)>); 3] = [
+        ("Overdue", vec![]),
+        ("Today", vec![]),
+        ("This week", vec![]),
+    ];
+    for todo in todos
+        .iter()
+        .filter(|t| t.done.is_empty() && !someday::is_someday(t))
+    {
+        let Some(due) = Date::parse(&todo.date) else {
+            continue;
+        };
+        let section = match due {
+            _ if date::is_past(&todo.date) => 0,
+            d if d == today => 1,
+            d if d <= week_end => 2,
+            _ => continue,
+        };
+        sections[section].1.push((due, todo));
+    }
+
+    let mut body = String::new();
+    for (title, todos) in sections.iter_mut() {
+        todos.sort_by_key(|(due, t)| {
+            (
+                *due,
+                date::time_of(&t.date),
+                t.id.parse::<usize>().unwrap_or(0),
+            )
+        });
+        body += &format!("{} ({})\n", title, todos.len());
+        for (due, todo) in todos.iter() {
+            body += &format!(
+                "  {}  #{} {}\n",
+                date::format_due(*due, date::time_of(&todo.date)),
+                todo.id,
+                todo.title
+            );
+        }
+        if todos.is_empty() {
+            body += "  Nothing\n";
+        }
+        body += "\n";
+    }
+
+    let subject = format!(
+        "TODO digest for {}: {} overdue, {} due today",
+        today,
+        sections[0].1.len(),
+        sections[1].1.len()
+    );
+    [
+        format!("From: {}", from),
+        format!("To: {}", to.join(", ")),
+        format!("Subject: {}", subject),
+        format!("Date: {}", date::format_rfc2822(date::now())),
+        "MIME-Version: 1.0".to_string(),
+        "Content-Type: text/plain; charset=utf-8".to_string(),
+        "Content-Transfer-Encoding: 8bit".to_string(),
+        "".to_string(),
+        body.trim_end().to_string(),
+        "".to_string(),
+    ]
+    .join("\n")
+}
@@ -0,0 +1,351 @@
+use crate::date::Date;
+use crate::goal::Goals;
+use crate::integrity::{self, Status};
+use crate::{columns, format, lock, migrations, term, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+pub fn doctor_command() -> Command {
+    Command::new("doctor")
+        .description("Check the TODO file for problems")
+        .usage("todo doctor [--fix] [--verify]")
+        .flag(
+            Flag::new("fix", FlagType::Bool)
+                .alias("f")
+                .description("Repair the problems that can be fixed safely"),
+        )
+        .flag(
+            Flag::new("verify", FlagType::Bool)
+                .description("Check the file against its checksum, offering to restore the backup"),
+        )
+        .action(|c| {
+            let path = Todos::todo_path();
+            if fs::metadata(&path).is_err() {
+                println!("No TODO file at {}", path);
+                return;
+            }
+            // Fixing and restoring rewrite the list, which no other
+            // command should change meanwhile.
+            if c.bool_flag("fix") || c.bool_flag("verify") {
+                lock::hold(&path);
+            }
+            if c.bool_flag("verify") {
+                verify(&path);
+            }
+            let content = match Todos::read_content() {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Failed to read {}: {}", path, e);
+                    exit(1);
+                }
+            };
+
+            let mut report = diagnose(&content, &Goals::read());
+            let leftovers = leftovers(&path);
+            for file in &leftovers {
+                report.problems.push(Problem {
+                    line: None,
+                    message: format!("{} is left over from an earlier run", file.display()),
+                    fixable: true,
+                });
+            }
+            for problem in &report.problems {
+                match problem.line {
+                    Some(line) => println!("line {}: {}", line, problem.message),
+                    None => println!("{}", problem.message),
+                }
+            }
+
+            if report.problems.is_empty() {
+                println!("No problems found");
+                return;
+            }
+
+            let fixable = report.problems.iter().filter(|p| p.fixable).count();
+            let remaining = report.problems.len() - fixable;
+            if !c.bool_flag("fix") {
+                println!(
+                    "{} problem(s) found, {} can be fixed with --fix",
+                    report.problems.len(),
+                    fixable
+                );
+                exit(1);
+            }
+
+            let mut todos = Todos {
+                headers: report.headers,
+                records: report.records,
+                skipped: vec![],
+            };
+            if let Err(e) = todos.save() {
+                eprintln!("Failed to save: {}", e);
+                exit(1);
+            }
+            for file in &leftovers {
+                if let Err(e) = fs::remove_file(file) {
+                    eprintln!("Failed to remove {}: {}", file.display(), e);
+                    exit(1);
+                }
+            }
+            println!("Fixed {} problem(s), {} remaining", fixable, remaining);
+            if remaining > 0 {
+                exit(1);
+            }
+        })
+}
+
+/// Say whether the file at `path` is as it was last saved, and offer to
+/// put the backup back when it is not.
+fn verify(path: &str) {
+    match integrity::status(path) {
+        Status::Intact => println!("{} matches its checksum", path),
+        Status::Unknown => println!(
+            "{} has no checksum yet; one is recorded when it is next saved",
+            path
+        ),
+        Status::Damaged => {
+            println!(
+                "{} does not match its checksum: it was not fully written, or was damaged or changed outside todo",
+                path
+            );
+            let backup = integrity::backup_path(path);
+            if fs::metadata(&backup).is_err() {
+                println!("There is no backup to restore");
+                exit(1);
+            }
+            let question = format!("Restore the backup {}? [y/N] ", backup);
+            if term::read_key(&question) != Some('y') {
+                exit(1);
+            }
+            if let Err(e) = integrity::restore(path) {
+                eprintln!("Failed to restore {}: {}", backup, e);
+                exit(1);
+            }
+            println!("Restored {} from {}", path, backup);
+        }
+    }
+}
+
+struct Problem {
+    /// The line of the list, none for problems outside it.
+    line: Option<usize>,
+    message: String,
+    fixable: bool,
+}
+
+/// Result of checking the raw file, with the records as they would be
+/// written back by `--fix`.
+struct Report {
+    headers: Vec<String>,
+    records: Vec<Todo>,
+    problems: Vec<Problem>,
+}
+
+/// Check `content`, with the goals TODOs may link to.
+fn diagnose(content: &str, goals: &Goals) -> Report {
+    let mut table = format::parse(content);
+    let mut problems = vec![];
+
+    if table.headers.is_empty() {
+        table.headers = Todos::default_headers();
+    } else if table.version < migrations::CURRENT_VERSION {
+        problems.push(Problem {
+            line: Some(1),
+            message: format!(
+                "format version {} is outdated (current is {})",
+                table.version,
+                migrations::CURRENT_VERSION
+            ),
+            fixable: true,
+        });
+        migrations::migrate(&mut table);
+    }
+
+    let fields = table.headers.len();
+    let columns = columns::map(&table.headers);
+    let mut records: Vec<Todo> = vec![];
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut duplicates = vec![];
+
+    for row in &table.rows {
+        if row.is_empty() {
+            problems.push(Problem {
+                line: Some(row.line),
+                message: "empty row".to_string(),
+                fixable: true,
+            });
+            continue;
+        }
+
+        if row.fields.len() != fields {
+            problems.push(Problem {
+                line: Some(row.line),
+                message: format!("expected {} fields, found {}", fields, row.fields.len()),
+                fixable: true,
+            });
+        }
+        let mut todo = Todo::from_row(&columns, &row.fields);
+        // Fields past the headers are kept, in columns of their own.
+        for (i, value) in row.fields.iter().enumerate().skip(fields) {
+            if !value.is_empty() {
+                let header = format!("extra_{}", i - fields + 1);
+                todo.other_columns.push((header, value.clone()));
+            }
+        }
+
+        if todo.id.parse::<usize>().is_err() {
+            problems.push(Problem {
+                line: Some(row.line),
+                message: format!("invalid ID '{}'", todo.id),
+                fixable: true,
+            });
+            duplicates.push(records.len());
+        } else if let Some(first) = seen.get(&todo.id) {
+            problems.push(Problem {
+                line: Some(row.line),
+                message: format!("duplicate ID {} (first seen on line {})", todo.id, first),
+                fixable: true,
+            });
+            duplicates.push(records.len());
+        } else {
+            seen.insert(todo.id.clone(), row.line);
+        }
+
+        if !todo.date.is_empty() && Date::parse(&todo.date).is_none() {
+            problems.push(Problem {
+                line: Some(row.line),
+                message: format!("invalid date '{}'", todo.date),
+                fixable: false,
+            });
+        }
+
+        if !todo.goal.is_empty() && goals.find(&todo.goal).is_err() {
+            problems.push(Problem {
+                line: Some(row.line),
+                message: format!("linked to goal {}, which does not exist", todo.goal),
+                fixable: true,
+            });
+            todo.goal.clear();
+        }
+        for attachment in &todo.attachments {
+            if !Path::new(attachment).exists() {
+                problems.push(Problem {
+                    line: Some(row.line),
+                    message: format!("attachment {} does not exist", attachment),
+                    fixable: false,
+                });
+            }
+        }
+
+        records.push(todo);
+    }
+
+    // Give rows with a duplicate or unusable ID a fresh one after the
+    // highest existing ID, so `add` keeps producing unique IDs.
+    let mut next_id = seen
+        .keys()
+        .filter_map(|id| id.parse().ok())
+        .max()
+        .unwrap_or(0);
+    for index in duplicates {
+        next_id += 1;
+        records[index].id = next_id.to_string();
+    }
+
+    Report {
+        headers: table.headers,
+        records,
+        problems,
+    }
+}
+
+/// Files next to the list at `path` that a process left when it was
+/// stopped halfway through writing: the temporary files of writes whose
+/// process is gone, and the socket of a daemon that no longer runs.
+/// `<list>.lock` stays by design, so it is not one of them.
+fn leftovers(path: &str) -> Vec<PathBuf> {
+    let path = Path::new(path);
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return vec![];
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let name = name.to_string_lossy();
+    let mut files = vec![];
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let file = entry.file_name().to_string_lossy().into_owned();
+        let Some(rest) = file.strip_prefix(&format!("{}.", name)) else {
+            continue;
+        };
+        let stale = match rest.split_once('-') {
+            // Of the list, or of a file next to it, like `crdt.save-<pid>`.
+            Some((kind, pid)) if crate::TEMPORARY.contains(&kind.rsplit('.').next().unwrap()) => {
+                pid.parse::<i32>().is_ok_and(|pid| !is_running(pid))
+            }
+            _ => rest == "sock" && !is_listening(&entry.path()),
+        };
+        if stale {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+    files
+}
+
+#[cfg(unix)]
+fn is_running(pid: i32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists.
+    let exists = unsafe { libc::kill(pid, 0) == 0 };
+    exists || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_running(_: i32) -> bool {
+    true
+}
+
+#[cfg(unix)]
+fn is_listening(socket: &Path) -> bool {
+    std::os::unix::net::UnixStream::connect(socket).is_ok()
+}
+
+#[cfg(not(unix))]
+fn is_listening(_: &Path) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_temporaries_of_every_writer() {
+        let dir = std::env::temp_dir().join(format!("todo-doctor-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let list = dir.join("list");
+        let own = std::process::id();
+        for file in [
+            "list.remote-999999999".to_string(),
+            "list.crdt.save-999999999".to_string(),
+            format!("list.save-{}", own),
+            "list.lock".to_string(),
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        let found = leftovers(&list.to_string_lossy());
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            found,
+            vec![
+                dir.join("list.crdt.save-999999999"),
+                dir.join("list.remote-999999999")
+            ]
+        );
+    }
+}
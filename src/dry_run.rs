@@ -0,0 +1,26 @@
+//! `--dry-run`, accepted by every command: it runs as usual but writes
+//! nothing, and the list says what would have changed when it would have
+//! been saved. Sync only reads from the service and says what it would
+//! have sent.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Take `--dry-run` out of `args`.
+pub fn select(args: &mut Vec<String>) {
+    let end = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    let before = args.len();
+    let mut i = 0;
+    args.retain(|a| {
+        i += 1;
+        i > end || a != "--dry-run"
+    });
+    if args.len() < before {
+        ENABLED.store(true, Ordering::SeqCst);
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,187 @@
+//! Escalating TODOs that stay open, so that old ones aren't quietly
+//! forgotten at the bottom of the list:
+//!
+//! ```toml
+//! [escalate.old]
+//! after = "14d"          # open this long since added, in days or weeks
+//! bump = "priority"      # one value up, for a field declared with values
+//!
+//! [escalate.stale]
+//! after = "30d"
+//! tag = "stale"
+//! top = true             # first in todo list, with a warning
+//! ```
+//!
+//! Escalation is worked out whenever `todo list` reads the list and is
+//! never saved, so it follows the TODO's age and goes away with the rule.
+//! Someday TODOs and those still to start don't age, and a TODO opts out
+//! with `todo set <id> escalate off`.
+
+use crate::config::{self, Config};
+use crate::{date, fields, lists, someday, Todo};
+use anyhow::bail;
+use std::sync::OnceLock;
+
+/// The field a TODO opts out with.
+pub const FIELD: &str = "escalate";
+
+static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+
+#[derive(Debug)]
+pub struct Rule {
+    days: i64,
+    bump: Option<String>,
+    tag: Option<String>,
+    top: bool,
+}
+
+impl Rule {
+    fn load(config: &Config, name: &str) -> anyhow::Result<Self> {
+        let key = |k: &str| format!("escalate.{}.{}", name, k);
+        let Some(after) = config.string(&key("after")) else {
+            bail!("after is missing");
+        };
+        let Some(days) = parse_days(after) else {
+            bail!(
+                "invalid age {}, expected days like 14d or weeks like 2w",
+                after
+            );
+        };
+        let bump = config.string(&key("bump")).map(str::to_string);
+        if let Some(field) = &bump {
+            if !fields::find_declared(field)
+                .is_some_and(|d| matches!(d.kind, fields::Kind::Enum(_)))
+            {
+                bail!("{} should be a field declared with values", field);
+            }
+        }
+        let tag = config
+            .string(&key("tag"))
+            .map(crate::tag_name)
+            .filter(|t| !t.is_empty());
+        let top = config.string(&key("top")) == Some("true");
+        if bump.is_none() && tag.is_none() && !top {
+            bail!("nothing to do, expected bump, tag or top");
+        }
+        Ok(Rule {
+            days,
+            bump,
+            tag,
+            top,
+        })
+    }
+}
+
+/// `14`, `14d` or `2w` as days.
+fn parse_days(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (n, unit) = match s.strip_suffix('w') {
+        Some(n) => (n, 7),
+        None => (s.strip_suffix('d').unwrap_or(s), 1),
+    };
+    n.parse::<i64>().ok().filter(|&n| n > 0).map(|n| n * unit)
+}
+
+/// The rules of `config`, youngest first. Broken ones are reported and
+/// left out.
+pub fn load(config: &Config) -> Vec<Rule> {
+    let mut rules: Vec<Rule> = config
+        .tables("escalate")
+        .into_iter()
+        .filter_map(|name| match Rule::load(config, name) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                eprintln!("Ignoring escalation {}: {}", name, e);
+                None
+            }
+        })
+        .collect();
+    rules.sort_by_key(|r| r.days);
+    rules
+}
+
+/// Days since `todo` was added, for TODOs that age.
+fn age(todo: &Todo, today: date::Date) -> Option<i64> {
+    if !todo.done.is_empty()
+        || someday::is_someday(todo)
+        || fields::get(todo, FIELD) == Some("off")
+        || date::Date::parse(&todo.start).is_some_and(|d| d > today)
+    {
+        return None;
+    }
+    let created = todo.created_at.parse::<u64>().ok()?;
+    Some(today.to_days() - date::Date::from_timestamp(created).to_days())
+}
+
+/// Escalate `records` by the rules that their age has reached. Returns
+/// the TODOs that rules with `top` put first, for `surface`.
+pub fn apply(records: &mut [Todo]) -> Vec<(String, String)> {
+    let rules = RULES.get_or_init(|| load(config::get()));
+    let today = date::today();
+    let mut top = vec![];
+    for todo in records.iter_mut() {
+        let Some(age) = age(todo, today) else {
+            continue;
+        };
+        let mut first = false;
+        for rule in rules.iter().filter(|r| age >= r.days) {
+            if let Some(field) = &rule.bump {
+                bump(todo, field);
+            }
+            if let Some(tag) = &rule.tag {
+                if !todo.tags.contains(tag) {
+                    todo.tags.push(tag.clone());
+                }
+            }
+            first |= rule.top;
+        }
+        if first {
+            top.push(key(todo));
+        }
+    }
+    top
+}
+
+/// A TODO among those of all lists.
+fn key(todo: &Todo) -> (String, String) {
+    let list = fields::get(todo, lists::FIELD).unwrap_or("");
+    (list.to_string(), todo.id.clone())
+}
+
+/// Move the TODOs `apply` returned first, keeping their order, with a
+/// warning when there are any.
+pub fn surface(records: &mut [Todo], top: &[(String, String)]) {
+    if top.is_empty() {
+        return;
+    }
+    records.sort_by_key(|t| !top.contains(&key(t)));
+    let count = records.iter().filter(|t| top.contains(&key(t))).count();
+    if count == 0 {
+        return;
+    }
+    let rules = RULES.get_or_init(|| load(config::get()));
+    let days = rules.iter().filter(|r| r.top).map(|r| r.days).min();
+    eprintln!(
+        "Warning: {} TODO{} open for {} days or more, listed first",
+        count,
+        if count == 1 { "" } else { "s" },
+        days.unwrap_or(0)
+    );
+}
+
+/// Move `field` of `todo` one value up its declared values, from none
+/// to the first.
+fn bump(todo: &mut Todo, field: &str) {
+    let Some(declared) = fields::find_declared(field) else {
+        return;
+    };
+    let fields::Kind::Enum(values) = &declared.kind else {
+        return;
+    };
+    let next = match fields::get(todo, field).and_then(|v| declared.rank(v)) {
+        Some(rank) => (rank + 1).min(values.len() - 1),
+        None => 0,
+    };
+    let value = values[next].clone();
+    fields::set(todo, field, &value);
+}
@@ -0,0 +1,166 @@
+//! `todo export`: the list for other programs, as the JSON of `--json`
+//! or as an Atom feed of what was recently added and completed, which a
+//! feed reader can follow once it is published somewhere, e.g. by cron:
+//!
+//! ```sh
+//! todo export --format atom > /var/www/html/family.xml
+//! ```
+//!
+//! `--format print` makes a sheet to print instead, see `print`.
+
+use crate::history::{self, Event};
+use crate::json::Value;
+use crate::{date, lists, print, query, workspace, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::process::exit;
+
+/// Entries in a feed without `--limit`.
+const DEFAULT_LIMIT: isize = 50;
+
+pub fn export_command() -> Command {
+    let command = Command::new("export")
+        .description("Print the list as JSON, as an Atom feed of recent changes or to print")
+        .usage(
+            "todo export [--format json|atom|print] [--limit <n>] [--where <query>] [--all-lists]",
+        )
+        .flag(Flag::new("format", FlagType::String).description("json (default), atom or print"))
+        .flag(
+            Flag::new("limit", FlagType::Int)
+                .alias("n")
+                .description(format!(
+                    "Number of feed entries (default: {})",
+                    DEFAULT_LIMIT
+                )),
+        )
+        .flag(
+            Flag::new("where", FlagType::String)
+                .alias("w")
+                .description("Only export TODOs matching a query, e.g. tag:handover"),
+        )
+        .flag(
+            Flag::new("all-lists", FlagType::Bool)
+                .description("Export the TODOs of all workspaces, each with its list"),
+        );
+    #[cfg(feature = "pdf")]
+    let command = command
+        .flag(Flag::new("pdf", FlagType::Bool).description("With --format print, print a PDF"));
+    command.action(|c| {
+        let all_lists = c.bool_flag("all-lists");
+        let mut todos = if all_lists {
+            lists::read_all()
+        } else {
+            Todos::read()
+        };
+        if let Ok(filter) = c.string_flag("where") {
+            match query::Query::parse(&filter) {
+                Ok(query) => todos.records.retain(|r| query.matches(r)),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            }
+        }
+        match c.string_flag("format").as_deref() {
+            Ok("json") | Err(_) => println!(
+                "{}",
+                Value::Array(todos.records.iter().map(Todo::to_json).collect())
+            ),
+            Ok("atom") => {
+                let limit = c.int_flag("limit").unwrap_or(DEFAULT_LIMIT).max(0) as usize;
+                print!("{}", atom(&todos, limit));
+            }
+            Ok("print") => {
+                let list = workspace::current().unwrap_or("default");
+                #[cfg(feature = "pdf")]
+                if c.bool_flag("pdf") {
+                    use std::io::Write;
+                    let pdf = print::pdf(&todos, all_lists, list);
+                    if let Err(e) = std::io::stdout().write_all(&pdf) {
+                        eprintln!("Failed to write the PDF: {}", e);
+                        exit(1);
+                    }
+                    return;
+                }
+                print!("{}", print::text(&todos, all_lists, list));
+            }
+            Ok(format) => {
+                eprintln!("Unknown format {}, expected json, atom or print", format);
+                exit(1);
+            }
+        }
+    })
+}
+
+/// A feed with an entry for each of the `limit` latest additions and
+/// completions, newest first.
+fn atom(todos: &Todos, limit: usize) -> String {
+    let path = Todos::todo_path();
+    let mut events: Vec<Event> = todos.records.iter().flat_map(history::events).collect();
+    events.sort_by_key(|e| std::cmp::Reverse(e.at));
+    events.truncate(limit);
+    let updated = events.first().map_or_else(date::now, |e| e.at);
+
+    let mut feed = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    feed += "<feed xmlns=\"http://www.w3.org/2005/Atom\">\n";
+    feed += &format!("  <title>TODOs in {}</title>\n", escape(&path));
+    feed += &format!("  <id>file://{}</id>\n", escape(&path));
+    feed += &format!("  <updated>{}</updated>\n", date::format_rfc3339(updated));
+    feed += "  <author><name>todo</name></author>\n";
+    for e in &events {
+        feed += "  <entry>\n";
+        feed += &format!(
+            "    <title>{} #{}: {}</title>\n",
+            match e.field {
+                Some(field) => format!("Changed {} of", field),
+                None if e.action == "added" => "Added".to_string(),
+                None => "Completed".to_string(),
+            },
+            e.todo.id,
+            escape(&e.todo.title)
+        );
+        feed += &format!(
+            "    <id>file://{}#{}-{}-{}</id>\n",
+            escape(&path),
+            escape(&e.todo.id),
+            e.label().replace(' ', "-"),
+            e.at
+        );
+        feed += &format!("    <updated>{}</updated>\n", date::format_rfc3339(e.at));
+        if !e.user.is_empty() {
+            feed += &format!("    <author><name>{}</name></author>\n", escape(e.user));
+        }
+        for url in &e.todo.urls {
+            feed += &format!("    <link href=\"{}\"/>\n", escape(url));
+        }
+        let summary = summary(e.todo);
+        if !summary.is_empty() {
+            feed += &format!("    <summary>{}</summary>\n", escape(&summary));
+        }
+        feed += "  </entry>\n";
+    }
+    feed += "</feed>\n";
+    feed
+}
+
+/// The date, tags and notes of `todo`, one per line.
+fn summary(todo: &Todo) -> String {
+    let mut lines = vec![];
+    if !todo.date.is_empty() {
+        lines.push(format!("Due {}", todo.date));
+    }
+    if !todo.tags.is_empty() {
+        let tags: Vec<String> = todo.tags.iter().map(|t| format!("#{}", t)).collect();
+        lines.push(tags.join(" "));
+    }
+    if !todo.notes.is_empty() {
+        lines.push(todo.notes.clone());
+    }
+    lines.join("\n")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,365 @@
+//! Custom fields: `key=value` metadata the user sets on a TODO, like
+//! `sprint=42`, kept in `Todo::fields`.
+//!
+//! Fields can also be declared in the config, which gives them `add` and
+//! `edit` flags, a list column and checked values:
+//!
+//! ```toml
+//! [fields]
+//! sprint = "string"
+//! review = "date"                         # stored as YYYY-MM-DD
+//! priority = ["low", "medium", "high"]    # one of these, in this order
+//! ```
+
+use crate::date::{self, Date};
+use crate::{config, query, Todo, Todos};
+use anyhow::bail;
+use seahorse::{Command, Context, Flag, FlagType};
+use std::process::exit;
+use std::sync::OnceLock;
+
+pub enum Kind {
+    String,
+    Date,
+    /// One of the values, which sort in this order.
+    Enum(Vec<String>),
+}
+
+/// A field declared in the `[fields]` section of the config.
+pub struct Declared {
+    pub name: String,
+    pub kind: Kind,
+}
+
+impl Declared {
+    /// `value` as stored, or why it does not fit.
+    pub fn check(&self, value: &str) -> Result<String, String> {
+        match &self.kind {
+            Kind::String => Ok(value.to_string()),
+            Kind::Date => match Date::parse_expr(value, date::today()) {
+                Some(d) => Ok(d.to_string()),
+                None => Err(format!("Invalid date: {}", value)),
+            },
+            Kind::Enum(values) => match values.iter().find(|v| v.eq_ignore_ascii_case(value)) {
+                Some(v) => Ok(v.clone()),
+                None => Err(format!(
+                    "{} must be one of {}",
+                    self.name,
+                    values.join(", ")
+                )),
+            },
+        }
+    }
+
+    /// Position of `value` among the allowed ones of an enum field.
+    pub fn rank(&self, value: &str) -> Option<usize> {
+        match &self.kind {
+            Kind::Enum(values) => values.iter().position(|v| v.eq_ignore_ascii_case(value)),
+            _ => None,
+        }
+    }
+}
+
+static DECLARED: OnceLock<Vec<Declared>> = OnceLock::new();
+
+/// The fields declared in the config, by name.
+pub fn declared() -> &'static [Declared] {
+    DECLARED.get_or_init(|| {
+        let config = config::get();
+        let mut fields = vec![];
+        for name in config.keys("fields") {
+            let key = format!("fields.{}", name);
+            let kind = match config.string(&key) {
+                Some("string") => Kind::String,
+                Some("date") => Kind::Date,
+                Some(other) => {
+                    eprintln!(
+                        "{}: {}: unknown type {}",
+                        config::Config::path(),
+                        key,
+                        other
+                    );
+                    continue;
+                }
+                None => Kind::Enum(config.list(&key)),
+            };
+            if !is_valid_key(name) {
+                eprintln!("{}: {}: invalid field name", config::Config::path(), key);
+                continue;
+            }
+            fields.push(Declared {
+                name: name.to_string(),
+                kind,
+            });
+        }
+        fields
+    })
+}
+
+pub fn find_declared(name: &str) -> Option<&'static Declared> {
+    declared().iter().find(|f| f.name == name)
+}
+
+/// `command` with a flag for each declared field it has no flag for yet.
+pub fn with_flags(mut command: Command) -> Command {
+    for field in declared() {
+        let taken = command.flags.iter().flatten().any(|f| f.name == field.name);
+        if !taken {
+            let description = match &field.kind {
+                Kind::String => format!("Set {}", field.name),
+                Kind::Date => format!("Set {} (a date)", field.name),
+                Kind::Enum(values) => format!("Set {}: {}", field.name, values.join(", ")),
+            };
+            command =
+                command.flag(Flag::new(&field.name, FlagType::String).description(description));
+        }
+    }
+    command
+}
+
+/// Set the declared fields given as flags, exiting on invalid values.
+pub fn apply_flags(c: &Context, todo: &mut Todo) {
+    for field in declared() {
+        let Ok(value) = c.string_flag(&field.name) else {
+            continue;
+        };
+        if value.is_empty() {
+            set(todo, &field.name, "");
+            continue;
+        }
+        match field.check(&value) {
+            Ok(value) => set(todo, &field.name, &value),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+    }
+}
+
+pub fn set_command() -> Command {
+    Command::new("set")
+        .description("Set a field or custom fields of a TODO, an empty value removes one")
+        .usage("todo set <todo id> <field> <value> | todo set <todo id> <key>=<value>...")
+        .action(|c| {
+            if let [id, field, value] = c.args.as_slice() {
+                if !field.contains('=') {
+                    return set_one(id, field, value);
+                }
+            }
+            let (id, pairs) = match c.args.split_first() {
+                Some((id, pairs)) if !pairs.is_empty() => (id, pairs),
+                _ => {
+                    eprintln!("Please specify an ID and key=value pairs");
+                    exit(1);
+                }
+            };
+            let pairs: Vec<(String, String)> = pairs
+                .iter()
+                .map(|p| match p.split_once('=') {
+                    Some((k, _)) if query::is_builtin(k) => {
+                        eprintln!("{} is a built-in field, please use `todo edit`", k);
+                        exit(1);
+                    }
+                    _ => match parse_pair(p) {
+                        Ok(pair) => pair,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            exit(1);
+                        }
+                    },
+                })
+                .collect();
+
+            let mut todos = Todos::read();
+            let todo = match todos.find_mut(id) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
+            for (key, value) in pairs {
+                set(todo, &key, &value);
+            }
+            println!("Updated #{}: {}", todo.id, todo.title);
+            todos.save().unwrap();
+        })
+}
+
+pub fn get_command() -> Command {
+    Command::new("get")
+        .description("Print one field of a TODO as stored, for scripts")
+        .usage("todo get <todo id> <field>")
+        .action(|c| {
+            let [id, field] = c.args.as_slice() else {
+                eprintln!("Please specify an ID and a field");
+                exit(1);
+            };
+            let todos = Todos::read();
+            let Some(todo) = todos.records.iter().find(|r| r.id == *id) else {
+                eprintln!("The specified ID does not exist");
+                exit(1);
+            };
+            // Like `git config`, nothing to print is a failure, so that
+            // scripts can tell.
+            match raw(todo, field) {
+                Some(value) if !value.is_empty() => println!("{}", value),
+                _ => exit(1),
+            }
+        })
+}
+
+/// The value of any field of `todo` as it is stored, with values of list
+/// fields like `url` on lines of their own: a column of the file, `due`
+/// for `date`, `tag` for `tags`, or a custom field.
+pub fn raw(todo: &Todo, field: &str) -> Option<String> {
+    let field = match field {
+        "due" => "date",
+        "tag" => "tags",
+        "urls" => "url",
+        field => field,
+    };
+    if let Some(i) = Todos::default_headers().iter().position(|h| h == field) {
+        return todo.to_fields().into_iter().nth(i);
+    }
+    let other = todo.other_columns.iter().find(|(h, _)| h == field);
+    get(todo, field)
+        .or(other.map(|(_, v)| v.as_str()))
+        .map(|v| v.to_string())
+}
+
+/// `todo set <id> <field> <value>`: one field, built-in or custom, with
+/// the value checked for the field and the change kept for `todo history`.
+fn set_one(id: &str, field: &str, value: &str) {
+    let (field, value) = match check(field, value) {
+        Ok(checked) => checked,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    let mut todos = Todos::read();
+    let todo = match todos.find_mut(id) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    let before = raw(todo, &field);
+    assign(todo, &field, value);
+    if raw(todo, &field) == before {
+        println!("Nothing to change for #{}: {}", todo.id, todo.title);
+        return;
+    }
+    let change = format!("{} {} {}", date::now(), field, crate::current_user());
+    todo.changes.push(change.trim_end().to_string());
+    println!("Set {} of #{}: {}", field, todo.id, todo.title);
+    todos.save().unwrap();
+}
+
+/// Set `field` of `todo` to `value`, both as returned by `check`.
+pub fn assign(todo: &mut Todo, field: &str, value: String) {
+    match field {
+        "date" => todo.date = value,
+        "title" => todo.title = value,
+        "start" => todo.start = value,
+        "assignee" => todo.assignee = value,
+        "repeat" => todo.repeat = value,
+        "estimate" => todo.estimate = value,
+        "url" => todo.urls = Some(value).filter(|v| !v.is_empty()).into_iter().collect(),
+        key => set(todo, key, &value),
+    }
+}
+
+/// `value` as stored for `field`, which `todo set` takes both built-in
+/// and custom ones for, or why it does not fit. An empty value clears
+/// the field.
+pub fn check(field: &str, value: &str) -> Result<(String, String), String> {
+    let field = match field {
+        "due" => "date",
+        "urls" => "url",
+        field => field,
+    };
+    let builtin = query::is_builtin(field) || Todos::default_headers().iter().any(|h| h == field);
+    let today = date::today();
+    let checked = match field {
+        "tag" | "tags" => return Err("Please use todo tag to change tags".to_string()),
+        crate::id_alias::FIELD => return Err("Please use todo alias-id to name a TODO".to_string()),
+        crate::focus::FIELD => {
+            return Err("Please use todo focus add and remove for the focus list".to_string())
+        }
+        "title" if value.trim().is_empty() => return Err("The title can't be empty".to_string()),
+        "date" | "start" | "assignee" | "repeat" | "estimate" | "url" if value.is_empty() => {
+            String::new()
+        }
+        "date" => date::parse_due(value, today).ok_or(format!("Invalid date: {}", value))?,
+        "start" => match Date::parse_expr(value, today) {
+            Some(start) => start.to_string(),
+            None => return Err(format!("Invalid date: {}", value)),
+        },
+        "repeat" if crate::repeat::Interval::parse(value).is_none() => {
+            return Err(format!("Invalid interval: {}", value))
+        }
+        "estimate" if value.parse::<u64>().is_err() => {
+            return Err(format!("Invalid estimate, expected minutes: {}", value))
+        }
+        "url" if !value.contains("://") => return Err(format!("Invalid URL: {}", value)),
+        "title" | "assignee" | "repeat" | "estimate" | "url" => value.to_string(),
+        field if builtin => return Err(format!("{} can't be set", field)),
+        field => return parse_pair(&format!("{}={}", field, value)).map_err(|e| e.to_string()),
+    };
+    Ok((field.to_string(), checked))
+}
+
+/// A custom field as `key=value`, with the value checked when the field
+/// is declared.
+pub fn parse_pair(pair: &str) -> anyhow::Result<(String, String)> {
+    match pair.split_once('=') {
+        Some((k, v)) if is_valid_key(k) => match find_declared(k) {
+            Some(field) if !v.is_empty() => match field.check(v) {
+                Ok(v) => Ok((k.to_string(), v)),
+                Err(e) => bail!(e),
+            },
+            _ => Ok((k.to_string(), v.to_string())),
+        },
+        _ => bail!("Invalid field: {}", pair),
+    }
+}
+
+/// Letters, digits, `-` and `_`, and not the name of a built-in field.
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        && !query::is_builtin(key)
+}
+
+/// The custom fields of `todo` as `(key, value)`, in the order they were
+/// first set.
+pub fn all(todo: &Todo) -> impl Iterator<Item = (&str, &str)> {
+    todo.fields.iter().filter_map(|f| f.split_once('='))
+}
+
+pub fn get<'a>(todo: &'a Todo, key: &str) -> Option<&'a str> {
+    all(todo).find(|(k, _)| *k == key).map(|(_, v)| v)
+}
+
+/// Set `key` to `value`, or remove it when `value` is empty.
+pub fn set(todo: &mut Todo, key: &str, value: &str) {
+    let position = todo
+        .fields
+        .iter()
+        .position(|f| f.split_once('=').is_some_and(|(k, _)| k == key));
+    let entry = format!("{}={}", key, value);
+    match (position, value.is_empty()) {
+        (Some(i), true) => {
+            todo.fields.remove(i);
+        }
+        (Some(i), false) => todo.fields[i] = entry,
+        (None, true) => {}
+        (None, false) => todo.fields.push(entry),
+    }
+}
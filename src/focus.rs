@@ -0,0 +1,291 @@
+//! Focus sessions on a TODO, and the focus list: the few TODOs being
+//! worked on now, at most `focus.limit` of them (3 by default), so that
+//! starting another means finishing or dropping one first.
+//!
+//! ```toml
+//! [focus]
+//! limit = 2
+//! ```
+
+use crate::{checklist, config, date, fields, notification, prompt, Todo, Todos};
+use seahorse::{Command, Context, Flag, FlagType};
+use std::io::Write;
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// When a TODO was put on the focus list, as a Unix timestamp.
+pub const FIELD: &str = "focused";
+/// TODOs on the focus list without `focus.limit`.
+const LIMIT: usize = 3;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+pub fn focus_command() -> Command {
+    Command::new("focus")
+        .description("Work on a TODO for a timed session and log the time")
+        .usage(
+            "todo focus [<todo id> [--minutes 25] [--break 5] | add <todo id> | remove <todo id>]",
+        )
+        .flag(
+            Flag::new("minutes", FlagType::Int)
+                .alias("m")
+                .description("Length of the session (default: 25)"),
+        )
+        .flag(
+            Flag::new("break", FlagType::Int)
+                .alias("b")
+                .description("Length of the break afterwards (default: 5)"),
+        )
+        .command(
+            Command::new("add")
+                .description("Put a TODO on the focus list, if there is room")
+                .usage("todo focus add <todo id>")
+                .action(add_action),
+        )
+        .command(
+            Command::new("remove")
+                .description("Take a TODO off the focus list")
+                .usage("todo focus remove <todo id>")
+                .alias("rm")
+                .action(remove_action),
+        )
+        .action(|c| {
+            let id = if c.args.len() == 1 {
+                &c.args[0]
+            } else {
+                eprintln!("Please specify one ID");
+                exit(1);
+            };
+            let minutes = c.int_flag("minutes").unwrap_or(25).max(1) as u64;
+            let break_minutes = c.int_flag("break").unwrap_or(5).max(1) as u64;
+
+            let title = match Todos::read().records.iter().find(|r| &r.id == id) {
+                Some(t) => t.title.clone(),
+                None => {
+                    eprintln!("The specified ID does not exist");
+                    exit(1);
+                }
+            };
+
+            println!(
+                "Focusing on #{} for {} minutes: {} (Ctrl-C to stop early)",
+                id, minutes, title
+            );
+            let start = date::now();
+            let elapsed = countdown(minutes);
+
+            // Read again, the list may have changed during the session.
+            let mut todos = Todos::read();
+            let todo = match todos.find_mut(id) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
+            todo.time_log.push(format!("{} {}", start, elapsed));
+            println!(
+                "Logged {} on #{} ({} in total)",
+                date::format_duration(elapsed),
+                todo.id,
+                date::format_duration(tracked_seconds(todo))
+            );
+            todos.save().unwrap();
+
+            if INTERRUPTED.load(Ordering::SeqCst) {
+                return;
+            }
+
+            notification::desktop("Focus session finished", &title);
+            if prompt(&format!("Mark #{} as done? [y/N] ", id)).eq_ignore_ascii_case("y") {
+                let todo = todos.done(id.clone()).unwrap();
+                todos.save().unwrap();
+                println!("Completed #{}: {}", todo.id, todo.title);
+            }
+            if prompt(&format!("Start a {} minute break? [y/N] ", break_minutes))
+                .eq_ignore_ascii_case("y")
+            {
+                countdown(break_minutes);
+                if !INTERRUPTED.load(Ordering::SeqCst) {
+                    notification::desktop("Break is over", &title);
+                }
+            }
+        })
+}
+
+pub fn now_command() -> Command {
+    Command::new("now")
+        .description("Show the focus list, what is being worked on now")
+        .usage("todo now")
+        .action(|_| {
+            let todos = Todos::read();
+            let limit = limit();
+            let focused = focused(&todos);
+            if focused.is_empty() {
+                println!(
+                    "Nothing in focus, put up to {} TODOs on the list with todo focus add <todo id>",
+                    limit
+                );
+                return;
+            }
+            println!("Now ({} of {})", focused.len(), limit);
+            for todo in focused {
+                let mut details = vec![];
+                if !todo.date.is_empty() {
+                    details.push(format!("due {}", date::display(&todo.date)));
+                }
+                if !todo.checklist.is_empty() {
+                    details.push(checklist::progress(todo));
+                }
+                let tracked = tracked_seconds(todo);
+                if tracked > 0 {
+                    details.push(format!("{} tracked", date::format_duration(tracked)));
+                }
+                let details = match details.is_empty() {
+                    true => String::new(),
+                    false => format!("  ({})", details.join(", ")),
+                };
+                println!("  #{} {}{}", todo.id, todo.title, details);
+            }
+        })
+}
+
+fn add_action(c: &Context) {
+    let [id] = c.args.as_slice() else {
+        eprintln!("Please specify one ID");
+        exit(1);
+    };
+    let mut todos = Todos::read();
+    let limit = limit();
+    let focused: Vec<String> = focused(&todos)
+        .iter()
+        .map(|t| format!("#{} {}", t.id, t.title))
+        .collect();
+    let todo = match todos.find_mut(id) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    if !todo.done.is_empty() {
+        eprintln!("#{} is already done", todo.id);
+        exit(1);
+    }
+    if fields::get(todo, FIELD).is_some() {
+        println!("#{} is already in focus: {}", todo.id, todo.title);
+        return;
+    }
+    if focused.len() >= limit {
+        eprintln!(
+            "The focus list is full ({} of {}), please finish or remove one first with todo focus remove <todo id>:",
+            focused.len(),
+            limit
+        );
+        for todo in focused {
+            eprintln!("  {}", todo);
+        }
+        exit(1);
+    }
+    fields::set(todo, FIELD, &date::now().to_string());
+    println!("Focusing on #{}: {}", todo.id, todo.title);
+    todos.save().unwrap();
+}
+
+fn remove_action(c: &Context) {
+    let [id] = c.args.as_slice() else {
+        eprintln!("Please specify one ID");
+        exit(1);
+    };
+    let mut todos = Todos::read();
+    let todo = match todos.find_mut(id) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    if fields::get(todo, FIELD).is_none() {
+        println!("#{} is not in focus: {}", todo.id, todo.title);
+        return;
+    }
+    fields::set(todo, FIELD, "");
+    println!("Removed #{} from focus: {}", todo.id, todo.title);
+    todos.save().unwrap();
+}
+
+/// The open TODOs on the focus list, in the order they were put there.
+/// Done ones drop off by themselves, and recurring ones stay on.
+fn focused(todos: &Todos) -> Vec<&Todo> {
+    let mut focused: Vec<(u64, &Todo)> = todos
+        .records
+        .iter()
+        .filter(|t| t.done.is_empty())
+        .filter_map(|t| fields::get(t, FIELD).map(|at| (at.parse().unwrap_or(0), t)))
+        .collect();
+    focused.sort_by_key(|(at, _)| *at);
+    focused.into_iter().map(|(_, t)| t).collect()
+}
+
+fn limit() -> usize {
+    match config::get().string("focus.limit") {
+        Some(limit) => match limit.parse::<usize>() {
+            Ok(limit) if limit > 0 => limit,
+            _ => {
+                eprintln!("focus.limit should be a number above 0: {}", limit);
+                exit(1);
+            }
+        },
+        None => LIMIT,
+    }
+}
+
+/// Count down `minutes` on one terminal line, returning the seconds that
+/// passed. Ctrl-C stops the countdown instead of the process, so the
+/// time so far can still be logged.
+fn countdown(minutes: u64) -> u64 {
+    // SAFETY: the handler only stores to an atomic.
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+
+    let total = Duration::from_secs(minutes * 60);
+    let start = Instant::now();
+    while start.elapsed() < total && !INTERRUPTED.load(Ordering::SeqCst) {
+        let left = (total - start.elapsed()).as_secs();
+        print!("\r{:02}:{:02} remaining ", left / 60, left % 60);
+        std::io::stdout().flush().unwrap();
+        thread::sleep(Duration::from_millis(200));
+    }
+    println!();
+
+    // SAFETY: restores the default disposition, no handler involved.
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+    start.elapsed().min(total).as_secs()
+}
+
+/// Total seconds logged in a TODO's time log.
+pub fn tracked_seconds(todo: &Todo) -> u64 {
+    tracked_since(todo, 0)
+}
+
+/// Time of the sessions started at or after `since`.
+pub fn tracked_since(todo: &Todo, since: u64) -> u64 {
+    todo.time_log
+        .iter()
+        .filter_map(|e| e.split_once(' '))
+        .filter(|(start, _)| start.parse::<u64>().is_ok_and(|s| s >= since))
+        .filter_map(|(_, secs)| secs.parse::<u64>().ok())
+        .sum()
+}
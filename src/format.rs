@@ -0,0 +1,152 @@
+/// First line of files written in a versioned format, e.g. `#version:2`.
+/// Files without it predate versioning and are treated as version 1.
+const VERSION_PREFIX: &str = "#version:";
+
+pub struct Row {
+    /// Line of the file the row starts on, for diagnostics.
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+impl Row {
+    pub fn is_empty(&self) -> bool {
+        self.fields.len() == 1 && self.fields[0].is_empty()
+    }
+}
+
+/// The data file as a header and rows of raw fields.
+pub struct Table {
+    pub version: u32,
+    pub headers: Vec<String>,
+    pub rows: Vec<Row>,
+}
+
+pub fn parse(content: &str) -> Table {
+    let (version, body, first_line) = match content.strip_prefix(VERSION_PREFIX) {
+        Some(rest) => {
+            let (v, body) = rest.split_once('\n').unwrap_or((rest, ""));
+            (Some(v.trim().parse().unwrap_or(1)), body, 2)
+        }
+        None => (None, content, 1),
+    };
+
+    // Unversioned files were written without escaping, so a plain split
+    // is the only faithful reading of them.
+    let mut rows = if version.is_none() {
+        body.lines()
+            .enumerate()
+            .map(|(i, l)| Row {
+                line: i + first_line,
+                fields: l.split(',').map(|f| f.to_string()).collect(),
+            })
+            .collect()
+    } else {
+        parse_quoted(body, first_line)
+    };
+
+    let headers = if rows.is_empty() {
+        vec![]
+    } else {
+        rows.remove(0).fields
+    };
+
+    Table {
+        version: version.unwrap_or(1),
+        headers,
+        rows,
+    }
+}
+
+fn parse_quoted(body: &str, first_line: usize) -> Vec<Row> {
+    let mut rows = vec![];
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = first_line;
+    let mut row_line = first_line;
+    let mut chars = body.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            '\n' if !in_quotes => {
+                fields.push(std::mem::take(&mut field));
+                rows.push(Row {
+                    line: row_line,
+                    fields: std::mem::take(&mut fields),
+                });
+                line += 1;
+                row_line = line;
+            }
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        rows.push(Row {
+            line: row_line,
+            fields,
+        });
+    }
+    rows
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn write_row(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|f| escape(f))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+pub fn write(version: u32, headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut s = format!("{}{}\n{}\n", VERSION_PREFIX, version, write_row(headers));
+    for row in rows {
+        s += &write_row(row);
+        s += "\n";
+    }
+    s
+}
+
+/// Split a field holding several values, one per line.
+pub fn split_list(field: &str) -> Vec<String> {
+    field
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// Join values into a single field with `split_list`'s encoding. Line
+/// breaks inside a value are replaced, since they separate values.
+pub fn join_list(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|v| v.replace(['\r', '\n'], " "))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,23 @@
+use std::process;
+
+/// The output of a git command run in the current directory, without the
+/// trailing newline.
+pub fn run(args: &[&str]) -> anyhow::Result<String> {
+    let output = process::Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string())
+}
+
+/// The branch checked out in the current directory's repository.
+pub fn branch() -> anyhow::Result<String> {
+    run(&["rev-parse", "--git-dir"]).map_err(|_| anyhow::anyhow!("Not in a git repository"))?;
+    run(&["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .map_err(|_| anyhow::anyhow!("HEAD is detached, not on a branch"))
+}
@@ -0,0 +1,253 @@
+use crate::date::{self, Date};
+use crate::{batch, dry_run, format, progress, term, Todos};
+use anyhow::bail;
+use cli_table::{format::Justify, print_stdout, Cell, Style, Table};
+use seahorse::{Command, Context, Flag, FlagType};
+use std::process::exit;
+
+/// Version of the goals file format.
+const GOALS_VERSION: u32 = 1;
+
+pub fn goal_command() -> Command {
+    Command::new("goal")
+        .description("Group TODOs under goals")
+        .usage(
+            "todo goal [create <title> | link <goal id> <todo id>... | unlink <todo id>... | list]",
+        )
+        .action(list_action)
+        .command(
+            Command::new("create")
+                .description("Create a goal")
+                .usage("todo goal create <title> [--due <date>]")
+                .flag(Flag::new("due", FlagType::String).description("Deadline of the goal"))
+                .action(|c| {
+                    if c.args.is_empty() {
+                        eprintln!("Please enter a title");
+                        exit(1);
+                    }
+                    let due = c.string_flag("due").unwrap_or_default();
+                    if !due.is_empty() && Date::parse(&due).is_none() {
+                        eprintln!("Invalid date: {}", due);
+                        exit(1);
+                    }
+
+                    let mut goals = Goals::read();
+                    let goal = goals.create(c.args.join(" "), due);
+                    goals.save().unwrap();
+                    println!("Created goal #{}: {}", goal.id, goal.title);
+                }),
+        )
+        .command(
+            Command::new("link")
+                .description("Link TODOs to a goal")
+                .usage("todo goal link <goal id> <todo id>...")
+                .action(|c| {
+                    let (goal_id, ids) = match c.args.split_first() {
+                        Some((g, ids)) if !ids.is_empty() => (g, ids),
+                        _ => {
+                            eprintln!("Please specify a goal ID and TODO IDs");
+                            exit(1);
+                        }
+                    };
+                    let goal = match Goals::read().find(goal_id) {
+                        Ok(g) => g.clone(),
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            exit(1);
+                        }
+                    };
+                    set_goal(ids, &goal.id);
+                    println!(
+                        "Linked {} TODO(s) to goal #{}: {}",
+                        ids.len(),
+                        goal.id,
+                        goal.title
+                    );
+                }),
+        )
+        .command(
+            Command::new("unlink")
+                .description("Unlink TODOs from their goal")
+                .usage("todo goal unlink <todo id>...")
+                .action(|c| {
+                    if c.args.is_empty() {
+                        eprintln!("Please specify TODO IDs");
+                        exit(1);
+                    }
+                    set_goal(&c.args, "");
+                    println!("Unlinked {} TODO(s)", c.args.len());
+                }),
+        )
+        .command(
+            Command::new("list")
+                .description("Show goals with their progress")
+                .usage("todo goal list")
+                .alias("ls")
+                .action(list_action),
+        )
+}
+
+pub fn goals_command() -> Command {
+    Command::new("goals")
+        .description("Show goals with their progress")
+        .usage("todo goals")
+        .action(list_action)
+}
+
+fn set_goal(ids: &[String], goal_id: &str) {
+    let mut todos = Todos::read();
+    for id in ids {
+        match todos.find_mut(id) {
+            Ok(t) => t.goal = goal_id.to_string(),
+            Err(e) => {
+                eprintln!("{}: {}", id, e);
+                exit(1);
+            }
+        }
+    }
+    todos.save().unwrap();
+}
+
+fn list_action(_: &Context) {
+    let goals = Goals::read();
+    let todos = Todos::read();
+    let table = goals
+        .goals
+        .iter()
+        .map(|g| {
+            let linked: Vec<_> = todos.records.iter().filter(|r| r.goal == g.id).collect();
+            // Done TODOs count fully, open ones by their own progress.
+            let percent = if linked.is_empty() {
+                0
+            } else {
+                linked
+                    .iter()
+                    .map(|r| {
+                        if r.done.is_empty() {
+                            progress::percent(r).unwrap_or(0)
+                        } else {
+                            100
+                        }
+                    })
+                    .sum::<u32>()
+                    / linked.len() as u32
+            };
+            let next = linked
+                .iter()
+                .filter(|r| r.done.is_empty())
+                .filter_map(|r| Date::parse(&r.date))
+                .min()
+                .map(|d| d.to_string())
+                .unwrap_or_default();
+            let done = linked.iter().filter(|r| !r.done.is_empty()).count();
+            vec![
+                g.id.clone().cell().justify(Justify::Center),
+                g.title.clone().cell(),
+                g.due.clone().cell().justify(Justify::Center),
+                format!("{}/{}", done, linked.len())
+                    .cell()
+                    .justify(Justify::Center),
+                format!("{}%", percent).cell().justify(Justify::Right),
+                next.cell().justify(Justify::Center),
+            ]
+        })
+        .table()
+        .title(
+            ["ID", "GOAL", "DUE", "TODOS", "DONE", "NEXT DEADLINE"]
+                .iter()
+                .map(|h| h.cell().bold(true).justify(Justify::Center)),
+        )
+        .bold(true)
+        .color_choice(term::color_choice());
+    print_stdout(table).unwrap();
+}
+
+#[derive(Debug, Clone)]
+pub struct Goal {
+    pub id: String,
+    pub title: String,
+    pub due: String,
+    created_at: String,
+}
+
+/// Goals, stored next to the data file.
+pub struct Goals {
+    goals: Vec<Goal>,
+}
+
+impl Goals {
+    fn path() -> String {
+        format!("{}.goals", Todos::todo_path())
+    }
+
+    pub fn read() -> Self {
+        let content = batch::read(&Self::path()).unwrap_or_default();
+        let table = format::parse(&content);
+        let goals = table
+            .rows
+            .iter()
+            .filter(|r| !r.is_empty())
+            .map(|r| {
+                let mut v = r.fields.clone();
+                v.resize(4, "".to_string());
+                Goal {
+                    id: v[0].clone(),
+                    title: v[1].clone(),
+                    due: v[2].clone(),
+                    created_at: v[3].clone(),
+                }
+            })
+            .collect();
+        Goals { goals }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        if dry_run::is_enabled() {
+            println!("Dry run, {} would be written", Self::path());
+            return Ok(());
+        }
+        let headers: Vec<String> = ["id", "title", "due", "created_at"]
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+        let rows: Vec<Vec<String>> = self
+            .goals
+            .iter()
+            .map(|g| {
+                vec![
+                    g.id.clone(),
+                    g.title.clone(),
+                    g.due.clone(),
+                    g.created_at.clone(),
+                ]
+            })
+            .collect();
+        batch::write(&Self::path(), format::write(GOALS_VERSION, &headers, &rows))?;
+        Ok(())
+    }
+
+    fn create(&mut self, title: String, due: String) -> Goal {
+        let id = self
+            .goals
+            .iter()
+            .filter_map(|g| g.id.parse::<usize>().ok())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let goal = Goal {
+            id: id.to_string(),
+            title,
+            due,
+            created_at: date::now().to_string(),
+        };
+        self.goals.push(goal.clone());
+        goal
+    }
+
+    pub fn find(&self, id: &str) -> anyhow::Result<&Goal> {
+        match self.goals.iter().find(|g| g.id == id) {
+            Some(g) => Ok(g),
+            None => bail!("The specified goal does not exist"),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{date, fields, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::process::exit;
+
+/// Events shown without `--limit`.
+const DEFAULT_LIMIT: isize = 20;
+
+pub struct Event<'a> {
+    pub at: u64,
+    /// Empty for TODOs from before users were recorded.
+    pub user: &'a str,
+    pub action: &'static str,
+    pub todo: &'a Todo,
+    /// The commit that completed the TODO, see `hook`.
+    pub commit: Option<&'a str>,
+    /// The field a `changed` event set.
+    pub field: Option<&'a str>,
+}
+
+impl Event<'_> {
+    /// What happened, like `added` or `set due`.
+    pub fn label(&self) -> String {
+        match self.field {
+            Some(field) => format!("set {}", field),
+            None => self.action.to_string(),
+        }
+    }
+}
+
+pub fn history_command() -> Command {
+    Command::new("history")
+        .description("Show who added and completed TODOs, or one TODO, newest first")
+        .usage("todo history [<todo id>] [--user <name>] [--limit <n>]")
+        .flag(Flag::new("user", FlagType::String).description("Only show what someone did"))
+        .flag(
+            Flag::new("limit", FlagType::Int)
+                .alias("n")
+                .description(format!(
+                    "Number of events to show (default: {})",
+                    DEFAULT_LIMIT
+                )),
+        )
+        .action(|c| {
+            let todos = Todos::read();
+            let records: Vec<&Todo> = match c.args.as_slice() {
+                [] => todos.records.iter().collect(),
+                [id] => match todos.records.iter().find(|r| r.id == *id) {
+                    Some(todo) => vec![todo],
+                    None => {
+                        eprintln!("The specified ID does not exist");
+                        exit(1);
+                    }
+                },
+                _ => {
+                    eprintln!("Please specify at most one ID");
+                    exit(1);
+                }
+            };
+            let mut events: Vec<Event> = records.into_iter().flat_map(events).collect();
+            if let Ok(user) = c.string_flag("user") {
+                events.retain(|e| e.user == user);
+            }
+            events.sort_by_key(|e| std::cmp::Reverse(e.at));
+            events.truncate(c.int_flag("limit").unwrap_or(DEFAULT_LIMIT).max(0) as usize);
+
+            if events.is_empty() {
+                println!("No history");
+            }
+            let width = events.iter().map(|e| e.user.len()).max().unwrap_or(0);
+            let actions = events.iter().map(|e| e.label().len()).max().unwrap_or(0);
+            for e in events {
+                let commit = e
+                    .commit
+                    .map(|c| format!(" (commit {})", c))
+                    .unwrap_or_default();
+                println!(
+                    "{}  {:<width$}  {:<actions$}  #{} {}{}",
+                    date::format_timestamp(e.at),
+                    e.user,
+                    e.label(),
+                    e.todo.id,
+                    e.todo.title,
+                    commit,
+                    width = width,
+                    actions = actions.max(9)
+                );
+            }
+        })
+}
+
+/// When `todo` was added and completed, including each completion of a
+/// recurring one, and when fields were set with `todo set`.
+pub fn events(todo: &Todo) -> Vec<Event<'_>> {
+    let mut events = vec![];
+    let mut push = |at: &str, user, action, commit, field| {
+        if let Ok(at) = at.parse() {
+            events.push(Event {
+                at,
+                user,
+                action,
+                todo,
+                commit,
+                field,
+            });
+        }
+    };
+    push(&todo.created_at, &todo.created_by, "added", None, None);
+    push(
+        &todo.completed_at,
+        &todo.completed_by,
+        "completed",
+        fields::get(todo, "commit"),
+        None,
+    );
+    for entry in &todo.completions {
+        let mut parts = entry.split(' ');
+        let at = parts.next().unwrap_or("");
+        push(at, parts.nth(1).unwrap_or(""), "completed", None, None);
+    }
+    for entry in &todo.changes {
+        let mut parts = entry.split(' ');
+        let (at, field) = (parts.next().unwrap_or(""), parts.next());
+        push(at, parts.next().unwrap_or(""), "changed", None, field);
+    }
+    events
+}
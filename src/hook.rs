@@ -0,0 +1,169 @@
+//! `todo hook install-git`: a git hook that completes the TODOs a commit
+//! message mentions as `todo:#42`, recording the commit in the `commit`
+//! field, which `todo history` shows. It is a post-commit hook, since the
+//! commit has no hash while its message is being written, and it uses the
+//! list `todo` would use in the repository.
+//!
+//! With `--pre-commit`, it installs a pre-commit hook instead, which
+//! fails while an open TODO of that list is tagged `blocker`, or the tag
+//! in `hook.blocker_tag`, unless the TODO was added with `--branch` for
+//! another branch. A project list, see `todo init`, keeps the blockers
+//! of other projects out.
+
+use crate::{config, dry_run, fields, git, tag_name, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::fs;
+use std::path::PathBuf;
+use std::process::exit;
+
+/// Marks hooks written by `install-git`, which it may replace.
+const MARKER: &str = "# Installed by todo hook install-git";
+const BLOCKER_TAG: &str = "blocker";
+
+pub fn hook_command() -> Command {
+    Command::new("hook")
+        .description("Connect the list to other tools through their hooks")
+        .usage("todo hook install-git [--pre-commit]")
+        .command(
+            Command::new("install-git")
+                .description("Complete TODOs mentioned as todo:#<id> in commit messages")
+                .usage("todo hook install-git [--pre-commit] [--force]")
+                .flag(
+                    Flag::new("pre-commit", FlagType::Bool)
+                        .description("Block commits while blocker TODOs are open instead"),
+                )
+                .flag(
+                    Flag::new("force", FlagType::Bool)
+                        .alias("f")
+                        .description("Replace a hook not installed by todo"),
+                )
+                .action(|c| {
+                    let (hook, command) = match c.bool_flag("pre-commit") {
+                        true => ("pre-commit", "pre-commit"),
+                        false => ("post-commit", "git-commit"),
+                    };
+                    if let Err(e) = install(hook, command, c.bool_flag("force")) {
+                        eprintln!("{}", e);
+                        exit(1);
+                    }
+                }),
+        )
+        .command(
+            Command::new("git-commit")
+                .description("Complete the TODOs the last commit mentions (run by the git hook)")
+                .usage("todo hook git-commit")
+                .action(|_| {
+                    if let Err(e) = complete_mentioned() {
+                        eprintln!("{}", e);
+                        exit(1);
+                    }
+                }),
+        )
+        .command(
+            Command::new("pre-commit")
+                .description("Fail while blocker TODOs are open (run by the git hook)")
+                .usage("todo hook pre-commit")
+                .action(|_| check_blockers()),
+        )
+}
+
+/// Install the git hook `hook`, running `todo hook <command>`.
+fn install(hook: &str, command: &str, force: bool) -> anyhow::Result<()> {
+    // Asking git finds the hooks of worktrees and core.hooksPath too.
+    let dir = PathBuf::from(git::run(&["rev-parse", "--git-path", "hooks"])?);
+    let path = dir.join(hook);
+    if let Ok(existing) = fs::read_to_string(&path) {
+        if !existing.contains(MARKER) && !force {
+            anyhow::bail!(
+                "{} already exists; run again with --force to replace it",
+                path.display()
+            );
+        }
+    }
+    let exe = std::env::current_exe()?;
+    let script = format!(
+        "#!/bin/sh\n{}\nexec '{}' hook {}\n",
+        MARKER,
+        exe.to_string_lossy().replace('\'', r"'\''"),
+        command
+    );
+    if dry_run::is_enabled() {
+        println!("Dry run, {} would be written", path.display());
+        return Ok(());
+    }
+    fs::create_dir_all(&dir)?;
+    fs::write(&path, script)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    }
+    println!("Installed {}", path.display());
+    Ok(())
+}
+
+fn complete_mentioned() -> anyhow::Result<()> {
+    let log = git::run(&["log", "-1", "--format=%h%n%B"])?;
+    let (hash, message) = log.split_once('\n').unwrap_or((&log, ""));
+    let ids = mentions(message);
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut todos = Todos::read();
+    for id in ids {
+        let Some(todo) = todos.records.iter().find(|r| r.id == id) else {
+            eprintln!("todo:#{} in commit {} does not exist", id, hash);
+            continue;
+        };
+        if !todo.done.is_empty() {
+            continue;
+        }
+        let todo = todos.done(id)?;
+        let todo = todos.find_mut(&todo.id)?;
+        fields::set(todo, "commit", hash);
+        println!("Completed #{} {}", todo.id, todo.title);
+    }
+    todos.save()
+}
+
+/// Exit with 1, listing them, while blocker TODOs are open.
+fn check_blockers() {
+    let tag = tag_name(
+        config::get()
+            .string("hook.blocker_tag")
+            .unwrap_or(BLOCKER_TAG),
+    );
+    let branch = git::branch().ok();
+    let todos = Todos::read();
+    let blockers: Vec<&Todo> = todos
+        .records
+        .iter()
+        .filter(|r| r.done.is_empty() && r.tags.contains(&tag))
+        .filter(|r| fields::get(r, "branch").is_none_or(|b| Some(b) == branch.as_deref()))
+        .collect();
+    if blockers.is_empty() {
+        return;
+    }
+    eprintln!("Not committing while these #{} TODOs are open:", tag);
+    for todo in &blockers {
+        eprintln!("  #{} {}", todo.id, todo.title);
+    }
+    eprintln!("(git commit --no-verify commits anyway)");
+    exit(1);
+}
+
+/// The IDs mentioned as `todo:#<id>`, in order and without repeats.
+fn mentions(message: &str) -> Vec<String> {
+    let mut ids: Vec<String> = vec![];
+    for (i, _) in message.match_indices("todo:#") {
+        let id: String = message[i + "todo:#".len()..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if !id.is_empty() && !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
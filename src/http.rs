@@ -0,0 +1,150 @@
+use crate::{json, log};
+use anyhow::{bail, Context};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+/// Reply to `request`.
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+    /// The `ETag` header, for conditional requests.
+    pub etag: Option<String>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn json(&self) -> anyhow::Result<json::Value> {
+        json::parse(&self.body).context("The response is not valid JSON")
+    }
+}
+
+/// Make an HTTP request with curl. The URL, headers and body are handed
+/// over on stdin rather than as arguments, as they often carry tokens that
+/// should not show up in `ps`.
+pub fn request(
+    method: &str,
+    url: &str,
+    headers: &[String],
+    body: Option<&str>,
+) -> anyhow::Result<Response> {
+    let body_len = body.map_or(0, str::len);
+    let mut config = format!("url = {}\nrequest = {}\n", quote(url), quote(method));
+    for header in headers {
+        config += &format!("header = {}\n", quote(header));
+    }
+    if let Some(body) = body {
+        config += &format!("data-binary = {}\n", quote(body));
+    }
+
+    let started = Instant::now();
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--location"])
+        .args(["--write-out", "\n%{http_code} %header{etag}"])
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl")?;
+    child.stdin.take().unwrap().write_all(config.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        log::warn("http", || {
+            format!("failed {} host={} {}", method, host(url), output.status)
+        });
+        bail!("Request to {} failed", host(url));
+    }
+
+    let out = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = out.rsplit_once('\n').unwrap_or(("", &out));
+    let (status, etag) = status.split_once(' ').unwrap_or((status, ""));
+    // Only the host, as paths and queries may carry tokens too.
+    log::debug("http", || {
+        format!(
+            "{} host={} status={} sent={} received={} elapsed_ms={}",
+            method,
+            host(url),
+            status.trim(),
+            body_len,
+            body.len(),
+            started.elapsed().as_millis()
+        )
+    });
+    Ok(Response {
+        status: status.trim().parse().unwrap_or(0),
+        body: body.to_string(),
+        etag: Some(etag.trim().to_string()).filter(|e| !e.is_empty()),
+    })
+}
+
+/// POST `body` as JSON, failing on non-success statuses.
+pub fn post_json(url: &str, headers: &[String], body: &json::Value) -> anyhow::Result<Response> {
+    let mut headers = headers.to_vec();
+    headers.push("Content-Type: application/json".to_string());
+    let response = request("POST", url, &headers, Some(&body.to_string()))?;
+    if !response.is_success() {
+        bail!("{} answered {}", host(url), response.status);
+    }
+    Ok(response)
+}
+
+/// The host of `url`, for messages that should not repeat secret paths.
+pub fn host(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, r)| r);
+    rest.split('/').next().unwrap_or(rest).to_string()
+}
+
+/// An `Authorization` header for HTTP basic authentication.
+pub fn basic_auth(user: &str, password: &str) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let input = format!("{}:{}", user, password);
+    let mut encoded = String::new();
+    for chunk in input.as_bytes().chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    format!("Authorization: Basic {}", encoded)
+}
+
+/// Percent-encode a query string or form value.
+pub fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// A string in curl config file syntax.
+fn quote(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\r' => out += "\\r",
+            '\t' => out += "\\t",
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,102 @@
+//! Markers shown in the list, which can be changed for fonts that lack
+//! the glyphs:
+//!
+//! ```toml
+//! [display]
+//! icons = "ascii"     # or "unicode"; by default ascii unless the locale is UTF-8
+//!
+//! [icons]
+//! done = "x"
+//! overdue = "!"
+//!
+//! [icons.priority]    # by the value of the `priority` field
+//! high = "!!"
+//! low = "."
+//! ```
+
+use crate::date;
+use crate::{config, fields, Todo};
+use std::env;
+use std::sync::OnceLock;
+
+const UNICODE: Theme = Theme {
+    done: "✓",
+    overdue: "⚠",
+    priorities: &[("high", "↑"), ("low", "↓")],
+};
+
+const ASCII: Theme = Theme {
+    done: "x",
+    overdue: "!",
+    priorities: &[("high", "!"), ("low", "-")],
+};
+
+struct Theme {
+    done: &'static str,
+    overdue: &'static str,
+    priorities: &'static [(&'static str, &'static str)],
+}
+
+fn theme() -> &'static Theme {
+    static THEME: OnceLock<&Theme> = OnceLock::new();
+    THEME.get_or_init(|| match config::get().string("display.icons") {
+        Some("ascii") => &ASCII,
+        Some("unicode") => &UNICODE,
+        _ if is_utf8_locale() => &UNICODE,
+        _ => &ASCII,
+    })
+}
+
+/// Whether the locale, from the variables that set it in order of
+/// precedence, uses UTF-8. Without any, as on Windows and macOS where the
+/// terminals use UTF-8 anyway, it is taken to.
+fn is_utf8_locale() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|v| env::var(v).ok().filter(|l| !l.is_empty()));
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        None => true,
+    }
+}
+
+fn icon(name: &str, default: &'static str) -> &'static str {
+    config::get()
+        .string(&format!("icons.{}", name))
+        .unwrap_or(default)
+}
+
+/// The marker of a completed TODO, empty for an open one.
+pub fn done(todo: &Todo) -> &'static str {
+    if todo.done.is_empty() {
+        ""
+    } else {
+        icon("done", theme().done)
+    }
+}
+
+/// The marker of an open TODO past its date, or empty.
+pub fn overdue(todo: &Todo) -> &'static str {
+    let late = todo.done.is_empty() && date::is_past(&todo.date);
+    if late {
+        icon("overdue", theme().overdue)
+    } else {
+        ""
+    }
+}
+
+/// The marker for the `priority` field of `todo`, or empty.
+pub fn priority(todo: &Todo) -> &'static str {
+    let Some(priority) = fields::get(todo, "priority") else {
+        return "";
+    };
+    let default = theme()
+        .priorities
+        .iter()
+        .find(|(p, _)| *p == priority)
+        .map_or("", |(_, icon)| icon);
+    icon(&format!("priority.{}", priority), default)
+}
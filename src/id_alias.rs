@@ -0,0 +1,222 @@
+//! Names for TODOs, for long-lived ones that are easier to remember by
+//! name than by number:
+//!
+//! ```sh
+//! todo alias-id 42 taxes
+//! todo done taxes
+//! ```
+//!
+//! A name is kept in the `alias` field of its TODO, so each list has its
+//! own, and commands that take IDs get the ID in its place before they
+//! run.
+
+use crate::{fields, Todo, Todos};
+use seahorse::{App, Command, Context, Flag, FlagType};
+use std::process::exit;
+
+pub const FIELD: &str = "alias";
+
+/// Which of the arguments of a command are TODO IDs.
+enum Ids {
+    First,
+    All,
+    /// All but the first, which is something else, like a goal.
+    Rest,
+}
+
+/// The commands that take IDs, with their subcommand, if any.
+const COMMANDS: &[(&str, Option<&str>, Ids)] = &[
+    ("alias-id", None, Ids::First),
+    ("attach", None, Ids::First),
+    ("attach", Some("open"), Ids::First),
+    ("check", Some("add"), Ids::First),
+    ("check", Some("done"), Ids::First),
+    ("comment", None, Ids::First),
+    ("copy", None, Ids::All),
+    ("delete", None, Ids::First),
+    ("done", None, Ids::First),
+    ("edit", None, Ids::First),
+    ("focus", None, Ids::First),
+    ("focus", Some("add"), Ids::First),
+    ("focus", Some("remove"), Ids::First),
+    ("get", None, Ids::First),
+    ("gh", Some("resolve"), Ids::First),
+    ("goal", Some("link"), Ids::Rest),
+    ("goal", Some("unlink"), Ids::All),
+    ("history", None, Ids::First),
+    ("jira", Some("done"), Ids::First),
+    ("move", None, Ids::All),
+    ("open", None, Ids::First),
+    ("progress", None, Ids::First),
+    ("set", None, Ids::First),
+    ("share", None, Ids::First),
+    ("show", None, Ids::First),
+    ("someday", None, Ids::First),
+    ("someday", Some("activate"), Ids::First),
+];
+
+pub fn alias_id_command() -> Command {
+    Command::new("alias-id")
+        .description("Name a TODO, so that commands take the name for its ID")
+        .usage("todo alias-id [<todo id> <name> | <todo id> --remove]")
+        .flag(Flag::new("remove", FlagType::Bool).description("Take the name off the TODO"))
+        .action(action)
+}
+
+fn action(c: &Context) {
+    let mut todos = Todos::read();
+    let (id, name) = match (c.args.as_slice(), c.bool_flag("remove")) {
+        ([], false) => return list(&todos),
+        ([id], true) => (id, ""),
+        ([id, name], false) => (id, name.as_str()),
+        _ => {
+            eprintln!("Please specify an ID and a name, or an ID and --remove");
+            exit(1);
+        }
+    };
+    if !name.is_empty() {
+        if let Err(e) = check_name(name) {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        if let Some(other) = find(&todos, name).filter(|t| t.id != *id) {
+            eprintln!(
+                "{} is already the name of #{}: {}",
+                name, other.id, other.title
+            );
+            exit(1);
+        }
+    }
+    let todo = match todos.find_mut(id) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    if fields::get(todo, FIELD).unwrap_or("") == name {
+        println!("Nothing to change for #{}: {}", todo.id, todo.title);
+        return;
+    }
+    fields::set(todo, FIELD, name);
+    let change = format!("{} {} {}", crate::date::now(), FIELD, crate::current_user());
+    todo.changes.push(change.trim_end().to_string());
+    match name {
+        "" => println!("Removed the name of #{}: {}", todo.id, todo.title),
+        name => println!("Named #{} {}: {}", todo.id, name, todo.title),
+    }
+    todos.save().unwrap();
+}
+
+fn list(todos: &Todos) {
+    let named: Vec<(&str, &Todo)> = todos
+        .records
+        .iter()
+        .filter_map(|t| fields::get(t, FIELD).map(|a| (a, t)))
+        .collect();
+    if named.is_empty() {
+        println!("No TODO has a name yet, give one with todo alias-id <todo id> <name>");
+        return;
+    }
+    let width = named.iter().map(|(a, _)| a.len()).max().unwrap_or(0);
+    for (name, todo) in named {
+        println!(
+            "{:<width$}  #{} {}",
+            name,
+            todo.id,
+            todo.title,
+            width = width
+        );
+    }
+}
+
+/// Why `name` can't name a TODO, if it can't: it can't look like an ID
+/// or take quoting in a shell.
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!(
+            "{} looks like an ID, please use a name with letters",
+            name
+        ));
+    }
+    if name.starts_with('-')
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        return Err(format!(
+            "Invalid name {}, use letters, numbers, -, _ and .",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// The TODO of `todos` named `name`.
+pub fn find<'a>(todos: &'a Todos, name: &str) -> Option<&'a Todo> {
+    todos
+        .records
+        .iter()
+        .find(|t| fields::get(t, FIELD) == Some(name))
+}
+
+/// Put the IDs of named TODOs in place of their names in the arguments
+/// of the command in `args`, which start with the program name.
+pub fn resolve(args: &mut [String], app: &App) {
+    let Some(command) = args.get(1).and_then(|a| named(app.commands.as_deref(), a)) else {
+        return;
+    };
+    let sub = args
+        .get(2)
+        .and_then(|a| named(command.commands.as_deref(), a));
+    let Some((_, _, ids)) = COMMANDS
+        .iter()
+        .find(|(name, s, _)| *name == command.name && *s == sub.map(|s| s.name.as_str()))
+    else {
+        return;
+    };
+    let flags = sub.unwrap_or(command).flags.as_deref().unwrap_or_default();
+    let mut positions = vec![];
+    let mut i = if sub.is_some() { 3 } else { 2 };
+    while i < args.len() {
+        match args[i].strip_prefix("--").or(args[i].strip_prefix('-')) {
+            // The value of a flag, like `--to work`, is no ID.
+            Some(flag) => {
+                let takes_value = flags.iter().any(|f| {
+                    (f.name == flag || f.alias.iter().flatten().any(|a| a == flag))
+                        && f.flag_type != FlagType::Bool
+                });
+                if takes_value {
+                    i += 1;
+                }
+            }
+            None => positions.push(i),
+        }
+        i += 1;
+    }
+    let positions = match ids {
+        Ids::First => &positions[..positions.len().min(1)],
+        Ids::All => &positions[..],
+        Ids::Rest => &positions[positions.len().min(1)..],
+    };
+    // Reading the list is only worth it when there is a name to look up.
+    if positions
+        .iter()
+        .all(|&i| args[i].chars().all(|c| c.is_ascii_digit()))
+    {
+        return;
+    }
+    let todos = Todos::read();
+    for &i in positions {
+        if let Some(todo) = find(&todos, &args[i]) {
+            args[i] = todo.id.clone();
+        }
+    }
+}
+
+/// The command of `commands` called `name` or by an alias of it.
+fn named<'a>(commands: Option<&'a [Command]>, name: &str) -> Option<&'a Command> {
+    commands?
+        .iter()
+        .find(|c| c.name == name || c.alias.iter().flatten().any(|a| a == name))
+}
@@ -0,0 +1,75 @@
+//! The JSON of `todo export`, as when someone hands over some of their
+//! TODOs:
+//!
+//! ```sh
+//! todo export --where tag:handover > handover.json
+//! todo import --from json handover.json --tag-with imported
+//! ```
+//!
+//! TODOs with the title and date of one already in the list are skipped,
+//! so importing the same file twice adds nothing.
+
+use super::Counts;
+use crate::json::{self, Value};
+use crate::{date, fields, rules, Todo, Todos};
+
+/// Add the TODOs of an export, each with `tags` too.
+pub fn import(todos: &mut Todos, content: &str, tags: &[String]) -> anyhow::Result<Counts> {
+    let exported = json::parse(content)?;
+    let Value::Array(items) = exported else {
+        anyhow::bail!("Not a list of TODOs exported by todo");
+    };
+
+    let mut counts = Counts::default();
+    for item in &items {
+        let Some(title) = item.get("title").as_str().map(str::trim) else {
+            anyhow::bail!("Not a list of TODOs exported by todo");
+        };
+        let due = item.get("date").as_str().unwrap_or("");
+        if todos
+            .records
+            .iter()
+            .any(|t| t.date == due && t.title.eq_ignore_ascii_case(title))
+        {
+            counts.unchanged += 1;
+            continue;
+        }
+
+        let strings = |key: &str| -> Vec<String> {
+            item.get(key)
+                .items()
+                .iter()
+                .filter_map(|v| Some(v.as_str()?.to_string()))
+                .collect()
+        };
+        let mut todo = Todo::new(
+            "".to_string(),
+            due.to_string(),
+            title.to_string(),
+            strings("urls"),
+            "".to_string(),
+        );
+        todo.tags = strings("tags");
+        for tag in tags {
+            if !todo.tags.contains(tag) {
+                todo.tags.push(tag.clone());
+            }
+        }
+        todo.assignee = item.get("assignee").as_str().unwrap_or("").to_string();
+        if item.get("done") == &Value::Bool(true) {
+            todo.done = "✓".to_string();
+            todo.completed_at = date::now().to_string();
+            todo.completed_by = crate::current_user();
+        }
+        if let Value::Object(pairs) = item.get("fields") {
+            for (key, value) in pairs {
+                fields::set(&mut todo, key, value.as_str().unwrap_or(""));
+            }
+        }
+        rules::apply(&mut todo);
+        let todo = todos.add(todo)?;
+        println!("Added #{}: {}", todo.id, todo.title);
+        counts.added += 1;
+    }
+    Ok(counts)
+}
@@ -0,0 +1,220 @@
+//! Importing TODOs from other apps' export files, and from those of
+//! `todo export`.
+//!
+//! What comes with an ID of its source, like Trello cards and the issues
+//! of `todo jira` and `todo gh`, is imported with a `remote_ids` entry
+//! `<source> ... <id>`, through `upsert`. Importing it again updates the
+//! TODO in place rather than adding another one: the fields the source
+//! has a value for are taken from it, tags are added and done TODOs are
+//! completed, but nothing is reopened. What was deleted since, and is in
+//! the trash, is left there.
+
+mod json;
+mod trello;
+
+use crate::trash::Trash;
+use crate::{date, dry_run, lists, rules, workspace, Todo, Todos};
+use seahorse::{Command, Context, Flag, FlagType};
+use std::fmt;
+use std::fs;
+use std::process::exit;
+
+/// How many TODOs an import added, updated and found as they were.
+#[derive(Debug, Default)]
+pub struct Counts {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} new, {} updated, {} unchanged",
+            self.added, self.updated, self.unchanged
+        )
+    }
+}
+
+/// Add `todo`, imported as the `remote_ids` entry `link`, or update the
+/// TODO imported from it before, telling about either. One imported
+/// before and deleted since, which is in `trash`, is not added again.
+pub fn upsert(
+    todos: &mut Todos,
+    trash: &Trash,
+    link: &str,
+    mut todo: Todo,
+    counts: &mut Counts,
+) -> anyhow::Result<()> {
+    let is_link = |t: &Todo| t.remote_ids.iter().any(|r| r == link);
+    let Some(existing) = todos.records.iter_mut().find(|t| is_link(t)) else {
+        if trash.deleted().any(|(_, t)| is_link(t)) {
+            counts.unchanged += 1;
+            return Ok(());
+        }
+        if !todo.remote_ids.iter().any(|r| r == link) {
+            todo.remote_ids.push(link.to_string());
+        }
+        rules::apply(&mut todo);
+        let todo = todos.add(todo)?;
+        println!("Added #{}: {}", todo.id, todo.title);
+        counts.added += 1;
+        return Ok(());
+    };
+
+    let mut changed = vec![];
+    if !todo.title.is_empty() && existing.title != todo.title {
+        existing.title = todo.title;
+        changed.push("title");
+    }
+    if !todo.date.is_empty() && existing.date != todo.date {
+        existing.date = todo.date;
+        changed.push("date");
+    }
+    if !todo.urls.is_empty() && existing.urls != todo.urls {
+        existing.urls = todo.urls;
+        changed.push("url");
+    }
+    if !todo.notes.is_empty() && existing.notes != todo.notes {
+        existing.notes = todo.notes;
+        changed.push("notes");
+    }
+    if !todo.checklist.is_empty() && existing.checklist != todo.checklist {
+        existing.checklist = todo.checklist;
+        changed.push("checklist");
+    }
+    let new_tags: Vec<String> = todo
+        .tags
+        .into_iter()
+        .filter(|t| !existing.tags.contains(t))
+        .collect();
+    if !new_tags.is_empty() {
+        existing.tags.extend(new_tags);
+        changed.push("tags");
+    }
+    if !todo.done.is_empty() && existing.done.is_empty() {
+        existing.done = todo.done;
+        existing.completed_at = date::now().to_string();
+        existing.completed_by = crate::current_user();
+        changed.push("done");
+    }
+
+    if changed.is_empty() {
+        counts.unchanged += 1;
+        return Ok(());
+    }
+    // Completing is in the history already.
+    for field in changed.iter().filter(|f| **f != "done") {
+        let change = format!("{} {} {}", date::now(), field, crate::current_user());
+        existing.changes.push(change.trim_end().to_string());
+    }
+    println!(
+        "Updated #{}: {} ({})",
+        existing.id,
+        existing.title,
+        changed.join(", ")
+    );
+    counts.updated += 1;
+    Ok(())
+}
+
+pub fn import_command() -> Command {
+    Command::new("import")
+        .description("Add TODOs from another app's export")
+        .usage("todo import --from json|trello <file> [--into <workspace>] [--tag-with <tag>]")
+        .flag(
+            Flag::new("from", FlagType::String)
+                .description("Format of the file: json, from todo export, or trello")
+                .alias("f"),
+        )
+        .flag(
+            Flag::new("into", FlagType::String)
+                .description("Workspace to import into, instead of the current list"),
+        )
+        .flag(Flag::new("tag-with", FlagType::String).description("Tag the imported TODOs"))
+        .action(import_action)
+}
+
+fn import_action(c: &Context) {
+    let path = match c.args.as_slice() {
+        [path] => path,
+        _ => {
+            eprintln!("Please specify one file");
+            exit(1);
+        }
+    };
+    let from = c.string_flag("from").unwrap_or_default();
+    let content = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            exit(1);
+        }
+    };
+
+    let into = c.string_flag("into").ok().map(|name| {
+        match workspace::lists().into_iter().find(|(n, _)| *n == name) {
+            Some((_, path)) => (name, path),
+            None => {
+                eprintln!("No such workspace: {}", name);
+                exit(1);
+            }
+        }
+    });
+    // Importing into the current list saves it like any other change.
+    let into = into.filter(|(_, path)| *path != Todos::todo_path());
+    let _lock = into.as_ref().map(|(_, path)| lists::lock(path));
+    let (mut todos, trash) = match &into {
+        Some((_, path)) => match lists::read(path) {
+            Ok(t) => (t, Trash::of(path)),
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path, e);
+                exit(1);
+            }
+        },
+        None => (Todos::read(), Trash::read()),
+    };
+    // Tagged before the TODOs are added, so that the defaults and rules
+    // for the tag apply to them.
+    let tags: Vec<String> = c
+        .string_flag("tag-with")
+        .map(|t| crate::tag_name(&t))
+        .into_iter()
+        .filter(|t| !t.is_empty())
+        .collect();
+    let result = match from.as_str() {
+        "json" => json::import(&mut todos, &content, &tags),
+        "trello" => trello::import(&mut todos, &trash, &content, &tags),
+        "" => {
+            eprintln!("Please specify the format with --from");
+            exit(1);
+        }
+        _ => {
+            eprintln!("Unknown format: {}", from);
+            exit(1);
+        }
+    };
+    match result {
+        Ok(counts) => {
+            match &into {
+                Some((name, _)) if dry_run::is_enabled() => {
+                    println!("Would import into {}: {}", name, counts);
+                    return;
+                }
+                Some((_, path)) => {
+                    if let Err(e) = lists::write(path, &todos) {
+                        eprintln!("Failed to write {}: {}", path, e);
+                        exit(1);
+                    }
+                }
+                None => todos.save().unwrap(),
+            }
+            println!("Imported {}", counts);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Trello board exports, from Menu > Print, export and share > Export as JSON.
+//!
+//! Lists become tags, except that cards in a list named like "Done" are
+//! imported as completed and the ones in "Someday" or "Maybe" as someday.
+//! Archived cards and lists are left out.
+
+use super::Counts;
+use crate::date::{self, Date};
+use crate::json::{self, Value};
+use crate::trash::Trash;
+use crate::{someday, Todo, Todos};
+use std::collections::HashMap;
+
+const DONE_LISTS: &[&str] = &["done", "complete", "completed", "finished"];
+const SOMEDAY_LISTS: &[&str] = &["someday", "maybe", "someday/maybe"];
+
+/// Add the open cards of a board, or update those imported before, each
+/// with `tags` too.
+pub fn import(
+    todos: &mut Todos,
+    trash: &Trash,
+    content: &str,
+    tags: &[String],
+) -> anyhow::Result<Counts> {
+    let board = json::parse(content)?;
+    if board.get("cards") == &Value::Null {
+        anyhow::bail!("Not a Trello board export");
+    }
+    let board_id = board.get("id").as_str().unwrap_or("");
+
+    let lists: HashMap<&str, &Value> = board
+        .get("lists")
+        .items()
+        .iter()
+        .filter_map(|l| Some((l.get("id").as_str()?, l)))
+        .collect();
+    let mut checklists: Vec<&Value> = board.get("checklists").items().iter().collect();
+    checklists.sort_by(|a, b| position(a).total_cmp(&position(b)));
+
+    let mut counts = Counts::default();
+    for card in board.get("cards").items() {
+        let Some(card_id) = card.get("id").as_str() else {
+            continue;
+        };
+        let list = card.get("idList").as_str().and_then(|id| lists.get(id));
+        if is_closed(card) || list.is_some_and(|l| is_closed(l)) {
+            continue;
+        }
+        let link = format!("trello {} {}", board_id, card_id);
+
+        let mut todo = Todo::new(
+            "".to_string(),
+            card.get("due")
+                .as_str()
+                .and_then(|d| Date::parse(d.get(..10)?))
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+            card.get("name").as_str().unwrap_or("").trim().to_string(),
+            card.get("shortUrl")
+                .as_str()
+                .or(card.get("url").as_str())
+                .map(|u| vec![u.to_string()])
+                .unwrap_or_default(),
+            "".to_string(),
+        );
+        todo.notes = card.get("desc").as_str().unwrap_or("").to_string();
+
+        let list_name = list
+            .and_then(|l| l.get("name").as_str())
+            .unwrap_or("")
+            .trim();
+        let done = card.get("dueComplete") == &Value::Bool(true);
+        if DONE_LISTS.contains(&list_name.to_lowercase().as_str()) || done {
+            todo.done = "✓".to_string();
+            todo.completed_at = date::now().to_string();
+            todo.completed_by = crate::current_user();
+        } else if SOMEDAY_LISTS.contains(&list_name.to_lowercase().as_str()) {
+            todo.status = someday::SOMEDAY.to_string();
+        } else if !list_name.is_empty() {
+            todo.tags.push(tag(list_name));
+        }
+        for label in card.get("labels").items() {
+            match label.get("name").as_str().map(tag) {
+                Some(t) if !t.is_empty() && !todo.tags.contains(&t) => todo.tags.push(t),
+                _ => {}
+            }
+        }
+        for tag in tags {
+            if !todo.tags.contains(tag) {
+                todo.tags.push(tag.clone());
+            }
+        }
+
+        for checklist in checklists
+            .iter()
+            .filter(|c| c.get("idCard").as_str() == Some(card_id))
+        {
+            let mut items: Vec<&Value> = checklist.get("checkItems").items().iter().collect();
+            items.sort_by(|a, b| position(a).total_cmp(&position(b)));
+            for item in items {
+                let checked = item.get("state").as_str() == Some("complete");
+                todo.checklist.push(format!(
+                    "[{}] {}",
+                    if checked { "x" } else { " " },
+                    item.get("name").as_str().unwrap_or("").trim()
+                ));
+            }
+        }
+
+        super::upsert(todos, trash, &link, todo, &mut counts)?;
+    }
+    Ok(counts)
+}
+
+fn is_closed(item: &Value) -> bool {
+    item.get("closed") == &Value::Bool(true)
+}
+
+fn position(item: &Value) -> f64 {
+    item.get("pos").as_f64().unwrap_or(0.0)
+}
+
+/// `To Do` becomes `to-do`.
+fn tag(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
@@ -0,0 +1,192 @@
+//! Creating a TODO list explicitly, globally or for a project.
+//!
+//! A project list is a `.todo` file in a directory. Like git does with
+//! `.git`, commands run anywhere below that directory use it instead of
+//! the global list.
+
+use crate::{config, crypt, dry_run, format, migrations, Todos};
+use dirs::home_dir;
+use seahorse::{Command, Context, Flag, FlagType};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, exit};
+
+/// Name of a project list file.
+const LOCAL_FILE: &str = ".todo";
+
+pub fn init_command() -> Command {
+    Command::new("init")
+        .description("Create a TODO list")
+        .usage("todo init [--local | --path <file>] [--encrypt <gpg key>] [--git]")
+        .flag(
+            Flag::new("local", FlagType::Bool)
+                .alias("l")
+                .description("Create a list for the project in the current directory"),
+        )
+        .flag(
+            Flag::new("path", FlagType::String)
+                .description("Where to create the global list, saved as storage.path"),
+        )
+        .flag(
+            Flag::new("backend", FlagType::String).description("Storage format, only text for now"),
+        )
+        .flag(
+            Flag::new("encrypt", FlagType::String)
+                .description("Encrypt the list with GnuPG to this key"),
+        )
+        .flag(
+            Flag::new("git", FlagType::Bool)
+                .description("Keep the list in a git repository of its own"),
+        )
+        .action(init_action)
+}
+
+fn init_action(c: &Context) {
+    match c.string_flag("backend").as_deref() {
+        Ok("text") | Err(_) => {}
+        Ok("sqlite") => {
+            eprintln!("The sqlite backend is not available in this build, please use text");
+            exit(1);
+        }
+        Ok(other) => {
+            eprintln!("Unknown backend: {}", other);
+            exit(1);
+        }
+    }
+    let local = c.bool_flag("local");
+    let custom = c.string_flag("path").ok();
+    let path = match (local, &custom) {
+        (true, Some(_)) => {
+            eprintln!("Please give either --local or --path");
+            exit(1);
+        }
+        (true, None) => env::current_dir()
+            .unwrap()
+            .join(LOCAL_FILE)
+            .to_string_lossy()
+            .into_owned(),
+        (false, Some(path)) => absolute(&config::expand_home(path)),
+        (false, None) => Todos::global_path(),
+    };
+    if Path::new(&path).exists() {
+        eprintln!("{} already exists", path);
+        exit(1);
+    }
+    let dir = Path::new(&path).parent().unwrap_or(Path::new("/"));
+    let git = c.bool_flag("git");
+    if git && home_dir().is_some_and(|home| home == dir) {
+        eprintln!("Please use --path to put the list in its own directory for git");
+        exit(1);
+    }
+
+    if let Err(e) = create(&path, c.string_flag("encrypt").ok().as_deref()) {
+        eprintln!("Failed to create {}: {}", path, e);
+        exit(1);
+    }
+    if let (false, Some(custom)) = (local, &custom) {
+        if let Err(e) = config::write_key("storage", "path", Some(custom)) {
+            eprintln!("Failed to write {}: {}", config::Config::path(), e);
+            exit(1);
+        }
+    }
+    if dry_run::is_enabled() {
+        if git {
+            println!(
+                "Dry run, the list would be committed to git in {}",
+                dir.display()
+            );
+        }
+        return;
+    }
+    let scope = if local { "project" } else { "global" };
+    println!("Created an empty {} TODO list in {}", scope, path);
+
+    if git {
+        if let Err(e) = init_git(dir, &path) {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+fn absolute(path: &str) -> String {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        path.to_string_lossy().into_owned()
+    } else {
+        env::current_dir()
+            .unwrap()
+            .join(path)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Write an empty list at `path`, encrypted to `key` if given.
+fn create(path: &str, key: Option<&str>) -> anyhow::Result<()> {
+    if dry_run::is_enabled() {
+        println!("Dry run, {} would be written", path);
+        return Ok(());
+    }
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut content = format::write(migrations::CURRENT_VERSION, &Todos::default_headers(), &[]);
+    if let Some(key) = key {
+        content = crypt::encrypt(&content, key)?;
+        crypt::set_recipient(path, key)?;
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Start a repository in `dir`, unless it is in one already, and commit
+/// the new list.
+fn init_git(dir: &Path, path: &str) -> anyhow::Result<()> {
+    let git = |args: &[&str]| -> anyhow::Result<bool> {
+        let status = process::Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .stdout(process::Stdio::null())
+            .status()
+            .map_err(|e| anyhow::anyhow!("Failed to run git: {}", e))?;
+        Ok(status.success())
+    };
+    if dir.ancestors().any(|d| d.join(".git").exists()) {
+        println!("{} is already in a git repository", dir.display());
+    } else if !git(&["init", "--quiet"])? {
+        anyhow::bail!("git init failed in {}", dir.display());
+    }
+
+    let mut files = vec![path.to_string()];
+    if crypt::recipient(path).is_some() {
+        files.push(format!("{}.gpg-id", path));
+    }
+    let mut add = vec!["add", "--"];
+    add.extend(files.iter().map(|f| f.as_str()));
+    if !git(&add)? || !git(&["commit", "--quiet", "-m", "Add TODO list"])? {
+        anyhow::bail!("Failed to commit {}", path);
+    }
+    println!("Committed it to git in {}", dir.display());
+    Ok(())
+}
+
+/// The project list in the current directory or the nearest parent, up
+/// to but not including the home directory, whose `.todo` is the global
+/// list by default.
+pub fn find_local() -> Option<String> {
+    let home = home_dir();
+    let cwd = env::current_dir().ok()?;
+    for dir in cwd.ancestors() {
+        if home.as_deref() == Some(dir) {
+            return None;
+        }
+        let candidate = dir.join(LOCAL_FILE);
+        if candidate.is_file() {
+            return Some(candidate.to_string_lossy().into_owned());
+        }
+    }
+    None
+}
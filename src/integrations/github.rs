@@ -0,0 +1,287 @@
+//! GitHub pull request reviews, through the GraphQL API: each unresolved
+//! review thread becomes a TODO, and completing it resolves the thread.
+//!
+//! The token is read from `$GITHUB_TOKEN` or `$GH_TOKEN`, or else from the
+//! keyring under the service `todo-github` and the host (`github.com`) as
+//! account. It needs access to the repository's pull requests. Imported
+//! TODOs keep a `github <host> <thread id>` entry in their `remote_ids`.
+
+use crate::json::Value;
+use crate::{auth, http, importers, trash, Todo, Todos};
+use anyhow::bail;
+use seahorse::{Command, Context, Flag, FlagType};
+use std::process::exit;
+
+const THREADS_QUERY: &str =
+    "query($owner: String!, $repo: String!, $number: Int!, $after: String) {
+  repository(owner: $owner, name: $repo) {
+    pullRequest(number: $number) {
+      reviewThreads(first: 100, after: $after) {
+        nodes {
+          id
+          isResolved
+          path
+          line
+          comments(first: 1) { nodes { body url author { login } } }
+        }
+        pageInfo { hasNextPage endCursor }
+      }
+    }
+  }
+}";
+
+const RESOLVE_MUTATION: &str = "mutation($id: ID!) {
+  resolveReviewThread(input: { threadId: $id }) { thread { isResolved } }
+}";
+
+pub fn gh_command() -> Command {
+    Command::new("gh")
+        .description("Import review comments from GitHub pull requests and resolve them")
+        .usage("todo gh [reviews | resolve]")
+        .action(|c| c.help())
+        .command(
+            Command::new("reviews")
+                .description("Add a TODO for each unresolved review thread of a pull request")
+                .usage("todo gh reviews <pull request URL>")
+                .flag(Flag::new("tag", FlagType::String).description("Tag imported TODOs"))
+                .action(reviews_action),
+        )
+        .command(
+            Command::new("resolve")
+                .description("Complete a TODO and resolve its review thread")
+                .usage("todo gh resolve <id>")
+                .action(resolve_action),
+        )
+}
+
+fn reviews_action(c: &Context) {
+    let [url] = c.args.as_slice() else {
+        eprintln!("Please specify the URL of a pull request");
+        exit(1);
+    };
+    let tag = c
+        .string_flag("tag")
+        .ok()
+        .map(|t| t.trim_start_matches('#').to_string());
+    let result = PullRequest::parse(url).and_then(|pr| {
+        let github = GitHub::connect(&pr.host)?;
+        github.import(&pr, tag)
+    });
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
+
+fn resolve_action(c: &Context) {
+    let [id] = c.args.as_slice() else {
+        eprintln!("Please specify one ID");
+        exit(1);
+    };
+    if let Err(e) = resolve(id) {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
+
+struct PullRequest {
+    host: String,
+    owner: String,
+    repo: String,
+    number: u64,
+}
+
+impl PullRequest {
+    /// `https://<host>/<owner>/<repo>/pull/<number>`, with anything after
+    /// the number, like `/files`, ignored.
+    fn parse(url: &str) -> anyhow::Result<Self> {
+        let rest = url.split_once("://").map_or(url, |(_, r)| r);
+        let parts: Vec<&str> = rest.split(['/', '#', '?']).collect();
+        match parts.as_slice() {
+            [host, owner, repo, "pull", number, ..] => match number.parse() {
+                Ok(number) => Ok(PullRequest {
+                    host: host.to_string(),
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                    number,
+                }),
+                Err(_) => bail!("{} is not a pull request number", number),
+            },
+            _ => bail!("{} is not the URL of a pull request", url),
+        }
+    }
+}
+
+struct GitHub {
+    host: String,
+    auth: String,
+}
+
+impl GitHub {
+    fn connect(host: &str) -> anyhow::Result<Self> {
+        let Some(token) = auth::secret("github", host) else {
+            bail!("Please set $GITHUB_TOKEN or store a token with todo auth set github");
+        };
+        Ok(GitHub {
+            host: host.to_string(),
+            auth: format!("Authorization: Bearer {}", token),
+        })
+    }
+
+    /// The endpoint, which GitHub Enterprise Server has under `/api`.
+    fn url(&self) -> String {
+        if self.host == "github.com" {
+            "https://api.github.com/graphql".to_string()
+        } else {
+            format!("https://{}/api/graphql", self.host)
+        }
+    }
+
+    fn call(&self, query: &str, variables: Vec<(&str, Value)>) -> anyhow::Result<Value> {
+        let body = Value::object(vec![
+            ("query", query.into()),
+            ("variables", Value::object(variables)),
+        ]);
+        let headers = vec![
+            self.auth.clone(),
+            "Accept: application/json".to_string(),
+            "Content-Type: application/json".to_string(),
+        ];
+        let response = http::request("POST", &self.url(), &headers, Some(&body.to_string()))?;
+        let reply = response.json().unwrap_or(Value::Null);
+        let errors: Vec<&str> = reply
+            .get("errors")
+            .items()
+            .iter()
+            .filter_map(|e| e.get("message").as_str())
+            .collect();
+        if !response.is_success() || !errors.is_empty() {
+            let message = match reply.get("message").as_str() {
+                Some(m) => m.to_string(),
+                None => errors.join(", "),
+            };
+            bail!("GitHub answered {}: {}", response.status, message);
+        }
+        Ok(reply.get("data").clone())
+    }
+
+    fn threads(&self, pr: &PullRequest) -> anyhow::Result<Vec<Value>> {
+        let mut threads = vec![];
+        let mut after = Value::Null;
+        loop {
+            let data = self.call(
+                THREADS_QUERY,
+                vec![
+                    ("owner", pr.owner.as_str().into()),
+                    ("repo", pr.repo.as_str().into()),
+                    ("number", Value::Number(pr.number as f64)),
+                    ("after", after),
+                ],
+            )?;
+            let page = data
+                .get("repository")
+                .get("pullRequest")
+                .get("reviewThreads");
+            if page == &Value::Null {
+                bail!("{}/{}#{} was not found", pr.owner, pr.repo, pr.number);
+            }
+            threads.extend(page.get("nodes").items().iter().cloned());
+            let info = page.get("pageInfo");
+            match info.get("endCursor").as_str() {
+                Some(cursor) if info.get("hasNextPage") == &Value::Bool(true) => {
+                    after = cursor.into()
+                }
+                _ => return Ok(threads),
+            }
+        }
+    }
+
+    fn link(&self, thread: &str) -> String {
+        format!("github {} {}", self.host, thread)
+    }
+
+    fn import(&self, pr: &PullRequest, tag: Option<String>) -> anyhow::Result<()> {
+        let threads = self.threads(pr)?;
+        let mut todos = Todos::read();
+        let trash = trash::Trash::read();
+
+        let mut counts = importers::Counts::default();
+        for thread in &threads {
+            let Some(id) = thread.get("id").as_str() else {
+                continue;
+            };
+            if thread.get("isResolved") == &Value::Bool(true) {
+                continue;
+            }
+            let link = self.link(id);
+            let comment = thread.get("comments").get("nodes").items().first();
+            let Some(comment) = comment else {
+                continue;
+            };
+            let body = comment.get("body").as_str().unwrap_or("").trim();
+            let path = thread.get("path").as_str().unwrap_or("");
+            let location = match thread.get("line").as_f64() {
+                Some(line) => format!("{}:{}", path, line),
+                None => path.to_string(),
+            };
+            let title = format!(
+                "{} ({})",
+                body.lines().next().unwrap_or("").trim(),
+                location
+            );
+            let mut todo = Todo::new(
+                "".to_string(),
+                "".to_string(),
+                title,
+                comment
+                    .get("url")
+                    .as_str()
+                    .map(|u| u.to_string())
+                    .into_iter()
+                    .collect(),
+                "".to_string(),
+            );
+            let author = comment
+                .get("author")
+                .get("login")
+                .as_str()
+                .unwrap_or("ghost");
+            todo.notes = format!(
+                "{}\n\n@{} on {}/{}#{}",
+                body, author, pr.owner, pr.repo, pr.number
+            );
+            todo.tags.extend(tag.clone());
+            importers::upsert(&mut todos, &trash, &link, todo, &mut counts)?;
+        }
+        todos.save()?;
+        println!(
+            "Imported review threads from {}/{}#{}: {}",
+            pr.owner, pr.repo, pr.number, counts
+        );
+        Ok(())
+    }
+}
+
+fn resolve(id: &str) -> anyhow::Result<()> {
+    let mut todos = Todos::read();
+    let todo = todos.find_mut(id)?;
+    let Some((host, thread)) = todo
+        .remote_ids
+        .iter()
+        .find_map(|r| r.strip_prefix("github ")?.split_once(' '))
+        .map(|(h, t)| (h.to_string(), t.to_string()))
+    else {
+        bail!("TODO #{} is not linked to a GitHub review thread", id);
+    };
+
+    let github = GitHub::connect(&host)?;
+    github.call(RESOLVE_MUTATION, vec![("id", thread.as_str().into())])?;
+
+    let todo = todos.done(id.to_string())?;
+    todos.save()?;
+    println!(
+        "Completed #{}: {} (resolved on {})",
+        todo.id, todo.title, host
+    );
+    Ok(())
+}
@@ -0,0 +1,270 @@
+//! Jira, through its REST API with an API token.
+//!
+//! ```toml
+//! [jira]
+//! url = "https://example.atlassian.net"
+//! email = "you@example.com"
+//! done_transition = "Done"    # default: the first one to a done status
+//! ```
+//!
+//! The token is read from `$JIRA_API_TOKEN`, or else from the keyring
+//! under the service `todo-jira` and the email as account. Imported TODOs
+//! keep a `jira <host> <issue key>` entry in their `remote_ids`.
+
+use crate::json::Value;
+use crate::{auth, config, http, importers, trash, Todo, Todos};
+use anyhow::bail;
+use seahorse::{Command, Context, Flag, FlagType};
+use std::env;
+use std::process::exit;
+
+const DEFAULT_JQL: &str = "assignee = currentUser() AND statusCategory != Done";
+
+pub fn jira_command() -> Command {
+    Command::new("jira")
+        .description("Import issues from Jira and complete them")
+        .usage("todo jira [import | done]")
+        .action(|c| c.help())
+        .command(
+            Command::new("import")
+                .description("Add a TODO for each issue a JQL query finds")
+                .usage(format!("todo jira import [--jql '{}']", DEFAULT_JQL))
+                .flag(Flag::new("jql", FlagType::String).description("Issues to import"))
+                .flag(Flag::new("tag", FlagType::String).description("Tag imported TODOs"))
+                .action(import_action),
+        )
+        .command(
+            Command::new("done")
+                .description("Complete a TODO and transition its issue to done")
+                .usage("todo jira done <id>")
+                .action(done_action),
+        )
+}
+
+fn import_action(c: &Context) {
+    let jql = c
+        .string_flag("jql")
+        .unwrap_or_else(|_| DEFAULT_JQL.to_string());
+    let tag = c
+        .string_flag("tag")
+        .ok()
+        .map(|t| t.trim_start_matches('#').to_string());
+    let result = Jira::connect().and_then(|jira| jira.import(&jql, tag));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
+
+fn done_action(c: &Context) {
+    if c.args.len() != 1 {
+        eprintln!("Please specify one ID");
+        exit(1);
+    }
+    let result = Jira::connect().and_then(|jira| jira.done(&c.args[0]));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
+
+struct Jira {
+    /// The site, without a trailing slash.
+    url: String,
+    auth: String,
+}
+
+impl Jira {
+    fn connect() -> anyhow::Result<Self> {
+        let config = config::get();
+        let setting = |var: &str, key: &str| {
+            env::var(var)
+                .ok()
+                .or_else(|| config.string(key).map(|s| s.to_string()))
+        };
+        let (Some(url), Some(email)) = (
+            setting("JIRA_URL", "jira.url"),
+            setting("JIRA_EMAIL", "jira.email"),
+        ) else {
+            bail!(
+                "Please set jira.url and jira.email in {}",
+                config::Config::path()
+            );
+        };
+        let Some(token) = auth::secret("jira", &email) else {
+            bail!("Please set $JIRA_API_TOKEN or store the token with todo auth set jira");
+        };
+        Ok(Jira {
+            url: url.trim_end_matches('/').to_string(),
+            auth: http::basic_auth(&email, &token),
+        })
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&Value>,
+    ) -> anyhow::Result<http::Response> {
+        let mut headers = vec![self.auth.clone(), "Accept: application/json".to_string()];
+        if body.is_some() {
+            headers.push("Content-Type: application/json".to_string());
+        }
+        let body = body.map(|b| b.to_string());
+        let url = format!("{}/rest/api/2/{}", self.url, path);
+        http::request(method, &url, &headers, body.as_deref())
+    }
+
+    fn call(&self, method: &str, path: &str, body: Option<&Value>) -> anyhow::Result<Value> {
+        Self::reply(self.send(method, path, body)?)
+    }
+
+    fn reply(response: http::Response) -> anyhow::Result<Value> {
+        if !response.is_success() {
+            let reply = response.json().unwrap_or(Value::Null);
+            let message = reply
+                .get("errorMessages")
+                .items()
+                .iter()
+                .filter_map(|m| m.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            bail!("Jira answered {}: {}", response.status, message);
+        }
+        if response.body.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        response.json()
+    }
+
+    /// Every issue `jql` finds. Jira Cloud pages with `search/jql`, while
+    /// Server and Data Center only have the older `search`.
+    fn search(&self, jql: &str) -> anyhow::Result<Vec<Value>> {
+        let query = format!(
+            "jql={}&fields=summary,duedate&maxResults=100",
+            http::encode(jql)
+        );
+        let mut issues = vec![];
+        let mut legacy = false;
+        let mut page = String::new();
+        loop {
+            let path = if legacy {
+                format!("search?{}&startAt={}", query, issues.len())
+            } else {
+                format!("search/jql?{}&nextPageToken={}", query, http::encode(&page))
+            };
+            let response = self.send("GET", &path, None)?;
+            if !legacy && page.is_empty() && response.status == 404 {
+                legacy = true;
+                continue;
+            }
+            let reply = Self::reply(response)?;
+            let found = reply.get("issues").items();
+            issues.extend(found.iter().cloned());
+            if legacy {
+                let total = reply.get("total").as_f64().unwrap_or(0.0) as usize;
+                if found.is_empty() || issues.len() >= total {
+                    return Ok(issues);
+                }
+            } else {
+                match reply.get("nextPageToken").as_str() {
+                    Some(next) => page = next.to_string(),
+                    None => return Ok(issues),
+                }
+            }
+        }
+    }
+
+    fn link(&self, key: &str) -> String {
+        format!("jira {} {}", http::host(&self.url), key)
+    }
+
+    fn import(&self, jql: &str, tag: Option<String>) -> anyhow::Result<()> {
+        let issues = self.search(jql)?;
+        let mut todos = Todos::read();
+        let trash = trash::Trash::read();
+
+        let mut counts = importers::Counts::default();
+        for issue in &issues {
+            let Some(key) = issue.get("key").as_str() else {
+                continue;
+            };
+            let link = self.link(key);
+            let fields = issue.get("fields");
+            let title = format!(
+                "{} {}",
+                key,
+                fields.get("summary").as_str().unwrap_or("").trim()
+            );
+            let mut todo = Todo::new(
+                "".to_string(),
+                fields.get("duedate").as_str().unwrap_or("").to_string(),
+                title,
+                vec![format!("{}/browse/{}", self.url, key)],
+                "".to_string(),
+            );
+            todo.tags.extend(tag.clone());
+            importers::upsert(&mut todos, &trash, &link, todo, &mut counts)?;
+        }
+        todos.save()?;
+        println!("Imported issues from {}: {}", http::host(&self.url), counts);
+        Ok(())
+    }
+
+    fn done(&self, id: &str) -> anyhow::Result<()> {
+        let mut todos = Todos::read();
+        let prefix = format!("jira {} ", http::host(&self.url));
+        let todo = todos.find_mut(id)?;
+        let Some(key) = todo
+            .remote_ids
+            .iter()
+            .find_map(|r| r.strip_prefix(&prefix))
+            .map(|k| k.to_string())
+        else {
+            bail!("TODO #{} is not linked to a Jira issue", id);
+        };
+
+        let path = format!("issue/{}/transitions", http::encode(&key));
+        let reply = self.call("GET", &path, None)?;
+        let wanted = config::get().string("jira.done_transition");
+        let transition = reply
+            .get("transitions")
+            .items()
+            .iter()
+            .find(|t| match wanted {
+                Some(w) => {
+                    t.get("name")
+                        .as_str()
+                        .is_some_and(|n| n.eq_ignore_ascii_case(w))
+                        || t.get("to")
+                            .get("name")
+                            .as_str()
+                            .is_some_and(|n| n.eq_ignore_ascii_case(w))
+                }
+                None => t.get("to").get("statusCategory").get("key").as_str() == Some("done"),
+            });
+        let Some(transition) = transition else {
+            bail!(
+                "{} has no transition to {}",
+                key,
+                wanted.unwrap_or("a done status")
+            );
+        };
+        let body = Value::object(vec![(
+            "transition",
+            Value::object(vec![("id", transition.get("id").clone())]),
+        )]);
+        self.call("POST", &path, Some(&body))?;
+
+        let todo = todos.done(id.to_string())?;
+        todos.save()?;
+        println!(
+            "Completed #{}: {} ({} is now {})",
+            todo.id,
+            todo.title,
+            key,
+            transition.get("to").get("name").as_str().unwrap_or("done")
+        );
+        Ok(())
+    }
+}
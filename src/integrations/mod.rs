@@ -0,0 +1,5 @@
+//! One-way links with issue trackers and code review: TODOs are imported
+//! from them and completing one updates the issue or review thread.
+
+pub mod github;
+pub mod jira;
@@ -0,0 +1,71 @@
+//! Checksums of the list, to notice when it was not fully written, or was
+//! damaged or changed by something else. Each save records the SHA-256
+//! of the file in `<list>.sha256`, and first keeps the version it
+//! replaces as `<list>.bak` if that one was intact, so that there is
+//! always a good copy to go back to. Reading a list that does not match
+//! its checksum warns; `todo doctor --verify` tells more and offers to
+//! restore the backup. A list edited by hand warns until it is saved.
+
+use crate::sha256;
+use std::fs;
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    /// Nothing was recorded yet, as for lists from before checksums.
+    Unknown,
+    Intact,
+    Damaged,
+}
+
+fn checksum_path(path: &str) -> String {
+    format!("{}.sha256", path)
+}
+
+pub fn backup_path(path: &str) -> String {
+    format!("{}.bak", path)
+}
+
+pub fn status(path: &str) -> Status {
+    let Ok(recorded) = fs::read_to_string(checksum_path(path)) else {
+        return Status::Unknown;
+    };
+    match fs::read(path) {
+        Ok(content) if sha256::hex(&sha256::digest(&content)) == recorded.trim() => Status::Intact,
+        _ => Status::Damaged,
+    }
+}
+
+/// Keep the list at `path` as the backup before it is replaced, unless it
+/// is damaged.
+pub fn before_write(path: &str) {
+    if status(path) != Status::Damaged {
+        let _ = fs::copy(path, backup_path(path));
+    }
+}
+
+/// Record the checksum of `content`, just written to `path`. A list that
+/// cannot have one, as in a read-only directory, goes without.
+pub fn record(path: &str, content: &[u8]) {
+    let _ = fs::write(
+        checksum_path(path),
+        format!("{}\n", sha256::hex(&sha256::digest(content))),
+    );
+}
+
+/// Warn when the list at `path` does not match its checksum.
+pub fn check(path: &str) {
+    if status(path) == Status::Damaged {
+        eprintln!(
+            "Warning: {} does not match its checksum, see todo doctor --verify",
+            path
+        );
+    }
+}
+
+/// Put the backup in place of the list at `path`.
+pub fn restore(path: &str) -> std::io::Result<()> {
+    let content = fs::read(backup_path(path))?;
+    fs::write(path, &content)?;
+    record(path, &content);
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Secrets from the system keyring, through the platform's command line
+//! tool: `security` on macOS and `secret-tool` (libsecret) elsewhere.
+//!
+//! To store one, e.g. for Jira, use `todo auth set jira`, see `auth`, or
+//! the tools themselves:
+//!
+//! ```sh
+//! security add-generic-password -s todo-jira -a you@example.com -w      # macOS
+//! secret-tool store --label "todo Jira" service todo-jira account you@example.com
+//! ```
+
+use anyhow::{bail, Context};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// The secret stored for `service` and `account`, if any.
+pub fn get(service: &str, account: &str) -> Option<String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut c = Command::new("security");
+        c.args(["find-generic-password", "-s", service, "-a", account, "-w"]);
+        c
+    } else {
+        let mut c = Command::new("secret-tool");
+        c.args(["lookup", "service", service, "account", account]);
+        c
+    };
+    let output = command.stderr(Stdio::null()).output().ok()?;
+    let secret = String::from_utf8(output.stdout).ok()?;
+    let secret = secret.trim_end_matches('\n');
+    if output.status.success() && !secret.is_empty() {
+        Some(secret.to_string())
+    } else {
+        None
+    }
+}
+
+/// Store `secret` for `service` and `account`, replacing any there was.
+/// It is handed over on stdin, so that it does not show up in `ps`.
+pub fn set(service: &str, account: &str, secret: &str) -> anyhow::Result<()> {
+    let (mut command, input) = if cfg!(target_os = "macos") {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let input = format!(
+            "add-generic-password -U -s {} -a {} -w {}\n",
+            quote(service),
+            quote(account),
+            quote(secret)
+        );
+        let mut c = Command::new("security");
+        c.arg("-i");
+        (c, input)
+    } else {
+        let mut c = Command::new("secret-tool");
+        c.args(["store", "--label", &format!("todo {}", service)])
+            .args(["service", service, "account", account]);
+        (c, secret.to_string())
+    };
+    run(&mut command, &input)
+}
+
+/// Remove the secret stored for `service` and `account`.
+pub fn delete(service: &str, account: &str) -> anyhow::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut c = Command::new("security");
+        c.args(["delete-generic-password", "-s", service, "-a", account]);
+        c
+    } else {
+        let mut c = Command::new("secret-tool");
+        c.args(["clear", "service", service, "account", account]);
+        c
+    };
+    run(&mut command, "")
+}
+
+fn run(command: &mut Command, input: &str) -> anyhow::Result<()> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run the keyring tool, security or secret-tool")?;
+    child.stdin.take().unwrap().write_all(input.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "The keyring failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
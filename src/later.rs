@@ -0,0 +1,71 @@
+use crate::{clipboard, rules, Todo, Todos};
+use seahorse::{Command, Context};
+use std::process::exit;
+
+/// Tag of the TODOs in the reading queue.
+pub const READ_LATER: &str = "read-later";
+
+pub fn later_command() -> Command {
+    Command::new("later")
+        .description("Save a link to read later")
+        .usage("todo later [<url> | list]")
+        .action(|c| {
+            let url = match c.args.as_slice() {
+                [] => return list_action(c),
+                [url] if clipboard::find_url(url) == Some(url.as_str()) => url,
+                _ => {
+                    eprintln!("Please specify one URL");
+                    exit(1);
+                }
+            };
+            add(url);
+        })
+        .command(
+            Command::new("list")
+                .description("Show the reading queue, oldest first")
+                .usage("todo later list")
+                .alias("ls")
+                .action(list_action),
+        )
+}
+
+fn add(url: &str) {
+    let mut todos = Todos::read();
+    let mut todo = Todo::new(
+        "".to_string(),
+        "".to_string(),
+        url.to_string(),
+        vec![url.to_string()],
+        "".to_string(),
+    );
+    todo.tags.push(READ_LATER.to_string());
+    #[cfg(feature = "fetch-title")]
+    match crate::page::fetch(url) {
+        Ok(page) => {
+            if !page.title.is_empty() {
+                todo.title = page.title.clone();
+            }
+            todo.estimate = page.reading_minutes().to_string();
+        }
+        Err(e) => eprintln!("Failed to fetch the page: {}", e),
+    }
+
+    rules::apply(&mut todo);
+    let todo = todos.add(todo).unwrap();
+    todos.save().unwrap();
+    match todo.estimate_label().as_str() {
+        "" => println!("Added #{}: {}", todo.id, todo.title),
+        read => println!("Added #{}: {} ({} read)", todo.id, todo.title, read),
+    }
+}
+
+fn list_action(_: &Context) {
+    let mut todos = Todos::read();
+    todos
+        .records
+        .retain(|t| t.tags.iter().any(|tag| tag == READ_LATER));
+    todos
+        .records
+        .sort_by_key(|t| t.created_at.parse::<u64>().unwrap_or(0));
+    todos.print_list(false);
+}
@@ -0,0 +1,196 @@
+//! The lists of all workspaces together: `todo list --all-lists` shows
+//! their TODOs in one table, with a LIST column saying whose they are,
+//! and `todo move` and `todo copy` take TODOs from the current list to
+//! another. A list can be given a color and an icon in the table, so
+//! that those of each stand out:
+//!
+//! ```toml
+//! [lists.work]
+//! color = "blue"      # a name, like red or cyan, a number or "r,g,b"
+//! icon = "💼"
+//!
+//! [lists.default]
+//! icon = "🏠"
+//! ```
+//!
+//! While listed, each TODO has its workspace in the `list` field, so
+//! that `--where list:work` narrows the table down to one of them.
+
+use crate::{batch, config, crypt, dry_run, fields, id_alias, integrity, lock, workspace, Todos};
+use cli_table::Color;
+use seahorse::{Command, Context, Flag, FlagType};
+use std::io::ErrorKind;
+use std::process::exit;
+
+pub const FIELD: &str = "list";
+
+pub fn move_command() -> Command {
+    Command::new("move")
+        .description("Move TODOs to the list of another workspace")
+        .usage("todo move <todo id>... --to <workspace>")
+        .alias("mv")
+        .flag(Flag::new("to", FlagType::String).description("Workspace to move them to"))
+        .action(|c| transfer(c, true))
+}
+
+pub fn copy_command() -> Command {
+    Command::new("copy")
+        .description("Copy TODOs to the list of another workspace")
+        .usage("todo copy <todo id>... --to <workspace>")
+        .alias("cp")
+        .flag(Flag::new("to", FlagType::String).description("Workspace to copy them to"))
+        .action(|c| transfer(c, false))
+}
+
+/// Add the TODOs to the other list under its next IDs, keeping when they
+/// were added and done and by whom, and only then take moved ones out
+/// of this list, so that a failure leaves them where they were.
+fn transfer(c: &Context, remove: bool) {
+    let Ok(to) = c.string_flag("to") else {
+        eprintln!("Please specify a workspace with --to");
+        exit(1);
+    };
+    if c.args.is_empty() {
+        eprintln!("Please specify at least one ID");
+        exit(1);
+    }
+    let Some((_, path)) = workspace::lists().into_iter().find(|(name, _)| *name == to) else {
+        eprintln!("No such workspace: {}", to);
+        exit(1);
+    };
+    if path == Todos::todo_path() {
+        eprintln!("The TODOs are already in the list of {}", to);
+        exit(1);
+    }
+
+    let mut todos = Todos::read();
+    let _lock = lock(&path);
+    let mut other = match read(&path) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            exit(1);
+        }
+    };
+    let mut transferred = vec![];
+    for id in &c.args {
+        let Some(todo) = todos.records.iter().find(|r| r.id == *id) else {
+            eprintln!("#{} does not exist", id);
+            exit(1);
+        };
+        let mut todo = todo.clone();
+        todo.id = other.next_id().to_string();
+        if !remove {
+            // A copy is a TODO of its own to the services the list syncs with.
+            todo.remote_ids.clear();
+        }
+        // Names go along unless the other list has the name already.
+        if let Some(name) = fields::get(&todo, id_alias::FIELD) {
+            if id_alias::find(&other, name).is_some() {
+                fields::set(&mut todo, id_alias::FIELD, "");
+            }
+        }
+        transferred.push((id, todo.clone()));
+        other.records.push(todo);
+    }
+
+    let (verb, past) = if remove {
+        ("move", "Moved")
+    } else {
+        ("copy", "Copied")
+    };
+    if dry_run::is_enabled() {
+        for (id, todo) in &transferred {
+            println!("Would {} #{} to {} as #{}", verb, id, to, todo.id);
+        }
+        return;
+    }
+    if let Err(e) = write(&path, &other) {
+        eprintln!("Failed to write {}: {}", path, e);
+        exit(1);
+    }
+    if remove {
+        todos.records.retain(|r| !c.args.contains(&r.id));
+        todos.save().unwrap();
+    }
+    for (id, todo) in &transferred {
+        println!("{} #{} to {} as #{}: {}", past, id, to, todo.id, todo.title);
+    }
+}
+
+/// The TODOs of the list at `path`, none when there is no such file.
+pub fn read(path: &str) -> anyhow::Result<Todos> {
+    integrity::check(path);
+    let content = match batch::read(path) {
+        Ok(c) if crypt::is_encrypted(&c) => crypt::decrypt(&c)?,
+        Ok(c) => c,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Todos::parse(&content).0)
+}
+
+/// Lock the other list at `path` until the returned file is dropped, or
+/// in a batch, which writes it at its end, until then.
+pub fn lock(path: &str) -> Option<std::fs::File> {
+    if batch::is_active() {
+        lock::hold_other_until_exit(path);
+        return None;
+    }
+    lock::hold_other(path)
+}
+
+/// Replace the list at `path` at once, encrypted if it is meant to be,
+/// or at the end of the batch, with the rest of it.
+pub fn write(path: &str, todos: &Todos) -> anyhow::Result<()> {
+    let content = match crypt::recipient(path) {
+        Some(recipient) => crypt::encrypt(&todos.content(), &recipient)?,
+        None => todos.content(),
+    };
+    if batch::is_active() {
+        batch::stage_other_list(path, content);
+        return Ok(());
+    }
+    integrity::before_write(path);
+    crate::write_file(path, &content, "move")?;
+    integrity::record(path, content.as_bytes());
+    Ok(())
+}
+
+/// The TODOs of all workspaces, each with its workspace in `FIELD`.
+pub fn read_all() -> Todos {
+    let mut merged = Todos {
+        headers: Todos::default_headers(),
+        records: vec![],
+        skipped: vec![],
+    };
+    for (name, path) in workspace::lists() {
+        let todos = match read(&path) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path, e);
+                exit(1);
+            }
+        };
+        merged
+            .records
+            .extend(todos.records.into_iter().map(|mut r| {
+                fields::set(&mut r, FIELD, &name);
+                r
+            }));
+    }
+    merged
+}
+
+/// How the list `name` is shown: with its icon, and in its color.
+pub fn label(name: &str) -> (String, Option<Color>) {
+    let config = config::get();
+    let text = match config.string(&format!("lists.{}.icon", name)) {
+        Some(icon) => format!("{} {}", icon, name),
+        None => name.to_string(),
+    };
+    let color = config
+        .string(&format!("lists.{}.color", name))
+        .and_then(|c| c.parse().ok());
+    (text, color)
+}
@@ -0,0 +1,71 @@
+//! Where a TODO can be done, kept in its `location` field: a label such
+//! as `supermarket` or `@errands`, or coordinates as `lat,long`. Labels
+//! can be given coordinates, so that `todo list --near` also finds the
+//! TODOs of places close by:
+//!
+//! ```toml
+//! [location]
+//! radius = 2                          # kilometres, default: 1
+//!
+//! [location.places]
+//! supermarket = "35.6581,139.7017"
+//! office = "35.6812,139.7671"
+//! ```
+
+use crate::{config, fields, Todo};
+
+pub const FIELD: &str = "location";
+
+/// Kilometres within which places count as near without `location.radius`.
+const DEFAULT_RADIUS: f64 = 1.0;
+
+pub fn get(todo: &Todo) -> Option<&str> {
+    fields::get(todo, FIELD)
+}
+
+/// Whether `todo` can be done at or near `place`: its label contains
+/// `place`, ignoring case, or both have coordinates within the radius.
+pub fn is_near(todo: &Todo, place: &str) -> bool {
+    let Some(location) = get(todo) else {
+        return false;
+    };
+    if location.to_lowercase().contains(&place.to_lowercase()) {
+        return true;
+    }
+    match (coordinates(location), coordinates(place)) {
+        (Some(a), Some(b)) => distance(a, b) <= radius(),
+        _ => false,
+    }
+}
+
+fn radius() -> f64 {
+    config::get()
+        .string("location.radius")
+        .and_then(|r| r.parse().ok())
+        .unwrap_or(DEFAULT_RADIUS)
+}
+
+/// `lat,long` in degrees, or those of a place in `[location.places]`.
+fn coordinates(s: &str) -> Option<(f64, f64)> {
+    let parse = |s: &str| {
+        let (lat, long) = s.split_once(',')?;
+        let (lat, long): (f64, f64) = (lat.trim().parse().ok()?, long.trim().parse().ok()?);
+        ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&long)).then_some((lat, long))
+    };
+    parse(s).or_else(|| {
+        config::get()
+            .section("location.places")
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s.trim()))
+            .and_then(|(_, c)| parse(c))
+    })
+}
+
+/// Great-circle distance in kilometres.
+fn distance((lat1, long1): (f64, f64), (lat2, long2): (f64, f64)) -> f64 {
+    const EARTH_RADIUS: f64 = 6371.0;
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let (dlat, dlong) = (lat2 - lat1, (long2 - long1).to_radians());
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlong / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
@@ -0,0 +1,126 @@
+//! Keeping two `todo` processes from changing the list at the same time,
+//! also when they are run by different users of a shared list.
+//!
+//! The lock is a `flock` on `<list>.lock`, taken when the list is read
+//! and held until the process exits or, for one that keeps running like
+//! `todo watch`, releases it. The file is left in place, readable
+//! and writable by everyone, and names the current holder.
+
+use std::fs::File;
+use std::sync::Mutex;
+
+/// How long to wait for another process before giving up.
+#[cfg(unix)]
+const WAIT_SECONDS: u64 = 10;
+
+static HELD: Mutex<Option<File>> = Mutex::new(None);
+/// Other lists locked until the process exits, by path.
+static OTHERS: Mutex<Vec<(String, File)>> = Mutex::new(vec![]);
+
+/// Lock the list at `path` until the process exits or `release`s it,
+/// waiting for whoever has it. Without a writable lock file, as on a
+/// read-only mount, the list is used unlocked since it cannot be changed
+/// anyway.
+pub fn hold(path: &str) {
+    let mut held = HELD.lock().unwrap();
+    if held.is_none() {
+        *held = acquire(path);
+    }
+}
+
+/// Also lock the list at `path`, such as one TODOs are moved to, until
+/// the returned file is dropped.
+pub fn hold_other(path: &str) -> Option<File> {
+    acquire(path)
+}
+
+/// Also lock the list at `path` until the process exits, like `hold`
+/// does the list, for a batch that writes it at its end.
+pub fn hold_other_until_exit(path: &str) {
+    let mut others = OTHERS.lock().unwrap();
+    if others.iter().any(|(p, _)| p == path) {
+        return;
+    }
+    if let Some(file) = acquire(path) {
+        others.push((path.to_string(), file));
+    }
+}
+
+/// Let other processes have the list again until the next `hold`.
+pub fn release() {
+    if HELD.lock().unwrap().take().is_some() {
+        crate::log::debug("lock", || "released".to_string());
+    }
+}
+
+#[cfg(unix)]
+fn acquire(path: &str) -> Option<File> {
+    use std::fs::{self, OpenOptions, Permissions};
+    use std::io::{Read, Seek, Write};
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    use std::os::unix::io::AsRawFd;
+    use std::process::exit;
+    use std::time::{Duration, Instant};
+
+    let lock_path = format!("{}.lock", path);
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .mode(0o666)
+        .open(&lock_path)
+        .ok()?;
+    // The umask may have taken write access from the others; only the
+    // owner can give it back, so this fails quietly for everyone else.
+    let _ = fs::set_permissions(&lock_path, Permissions::from_mode(0o666));
+
+    let try_lock =
+        |file: &File| unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) == 0 };
+    let start = Instant::now();
+    let mut holder = None;
+    while !try_lock(&file) {
+        let holder = holder.get_or_insert_with(|| {
+            let mut holder = String::new();
+            let _ = file.read_to_string(&mut holder);
+            let holder = match holder.trim() {
+                "" => "another process".to_string(),
+                h => h.to_string(),
+            };
+            eprintln!("Waiting for {} to finish with {}...", holder, path);
+            crate::log::info("lock", || {
+                format!("waiting path={} holder={:?}", path, holder)
+            });
+            holder
+        });
+        if start.elapsed() > Duration::from_secs(WAIT_SECONDS) {
+            crate::log::warn("lock", || {
+                format!("gave up path={} holder={:?}", path, holder)
+            });
+            eprintln!(
+                "Gave up waiting for {} after {} seconds",
+                holder, WAIT_SECONDS
+            );
+            exit(1);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    crate::log::debug("lock", || {
+        let waited = start.elapsed().as_millis();
+        format!("acquired path={} waited_ms={}", lock_path, waited)
+    });
+    let _ = file.set_len(0);
+    let _ = file.rewind();
+    let _ = write!(
+        file,
+        "{} (pid {})",
+        crate::current_user(),
+        std::process::id()
+    );
+    Some(file)
+}
+
+#[cfg(not(unix))]
+fn acquire(_: &str) -> Option<File> {
+    None
+}
@@ -0,0 +1,230 @@
+//! Logging for debugging, controlled by `RUST_LOG` like the Rust tools
+//! that use `env_logger` or `tracing`:
+//!
+//! ```sh
+//! RUST_LOG=debug todo sync ha        # everything at debug or above
+//! RUST_LOG=warn,lock=trace todo ls   # and all about the lock
+//! ```
+//!
+//! The levels are `error`, `warn`, `info`, `debug` and `trace`, and the
+//! targets `command`, `file`, `lock`, `http` for the requests of syncs
+//! and integrations, `sync` and `daemon`. A `todo=<level>` is the same as `<level>`. Lines go to
+//! stderr, as `<time> <LEVEL> <target>: <message>`, where messages are
+//! `key=value` pairs after a word or two.
+//!
+//! With `log.file` set, they go to a file instead, which is also where
+//! the daemon and `todo serve` can be debugged from:
+//!
+//! ```toml
+//! [log]
+//! file = true     # or a path, rather than ~/.local/share/todo/todo.log
+//! level = "debug" # when RUST_LOG is not set, info otherwise
+//! ```
+//!
+//! The file is rotated once it grows past 1 MiB, keeping three old ones
+//! as `todo.log.1` to `todo.log.3`.
+
+use crate::config;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_SIZE: u64 = 1024 * 1024;
+const KEEP: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "error" => Some(Level::Error),
+            "warn" | "warning" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+/// What to log: the level of each target that has one, and of all
+/// others.
+#[derive(Debug, PartialEq)]
+struct Filter {
+    default: Option<Level>,
+    targets: Vec<(String, Level)>,
+}
+
+impl Filter {
+    /// A filter like `RUST_LOG`'s, skipping what is not understood.
+    fn parse(s: &str) -> Self {
+        let mut filter = Filter {
+            default: None,
+            targets: vec![],
+        };
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.split_once('=') {
+                Some(("todo", level)) => filter.default = Level::parse(level).or(filter.default),
+                Some((target, level)) => {
+                    if let Some(level) = Level::parse(level) {
+                        filter.targets.push((target.to_string(), level));
+                    }
+                }
+                None => filter.default = Level::parse(part).or(filter.default),
+            }
+        }
+        filter
+    }
+
+    fn allows(&self, level: Level, target: &str) -> bool {
+        let max = self
+            .targets
+            .iter()
+            .rev()
+            .find(|(t, _)| t == target)
+            .map(|(_, l)| *l)
+            .or(self.default);
+        max.is_some_and(|max| level <= max)
+    }
+}
+
+struct Logger {
+    filter: Filter,
+    /// The log file, or `None` for stderr.
+    file: Option<String>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+/// Held while writing, so that lines of threads don't interleave.
+static WRITING: Mutex<()> = Mutex::new(());
+
+fn logger() -> &'static Logger {
+    LOGGER.get_or_init(|| {
+        let config = config::get();
+        let file = match config.string("log.file") {
+            None | Some("false") => None,
+            Some("true") => Some(default_path()),
+            Some(path) => Some(config::expand_home(path)),
+        };
+        let filter = match env::var("RUST_LOG") {
+            Ok(s) => Filter::parse(&s),
+            Err(_) if file.is_some() => Filter::parse(config.string("log.level").unwrap_or("info")),
+            Err(_) => Filter::parse(""),
+        };
+        Logger { filter, file }
+    })
+}
+
+/// The log file, if there is one.
+pub fn path() -> Option<&'static str> {
+    logger().file.as_deref()
+}
+
+fn default_path() -> String {
+    let dir = dirs::data_dir().unwrap_or_else(|| ".".into()).join("todo");
+    dir.join("todo.log").to_string_lossy().to_string()
+}
+
+pub fn warn(target: &str, message: impl FnOnce() -> String) {
+    log(Level::Warn, target, message)
+}
+
+pub fn info(target: &str, message: impl FnOnce() -> String) {
+    log(Level::Info, target, message)
+}
+
+pub fn debug(target: &str, message: impl FnOnce() -> String) {
+    log(Level::Debug, target, message)
+}
+
+/// Log the message of `message` at `level` for `target`, if the filter
+/// lets it through; it is only made then.
+pub fn log(level: Level, target: &str, message: impl FnOnce() -> String) {
+    let logger = logger();
+    if !logger.filter.allows(level, target) {
+        return;
+    }
+    let line = format!(
+        "{} {:<5} {}[{}] {}: {}\n",
+        timestamp(),
+        level.name(),
+        env!("CARGO_PKG_NAME"),
+        std::process::id(),
+        target,
+        message()
+    );
+    let _writing = WRITING.lock().unwrap_or_else(|e| e.into_inner());
+    match &logger.file {
+        Some(path) => append(path, &line),
+        None => eprint!("{}", line),
+    }
+}
+
+/// Append `line` to the file at `path`, rotating it first when it is
+/// full. Failing to is not worth failing the command over.
+fn append(path: &str, line: &str) {
+    if fs::metadata(path).is_ok_and(|m| m.len() + line.len() as u64 > MAX_SIZE) {
+        for i in (1..KEEP).rev() {
+            let _ = fs::rename(format!("{}.{}", path, i), format!("{}.{}", path, i + 1));
+        }
+        let _ = fs::rename(path, format!("{}.1", path));
+    }
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = file.write_all(line.as_bytes());
+    }
+}
+
+/// The time in UTC, as `2026-01-02T03:04:05.678Z`.
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs();
+    let day = crate::date::Date::from_days((secs / 86400) as i64);
+    format!(
+        "{}T{:02}:{:02}:{:02}.{:03}Z",
+        day,
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60,
+        now.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_filters_like_rust_log() {
+        let filter = Filter::parse("warn,lock=trace,http=nonsense");
+        assert!(filter.allows(Level::Warn, "file"));
+        assert!(!filter.allows(Level::Info, "file"));
+        assert!(filter.allows(Level::Trace, "lock"));
+        assert!(!filter.allows(Level::Info, "http"));
+        assert!(!Filter::parse("").allows(Level::Error, "file"));
+        assert!(Filter::parse("todo=debug").allows(Level::Debug, "daemon"));
+    }
+}
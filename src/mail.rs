@@ -0,0 +1,63 @@
+use crate::{auth, config};
+use anyhow::{bail, Context};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Send an RFC 5322 `message` through the SMTP server of the `[smtp]`
+/// config section, using curl:
+///
+/// ```toml
+/// [smtp]
+/// host = "smtp.example.com"
+/// port = 465          # 465 for implicit TLS, anything else uses STARTTLS
+/// user = "me@example.com"
+/// password = "..."    # or $TODO_SMTP_PASSWORD
+/// ```
+pub fn send(message: &str, from: &str, to: &[String]) -> anyhow::Result<()> {
+    let config = config::get();
+    let host = match config.string("smtp.host") {
+        Some(h) => h,
+        None => bail!("Please set smtp.host in {}", config::Config::path()),
+    };
+    let port = config.string("smtp.port").unwrap_or("465");
+    let scheme = if port == "465" { "smtps" } else { "smtp" };
+
+    let path = env::temp_dir().join(format!("todo-mail-{}.eml", std::process::id()));
+    fs::write(&path, message)?;
+
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--ssl-reqd", "--crlf"])
+        .arg("--url")
+        .arg(format!("{}://{}:{}", scheme, host, port))
+        .arg("--mail-from")
+        .arg(from)
+        .arg("--upload-file")
+        .arg(&path)
+        // Credentials go through stdin so they don't show up in `ps`.
+        .args(["--config", "-"])
+        .stdin(Stdio::piped());
+    for rcpt in to {
+        command.arg("--mail-rcpt").arg(rcpt);
+    }
+
+    let result = (|| {
+        let mut child = command.spawn().context("Failed to run curl")?;
+        if let Some(user) = config.string("smtp.user") {
+            let password = auth::secret("smtp", user)
+                .or_else(|| config.string("smtp.password").map(|p| p.to_string()))
+                .unwrap_or_default();
+            let credentials = format!("{}:{}", user, password);
+            writeln!(child.stdin.take().unwrap(), "user = {:?}", credentials)?;
+        }
+        drop(child.stdin.take());
+        if !child.wait()?.success() {
+            bail!("Failed to send mail through {}", host);
+        }
+        Ok(())
+    })();
+    let _ = fs::remove_file(&path);
+    result
+}
@@ -1,97 +1,1102 @@
 use anyhow::bail;
-use cli_table::{format::Justify, print_stdout, Cell, Style, Table};
+use cli_table::{format::Justify, Cell, Style, Table};
 use dirs::home_dir;
 use seahorse::{App, Command, Context, Flag, FlagType};
+use std::collections::HashSet;
 use std::env;
 use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use std::io::{ErrorKind, Read, Write};
 use std::process::exit;
 
+mod add_json;
+mod alias;
+mod attach;
+mod auth;
+mod batch;
+mod bot;
+mod bugreport;
+mod calendar;
+mod capture;
+mod checklist;
+mod clean;
+mod clipboard;
+mod collate;
+mod columns;
+mod config;
+mod crdt;
+mod cron;
+mod crypt;
+mod daemon;
+mod date;
+mod diff;
+mod digest;
+mod doctor;
+mod dry_run;
+mod escalate;
+mod export;
+mod fields;
+mod focus;
+mod format;
+mod git;
+mod goal;
+mod history;
+mod hook;
+mod http;
+mod icons;
+mod id_alias;
+mod importers;
+mod init;
+mod integrations;
+mod integrity;
+mod json;
+mod keyring;
+mod later;
+mod lists;
+mod location;
+mod lock;
+mod log;
+mod mail;
+mod malformed;
+mod merge;
+mod migrations;
+mod notification;
+mod notifiers;
+mod oauth;
+mod open;
+#[cfg(feature = "fetch-title")]
+mod page;
+mod pattern;
+mod plan;
+mod print;
+mod progress;
+mod qr;
+mod query;
+mod quickfix;
+mod relay;
+mod renumber;
+mod repeat;
+mod report;
+mod review;
+mod rollover;
+mod rpc;
+mod rules;
+mod setup;
+mod sha256;
+mod share;
+mod someday;
+mod sort;
+mod stats;
+mod storage;
+mod sync;
+mod tag;
+mod template;
+mod term;
+mod timeline;
+mod timings;
+mod trash;
+mod watch;
+mod webhook;
+mod workspace;
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    workspace::select(&mut args);
+    dry_run::select(&mut args);
+    timings::select(&mut args);
+    malformed::select(&mut args);
+    if daemon::select_client(&mut args) {
+        if let Some(code) = daemon::forward(&args) {
+            exit(code);
+        }
+    }
+    if args.get(1).map(|a| a.as_str()) != Some("init") {
+        setup::run_if_first();
+    }
+    run(args);
+}
+
+/// Run the command in `args`, which start with the program name.
+fn run(args: Vec<String>) {
+    log::info("command", || {
+        format!("run command={}", args.get(1).map_or("", |a| a.as_str()))
+    });
+    log::debug("command", || format!("run args={:?}", &args[1..]));
+    let app = app();
+    let commands: Vec<String> = app
+        .commands
+        .iter()
+        .flatten()
+        .flat_map(|c| c.alias.iter().flatten().chain([&c.name]).cloned())
+        .collect();
+    let mut args = alias::expand(args, commands);
+    id_alias::resolve(&mut args, &app);
+    app.run(args);
+}
+
+fn app() -> App {
     let app = App::new(env!("CARGO_PKG_NAME"))
         .description(env!("CARGO_PKG_DESCRIPTION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .version(env!("CARGO_PKG_VERSION"))
         .usage("todo [sub command] [args]")
         .command(ls_command())
+        .command(watch::watch_command())
         .command(add_command())
+        .command(capture::capture_command())
         .command(delete_command())
         .command(done_command())
         .command(clear_command())
-        .flag(
-            Flag::new("all", FlagType::Bool)
-                .alias("a")
-                .description("Show all TODOs"),
-        )
-        .action(ls_action);
+        .command(show_command())
+        .command(comment_command())
+        .command(fields::get_command())
+        .command(fields::set_command())
+        .command(attach::attach_command())
+        .command(edit_command())
+        .command(tag::tag_command())
+        .command(open_command())
+        .command(share::share_command())
+        .command(checklist::check_command())
+        .command(progress::progress_command())
+        .command(focus::focus_command())
+        .command(focus::now_command())
+        .command(review::review_command())
+        .command(clean::check_clean_command())
+        .command(someday::someday_command())
+        .command(id_alias::alias_id_command())
+        .command(later::later_command())
+        .command(goal::goal_command())
+        .command(goal::goals_command())
+        .command(calendar::cal_command())
+        .command(timeline::timeline_command())
+        .command(plan::plan_command())
+        .command(stats::stats_command())
+        .command(history::history_command())
+        .command(hook::hook_command())
+        .command(cron::cron_command())
+        .command(cron::tick_command())
+        .command(rollover::rollover_command())
+        .command(report::report_command())
+        .command(digest::email_digest_command())
+        .command(notifiers::notify_command())
+        .command(bot::bot_command())
+        .command(sync::sync_command())
+        .command(integrations::jira::jira_command())
+        .command(integrations::github::gh_command())
+        .command(export::export_command())
+        .command(importers::import_command())
+        .command(init::init_command())
+        .command(workspace::workspace_command())
+        .command(lists::move_command())
+        .command(lists::copy_command())
+        .command(merge::merge_command())
+        .command(diff::diff_command())
+        .command(doctor::doctor_command())
+        .command(bugreport::bugreport_command())
+        .command(auth::auth_command())
+        .command(alias::alias_command())
+        .command(daemon::daemon_command())
+        .command(rpc::rpc_command())
+        .command(relay::serve_command())
+        .command(batch::batch_command())
+        .command(renumber::renumber_command())
+        .command(trash::trash_command());
+    list_flags()
+        .into_iter()
+        .fold(app, |app, flag| app.flag(flag))
+        .action(ls_action)
+}
 
-    app.run(args);
+/// Flags shared by `todo list` and plain `todo`.
+fn list_flags() -> Vec<Flag> {
+    vec![
+        Flag::new("all", FlagType::Bool)
+            .alias("a")
+            .description("Show all TODOs, including done, someday and scheduled ones"),
+        Flag::new("assignee", FlagType::String).description("Show TODOs assigned to a person"),
+        Flag::new("tag", FlagType::String).description("Show TODOs with a tag"),
+        Flag::new("where", FlagType::String)
+            .alias("w")
+            .description("Show TODOs matching a query, e.g. 'tag:work sprint>=40'"),
+        Flag::new("mine", FlagType::Bool)
+            .alias("m")
+            .description("Show TODOs assigned to you (user.name in the config)"),
+        Flag::new("scheduled", FlagType::Bool)
+            .description("Show only TODOs with a start date still to come"),
+        Flag::new("sort", FlagType::String)
+            .description("Order by fields, like priority:desc,due,title"),
+        Flag::new("branch", FlagType::Bool)
+            .description("Show TODOs added with --branch on the current git branch"),
+        Flag::new("near", FlagType::String)
+            .description("Show TODOs for a location, by label or near lat,long"),
+        Flag::new("all-lists", FlagType::Bool)
+            .description("Show the TODOs of all workspaces, with a LIST column"),
+        Flag::new("format", FlagType::String)
+            .description(
+                "Print as a table (default), quickfix for Vim's :cexpr, or a template like '{id}\\t{title}'",
+            ),
+    ]
 }
 
 fn ls_action(c: &Context) {
-    let todos = Todos::read();
+    let all_lists = c.bool_flag("all-lists");
+    let mut todos = if all_lists {
+        lists::read_all()
+    } else {
+        Todos::read()
+    };
     let all = c.bool_flag("all");
-    todos.print_list(all);
+    let top = escalate::apply(&mut todos.records);
+
+    let filtering = timings::start("filter");
+    let assignee = if c.bool_flag("mine") {
+        Some(current_user())
+    } else {
+        c.string_flag("assignee").ok()
+    };
+    if let Some(assignee) = assignee {
+        todos.records.retain(|r| r.assignee == assignee);
+    }
+    if let Ok(tag) = c.string_flag("tag") {
+        let tag = tag_name(&tag);
+        todos.records.retain(|r| r.tags.contains(&tag));
+    }
+    if let Ok(place) = c.string_flag("near") {
+        todos.records.retain(|r| location::is_near(r, &place));
+    }
+    if c.bool_flag("branch") {
+        let branch = current_branch();
+        todos
+            .records
+            .retain(|r| fields::get(r, "branch") == Some(branch.as_str()));
+    }
+    if let Ok(filter) = c.string_flag("where") {
+        match query::Query::parse(&filter) {
+            Ok(query) => todos.records.retain(|r| query.matches(r)),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+    }
+    if !all {
+        todos.records.retain(|r| !someday::is_someday(r));
+    }
+    // Like a tickler file, TODOs stay out of sight until they start.
+    let today = date::today();
+    let scheduled = |r: &Todo| date::Date::parse(&r.start).is_some_and(|d| d > today);
+    if c.bool_flag("scheduled") {
+        todos.records.retain(scheduled);
+    } else if !all {
+        todos.records.retain(|r| !scheduled(r));
+    }
+    if let Ok(keys) = c.string_flag("sort") {
+        if let Err(e) = sort::Sort::parse(&keys).and_then(|s| s.apply(&mut todos.records)) {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+    escalate::surface(&mut todos.records, &top);
+    drop(filtering);
+
+    match c.string_flag("format").as_deref() {
+        Ok("quickfix") => {
+            let _timing = timings::start("render");
+            let records: Vec<&Todo> = todos
+                .records
+                .iter()
+                .filter(|r| all || r.done.is_empty())
+                .collect();
+            quickfix::print(&records);
+        }
+        Ok("table") | Err(_) => todos.print_table(all, all_lists),
+        Ok(format) if format.contains('{') => {
+            let template = match template::Template::parse(format)
+                .and_then(|t| t.check(&todos.records).map(|_| t))
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
+            let _timing = timings::start("render");
+            for record in todos.records.iter().filter(|r| all || r.done.is_empty()) {
+                println!("{}", template.render(record));
+            }
+        }
+        Ok(format) => {
+            eprintln!(
+                "Unknown format {}, expected table, quickfix or a template with {{field}}s",
+                format
+            );
+            exit(1);
+        }
+    }
+}
+
+/// What the files written next to a file before they are renamed over it
+/// are named after, as `<file>.<kind>-<pid>`: by `write_file`, `todo
+/// batch`, `todo move` and pulling the remote list. `todo doctor` finds
+/// those a stopped process left behind.
+const TEMPORARY: &[&str] = &["save", "batch", "move", "remote"];
+
+/// The file this process writes before renaming it over `path`, see
+/// `TEMPORARY`.
+fn temporary_path(path: &str, kind: &str) -> String {
+    debug_assert!(TEMPORARY.contains(&kind));
+    format!("{}.{}-{}", path, kind, std::process::id())
+}
+
+/// Replace the file at `path` with `content` at once, through a file
+/// next to it, so that it is never partly written and nothing of a
+/// longer old version stays behind. A symlinked file is replaced where
+/// the link points.
+fn write_file(path: &str, content: &str, kind: &str) -> anyhow::Result<()> {
+    match Replacement::write(path, content, kind) {
+        Ok(replacement) => Ok(replacement.finish()?),
+        Err(e) if is_read_only(&e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn is_read_only(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem
+    )
+}
+
+/// A file written next to the one it replaces, see `write_file`, so that
+/// `todo batch` can write all of them before replacing any.
+struct Replacement {
+    target: String,
+    temporary: String,
+}
+
+impl Replacement {
+    fn write(path: &str, content: &str, kind: &str) -> std::io::Result<Self> {
+        let target = std::fs::canonicalize(path)
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| path.to_string());
+        let read_only = |kind| {
+            std::io::Error::new(
+                kind,
+                format!("{} is read-only, so it can be listed but not changed", path),
+            )
+        };
+        // Renaming over the file only takes the directory to be writable,
+        // so see that the file itself could be written, as one of another
+        // user may not be.
+        let metadata = match OpenOptions::new().write(true).open(&target) {
+            Ok(file) => Some(file.metadata()?),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) if is_read_only(&e) => return Err(read_only(e.kind())),
+            Err(e) => return Err(e),
+        };
+        if let Some(dir) = std::path::Path::new(&target).parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temporary = temporary_path(&target, kind);
+        let written = File::create(&temporary).and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+            if let Some(metadata) = metadata {
+                file.set_permissions(metadata.permissions())?;
+            }
+            Ok(())
+        });
+        match written {
+            Ok(()) => Ok(Replacement { target, temporary }),
+            Err(e) => {
+                let _ = std::fs::remove_file(&temporary);
+                match is_read_only(&e) {
+                    true => Err(read_only(e.kind())),
+                    false => Err(e),
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> std::io::Result<()> {
+        std::fs::rename(&self.temporary, &self.target).inspect_err(|_| {
+            let _ = std::fs::remove_file(&self.temporary);
+        })
+    }
+
+    fn discard(self) {
+        let _ = std::fs::remove_file(&self.temporary);
+    }
+}
+
+/// Whether to show the list after a change: `--show-list`, or always
+/// with `display.show_list = true`.
+fn show_list(c: &Context) -> bool {
+    c.bool_flag("show-list") || config::get().string("display.show_list") == Some("true")
+}
+
+/// The git branch for `--branch`, exiting outside a repository.
+fn current_branch() -> String {
+    match git::branch() {
+        Ok(branch) => branch,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+/// Name used for `--mine` and to record who added or completed a TODO:
+/// `user.name` from the config, or the login name.
+pub fn current_user() -> String {
+    match config::get().string("user.name") {
+        Some(name) => name.to_string(),
+        None => env::var("USER")
+            .or_else(|_| env::var("LOGNAME"))
+            .unwrap_or_default(),
+    }
 }
 
 fn ls_command() -> Command {
-    Command::new("list")
+    let command = Command::new("list")
         .description("Show all TODOs")
         .usage("todo list")
         .alias("ls")
-        .alias("l")
-        .flag(
-            Flag::new("all", FlagType::Bool)
-                .alias("a")
-                .description("Show all TODOs"),
-        )
+        .alias("l");
+    list_flags()
+        .into_iter()
+        .fold(command, |command, flag| command.flag(flag))
         .action(ls_action)
 }
 
 fn add_command() -> Command {
-    Command::new("add")
+    let command = Command::new("add")
         .description("Add a TODO")
         .usage("todo add <text>")
         .alias("a")
         .flag(
             Flag::new("date", FlagType::String)
                 .alias("d")
-                .description("Date"),
+                .description("Date, optionally with a time, e.g. tomorrow or 'fri 14:00'"),
         )
+        .flag(Flag::new("smart-dates", FlagType::Bool).description(
+            "Take the date from the text, like 'by friday' (default: add.smart_dates)",
+        ))
         .flag(
             Flag::new("url", FlagType::String)
                 .alias("u")
-                .description("URL"),
+                .description("URL (can be given more than once)"),
         )
-        .action(|c| {
-            let title = if !c.args.is_empty() {
-                c.args.join(" ")
+        .flag(Flag::new("tag", FlagType::String).description("Tag (can be given more than once)"))
+        .flag(
+            Flag::new("start", FlagType::String)
+                .description("Date the work is planned to start, hidden from the list until then"),
+        )
+        .flag(
+            Flag::new("repeat", FlagType::String)
+                .description("Make the TODO recur: daily, weekly, monthly, yearly or e.g. 3d"),
+        )
+        .flag(Flag::new("assign", FlagType::String).description("Person the TODO is assigned to"))
+        .flag(
+            Flag::new("location", FlagType::String)
+                .description("Where it can be done, a label like supermarket or lat,long"),
+        )
+        .flag(
+            Flag::new("branch", FlagType::Bool)
+                .description("Associate the TODO with the current git branch"),
+        )
+        .flag(
+            Flag::new("show-list", FlagType::Bool)
+                .alias("s")
+                .description("Show the list after adding (always with display.show_list = true)"),
+        )
+        .flag(
+            Flag::new("from-clipboard", FlagType::Bool).description(
+                "Take the title from the clipboard's first line, and the rest as notes",
+            ),
+        )
+        .flag(Flag::new("json", FlagType::Bool).description("Print the resulting TODO as JSON"))
+        .flag(
+            Flag::new("json-stdin", FlagType::Bool)
+                .description("Add the TODO or array of TODOs read from stdin as JSON"),
+        );
+    #[cfg(feature = "fetch-title")]
+    let command = command.flag(
+        Flag::new("fetch-title", FlagType::Bool)
+            .description("Use the title of the linked page (done anyway when the text is a URL)"),
+    );
+    let command = fields::with_flags(command);
+    command.action(|c| {
+        if c.bool_flag("json-stdin") {
+            add_json::add(c);
+            return;
+        }
+        let (mut urls, args) = repeated_flag(c, &c.args, "url", Some("u"));
+        let (tags, args) = repeated_flag(c, &args, "tag", None);
+        let mut notes = "".to_string();
+        let title = if c.bool_flag("from-clipboard") {
+            let text = match clipboard::read() {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
+            let text = text.trim();
+            if let Some(url) = clipboard::find_url(text) {
+                if !urls.iter().any(|u| u == url) {
+                    urls.push(url.to_string());
+                }
+            }
+            // With a title given, all of the clipboard goes to the notes.
+            let (first, rest) = if !args.is_empty() {
+                (args.join(" "), text)
             } else {
-                eprintln!("Please enter a title");
-                exit(1);
+                let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+                (first.trim().to_string(), rest)
             };
+            notes = rest.trim().to_string();
+            if first.is_empty() {
+                eprintln!("The clipboard is empty");
+                exit(1);
+            }
+            first
+        } else if !args.is_empty() {
+            args.join(" ")
+        } else if c.bool_flag("fetch-title") && !urls.is_empty() {
+            "".to_string()
+        } else {
+            eprintln!("Please enter a title");
+            exit(1);
+        };
+        #[cfg(feature = "fetch-title")]
+        let title = fetched_title(c, title, &mut urls);
 
-            let date = c.string_flag("date").unwrap_or("".to_string());
-            let url = c.string_flag("url").unwrap_or("".to_string());
+        let mut title = title;
+        let date = match c.string_flag("date") {
+            Ok(d) => resolve_date(&d),
+            Err(_) => smart_date(c, &mut title).unwrap_or_default(),
+        };
 
-            let mut todos = Todos::read();
+        let assignee = c.string_flag("assign").ok();
+        let start = date_flag(c, "start").unwrap_or_default();
+        let repeat = repeat_flag(c).unwrap_or_default();
+
+        let mut todos = Todos::read();
 
-            if todos.add(date, title, url).is_err() {
+        let mut todo = Todo::new("".to_string(), date, title, urls, "".to_string());
+        todo.assignee = assignee.clone().unwrap_or_default();
+        todo.start = start;
+        todo.repeat = repeat;
+        todo.tags = tags.iter().map(|t| tag_name(t)).collect();
+        todo.notes = notes;
+        if let Ok(location) = c.string_flag("location") {
+            fields::set(&mut todo, location::FIELD, &location);
+        }
+        if c.bool_flag("branch") {
+            fields::set(&mut todo, "branch", &current_branch());
+        }
+        fields::apply_flags(c, &mut todo);
+        rules::apply_to_new(&mut todo, assignee.is_some());
+        let todo = match todos.add(todo) {
+            Ok(t) => t,
+            Err(_) => {
                 eprintln!("Failed to add.");
                 exit(1);
             }
+        };
+
+        todos.save().unwrap();
+        if c.bool_flag("json") {
+            println!("{}", todo.to_json());
+        } else {
+            println!("Added #{}: {}", todo.id, todo.title);
+        }
+        if show_list(c) {
+            todos.print_list(false);
+        }
+    })
+}
+
+/// With `--fetch-title`, or when the text is only a URL, the title of
+/// the page it links to. Failing that, `title` is kept.
+#[cfg(feature = "fetch-title")]
+fn fetched_title(c: &Context, title: String, urls: &mut Vec<String>) -> String {
+    let only_url = clipboard::find_url(&title) == Some(title.trim());
+    if !only_url && !c.bool_flag("fetch-title") {
+        return title;
+    }
+    let url = match clipboard::find_url(&title) {
+        Some(u) => u.to_string(),
+        None => match urls.first() {
+            Some(u) => u.clone(),
+            None => {
+                eprintln!("Please specify a URL to fetch the title from");
+                exit(1);
+            }
+        },
+    };
+    if !urls.contains(&url) {
+        urls.insert(0, url.clone());
+    }
+    match page::title(&url) {
+        Ok(fetched) => fetched,
+        Err(e) => {
+            eprintln!("Failed to fetch the title: {}", e);
+            if title.is_empty() {
+                url
+            } else {
+                title
+            }
+        }
+    }
+}
+
+/// With `--smart-dates` or `add.smart_dates`, the date mentioned in
+/// `title`, which is left without it when `add.strip_dates` is set.
+fn smart_date(c: &Context, title: &mut String) -> Option<String> {
+    let config = config::get();
+    if !c.bool_flag("smart-dates") && config.string("add.smart_dates") != Some("true") {
+        return None;
+    }
+    let (due, rest) = date::find_due(title, date::today())?;
+    if config.string("add.strip_dates") == Some("true") && !rest.is_empty() {
+        *title = rest;
+    }
+    Some(due)
+}
+
+/// A tag as stored, so `#work` and `work` are the same tag.
+fn tag_name(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_string()
+}
+
+/// `date` as `YYYY-MM-DD` when it is an expression like `tomorrow` or
+/// `+3d`, otherwise as given.
+fn resolve_date(date: &str) -> String {
+    date::parse_due(date, date::today()).unwrap_or_else(|| date.to_string())
+}
+
+/// A date flag such as `--start tomorrow`, as `YYYY-MM-DD`. See
+/// `Date::parse_expr` for what is accepted.
+fn date_flag(c: &Context, name: &str) -> Option<String> {
+    let value = c.string_flag(name).ok()?;
+    match date::Date::parse_expr(&value, date::today()) {
+        Some(d) => Some(d.to_string()),
+        None => {
+            eprintln!("Invalid date: {}", value);
+            exit(1);
+        }
+    }
+}
+
+/// `--repeat`, checked with `repeat::Interval::parse`. An empty value
+/// stops a TODO from recurring.
+fn repeat_flag(c: &Context) -> Option<String> {
+    let value = c.string_flag("repeat").ok()?;
+    if !value.is_empty() && repeat::Interval::parse(&value).is_none() {
+        eprintln!("Invalid interval: {}", value);
+        exit(1);
+    }
+    Some(value)
+}
 
-            todos.save(false).unwrap();
+/// All values of a flag that may be given more than once, and what is
+/// left of `args` without them. seahorse only consumes the first
+/// occurrence, so the others are still in `c.args`.
+fn repeated_flag(
+    c: &Context,
+    args: &[String],
+    name: &str,
+    alias: Option<&str>,
+) -> (Vec<String>, Vec<String>) {
+    let mut values: Vec<String> = c.string_flag(name).into_iter().collect();
+    let long = format!("--{}", name);
+    let short = alias.map(|a| format!("-{}", a));
+    let mut rest = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if *arg == long || Some(arg) == short.as_ref() {
+            values.extend(iter.next().cloned());
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+    (values, rest)
+}
+
+fn edit_command() -> Command {
+    let command = Command::new("edit")
+        .description("Change the fields of a TODO")
+        .usage("todo edit <todo id | --where <filter>> [--title <text>] [--date <date>] [--start <date>] [--repeat <interval>] [--tag <tag>] [--untag <tag>] [--add-url <url>] [--remove-url <url>] [--set <key>=<value>]")
+        .alias("e")
+        .flag(
+            Flag::new("title", FlagType::String)
+                .alias("t")
+                .description("New title"),
+        )
+        .flag(
+            Flag::new("date", FlagType::String)
+                .alias("d")
+                .description("New date"),
+        )
+        .flag(Flag::new("start", FlagType::String).description("New start date"))
+        .flag(
+            Flag::new("repeat", FlagType::String)
+                .description("New recurrence, or \"\" to stop recurring"),
+        )
+        .flag(
+            Flag::new("tag", FlagType::String).description("Add a tag (can be given more than once)"),
+        )
+        .flag(
+            Flag::new("untag", FlagType::String)
+                .description("Remove a tag (can be given more than once)"),
+        )
+        .flag(
+            Flag::new("add-url", FlagType::String)
+                .description("Add a URL (can be given more than once)"),
+        )
+        .flag(
+            Flag::new("remove-url", FlagType::String)
+                .description("Remove a URL (can be given more than once)"),
+        )
+        .flag(Flag::new("assign", FlagType::String).description("Person the TODO is assigned to"))
+        .flag(
+            Flag::new("location", FlagType::String)
+                .description("Where it can be done, or '' to remove it"),
+        )
+        .flag(
+            Flag::new("add-tag", FlagType::String)
+                .description("Add a tag, like --tag (can be given more than once)"),
+        )
+        .flag(
+            Flag::new("set", FlagType::String).description(
+                "Set a field, like due=+1w or sprint=42 (can be given more than once)",
+            ),
+        )
+        .flag(
+            Flag::new("where", FlagType::String)
+                .alias("w")
+                .description("Edit every TODO matching a filter, like 'tag:sprint41'"),
+        )
+        .flag(
+            Flag::new("yes", FlagType::Bool)
+                .alias("y")
+                .description("Edit the TODOs matching --where without asking"),
+        )
+        .flag(Flag::new("json", FlagType::Bool).description("Print the resulting TODO as JSON"));
+    fields::with_flags(command).action(|c| {
+        let (add_urls, args) = repeated_flag(c, &c.args, "add-url", None);
+        let (remove_urls, args) = repeated_flag(c, &args, "remove-url", None);
+        let (mut add_tags, args) = repeated_flag(c, &args, "tag", None);
+        let (more_tags, args) = repeated_flag(c, &args, "add-tag", None);
+        add_tags.extend(more_tags);
+        let (remove_tags, args) = repeated_flag(c, &args, "untag", None);
+        let (sets, args) = repeated_flag(c, &args, "set", None);
+        let sets: Vec<(String, String)> = sets.iter().map(|s| set_flag(s)).collect();
+
+        let start = date_flag(c, "start");
+        let repeat = repeat_flag(c);
+        let edit = |todo: &mut Todo| {
+            if let Some(start) = &start {
+                todo.start = start.clone();
+            }
+            if let Some(repeat) = &repeat {
+                todo.repeat = repeat.clone();
+            }
+            if let Ok(title) = c.string_flag("title") {
+                todo.title = title;
+            }
+            if let Ok(date) = c.string_flag("date") {
+                todo.date = resolve_date(&date);
+            }
+            if let Ok(assignee) = c.string_flag("assign") {
+                todo.assignee = assignee;
+            }
+            if let Ok(location) = c.string_flag("location") {
+                fields::set(todo, location::FIELD, &location);
+            }
+            for (key, value) in &sets {
+                match key.as_str() {
+                    "date" => todo.date = value.clone(),
+                    "title" => todo.title = value.clone(),
+                    "start" => todo.start = value.clone(),
+                    "assignee" => todo.assignee = value.clone(),
+                    "repeat" => todo.repeat = value.clone(),
+                    key => fields::set(todo, key, value),
+                }
+            }
+            let repeat_changed = repeat.is_some() || sets.iter().any(|(k, _)| k == "repeat");
+            if repeat_changed && repeat::is_recurring(todo) && todo.date.is_empty() {
+                todo.date = date::today().to_string();
+            }
+            for url in &remove_urls {
+                match todo.urls.iter().position(|u| u == url) {
+                    Some(i) => {
+                        todo.urls.remove(i);
+                    }
+                    None => {
+                        eprintln!("#{} has no URL {}", todo.id, url);
+                        exit(1);
+                    }
+                }
+            }
+            todo.urls.extend(add_urls.iter().cloned());
+            for tag in &remove_tags {
+                let tag = tag_name(tag);
+                todo.tags.retain(|t| *t != tag);
+            }
+            for tag in &add_tags {
+                let tag = tag_name(tag);
+                if !todo.tags.contains(&tag) {
+                    todo.tags.push(tag);
+                }
+            }
+            fields::apply_flags(c, todo);
+        };
+
+        if let Ok(filter) = c.string_flag("where") {
+            if !args.is_empty() {
+                eprintln!("Please specify either an ID or --where");
+                exit(1);
+            }
+            let query = match query::Query::parse(&filter) {
+                Ok(q) => q,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
+            let mut todos = Todos::read();
+            let headers = Todos::default_headers();
+            let mut changed = 0;
+            for todo in todos.records.iter_mut().filter(|r| query.matches(r)) {
+                let before = todo.to_fields();
+                edit(todo);
+                let after = todo.to_fields();
+                if before == after {
+                    continue;
+                }
+                println!("#{} {}", todo.id, todo.title);
+                print_diff(&headers, &before, &after);
+                record_changes(todo, &before);
+                changed += 1;
+            }
+            if changed == 0 {
+                println!("Nothing to change");
+                return;
+            }
+            if dry_run::is_enabled() {
+                println!("Dry run, {} TODO(s) would be edited", changed);
+                return;
+            }
+            let question = format!("Edit {} TODO(s)? [y/N] ", changed);
+            if !c.bool_flag("yes") && term::read_key(&question) != Some('y') {
+                eprintln!("Cancelled");
+                exit(1);
+            }
+            todos.save().unwrap();
+            println!("Edited {} TODOs", changed);
+            return;
+        }
+
+        let id = match args.as_slice() {
+            [id] => id,
+            _ => {
+                eprintln!("Please specify one ID");
+                exit(1);
+            }
+        };
+        let mut todos = Todos::read();
+        let todo = match todos.find_mut(id) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        };
+        let before = todo.to_fields();
+        edit(todo);
+        record_changes(todo, &before);
+
+        let todo = todo.clone();
+        todos.save().unwrap();
+        if c.bool_flag("json") {
+            println!("{}", todo.to_json());
+        } else {
+            println!("Edited #{}: {}", todo.id, todo.title);
+        }
+    })
+}
+
+/// The fields that differ between `before` and `after`, named by
+/// `headers`, as removed and added lines.
+fn print_diff(headers: &[String], before: &[String], after: &[String]) {
+    for ((name, old), new) in headers.iter().zip(before).zip(after) {
+        if old != new {
+            println!("- {}: {}", name, old.replace('\n', ", "));
+            println!("+ {}: {}", name, new.replace('\n', ", "));
+        }
+    }
+}
+
+/// A `--set key=value` of `todo edit`, for built-in fields (`due` is
+/// `date`) and custom ones, checked and with dates resolved.
+fn set_flag(pair: &str) -> (String, String) {
+    let invalid = |message: String| -> ! {
+        eprintln!("{}", message);
+        exit(1);
+    };
+    let Some((key, value)) = pair.split_once('=') else {
+        invalid(format!("Invalid --set, expected key=value: {}", pair));
+    };
+    let value = value.to_string();
+    match key {
+        "date" | "due" => ("date".to_string(), resolve_date(&value)),
+        "start" if value.is_empty() => (key.to_string(), value),
+        "start" => match date::Date::parse_expr(&value, date::today()) {
+            Some(d) => (key.to_string(), d.to_string()),
+            None => invalid(format!("Invalid date: {}", value)),
+        },
+        "repeat" if !value.is_empty() && repeat::Interval::parse(&value).is_none() => {
+            invalid(format!("Invalid interval: {}", value))
+        }
+        "title" | "assignee" | "repeat" => (key.to_string(), value),
+        key if query::is_builtin(key) => invalid(format!("{} can't be changed with --set", key)),
+        _ => fields::parse_pair(pair).unwrap_or_else(|e| invalid(e.to_string())),
+    }
+}
+
+fn open_command() -> Command {
+    Command::new("open")
+        .description("Open a URL of a TODO in the browser")
+        .usage("todo open <todo id> [url number]")
+        .alias("o")
+        .action(|c| {
+            let (id, n) = match c.args.as_slice() {
+                [id] => (id, None),
+                [id, n] => (id, Some(n.clone())),
+                _ => {
+                    eprintln!("Please specify one ID");
+                    exit(1);
+                }
+            };
+
+            let todos = Todos::read();
+            let todo = match todos.records.iter().find(|r| &r.id == id) {
+                Some(t) => t,
+                None => {
+                    eprintln!("The specified ID does not exist");
+                    exit(1);
+                }
+            };
+
+            let n = match (n, todo.urls.len()) {
+                (_, 0) => {
+                    eprintln!("#{} has no URL", todo.id);
+                    exit(1);
+                }
+                (Some(n), _) => n,
+                (None, 1) => "1".to_string(),
+                (None, _) => {
+                    for (i, url) in todo.urls.iter().enumerate() {
+                        println!("{}. {}", i + 1, url);
+                    }
+                    prompt("Open which URL? ")
+                }
+            };
+            let url = match n
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+            {
+                Some(i) if i < todo.urls.len() => &todo.urls[i],
+                _ => {
+                    eprintln!("#{} has no URL {}", todo.id, n.trim());
+                    exit(1);
+                }
+            };
+
+            if let Err(e) = open::open(url) {
+                eprintln!("{}", e);
+                exit(1);
+            }
         })
 }
 
+/// Print `message` and read a line from stdin.
+fn prompt(message: &str) -> String {
+    print!("{}", message);
+    std::io::stdout().flush().unwrap();
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line).unwrap();
+    line.trim().to_string()
+}
+
+/// Let the user check open TODOs to `verb` and confirm. Exits when there
+/// is nothing to pick or nothing was picked.
+fn select_open(todos: &Todos, verb: &str) -> Vec<String> {
+    let open: Vec<&Todo> = todos.records.iter().filter(|r| r.done.is_empty()).collect();
+    if open.is_empty() {
+        println!("No open TODOs");
+        exit(0);
+    }
+    let items: Vec<String> = open
+        .iter()
+        .map(|t| format!("#{} {}", t.id, t.title))
+        .collect();
+    let ids: Vec<String> = match term::multi_select(&format!("{} which TODOs?", verb), &items) {
+        Some(picked) => picked.into_iter().map(|i| open[i].id.clone()).collect(),
+        None => {
+            eprintln!("Cancelled");
+            exit(1);
+        }
+    };
+    if ids.is_empty() {
+        println!("Nothing selected");
+        exit(0);
+    }
+    let question = format!("{} {} TODO(s)? [y/N] ", verb, ids.len());
+    if term::read_key(&question) != Some('y') {
+        eprintln!("Cancelled");
+        exit(1);
+    }
+    ids
+}
+
 fn delete_command() -> Command {
     Command::new("delete")
-        .description("Delete a TODO with a specified ID")
-        .usage("todo delete <todo id>")
+        .description("Move a TODO with a specified ID to the trash")
+        .usage("todo delete <todo id> | todo delete -i")
         .alias("del")
+        .flag(
+            Flag::new("show-list", FlagType::Bool)
+                .alias("s")
+                .description("Show the list after deleting (always with display.show_list = true)"),
+        )
+        .flag(
+            Flag::new("interactive", FlagType::Bool)
+                .alias("i")
+                .description("Pick several open TODOs to delete from a list"),
+        )
         .action(|c| {
+            if c.bool_flag("interactive") {
+                let mut todos = Todos::read();
+                let mut trash = trash::Trash::read();
+                for id in select_open(&todos, "Delete") {
+                    let todo = todos.delete(id).unwrap();
+                    println!("Deleted #{}: {}", todo.id, todo.title);
+                    trash.push(todo);
+                }
+                save_with_trash(&mut todos, &mut trash);
+                if show_list(c) {
+                    todos.print_list(false);
+                }
+                return;
+            }
             let id = if c.args.len() == 1 {
                 &c.args[0]
             } else {
@@ -100,21 +1105,82 @@ fn delete_command() -> Command {
             };
 
             let mut todos = Todos::read();
-            if let Err(e) = todos.delete(id.clone()) {
-                eprintln!("{}", e);
-                exit(1);
-            }
+            let todo = match todos.delete(id.clone()) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
 
-            todos.save(true).unwrap();
+            let deleted = format!("Deleted #{}: {}", todo.id, todo.title);
+            let mut trash = trash::Trash::read();
+            trash.push(todo);
+            save_with_trash(&mut todos, &mut trash);
+            println!("{}", deleted);
+            if show_list(c) {
+                todos.print_list(false);
+            }
         })
 }
 
+/// Keep which fields of `todo` changed since it had the fields `before`,
+/// for `todo history` and `todo merge`.
+fn record_changes(todo: &mut Todo, before: &[String]) {
+    let now = date::now();
+    let headers = Todos::default_headers();
+    for (name, (before, after)) in headers.iter().zip(before.iter().zip(todo.to_fields())) {
+        if *before != after {
+            let change = format!("{} {} {}", now, name, current_user());
+            todo.changes.push(change.trim_end().to_string());
+        }
+    }
+}
+
+/// Save the trash with the TODOs taken out of `todos`, and only then
+/// `todos`, so that a failure never loses them.
+fn save_with_trash(todos: &mut Todos, trash: &mut trash::Trash) {
+    if let Err(e) = trash.save() {
+        eprintln!("Failed to save the trash, nothing was deleted: {}", e);
+        exit(1);
+    }
+    if let Err(e) = todos.save() {
+        eprintln!("Failed to save: {}", e);
+        exit(1);
+    }
+}
+
 fn done_command() -> Command {
     Command::new("done")
         .description("Complete the TODO for the specified ID")
-        .usage("todo done <todo id>")
+        .usage("todo done <todo id> | todo done -i")
         .alias("d")
+        .flag(
+            Flag::new("show-list", FlagType::Bool)
+                .alias("s")
+                .description(
+                    "Show the list after completing (always with display.show_list = true)",
+                ),
+        )
+        .flag(Flag::new("json", FlagType::Bool).description("Print the resulting TODO as JSON"))
+        .flag(
+            Flag::new("interactive", FlagType::Bool)
+                .alias("i")
+                .description("Pick several open TODOs to complete from a list"),
+        )
         .action(|c| {
+            if c.bool_flag("interactive") {
+                let mut todos = Todos::read();
+                for id in select_open(&todos, "Complete") {
+                    let todo = todos.done(id).unwrap();
+                    println!("Completed #{}: {}", todo.id, todo.title);
+                }
+                todos.save().unwrap();
+                if show_list(c) {
+                    todos.print_list(false);
+                }
+                return;
+            }
             let id = if c.args.len() == 1 {
                 &c.args[0]
             } else {
@@ -123,27 +1189,218 @@ fn done_command() -> Command {
             };
 
             let mut todos = Todos::read();
-            if let Err(e) = todos.done(id.clone()) {
-                eprintln!("{}", e);
-                exit(1);
-            }
+            let todo = match todos.done(id.clone()) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
 
-            todos.save(false).unwrap();
+            todos.save().unwrap();
+            if c.bool_flag("json") {
+                println!("{}", todo.to_json());
+            } else if repeat::is_recurring(&todo) {
+                println!(
+                    "Completed #{}: {} (next: {}, streak: {})",
+                    todo.id,
+                    todo.title,
+                    todo.date,
+                    repeat::streak_label(&todo)
+                );
+            } else {
+                println!("Completed #{}: {}", todo.id, todo.title);
+            }
+            if show_list(c) {
+                todos.print_list(false);
+            }
         })
 }
 
 fn clear_command() -> Command {
     Command::new("clear")
-        .description("Delete all TODOs")
+        .description("Move all TODOs to the trash")
         .usage("todo clear")
         .alias("cl")
-        .action(|_| {
+        .flag(
+            Flag::new("show-list", FlagType::Bool)
+                .alias("s")
+                .description("Show the list after clearing (always with display.show_list = true)"),
+        )
+        .action(|c| {
             let mut todos = Todos::read();
-            if todos.clear().is_err() {
-                eprintln!("Failed to delete.");
+            let cleared = match todos.clear() {
+                Ok(r) => r,
+                Err(_) => {
+                    eprintln!("Failed to delete.");
+                    exit(1);
+                }
+            };
+            let count = cleared.len();
+            let mut trash = trash::Trash::read();
+            for todo in cleared {
+                trash.push(todo);
+            }
+            save_with_trash(&mut todos, &mut trash);
+            println!("Cleared {} TODOs", count);
+            if show_list(c) {
+                todos.print_list(false);
+            }
+        })
+}
+
+/// A column of the `print_list` table.
+struct Column {
+    name: &'static str,
+    value: fn(&Todo) -> String,
+    justify: Justify,
+    /// Only shown when some listed TODO has a value for it.
+    optional: bool,
+}
+
+const LIST_COLUMNS: &[Column] = &[
+    Column {
+        name: "id",
+        value: |t| t.id.clone(),
+        justify: Justify::Center,
+        optional: false,
+    },
+    Column {
+        name: "start",
+        value: |t| date::display(&t.start),
+        justify: Justify::Center,
+        optional: true,
+    },
+    Column {
+        name: "date",
+        value: |t| labelled(icons::overdue(t), date::display(&t.date)),
+        justify: Justify::Center,
+        optional: false,
+    },
+    Column {
+        name: "title",
+        value: |t| labelled(icons::priority(t), t.title.clone()),
+        justify: Justify::Left,
+        optional: false,
+    },
+    Column {
+        name: "url",
+        value: |t| t.urls.join("\n"),
+        justify: Justify::Left,
+        optional: false,
+    },
+    Column {
+        name: "tags",
+        value: |t| t.tags.join(", "),
+        justify: Justify::Left,
+        optional: true,
+    },
+    Column {
+        name: "location",
+        value: |t| location::get(t).unwrap_or("").to_string(),
+        justify: Justify::Left,
+        optional: true,
+    },
+    Column {
+        name: "check",
+        value: checklist::progress,
+        justify: Justify::Center,
+        optional: true,
+    },
+    Column {
+        name: "progress",
+        value: progress::bar,
+        justify: Justify::Left,
+        optional: true,
+    },
+    Column {
+        name: "estimate",
+        value: Todo::estimate_label,
+        justify: Justify::Center,
+        optional: true,
+    },
+    Column {
+        name: "streak",
+        value: repeat::streak_label,
+        justify: Justify::Center,
+        optional: true,
+    },
+    Column {
+        name: "assignee",
+        value: |t| t.assignee.clone(),
+        justify: Justify::Left,
+        optional: true,
+    },
+    Column {
+        name: "by",
+        value: Todo::by_label,
+        justify: Justify::Left,
+        optional: true,
+    },
+    Column {
+        name: "done",
+        value: |t| icons::done(t).to_string(),
+        justify: Justify::Center,
+        optional: false,
+    },
+];
+
+/// `text` after `icon`, if there is one.
+fn labelled(icon: &str, text: String) -> String {
+    if icon.is_empty() {
+        text
+    } else {
+        format!("{} {}", icon, text)
+    }
+}
+
+fn show_command() -> Command {
+    Command::new("show")
+        .description("Show every detail of a TODO")
+        .usage("todo show <todo id>")
+        .action(|c| {
+            let id = if c.args.len() == 1 {
+                &c.args[0]
+            } else {
+                eprintln!("Please specify one ID");
                 exit(1);
+            };
+
+            let todos = Todos::read();
+            match todos.records.iter().find(|r| &r.id == id) {
+                Some(todo) => todo.print_details(),
+                None => {
+                    eprintln!("The specified ID does not exist");
+                    exit(1);
+                }
             }
-            todos.save(true).unwrap();
+        })
+}
+
+fn comment_command() -> Command {
+    Command::new("comment")
+        .description("Add a timestamped comment to a TODO")
+        .usage("todo comment <todo id> <text>")
+        .action(|c| {
+            let (id, text) = match c.args.split_first() {
+                Some((id, text)) if !text.is_empty() => (id, text.join(" ")),
+                _ => {
+                    eprintln!("Please specify an ID and a comment");
+                    exit(1);
+                }
+            };
+
+            let mut todos = Todos::read();
+            let todo = match todos.find_mut(id) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
+            todo.comments.push(format!("{} {}", date::now(), text));
+            println!("Commented on #{}: {}", todo.id, todo.title);
+            todos.save().unwrap();
         })
 }
 
@@ -152,26 +1409,347 @@ struct Todo {
     id: String,
     date: String,
     title: String,
-    url: String,
+    urls: Vec<String>,
     done: String,
+    created_at: String,
+    completed_at: String,
+    assignee: String,
+    /// `<timestamp> <text>` entries, oldest first.
+    comments: Vec<String>,
+    /// Paths of attached files.
+    attachments: Vec<String>,
+    /// Checklist items, `[x] text` or `[ ] text`.
+    checklist: Vec<String>,
+    /// Percentage set with `todo progress`, see `progress::percent`.
+    progress: String,
+    /// Focus sessions as `<start timestamp> <seconds>`.
+    time_log: Vec<String>,
+    /// When `todo review` last went over this TODO.
+    reviewed_at: String,
+    /// Empty for active TODOs, or `someday::SOMEDAY`.
+    status: String,
+    /// ID of the goal this TODO is linked to.
+    goal: String,
+    /// `YYYY-MM-DD` the work is planned to start, for `todo timeline`.
+    start: String,
+    /// How often the TODO recurs, see `repeat::Interval::parse`.
+    repeat: String,
+    /// Completions of a recurring TODO as `<timestamp> <due date> [<user>]`.
+    completions: Vec<String>,
+    /// Tag names, without a leading `#`.
+    tags: Vec<String>,
+    /// Copies in other services, as `<service> ...` entries. See
+    /// `sync::Link` and `integrations::jira`.
+    remote_ids: Vec<String>,
+    /// Free-form text, may span several lines.
+    notes: String,
+    /// Minutes the TODO is expected to take.
+    estimate: String,
+    /// Custom fields as `key=value`, see `fields`.
+    fields: Vec<String>,
+    /// Who added the TODO, see `current_user`.
+    created_by: String,
+    /// Who completed it.
+    completed_by: String,
+    /// Fields set with `todo set`, as `<timestamp> <field> [<user>]`.
+    changes: Vec<String>,
+    /// Values of the columns of the file that aren't used, by header,
+    /// kept for writing back, see `columns`.
+    other_columns: Vec<(String, String)>,
 }
 
 impl Todo {
-    pub fn new(id: String, date: String, title: String, url: String, done: String) -> Self {
+    pub fn new(id: String, date: String, title: String, urls: Vec<String>, done: String) -> Self {
         Self {
             id,
             date,
             title,
-            url,
+            urls,
             done,
+            created_at: "".to_string(),
+            completed_at: "".to_string(),
+            assignee: "".to_string(),
+            comments: vec![],
+            attachments: vec![],
+            checklist: vec![],
+            progress: "".to_string(),
+            time_log: vec![],
+            reviewed_at: "".to_string(),
+            status: "".to_string(),
+            goal: "".to_string(),
+            start: "".to_string(),
+            repeat: "".to_string(),
+            completions: vec![],
+            tags: vec![],
+            remote_ids: vec![],
+            notes: "".to_string(),
+            estimate: "".to_string(),
+            fields: vec![],
+            created_by: "".to_string(),
+            completed_by: "".to_string(),
+            changes: vec![],
+            other_columns: vec![],
         }
     }
 
-    pub fn to_csv(&self) -> String {
-        format!(
-            "{},{},{},{},{}",
-            self.id, self.date, self.title, self.url, self.done
-        )
+    /// Build a record from the fields of a row, in `Todos::default_headers` order.
+    pub fn from_fields(fields: &[String]) -> Self {
+        let mut v = fields.to_vec();
+        v.resize(Todos::default_headers().len(), "".to_string());
+        let mut todo = Todo::new(
+            v[0].clone(),
+            v[1].clone(),
+            v[2].clone(),
+            format::split_list(&v[3]),
+            v[4].clone(),
+        );
+        todo.created_at = v[5].clone();
+        todo.completed_at = v[6].clone();
+        todo.assignee = v[7].clone();
+        todo.comments = format::split_list(&v[8]);
+        todo.attachments = format::split_list(&v[9]);
+        todo.checklist = format::split_list(&v[10]);
+        todo.progress = v[11].clone();
+        todo.time_log = format::split_list(&v[12]);
+        todo.reviewed_at = v[13].clone();
+        todo.status = v[14].clone();
+        todo.goal = v[15].clone();
+        todo.start = v[16].clone();
+        todo.repeat = v[17].clone();
+        todo.completions = format::split_list(&v[18]);
+        todo.tags = format::split_list(&v[19]);
+        todo.remote_ids = format::split_list(&v[20]);
+        todo.notes = v[21].clone();
+        todo.estimate = v[22].clone();
+        todo.fields = format::split_list(&v[23]);
+        todo.created_by = v[24].clone();
+        todo.completed_by = v[25].clone();
+        todo.changes = format::split_list(&v[26]);
+        todo
+    }
+
+    /// Replace the fields of `to_fields` with `fields`, keeping the
+    /// other columns.
+    pub fn set_fields(&mut self, fields: &[String]) {
+        let others = std::mem::take(&mut self.other_columns);
+        *self = Self::from_fields(fields);
+        self.other_columns = others;
+    }
+
+    /// Build a record from a row of a file with `columns`, see
+    /// `columns::map`.
+    pub fn from_row(columns: &[columns::Column], row: &[String]) -> Self {
+        let mut fields = vec![String::new(); Todos::default_headers().len()];
+        let mut others = vec![];
+        for (column, value) in columns.iter().zip(row) {
+            match column {
+                columns::Column::Known(i) => fields[*i] = value.clone(),
+                columns::Column::Other(_) if value.is_empty() => {}
+                columns::Column::Other(header) => others.push((header.clone(), value.clone())),
+            }
+        }
+        let mut todo = Self::from_fields(&fields);
+        todo.other_columns = others;
+        todo
+    }
+
+    /// The row of the record in a file with `columns`.
+    pub fn to_row(&self, columns: &[columns::Column]) -> Vec<String> {
+        let mut fields = self.to_fields();
+        columns
+            .iter()
+            .map(|column| match column {
+                columns::Column::Known(i) => std::mem::take(&mut fields[*i]),
+                columns::Column::Other(header) => self
+                    .other_columns
+                    .iter()
+                    .find(|(h, _)| h == header)
+                    .map(|(_, v)| v.clone())
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    pub fn to_json(&self) -> json::Value {
+        json::Value::object(vec![
+            (
+                "id",
+                self.id
+                    .parse::<f64>()
+                    .map(json::Value::Number)
+                    .unwrap_or_else(|_| self.id.as_str().into()),
+            ),
+            ("date", self.date.as_str().into()),
+            ("title", self.title.as_str().into()),
+            (
+                "urls",
+                json::Value::Array(self.urls.iter().map(|u| u.as_str().into()).collect()),
+            ),
+            (
+                "tags",
+                json::Value::Array(self.tags.iter().map(|t| t.as_str().into()).collect()),
+            ),
+            ("done", (!self.done.is_empty()).into()),
+            ("assignee", self.assignee.as_str().into()),
+            (
+                "fields",
+                json::Value::object(fields::all(self).map(|(k, v)| (k, v.into())).collect()),
+            ),
+        ])
+    }
+
+    /// Who added the TODO, and who completed it if someone else did.
+    pub fn by_label(&self) -> String {
+        if self.completed_by.is_empty() || self.completed_by == self.created_by {
+            self.created_by.clone()
+        } else {
+            format!("{} → {}", self.created_by, self.completed_by)
+        }
+    }
+
+    /// `estimate` as a duration like `1h30m`, or empty.
+    pub fn estimate_label(&self) -> String {
+        match self.estimate.parse::<u64>() {
+            Ok(minutes) => date::format_duration(minutes * 60),
+            Err(_) => "".to_string(),
+        }
+    }
+
+    pub fn print_details(&self) {
+        println!("#{} {}", self.id, self.title);
+        let timestamp = |ts: &str| match ts.parse() {
+            Ok(ts) => date::format_timestamp(ts),
+            Err(_) => "".to_string(),
+        };
+        let goal = match goal::Goals::read().find(&self.goal) {
+            Ok(g) => format!("#{} {}", g.id, g.title),
+            Err(_) => self.goal.clone(),
+        };
+        let tracked = match focus::tracked_seconds(self) {
+            0 => "".to_string(),
+            secs => date::format_duration(secs),
+        };
+        let focused = match fields::get(self, focus::FIELD) {
+            Some(at) => format!("since {}", timestamp(at)),
+            None => "".to_string(),
+        };
+        let by = |ts: String, user: &str| {
+            if user.is_empty() {
+                ts
+            } else {
+                format!("{} by {}", ts, user)
+            }
+        };
+        let done = if self.done.is_empty() {
+            "".to_string()
+        } else {
+            by(
+                format!("{} {}", icons::done(self), timestamp(&self.completed_at)),
+                &self.completed_by,
+            )
+        };
+        for (label, value) in [
+            ("Start", date::display(&self.start)),
+            ("Date", date::display(&self.date)),
+            ("Repeat", self.repeat.clone()),
+            ("Streak", repeat::streak_label(self)),
+            ("URL", self.urls.join("\n          ")),
+            ("Tags", self.tags.join(", ")),
+            ("Status", self.status.clone()),
+            ("Goal", goal),
+            (
+                "Alias",
+                fields::get(self, id_alias::FIELD).unwrap_or("").to_string(),
+            ),
+            ("Assignee", self.assignee.clone()),
+            ("Focus", focused),
+            ("Progress", progress::bar(self)),
+            ("Estimate", self.estimate_label()),
+            ("Tracked", tracked),
+            ("Created", by(timestamp(&self.created_at), &self.created_by)),
+            ("Done", done),
+        ] {
+            if !value.trim().is_empty() {
+                println!("{:<10}{}", format!("{}:", label), value.trim());
+            }
+        }
+
+        let custom: Vec<(&str, &str)> = fields::all(self)
+            .filter(|(k, _)| *k != id_alias::FIELD && *k != focus::FIELD)
+            .collect();
+        if !custom.is_empty() {
+            println!("\nFields:");
+            let width = custom.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+            for (key, value) in custom {
+                println!("  {:<width$}  {}", key, value, width = width);
+            }
+        }
+
+        if !self.notes.trim().is_empty() {
+            println!("\nNotes:");
+            for line in self.notes.trim_end().lines() {
+                println!("  {}", line);
+            }
+        }
+
+        if !self.checklist.is_empty() {
+            println!("\nChecklist ({}):", checklist::progress(self));
+            for (i, item) in self.checklist.iter().enumerate() {
+                let mark = if checklist::is_checked(item) {
+                    "x"
+                } else {
+                    " "
+                };
+                println!("  {}. [{}] {}", i + 1, mark, checklist::item_text(item));
+            }
+        }
+
+        if !self.attachments.is_empty() {
+            println!("\nAttachments:");
+            for (i, attachment) in self.attachments.iter().enumerate() {
+                println!("  {}. {}", i + 1, attachment);
+            }
+        }
+
+        if !self.comments.is_empty() {
+            println!("\nComments:");
+            for comment in &self.comments {
+                let (ts, text) = comment.split_once(' ').unwrap_or(("", comment));
+                println!("  {}  {}", timestamp(ts), text);
+            }
+        }
+    }
+
+    pub fn to_fields(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.date.clone(),
+            self.title.clone(),
+            format::join_list(&self.urls),
+            self.done.clone(),
+            self.created_at.clone(),
+            self.completed_at.clone(),
+            self.assignee.clone(),
+            format::join_list(&self.comments),
+            format::join_list(&self.attachments),
+            format::join_list(&self.checklist),
+            self.progress.clone(),
+            format::join_list(&self.time_log),
+            self.reviewed_at.clone(),
+            self.status.clone(),
+            self.goal.clone(),
+            self.start.clone(),
+            self.repeat.clone(),
+            format::join_list(&self.completions),
+            format::join_list(&self.tags),
+            format::join_list(&self.remote_ids),
+            self.notes.clone(),
+            self.estimate.clone(),
+            format::join_list(&self.fields),
+            self.created_by.clone(),
+            self.completed_by.clone(),
+            format::join_list(&self.changes),
+        ]
     }
 }
 
@@ -179,136 +1757,449 @@ impl Todo {
 struct Todos {
     headers: Vec<String>,
     records: Vec<Todo>,
+    /// Records set aside as they were read, see `malformed`.
+    skipped: Vec<malformed::Skipped>,
 }
 
 impl Todos {
     pub fn read() -> Self {
-        let file = Self::read_file(true, true, true, false);
-        let buf = BufReader::new(file);
-        let bufs: Vec<String> = buf.lines().map(|l| l.unwrap()).collect();
-        let records = if bufs.len() > 1 {
-            bufs[1..]
-                .iter()
-                .map(|b| {
-                    let v: Vec<String> = b.split(',').into_iter().map(|r| r.to_string()).collect();
-                    Todo::new(
-                        v[0].clone(),
-                        v[1].clone(),
-                        v[2].clone(),
-                        v[3].clone(),
-                        v[4].clone(),
-                    )
-                })
-                .collect::<Vec<Todo>>()
-        } else {
-            vec![]
+        let _timing = timings::start("load");
+        let path = Self::todo_path();
+        lock::hold(&path);
+        if let Some(todos) = batch::list().or_else(|| daemon::cached(&path)) {
+            log::debug("file", || format!("read path={} cached=true", path));
+            return todos;
+        }
+        storage::pull(&path);
+        integrity::check(&path);
+        let content = match Self::read_content() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", Self::todo_path(), e);
+                exit(1);
+            }
         };
 
-        let headers = match bufs.first() {
-            Some(h) => h.split(',').map(|a| a.to_string()).collect(),
-            None => vec![
-                "id".to_string(),
-                "date".to_string(),
-                "title".to_string(),
-                "url".to_string(),
-                "done".to_string(),
-            ],
+        log::debug("file", || {
+            format!("read path={} bytes={}", path, content.len())
+        });
+        let (mut todos, outdated) = Self::parse(&content);
+        malformed::report(&path, &todos.skipped);
+        let missing = columns::missing(&todos.headers);
+        if !missing.is_empty() {
+            eprintln!(
+                "{} has no {} column, which columns.require asks for",
+                path,
+                missing.join(", ")
+            );
+            exit(1);
+        }
+        if let Some(version) = outdated.filter(|_| !dry_run::is_enabled()) {
+            let path = Self::todo_path();
+            match migrations::backup(&path, version) {
+                Ok(backup) => eprintln!(
+                    "Upgrading {} to format version {} (backup: {})",
+                    path,
+                    migrations::CURRENT_VERSION,
+                    backup
+                ),
+                Err(e) => {
+                    eprintln!("Failed to back up {} before upgrading: {}", path, e);
+                    exit(1);
+                }
+            }
+            todos.save().unwrap();
+        }
+        todos
+    }
+
+    /// The TODOs in `content`, upgraded to the current format, and the
+    /// version they were upgraded from if they were.
+    fn parse(content: &str) -> (Self, Option<u32>) {
+        let mut table = format::parse(content);
+        if table.headers.is_empty() {
+            let todos = Todos {
+                headers: Self::default_headers(),
+                records: vec![],
+                skipped: vec![],
+            };
+            return (todos, None);
+        }
+
+        let version = table.version;
+        let outdated = version < migrations::CURRENT_VERSION;
+        if outdated {
+            migrations::migrate(&mut table);
+        }
+
+        let columns = columns::map(&table.headers);
+        let (mut records, mut skipped) = (vec![], vec![]);
+        let mut seen = HashSet::new();
+        for row in table.rows.iter().filter(|r| !r.is_empty()) {
+            let todo = Todo::from_row(&columns, &row.fields);
+            match malformed::problem(&todo, row.fields.len(), columns.len(), &seen) {
+                Some(problem) => skipped.push(malformed::Skipped {
+                    line: row.line,
+                    problem,
+                    todo,
+                    overflow: row.fields.iter().skip(columns.len()).cloned().collect(),
+                }),
+                None => {
+                    seen.insert(todo.id.clone());
+                    records.push(todo);
+                }
+            }
+        }
+        let todos = Todos {
+            headers: table.headers,
+            records,
+            skipped,
         };
-        Todos { headers, records }
+        (todos, outdated.then_some(version))
     }
 
-    pub fn save(&mut self, t: bool) -> anyhow::Result<()> {
-        let mut file = Self::read_file(false, true, true, t);
-        let s = format!("{}\n{}", self.headers.join(","), self.to_vec().join("\n"));
-        file.write_all(s.as_bytes())?;
-        file.flush()?;
+    fn default_headers() -> Vec<String> {
+        vec![
+            "id".to_string(),
+            "date".to_string(),
+            "title".to_string(),
+            "url".to_string(),
+            "done".to_string(),
+            "created_at".to_string(),
+            "completed_at".to_string(),
+            "assignee".to_string(),
+            "comments".to_string(),
+            "attachments".to_string(),
+            "checklist".to_string(),
+            "progress".to_string(),
+            "time_log".to_string(),
+            "reviewed_at".to_string(),
+            "status".to_string(),
+            "goal".to_string(),
+            "start".to_string(),
+            "repeat".to_string(),
+            "completions".to_string(),
+            "tags".to_string(),
+            "remote_ids".to_string(),
+            "notes".to_string(),
+            "estimate".to_string(),
+            "fields".to_string(),
+            "created_by".to_string(),
+            "completed_by".to_string(),
+            "changes".to_string(),
+        ]
+    }
+
+    /// The text of the TODO file, decrypted if it is encrypted.
+    /// Only a missing file is opened for writing, to create it, so that
+    /// a read-only list can still be looked at.
+    pub fn read_content() -> anyhow::Result<String> {
+        let missing = !std::path::Path::new(&Self::todo_path()).exists();
+        if missing && dry_run::is_enabled() {
+            return Ok(String::new());
+        }
+        let mut file = Self::read_file(true, missing, missing);
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        if crypt::is_encrypted(&content) {
+            content = crypt::decrypt(&content)?;
+        }
+        Ok(content)
+    }
+
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        let _timing = timings::start("save");
+        if batch::is_active() {
+            batch::stage_list(self);
+            return Ok(());
+        }
+        if dry_run::is_enabled() {
+            let (saved, _) = Self::parse(&Self::read_content()?);
+            self.print_changes(&saved);
+            return Ok(());
+        }
+        let saved = match webhook::is_configured() {
+            true => Some(Self::parse(&Self::read_content()?).0),
+            false => None,
+        };
+        let path = Self::todo_path();
+        let mut s = self.content();
+        let recipient = crypt::recipient(&path);
+        if let Some(recipient) = &recipient {
+            s = crypt::encrypt(&s, recipient)?;
+        }
+        integrity::before_write(&path);
+        write_file(&path, &s, "save")?;
+        log::debug("file", || format!("write path={} bytes={}", path, s.len()));
+        integrity::record(&path, s.as_bytes());
+        storage::push(&path, &s);
+        crdt::saved(&path, self);
+        if let Some(saved) = saved {
+            webhook::emit(&saved, self);
+        }
 
         Ok(())
     }
 
-    fn read_file(r: bool, w: bool, c: bool, t: bool) -> File {
-        OpenOptions::new()
+    /// The list as it is written to the file, before any encryption.
+    fn content(&self) -> String {
+        let skipped = self.skipped.iter().map(|s| &s.todo);
+        let others = self
+            .records
+            .iter()
+            .chain(skipped)
+            .flat_map(|r| r.other_columns.iter().map(|(h, _)| h.as_str()));
+        let headers = columns::to_write(&self.headers, others);
+        let columns = columns::map(&headers);
+        let mut rows: Vec<Vec<String>> = self.records.iter().map(|r| r.to_row(&columns)).collect();
+        rows.extend(self.skipped.iter().map(|s| {
+            let mut row = s.todo.to_row(&columns);
+            row.extend(s.overflow.iter().cloned());
+            row
+        }));
+        format::write(migrations::CURRENT_VERSION, &headers, &rows)
+    }
+
+    /// What saving would change compared to `saved`, for `--dry-run`.
+    fn print_changes(&self, saved: &Todos) {
+        let headers = Self::default_headers();
+        let mut changed = false;
+        for old in &saved.records {
+            if !self.records.iter().any(|r| r.id == old.id) {
+                println!("Would delete #{}: {}", old.id, old.title);
+                changed = true;
+            }
+        }
+        for new in &self.records {
+            match saved.records.iter().find(|r| r.id == new.id) {
+                None => println!("Would add #{}: {}", new.id, new.title),
+                Some(old) if old.to_fields() != new.to_fields() => {
+                    println!("Would change #{}: {}", new.id, new.title);
+                    print_diff(&headers, &old.to_fields(), &new.to_fields());
+                }
+                Some(_) => continue,
+            }
+            changed = true;
+        }
+        if !changed {
+            println!("Dry run, nothing would change");
+        }
+    }
+
+    fn read_file(r: bool, w: bool, c: bool) -> File {
+        let path = Self::todo_path();
+        if let Some(dir) = std::path::Path::new(&path).parent() {
+            if c && !dir.exists() {
+                if let Err(e) = std::fs::create_dir_all(dir) {
+                    eprintln!("Failed to create {}: {}", dir.display(), e);
+                    exit(1);
+                }
+            }
+        }
+        match OpenOptions::new()
             .read(r)
             .write(w)
             .create(c)
-            .truncate(t)
-            .open(Self::todo_path())
-            .unwrap()
+            .truncate(false)
+            .open(&path)
+        {
+            Ok(file) => file,
+            Err(e)
+                if w && matches!(
+                    e.kind(),
+                    ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem
+                ) =>
+            {
+                eprintln!("{} is read-only, so it can be listed but not changed", path);
+                exit(1);
+            }
+            Err(e) => {
+                eprintln!("Failed to open {}: {}", path, e);
+                exit(1);
+            }
+        }
     }
 
+    /// The nearest project list, see `init::find_local`, or the global
+    /// one. Other files, like the trash, are kept next to it under the
+    /// same name.
     fn todo_path() -> String {
+        init::find_local().unwrap_or_else(Self::global_path)
+    }
+
+    /// `storage.path` from the config, or `~/.todo`.
+    fn global_path() -> String {
+        match config::get().string("storage.path") {
+            Some(path) => config::expand_home(path),
+            None => Self::default_path(),
+        }
+    }
+
+    fn default_path() -> String {
         let home = home_dir().unwrap();
         let home = home.to_str().unwrap();
         format!("{}/.todo", home)
     }
 
-    pub fn done(&mut self, id: String) -> anyhow::Result<()> {
+    pub fn find_mut(&mut self, id: &str) -> anyhow::Result<&mut Todo> {
+        match self.records.iter_mut().find(|r| r.id == id) {
+            Some(t) => Ok(t),
+            None => bail!("The specified ID does not exist"),
+        }
+    }
+
+    pub fn done(&mut self, id: String) -> anyhow::Result<Todo> {
         let todo = self.records.iter_mut().find(|r| r.id == id);
 
-        let mut todo = match todo {
+        let todo = match todo {
             Some(t) => t,
             None => bail!("The specified ID does not exist"),
         };
 
-        todo.done = "✓".to_string();
-        self.print_list(false);
-        Ok(())
+        if repeat::is_recurring(todo) {
+            repeat::complete(todo);
+        } else {
+            todo.done = "✓".to_string();
+            todo.completed_at = date::now().to_string();
+            todo.completed_by = current_user();
+        }
+        Ok(todo.clone())
     }
 
-    pub fn delete(&mut self, id: String) -> anyhow::Result<()> {
+    pub fn delete(&mut self, id: String) -> anyhow::Result<Todo> {
         let index = self.records.iter_mut().position(|r| r.id == id);
         let index = match index {
             Some(i) => i,
             None => bail!("The specified ID does not exist"),
         };
-        self.records.remove(index);
-        self.print_list(false);
-        Ok(())
+        Ok(self.records.remove(index))
     }
 
-    pub fn add(&mut self, date: String, title: String, url: String) -> anyhow::Result<()> {
-        let last_id = match self.records.last() {
-            Some(l) => l.id.parse().unwrap(),
-            None => 0,
-        };
-        let id = last_id + 1;
+    /// Add `todo` under the next free ID.
+    pub fn add(&mut self, mut todo: Todo) -> anyhow::Result<Todo> {
+        todo.id = self.next_id().to_string();
+        todo.created_at = date::now().to_string();
+        todo.created_by = current_user();
+        self.records.push(todo.clone());
+        Ok(todo)
+    }
+
+    pub fn next_id(&self) -> usize {
+        // Not those of records set aside either, which may come back.
+        let skipped = self.skipped.iter().map(|s| &s.todo);
         self.records
-            .push(Todo::new(id.to_string(), date, title, url, "".to_string()));
-        self.print_list(false);
-        Ok(())
+            .iter()
+            .chain(skipped)
+            .filter_map(|r| r.id.parse::<usize>().ok())
+            .max()
+            .unwrap_or(0)
+            + 1
     }
 
-    pub fn clear(&mut self) -> anyhow::Result<()> {
-        self.records = vec![];
-        self.print_list(false);
-        Ok(())
+    pub fn clear(&mut self) -> anyhow::Result<Vec<Todo>> {
+        Ok(std::mem::take(&mut self.records))
     }
 
     pub fn print_list(&self, all: bool) {
-        let table = self
+        self.print_table(all, false)
+    }
+
+    /// The table of `print_list`, led by a LIST column for TODOs of
+    /// several lists, see `lists`.
+    fn print_table(&self, all: bool, lists: bool) {
+        let _timing = timings::start("render");
+        let records: Vec<&Todo> = self
             .records
             .iter()
             .filter(|r| if all { true } else { r.done.is_empty() })
-            .map(|r| {
-                vec![
-                    r.id.clone().cell().justify(Justify::Center),
-                    r.date.clone().cell().justify(Justify::Center),
-                    r.title.clone().cell(),
-                    r.url.clone().cell(),
-                    r.done.clone().cell().justify(Justify::Center),
-                ]
+            .collect();
+        // Who did what only matters once more than one person has.
+        let mut users: Vec<&str> = records
+            .iter()
+            .flat_map(|r| [r.created_by.as_str(), r.completed_by.as_str()])
+            .filter(|u| !u.is_empty())
+            .collect();
+        users.sort();
+        users.dedup();
+        let columns: Vec<&Column> = LIST_COLUMNS
+            .iter()
+            .filter(|c| c.name != "by" || users.len() > 1)
+            .filter(|c| !c.optional || records.iter().any(|r| !(c.value)(r).is_empty()))
+            .collect();
+        // Declared custom fields go before `done`, when some TODO has one.
+        let declared: Vec<&str> = fields::declared()
+            .iter()
+            .map(|f| f.name.as_str())
+            .filter(|name| *name != location::FIELD)
+            .filter(|name| records.iter().any(|r| fields::get(r, name).is_some()))
+            .collect();
+        let (last, columns) = columns.split_last().unwrap();
+        let lead: &[&str] = if lists { &[lists::FIELD] } else { &[] };
+        let names: Vec<&str> = lead
+            .iter()
+            .copied()
+            .chain(columns.iter().map(|c| c.name))
+            .chain(declared.iter().copied())
+            .chain([last.name])
+            .collect();
+        let justify: Vec<Justify> = lead
+            .iter()
+            .map(|_| Justify::Left)
+            .chain(columns.iter().map(|c| c.justify))
+            .chain(declared.iter().map(|_| Justify::Left))
+            .chain([last.justify])
+            .collect();
+        let labels: Vec<(String, Option<cli_table::Color>)> = records
+            .iter()
+            .map(|r| lists::label(fields::get(r, lists::FIELD).unwrap_or("")))
+            .collect();
+
+        let mut rows: Vec<Vec<String>> = records
+            .iter()
+            .zip(&labels)
+            .map(|(r, (label, _))| {
+                let mut cells: Vec<String> = lead.iter().map(|_| label.clone()).collect();
+                cells.extend(columns.iter().map(|c| (c.value)(r)));
+                cells.extend(
+                    declared
+                        .iter()
+                        .map(|name| fields::get(r, name).unwrap_or("").to_string()),
+                );
+                cells.push((last.value)(r));
+                cells
+            })
+            .collect();
+        let headers: Vec<String> = names.iter().map(|n| n.to_uppercase()).collect();
+        term::fit_columns(&mut rows, &headers, &names);
+        let hidden = term::hide_clusters(&mut rows);
+
+        let table = rows
+            .into_iter()
+            .zip(&labels)
+            .map(|(cells, (_, color))| {
+                cells
+                    .into_iter()
+                    .zip(&justify)
+                    .enumerate()
+                    .map(|(i, (text, justify))| {
+                        let cell = text.cell().justify(*justify);
+                        if i < lead.len() {
+                            cell.foreground_color(*color)
+                        } else {
+                            cell
+                        }
+                    })
+                    .collect::<Vec<_>>()
             })
             .table()
             .title(
-                self.headers
+                headers
                     .iter()
-                    .map(|h| h.to_uppercase().cell().bold(true).justify(Justify::Center)),
+                    .map(|h| h.cell().bold(true).justify(Justify::Center)),
             )
-            .bold(true);
-        print_stdout(table).unwrap();
-    }
-
-    pub fn to_vec(&self) -> Vec<String> {
-        self.records.iter().map(|r| r.to_csv()).collect()
+            .bold(true)
+            .color_choice(term::color_choice());
+        let table = table.display().unwrap().to_string();
+        print!("{}", term::restore_clusters(table, &hidden));
     }
 }
@@ -0,0 +1,84 @@
+//! Records of the list that can't be trusted: with more fields than the
+//! file has columns, or without a usable or unique ID. By default they
+//! are set aside with a warning, and written back as they were, so that
+//! nothing is lost before `todo doctor --fix` sorts them out.
+//!
+//! With `--strict`, accepted by every command, or `storage.strict = true`,
+//! the first of them is an error instead, for scripts that would rather
+//! stop than work on part of the list.
+
+use crate::{config, Todo};
+use std::collections::HashSet;
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Take `--strict` out of `args`.
+pub fn select(args: &mut Vec<String>) {
+    let end = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    let before = args.len();
+    let mut i = 0;
+    args.retain(|a| {
+        i += 1;
+        i > end || a != "--strict"
+    });
+    if args.len() < before {
+        STRICT.store(true, Ordering::SeqCst);
+    }
+}
+
+pub fn is_strict() -> bool {
+    STRICT.load(Ordering::SeqCst) || config::get().string("storage.strict") == Some("true")
+}
+
+/// A record set aside, as read.
+#[derive(Debug, Clone)]
+pub struct Skipped {
+    pub line: usize,
+    pub problem: String,
+    pub todo: Todo,
+    /// The fields past the last column.
+    pub overflow: Vec<String>,
+}
+
+/// What is wrong with `todo`, read from a row of `fields` fields in a
+/// file of `columns` columns, given the IDs `seen` so far.
+pub fn problem(
+    todo: &Todo,
+    fields: usize,
+    columns: usize,
+    seen: &HashSet<String>,
+) -> Option<String> {
+    if fields > columns {
+        Some(format!("{} fields for {} columns", fields, columns))
+    } else if todo.id.parse::<usize>().is_err() {
+        Some(format!("invalid ID '{}'", todo.id))
+    } else if seen.contains(&todo.id) {
+        Some(format!("duplicate ID {}", todo.id))
+    } else {
+        None
+    }
+}
+
+/// Tell about the records of the list at `path` that were set aside, or
+/// exit on the first of them in strict mode.
+pub fn report(path: &str, skipped: &[Skipped]) {
+    let Some(first) = skipped.first() else {
+        return;
+    };
+    if is_strict() {
+        eprintln!("{} line {}: {}", path, first.line, first.problem);
+        exit(1);
+    }
+    let lines: Vec<String> = skipped.iter().map(|s| s.line.to_string()).collect();
+    eprintln!(
+        "Warning: skipped {} malformed record{} of {} on line {} ({}{}), see todo doctor",
+        skipped.len(),
+        if skipped.len() == 1 { "" } else { "s" },
+        path,
+        lines.join(", "),
+        first.problem,
+        if skipped.len() == 1 { "" } else { ", ..." }
+    );
+}
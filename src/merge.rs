@@ -0,0 +1,260 @@
+//! `todo merge`: fold another list into this one, as when consolidating
+//! the lists of two machines. A TODO there is the same as one here when
+//! they share a synced copy in some service, or else when they have the
+//! same title and date. Its comments, tags, links and other lists of
+//! things are combined, it was added when the first of them was, and a
+//! field empty on one side takes the value of the other. Where both have
+//! different values, the user picks one, or `--strategy` does:
+//!
+//! - `ours` keeps the value here;
+//! - `theirs` takes the value there;
+//! - `newest` takes the value of the TODO that was changed last, as far
+//!   as can be told from when it was added, commented on, worked on,
+//!   completed and reviewed.
+//!
+//! The other TODOs are added under the next free IDs, except those in
+//! the trash here, and TODOs in the trash there are deleted here, both
+//! unless the TODO was changed after it was deleted.
+
+use crate::trash::Trash;
+use crate::{format, lists, term, workspace, Todo, Todos};
+use seahorse::{Command, Context, Flag, FlagType};
+use std::path::Path;
+use std::process::exit;
+
+/// Fields holding several values, which are combined rather than picked.
+const COMBINED: &[&str] = &[
+    "url",
+    "comments",
+    "attachments",
+    "time_log",
+    "completions",
+    "tags",
+    "remote_ids",
+    "changes",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Strategy {
+    Ask,
+    Ours,
+    Theirs,
+    Newest,
+}
+
+pub fn merge_command() -> Command {
+    Command::new("merge")
+        .description("Merge another list into this one")
+        .usage("todo merge <workspace | file> [--strategy newest|ours|theirs]")
+        .flag(
+            Flag::new("strategy", FlagType::String)
+                .alias("s")
+                .description(
+                    "Settle differing fields with newest, ours or theirs instead of asking",
+                ),
+        )
+        .action(merge_action)
+}
+
+fn merge_action(c: &Context) {
+    let source = match c.args.as_slice() {
+        [source] => source,
+        _ => {
+            eprintln!("Please specify one workspace or file to merge");
+            exit(1);
+        }
+    };
+    let strategy = match c.string_flag("strategy").as_deref() {
+        Err(_) => Strategy::Ask,
+        Ok("ours") => Strategy::Ours,
+        Ok("theirs") => Strategy::Theirs,
+        Ok("newest") => Strategy::Newest,
+        Ok(s) => {
+            eprintln!("Unknown strategy {}, expected newest, ours or theirs", s);
+            exit(1);
+        }
+    };
+    let path = match workspace::lists()
+        .into_iter()
+        .find(|(name, _)| name == source)
+    {
+        Some((_, path)) => path,
+        None if Path::new(source).is_file() => source.to_string(),
+        None => {
+            eprintln!("No such workspace or file: {}", source);
+            exit(1);
+        }
+    };
+    let canonical = |path: &str| Path::new(path).canonicalize().ok();
+    if canonical(&path) == canonical(&Todos::todo_path()) {
+        eprintln!("{} is the current list", source);
+        exit(1);
+    }
+
+    let mut todos = Todos::read();
+    let theirs = match lists::read(&path) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            exit(1);
+        }
+    };
+    let mut trash = Trash::read();
+    let their_trash = Trash::of(&path);
+    let headers = Todos::default_headers();
+    let (mut added, mut merged, mut same, mut deleted) = (0, 0, 0, 0);
+    let mut matched: Vec<String> = vec![];
+
+    // Deleted on one side counts unless changed since on the other.
+    for (deleted_at, gone) in their_trash.deleted() {
+        if theirs.records.iter().any(|r| is_same(r, gone)) {
+            continue;
+        }
+        let Some(i) = todos
+            .records
+            .iter()
+            .position(|r| is_same(r, gone) && changed_at(r) <= deleted_at)
+        else {
+            continue;
+        };
+        let todo = todos.records.remove(i);
+        println!("Deleted #{}: {}", todo.id, todo.title);
+        trash.push_deleted_at(todo, deleted_at);
+        deleted += 1;
+    }
+    for their in theirs.records {
+        let deleted_here = trash
+            .deleted()
+            .any(|(at, gone)| is_same(gone, &their) && changed_at(&their) <= at);
+        if deleted_here && !todos.records.iter().any(|r| is_same(r, &their)) {
+            println!("Skipped {}: deleted here", their.title);
+            continue;
+        }
+        let found = todos
+            .records
+            .iter_mut()
+            .filter(|r| !matched.contains(&r.id))
+            .find(|r| is_same(r, &their));
+        let Some(our) = found else {
+            let mut todo = their;
+            todo.id = todos.next_id().to_string();
+            println!("Added #{}: {}", todo.id, todo.title);
+            matched.push(todo.id.clone());
+            todos.records.push(todo);
+            added += 1;
+            continue;
+        };
+        matched.push(our.id.clone());
+        let before = our.to_fields();
+        let newest_is_theirs = changed_at(&their) > changed_at(our);
+        let theirs_fields = their.to_fields();
+        let mut fields = before.clone();
+        for (i, name) in headers.iter().enumerate().skip(1) {
+            let (ours, theirs) = (&before[i], &theirs_fields[i]);
+            if ours == theirs || theirs.is_empty() {
+                continue;
+            }
+            fields[i] = if ours.is_empty() {
+                theirs.clone()
+            } else if name == "created_at" {
+                ours.min(theirs).clone()
+            } else if COMBINED.contains(&name.as_str()) {
+                combine(ours, theirs)
+            } else {
+                let take_theirs = match strategy {
+                    Strategy::Ours => false,
+                    Strategy::Theirs => true,
+                    Strategy::Newest => newest_is_theirs,
+                    Strategy::Ask => ask(our, name, ours, theirs),
+                };
+                if take_theirs {
+                    theirs.clone()
+                } else {
+                    ours.clone()
+                }
+            };
+        }
+        // Columns only the other list has are kept, see `columns`.
+        let mut others = their.other_columns.clone();
+        others.retain(|(h, _)| !our.other_columns.iter().any(|(o, _)| o == h));
+        if fields == before && others.is_empty() {
+            same += 1;
+        } else {
+            our.set_fields(&fields);
+            our.other_columns.extend(others);
+            println!("Merged #{}: {}", our.id, our.title);
+            merged += 1;
+        }
+    }
+    crate::save_with_trash(&mut todos, &mut trash);
+    println!(
+        "{} added, {} merged, {} already the same, {} deleted",
+        added, merged, same, deleted
+    );
+}
+
+fn is_same(our: &Todo, their: &Todo) -> bool {
+    if our
+        .remote_ids
+        .iter()
+        .any(|id| their.remote_ids.contains(id))
+    {
+        return true;
+    }
+    our.date == their.date && our.title.trim().to_lowercase() == their.title.trim().to_lowercase()
+}
+
+/// The values of both, in order and without repeating any.
+fn combine(ours: &str, theirs: &str) -> String {
+    let mut values = format::split_list(ours);
+    for value in format::split_list(theirs) {
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    format::join_list(&values)
+}
+
+/// Whether the user wants their value of the field `name` of `todo`.
+fn ask(todo: &Todo, name: &str, ours: &str, theirs: &str) -> bool {
+    if !term::is_tty() {
+        eprintln!(
+            "#{} has a different {} there; please choose with --strategy",
+            todo.id, name
+        );
+        exit(1);
+    }
+    println!("#{} {}: {} differs", todo.id, todo.title, name);
+    println!("  ours:   {}", ours.replace('\n', ", "));
+    println!("  theirs: {}", theirs.replace('\n', ", "));
+    match term::read_key("Keep [o]urs or take [t]heirs? ") {
+        Some('o') => false,
+        Some('t') => true,
+        _ => {
+            eprintln!("Cancelled");
+            exit(1);
+        }
+    }
+}
+
+/// The latest timestamp in `todo`.
+fn changed_at(todo: &Todo) -> u64 {
+    let leading = |entries: &[String]| {
+        entries
+            .iter()
+            .filter_map(|e| e.split_whitespace().next()?.parse().ok())
+            .max()
+            .unwrap_or(0)
+    };
+    [&todo.created_at, &todo.completed_at, &todo.reviewed_at]
+        .iter()
+        .filter_map(|t| t.parse().ok())
+        .chain([
+            leading(&todo.comments),
+            leading(&todo.time_log),
+            leading(&todo.completions),
+            leading(&todo.changes),
+        ])
+        .max()
+        .unwrap_or(0)
+}
@@ -0,0 +1,147 @@
+use crate::format::Table;
+use std::fs;
+
+/// Version written by `Todos::save`.
+pub const CURRENT_VERSION: u32 = 20;
+
+/// A migration upgrades a table from the previous version to `to`.
+struct Migration {
+    to: u32,
+    run: fn(&mut Table),
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        to: 2,
+        run: add_timestamps,
+    },
+    Migration {
+        to: 3,
+        run: |t| add_column(t, "assignee"),
+    },
+    Migration {
+        to: 4,
+        run: |t| add_column(t, "comments"),
+    },
+    Migration {
+        to: 5,
+        run: |t| add_column(t, "attachments"),
+    },
+    Migration {
+        to: 6,
+        run: |t| add_column(t, "checklist"),
+    },
+    Migration {
+        to: 7,
+        run: |t| add_column(t, "progress"),
+    },
+    Migration {
+        to: 8,
+        run: |t| add_column(t, "time_log"),
+    },
+    Migration {
+        to: 9,
+        run: |t| add_column(t, "reviewed_at"),
+    },
+    Migration {
+        to: 10,
+        run: |t| add_column(t, "status"),
+    },
+    Migration {
+        to: 11,
+        run: |t| add_column(t, "goal"),
+    },
+    Migration {
+        to: 12,
+        run: |t| add_column(t, "start"),
+    },
+    Migration {
+        to: 13,
+        run: add_recurrence,
+    },
+    Migration {
+        to: 14,
+        run: |t| add_column(t, "tags"),
+    },
+    Migration {
+        to: 15,
+        run: |t| add_column(t, "remote_ids"),
+    },
+    Migration {
+        to: 16,
+        run: |t| add_column(t, "notes"),
+    },
+    Migration {
+        to: 17,
+        run: |t| add_column(t, "estimate"),
+    },
+    Migration {
+        to: 18,
+        run: |t| add_column(t, "fields"),
+    },
+    Migration {
+        to: 19,
+        run: add_attribution,
+    },
+    Migration {
+        to: 20,
+        run: |t| add_column(t, "changes"),
+    },
+];
+
+/// Upgrade `table` in place to the current version.
+pub fn migrate(table: &mut Table) {
+    let from = table.version;
+    for migration in MIGRATIONS.iter().filter(|m| m.to > from) {
+        (migration.run)(table);
+        table.version = migration.to;
+    }
+}
+
+/// Copy the file at `path` aside before it is rewritten by a migration.
+pub fn backup(path: &str, version: u32) -> std::io::Result<String> {
+    let backup = format!("{}.v{}.bak", path, version);
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+/// v2: fields are escaped on write, and records carry creation and
+/// completion timestamps.
+fn add_timestamps(table: &mut Table) {
+    let fields = table.headers.len();
+    table.headers.push("created_at".to_string());
+    table.headers.push("completed_at".to_string());
+    for row in table.rows.iter_mut().filter(|r| !r.is_empty()) {
+        // v1 never escaped commas, so a row with extra fields is a title
+        // that contained some.
+        if fields >= 3 && row.fields.len() > fields {
+            let extra = row.fields.len() - fields;
+            let title = row.fields.drain(2..3 + extra).collect::<Vec<String>>();
+            row.fields.insert(2, title.join(","));
+        }
+        row.fields.resize(fields, "".to_string());
+        row.fields.push("".to_string());
+        row.fields.push("".to_string());
+    }
+}
+
+/// v13: recurring TODOs, with a log of their completions.
+fn add_recurrence(table: &mut Table) {
+    add_column(table, "repeat");
+    add_column(table, "completions");
+}
+
+/// v19: who added and who completed each TODO, for shared lists.
+fn add_attribution(table: &mut Table) {
+    add_column(table, "created_by");
+    add_column(table, "completed_by");
+}
+
+/// Append an empty column, for fields that have no value in older files.
+fn add_column(table: &mut Table, name: &str) {
+    table.headers.push(name.to_string());
+    let fields = table.headers.len();
+    for row in table.rows.iter_mut().filter(|r| !r.is_empty()) {
+        row.fields.resize(fields, "".to_string());
+    }
+}
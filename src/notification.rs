@@ -0,0 +1,28 @@
+use std::process::{Command, Stdio};
+
+/// Show a desktop notification, falling back to the terminal bell when
+/// no notifier is available.
+pub fn desktop(title: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut c = Command::new("osascript");
+        c.arg("-e").arg(format!(
+            "display notification {:?} with title {:?}",
+            body, title
+        ));
+        c
+    } else {
+        let mut c = Command::new("notify-send");
+        c.arg(title).arg(body);
+        c
+    };
+
+    let shown = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    if !shown {
+        print!("\x07");
+    }
+}
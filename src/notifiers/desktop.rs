@@ -0,0 +1,21 @@
+use super::{Notifier, Summary};
+use crate::notification;
+
+/// A desktop notification, see `notification::desktop`.
+pub struct Desktop;
+
+impl Notifier for Desktop {
+    fn name(&self) -> String {
+        "the desktop".to_string()
+    }
+
+    fn send(&self, summary: &Summary) -> anyhow::Result<()> {
+        let title = format!(
+            "{} overdue, {} due today",
+            summary.overdue.len(),
+            summary.today.len()
+        );
+        notification::desktop(&title, &summary.text(|s| s.to_string()));
+        Ok(())
+    }
+}
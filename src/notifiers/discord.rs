@@ -0,0 +1,38 @@
+use super::{Notifier, Summary};
+use crate::http;
+use crate::json::Value;
+
+/// Discord rejects messages longer than this.
+const MAX_CONTENT: usize = 2000;
+
+/// A Discord channel webhook.
+pub struct Discord {
+    url: String,
+}
+
+impl Discord {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+        }
+    }
+}
+
+impl Notifier for Discord {
+    fn name(&self) -> String {
+        format!("Discord ({})", http::host(&self.url))
+    }
+
+    fn send(&self, summary: &Summary) -> anyhow::Result<()> {
+        let mut text = summary.text(|s| format!("**{}**", s));
+        if text.chars().count() > MAX_CONTENT {
+            text = text.chars().take(MAX_CONTENT - 1).collect::<String>() + "…";
+        }
+        http::post_json(
+            &self.url,
+            &[],
+            &Value::object(vec![("content", text.as_str().into())]),
+        )
+        .map(|_| ())
+    }
+}
@@ -0,0 +1,162 @@
+//! Backends that `todo notify` sends due-today and overdue summaries to.
+//!
+//! Each backend implements `Notifier`. Webhooks are routed by tag with
+//! the `[notify]` config section:
+//!
+//! ```toml
+//! [notify]
+//! webhook = "https://hooks.slack.com/services/..."   # everything else
+//!
+//! [notify.tags]
+//! work = "https://discord.com/api/webhooks/..."
+//! ```
+
+mod desktop;
+mod discord;
+mod slack;
+
+use crate::date::{self, Date};
+use crate::{config, someday, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::process::exit;
+
+pub trait Notifier {
+    /// Where the summary goes, for messages.
+    fn name(&self) -> String;
+    fn send(&self, summary: &Summary) -> anyhow::Result<()>;
+}
+
+/// Open TODOs that are overdue or due today.
+pub struct Summary<'a> {
+    pub overdue: Vec<&'a Todo>,
+    pub today: Vec<&'a Todo>,
+}
+
+impl<'a> Summary<'a> {
+    fn new(todos: &[&'a Todo], today: Date) -> Self {
+        let mut summary = Summary {
+            overdue: vec![],
+            today: vec![],
+        };
+        for todo in todos {
+            match Date::parse(&todo.date) {
+                Some(_) if date::is_past(&todo.date) => summary.overdue.push(todo),
+                Some(d) if d == today => summary.today.push(todo),
+                _ => {}
+            }
+        }
+        summary.today.sort_by_key(|t| date::time_of(&t.date));
+        summary
+    }
+
+    fn is_empty(&self) -> bool {
+        self.overdue.is_empty() && self.today.is_empty()
+    }
+
+    /// One line per TODO under a heading per section, with headings
+    /// wrapped by `bold` for the backend's markup.
+    pub fn text(&self, bold: fn(&str) -> String) -> String {
+        let mut out = vec![];
+        for (title, todos) in [("Overdue", &self.overdue), ("Due today", &self.today)] {
+            if todos.is_empty() {
+                continue;
+            }
+            out.push(bold(&format!("{} ({})", title, todos.len())));
+            for todo in todos.iter() {
+                out.push(format!("• #{} {} ({})", todo.id, todo.title, todo.date));
+            }
+        }
+        out.join("\n")
+    }
+}
+
+pub fn notify_command() -> Command {
+    Command::new("notify")
+        .description("Send a summary of overdue TODOs and those due today")
+        .usage("todo notify [--webhook]")
+        .flag(
+            Flag::new("webhook", FlagType::Bool)
+                .alias("w")
+                .description("Post to the webhooks in the [notify] config section"),
+        )
+        .action(|c| {
+            let todos = Todos::read();
+            let open: Vec<&Todo> = todos
+                .records
+                .iter()
+                .filter(|t| t.done.is_empty() && !someday::is_someday(t))
+                .collect();
+            let today = date::today();
+
+            let routes: Vec<(Box<dyn Notifier>, Vec<&Todo>)> = if c.bool_flag("webhook") {
+                webhook_routes(&open)
+            } else {
+                vec![(Box::new(desktop::Desktop), open)]
+            };
+
+            let mut failed = false;
+            for (notifier, todos) in routes {
+                let summary = Summary::new(&todos, today);
+                if summary.is_empty() {
+                    continue;
+                }
+                match notifier.send(&summary) {
+                    Ok(()) => println!("Notified {}", notifier.name()),
+                    Err(e) => {
+                        eprintln!("{}: {}", notifier.name(), e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                exit(1);
+            }
+        })
+}
+
+/// Split `todos` over the configured webhooks. A TODO goes to the hook of
+/// each of its tags that has one, or to `notify.webhook` if none does.
+fn webhook_routes<'a>(todos: &[&'a Todo]) -> Vec<(Box<dyn Notifier>, Vec<&'a Todo>)> {
+    let config = config::get();
+    let tags = config.section("notify.tags");
+    let default = config.string("notify.webhook");
+    if tags.is_empty() && default.is_none() {
+        eprintln!(
+            "Please set notify.webhook or [notify.tags] in {}",
+            config::Config::path()
+        );
+        exit(1);
+    }
+
+    let mut routes: Vec<(&str, Vec<&Todo>)> = vec![];
+    for todo in todos {
+        let mut urls: Vec<&str> = tags
+            .iter()
+            .filter(|(tag, _)| todo.tags.iter().any(|t| t == tag))
+            .map(|(_, url)| *url)
+            .collect();
+        if urls.is_empty() {
+            urls.extend(default);
+        }
+        urls.dedup();
+        for url in urls {
+            match routes.iter_mut().find(|(u, _)| *u == url) {
+                Some((_, todos)) => todos.push(todo),
+                None => routes.push((url, vec![todo])),
+            }
+        }
+    }
+    routes
+        .into_iter()
+        .map(|(url, todos)| (webhook(url), todos))
+        .collect()
+}
+
+/// The backend for an incoming webhook URL.
+fn webhook(url: &str) -> Box<dyn Notifier> {
+    if url.contains("discord.com/") || url.contains("discordapp.com/") {
+        Box::new(discord::Discord::new(url))
+    } else {
+        Box::new(slack::Slack::new(url))
+    }
+}
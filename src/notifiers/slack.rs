@@ -0,0 +1,32 @@
+use super::{Notifier, Summary};
+use crate::http;
+use crate::json::Value;
+
+/// A Slack incoming webhook.
+pub struct Slack {
+    url: String,
+}
+
+impl Slack {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+        }
+    }
+}
+
+impl Notifier for Slack {
+    fn name(&self) -> String {
+        format!("Slack ({})", http::host(&self.url))
+    }
+
+    fn send(&self, summary: &Summary) -> anyhow::Result<()> {
+        let text = summary.text(|s| format!("*{}*", s));
+        http::post_json(
+            &self.url,
+            &[],
+            &Value::object(vec![("text", text.as_str().into())]),
+        )
+        .map(|_| ())
+    }
+}
@@ -0,0 +1,170 @@
+use crate::json::{self, Value};
+use crate::{date, http, Todos};
+use anyhow::{bail, Context};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+/// An OAuth 2.0 provider that supports the device authorization grant,
+/// where the user approves access in a browser on any device.
+pub struct Provider {
+    /// Short name used for the token file, e.g. `gtasks`.
+    pub name: &'static str,
+    pub device_url: String,
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scope: &'static str,
+}
+
+impl Provider {
+    fn token_path(&self) -> String {
+        format!("{}.{}-token", Todos::todo_path(), self.name)
+    }
+
+    /// A valid access token, refreshing the stored one or asking the user
+    /// to sign in when needed.
+    pub fn access_token(&self) -> anyhow::Result<String> {
+        let stored = fs::read_to_string(self.token_path())
+            .ok()
+            .and_then(|s| json::parse(&s).ok());
+        if let Some(token) = &stored {
+            let expires_at = token.get("expires_at").as_f64().unwrap_or(0.0) as u64;
+            if let Some(access) = token.get("access_token").as_str() {
+                if expires_at > date::now() + 60 {
+                    return Ok(access.to_string());
+                }
+            }
+            if let Some(refresh) = token.get("refresh_token").as_str() {
+                let mut form = vec![("grant_type", "refresh_token"), ("refresh_token", refresh)];
+                form.extend(self.client());
+                match self.token(&form) {
+                    Ok(access) => return Ok(access),
+                    Err(e) => eprintln!("Failed to refresh the {} token: {}", self.name, e),
+                }
+            }
+        }
+        self.device_flow()
+    }
+
+    /// Forget the stored token.
+    pub fn logout(&self) -> anyhow::Result<()> {
+        match fs::remove_file(self.token_path()) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn client(&self) -> Vec<(&str, &str)> {
+        let mut client = vec![("client_id", self.client_id.as_str())];
+        if let Some(secret) = &self.client_secret {
+            client.push(("client_secret", secret.as_str()));
+        }
+        client
+    }
+
+    fn device_flow(&self) -> anyhow::Result<String> {
+        let mut form = vec![("scope", self.scope)];
+        form.extend(self.client());
+        let reply = post_form(&self.device_url, &form)?;
+        let (Some(device_code), Some(user_code)) = (
+            reply.get("device_code").as_str(),
+            reply.get("user_code").as_str(),
+        ) else {
+            bail!("Unexpected reply from {}", http::host(&self.device_url));
+        };
+        let url = reply
+            .get("verification_url")
+            .as_str()
+            .or(reply.get("verification_uri").as_str())
+            .unwrap_or("");
+        let mut interval = reply.get("interval").as_f64().unwrap_or(5.0) as u64;
+        println!(
+            "To allow access, open {} and enter the code {}",
+            url, user_code
+        );
+
+        let mut form = vec![
+            ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ("device_code", device_code),
+        ];
+        form.extend(self.client());
+        loop {
+            thread::sleep(Duration::from_secs(interval));
+            match self.token(&form) {
+                Ok(access) => {
+                    println!("Signed in");
+                    return Ok(access);
+                }
+                Err(e) => match e.to_string().as_str() {
+                    "authorization_pending" => {}
+                    "slow_down" => interval += 5,
+                    _ => return Err(e),
+                },
+            }
+        }
+    }
+
+    /// Request a token and store it. Errors carry the OAuth error code.
+    fn token(&self, form: &[(&str, &str)]) -> anyhow::Result<String> {
+        let reply = post_form(&self.token_url, form)?;
+        if let Some(error) = reply.get("error").as_str() {
+            bail!("{}", error);
+        }
+        let Some(access) = reply.get("access_token").as_str() else {
+            bail!("Unexpected reply from {}", http::host(&self.token_url));
+        };
+
+        // Refresh responses may leave the refresh token out; keep the old one.
+        let refresh = reply
+            .get("refresh_token")
+            .as_str()
+            .map(|r| r.to_string())
+            .or_else(|| {
+                let old = fs::read_to_string(self.token_path()).ok()?;
+                json::parse(&old)
+                    .ok()?
+                    .get("refresh_token")
+                    .as_str()
+                    .map(|r| r.to_string())
+            });
+        let expires_in = reply.get("expires_in").as_f64().unwrap_or(3600.0);
+        let mut pairs = vec![
+            ("access_token", Value::from(access)),
+            ("expires_at", Value::Number(date::now() as f64 + expires_in)),
+        ];
+        if let Some(refresh) = &refresh {
+            pairs.push(("refresh_token", refresh.as_str().into()));
+        }
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(self.token_path())
+            .with_context(|| format!("Failed to write {}", self.token_path()))?;
+        write!(file, "{}", Value::object(pairs))?;
+        Ok(access.to_string())
+    }
+}
+
+/// POST a form and parse the JSON reply, whatever the status: OAuth
+/// servers report errors like `authorization_pending` in the body.
+fn post_form(url: &str, form: &[(&str, &str)]) -> anyhow::Result<Value> {
+    let body = form
+        .iter()
+        .map(|(k, v)| format!("{}={}", http::encode(k), http::encode(v)))
+        .collect::<Vec<_>>()
+        .join("&");
+    http::request(
+        "POST",
+        url,
+        &["Content-Type: application/x-www-form-urlencoded".to_string()],
+        Some(&body),
+    )?
+    .json()
+}
@@ -0,0 +1,24 @@
+use anyhow::{bail, Context};
+use std::process::Command;
+
+/// Open a file or URL with the platform's default application.
+pub fn open(target: &str) -> anyhow::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    } else {
+        Command::new("xdg-open")
+    };
+
+    let status = command
+        .arg(target)
+        .status()
+        .with_context(|| format!("Failed to open {}", target))?;
+    if !status.success() {
+        bail!("Failed to open {}", target);
+    }
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Web pages linked from TODOs, fetched with `http::request`.
+
+use crate::http;
+use anyhow::bail;
+
+/// Reading speed for `Page::reading_minutes`, a typical adult's.
+const WORDS_PER_MINUTE: usize = 230;
+
+pub struct Page {
+    /// Empty when the page has none.
+    pub title: String,
+    /// Words of the text, leaving out scripts and styles.
+    pub words: usize,
+}
+
+impl Page {
+    pub fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(WORDS_PER_MINUTE).max(1)
+    }
+}
+
+/// The HTML page at `url`.
+pub fn fetch(url: &str) -> anyhow::Result<Page> {
+    let response = http::request("GET", url, &["Accept: text/html".to_string()], None)?;
+    if !response.is_success() {
+        bail!("{} answered {}", http::host(url), response.status);
+    }
+    Ok(Page {
+        title: element_text(&response.body, "title").unwrap_or_default(),
+        words: count_words(&response.body),
+    })
+}
+
+/// The `<title>` of the HTML page at `url`.
+pub fn title(url: &str) -> anyhow::Result<String> {
+    let page = fetch(url)?;
+    if page.title.is_empty() {
+        bail!("{} has no title", url);
+    }
+    Ok(page.title)
+}
+
+/// Words outside of tags in the `<body>`, or the whole document if it
+/// has none.
+fn count_words(html: &str) -> usize {
+    let lower = html.to_ascii_lowercase();
+    let mut rest = match lower.find("<body") {
+        Some(i) => &lower[i..],
+        None => &lower[..],
+    };
+    let mut words = 0;
+    while let Some(open) = rest.find('<') {
+        words += rest[..open].split_whitespace().count();
+        rest = &rest[open..];
+        let skip_to = ["script", "style"]
+            .iter()
+            .find(|name| rest[1..].starts_with(*name))
+            .map(|name| format!("</{}", name));
+        let end = match skip_to {
+            Some(close) => rest
+                .find(&close)
+                .and_then(|i| Some(i + rest[i..].find('>')?)),
+            None => rest.find('>'),
+        };
+        match end {
+            Some(end) => rest = &rest[end + 1..],
+            None => return words,
+        }
+    }
+    words + rest.split_whitespace().count()
+}
+
+/// The text of the first `<name>` element, with entities decoded and
+/// whitespace collapsed.
+fn element_text(html: &str, name: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets the same as in `html`.
+    let lower = html.to_ascii_lowercase();
+    let open = format!("<{}", name);
+    let mut from = 0;
+    let start = loop {
+        let at = from + lower[from..].find(&open)?;
+        let after = lower[at + open.len()..].chars().next()?;
+        if after == '>' || after.is_whitespace() {
+            break at + lower[at..].find('>')? + 1;
+        }
+        from = at + open.len();
+    };
+    let end = start + lower[start..].find(&format!("</{}", name))?;
+    let text = decode_entities(&html[start..end]);
+    Some(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn decode_entities(s: &str) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out += &rest[..i];
+        rest = &rest[i..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                e if e.starts_with("#x") || e.starts_with("#X") => {
+                    char::from_u32(u32::from_str_radix(&e[2..], 16).ok()?)?
+                }
+                e if e.starts_with('#') => char::from_u32(e[1..].parse().ok()?)?,
+                _ => return None,
+            };
+            Some((c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out + rest
+}
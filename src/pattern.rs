@@ -0,0 +1,308 @@
+//! Regular expressions, as far as matching titles and URLs needs them.
+//!
+//! Supported are literal characters, `.`, `[a-z]` and `[^...]` classes,
+//! `\d`, `\w` and `\s`, `^` and `$`, `(...)` groups, `|` alternatives,
+//! and the `*`, `+` and `?` repetitions. A pattern matches anywhere in
+//! the text unless anchored.
+
+use anyhow::bail;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Group(Vec<Vec<Piece>>),
+}
+
+#[derive(Debug, Clone)]
+struct Piece {
+    node: Node,
+    min: usize,
+    max: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    alternatives: Vec<Vec<Piece>>,
+    ignore_case: bool,
+}
+
+impl Pattern {
+    pub fn parse(s: &str, ignore_case: bool) -> anyhow::Result<Self> {
+        let source = if ignore_case {
+            s.to_lowercase()
+        } else {
+            s.to_string()
+        };
+        let chars: Vec<char> = source.chars().collect();
+        let mut parser = Parser { chars, pos: 0 };
+        let alternatives = parser.alternatives()?;
+        if parser.pos < parser.chars.len() {
+            bail!("Unmatched ) in pattern {}", s);
+        }
+        Ok(Pattern {
+            alternatives,
+            ignore_case,
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = if self.ignore_case {
+            text.to_lowercase().chars().collect()
+        } else {
+            text.chars().collect()
+        };
+        let matcher = Matcher { text: &text };
+        (0..=text.len()).any(|start| matcher.alternatives(&self.alternatives, start, &mut |_| true))
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn alternatives(&mut self) -> anyhow::Result<Vec<Vec<Piece>>> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> anyhow::Result<Vec<Piece>> {
+        let mut pieces = vec![];
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let node = self.atom()?;
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                _ => (1, Some(1)),
+            };
+            if max != Some(1) || min == 0 {
+                if matches!(node, Node::Start | Node::End) {
+                    bail!("Nothing to repeat before {}", self.chars[self.pos]);
+                }
+                self.pos += 1;
+            }
+            pieces.push(Piece { node, min, max });
+        }
+        Ok(pieces)
+    }
+
+    fn atom(&mut self) -> anyhow::Result<Node> {
+        Ok(match self.next() {
+            Some('.') => Node::Any,
+            Some('^') => Node::Start,
+            Some('$') => Node::End,
+            Some('(') => {
+                let alternatives = self.alternatives()?;
+                if self.next() != Some(')') {
+                    bail!("Unclosed ( in pattern");
+                }
+                Node::Group(alternatives)
+            }
+            Some('[') => self.class()?,
+            Some('\\') => match self.next() {
+                Some(c) => escape(c),
+                None => bail!("Pattern ends with \\"),
+            },
+            Some(c @ ('*' | '+' | '?')) => bail!("Nothing to repeat before {}", c),
+            Some(c) => Node::Char(c),
+            None => unreachable!("atom is only parsed before a character"),
+        })
+    }
+
+    fn class(&mut self) -> anyhow::Result<Node> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut ranges = vec![];
+        let mut first = true;
+        loop {
+            let c = match self.next() {
+                None => bail!("Unclosed [ in pattern"),
+                Some(']') if !first => break,
+                Some('\\') => match self.next() {
+                    Some(c) => match escape(c) {
+                        Node::Class { ranges: r, .. } => {
+                            ranges.extend(r);
+                            first = false;
+                            continue;
+                        }
+                        Node::Char(c) => c,
+                        _ => c,
+                    },
+                    None => bail!("Unclosed [ in pattern"),
+                },
+                Some(c) => c,
+            };
+            first = false;
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&e| e != ']') {
+                let end = self.chars[self.pos + 1];
+                self.pos += 2;
+                ranges.push((c, end));
+            } else {
+                ranges.push((c, c));
+            }
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+}
+
+/// What `\c` stands for.
+fn escape(c: char) -> Node {
+    let class = |ranges: &[(char, char)]| Node::Class {
+        ranges: ranges.to_vec(),
+        negated: false,
+    };
+    match c {
+        'd' => class(&[('0', '9')]),
+        'w' => class(&[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')]),
+        's' => class(&[(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')]),
+        'n' => Node::Char('\n'),
+        't' => Node::Char('\t'),
+        c => Node::Char(c),
+    }
+}
+
+/// A backtracking matcher: each step calls `next` with the position it
+/// reached, which says whether the rest of the pattern matches from
+/// there.
+struct Matcher<'a> {
+    text: &'a [char],
+}
+
+impl Matcher<'_> {
+    fn alternatives(
+        &self,
+        alternatives: &[Vec<Piece>],
+        pos: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        alternatives
+            .iter()
+            .any(|pieces| self.sequence(pieces, pos, next))
+    }
+
+    fn sequence(&self, pieces: &[Piece], pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+        match pieces.split_first() {
+            None => next(pos),
+            Some((piece, rest)) => {
+                self.repeat(piece, 0, pos, &mut |p| self.sequence(rest, p, next))
+            }
+        }
+    }
+
+    /// Match `piece` again after `count` times, as often as possible.
+    fn repeat(
+        &self,
+        piece: &Piece,
+        count: usize,
+        pos: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        if piece.max.is_none_or(|max| count < max)
+            && self.node(&piece.node, pos, &mut |p| {
+                // Repeating an empty match past `min` would go on forever.
+                (p != pos || count < piece.min) && self.repeat(piece, count + 1, p, next)
+            })
+        {
+            return true;
+        }
+        count >= piece.min && next(pos)
+    }
+
+    fn node(&self, node: &Node, pos: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+        let c = self.text.get(pos).copied();
+        match node {
+            Node::Char(expected) => c == Some(*expected) && next(pos + 1),
+            Node::Any => c.is_some() && next(pos + 1),
+            Node::Class { ranges, negated } => {
+                c.is_some_and(|c| ranges.iter().any(|&(a, b)| a <= c && c <= b) != *negated)
+                    && next(pos + 1)
+            }
+            Node::Start => pos == 0 && next(pos),
+            Node::End => pos == self.text.len() && next(pos),
+            Node::Group(alternatives) => self.alternatives(alternatives, pos, next),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pattern;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Pattern::parse(pattern, false).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn literals_match_anywhere() {
+        assert!(matches("review", "Code review for #12"));
+        assert!(!matches("review", "Reviewed"));
+    }
+
+    #[test]
+    fn alternatives() {
+        assert!(matches("review|PR", "Merge PR 4"));
+        assert!(matches("review|PR", "review docs"));
+        assert!(!matches("review|PR", "pr"));
+        assert!(matches("^(fix|bug):", "bug: crash"));
+        assert!(!matches("^(fix|bug):", "a fix: x"));
+    }
+
+    #[test]
+    fn repetitions_and_classes() {
+        assert!(matches(r"^JIRA-\d+$", "JIRA-123"));
+        assert!(!matches(r"^JIRA-\d+$", "JIRA-"));
+        assert!(matches("colou?r", "color"));
+        assert!(matches("a.*z", "a to z"));
+        assert!(matches("[^a-z ]", "abc D"));
+        assert!(!matches("[^a-z ]", "abc d"));
+        assert!(matches(
+            r"github\.com/.+/pull/",
+            "https://github.com/o/r/pull/1"
+        ));
+        assert!(!matches(r"github\.com", "githubXcom"));
+        assert!(matches("(ab)*c$", "ababc"));
+        assert!(matches("(a*)*b", "aaab"));
+    }
+
+    #[test]
+    fn ignore_case() {
+        let pattern = Pattern::parse("review|PR", true).unwrap();
+        assert!(pattern.is_match("Quick pr"));
+        assert!(pattern.is_match("REVIEW"));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        for pattern in ["(a", "a)", "[ab", "*a", "a\\", "^*"] {
+            assert!(Pattern::parse(pattern, false).is_err(), "{}", pattern);
+        }
+    }
+}
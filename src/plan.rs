@@ -0,0 +1,142 @@
+//! `todo plan`: the coming days with what is due on each and how long
+//! it is estimated to take, so that an overbooked day shows before it
+//! comes. The time there is for TODOs each day is set in minutes, like
+//! estimates:
+//!
+//! ```toml
+//! [plan]
+//! capacity = 360
+//! ```
+//!
+//! Overdue TODOs are planned for today, and TODOs without an estimate are
+//! listed but counted apart, as they may take any time.
+
+use crate::date::{self, Date};
+use crate::{config, someday, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::process::exit;
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+pub fn plan_command() -> Command {
+    Command::new("plan")
+        .description("Show the coming days with their due TODOs and estimated time")
+        .usage("todo plan [--days 7] [--capacity <minutes>]")
+        .flag(
+            Flag::new("days", FlagType::Int)
+                .alias("d")
+                .description("Days to plan, from today (default: 7)"),
+        )
+        .flag(
+            Flag::new("capacity", FlagType::Int)
+                .alias("c")
+                .description("Minutes there are for TODOs each day (default: plan.capacity)"),
+        )
+        .action(|c| {
+            let days = c.int_flag("days").unwrap_or(7);
+            if days < 1 {
+                eprintln!("--days must be at least 1");
+                exit(1);
+            }
+            let capacity = match c.int_flag("capacity") {
+                Ok(minutes) => Some(minutes.max(0) as u64),
+                Err(_) => match config::get().string("plan.capacity") {
+                    Some(minutes) => match minutes.parse::<u64>() {
+                        Ok(minutes) => Some(minutes),
+                        Err(_) => {
+                            eprintln!("plan.capacity should be minutes: {}", minutes);
+                            exit(1);
+                        }
+                    },
+                    None => None,
+                },
+            };
+
+            let today = date::today();
+            let todos = Todos::read();
+            let due: Vec<(Date, &Todo)> = todos
+                .records
+                .iter()
+                .filter(|t| t.done.is_empty() && !someday::is_someday(t))
+                .filter_map(|t| date::sort_key(&t.date).map(|(d, _)| (d.max(today), t)))
+                .collect();
+            print!("{}", render(today, days as i64, capacity, &due));
+        })
+}
+
+/// One block per day, headed by its load, and a summary of the days
+/// over capacity.
+fn render(today: Date, days: i64, capacity: Option<u64>, due: &[(Date, &Todo)]) -> String {
+    let mut out = String::new();
+    let mut over = vec![];
+    for day in (0..days).map(|i| today.add_days(i)) {
+        let mut todos: Vec<&Todo> = due
+            .iter()
+            .filter(|(d, _)| *d == day)
+            .map(|(_, t)| *t)
+            .collect();
+        todos.sort_by_key(|t| (date::sort_key(&t.date), t.id.parse::<usize>().unwrap_or(0)));
+        let minutes: u64 = todos
+            .iter()
+            .filter_map(|t| t.estimate.parse::<u64>().ok())
+            .sum();
+        let unestimated = todos
+            .iter()
+            .filter(|t| t.estimate.parse::<u64>().is_err())
+            .count();
+
+        let mut header = label(day);
+        if day == today {
+            header += " (today)";
+        }
+        header += &format!("  {}", duration(minutes));
+        if let Some(capacity) = capacity {
+            header += &format!(" of {}", duration(capacity));
+            if minutes > capacity {
+                header += &format!(", {} over", duration(minutes - capacity));
+                over.push(day);
+            }
+        }
+        if unestimated > 0 {
+            header += &format!(
+                ", {} without estimate{}",
+                unestimated,
+                if unestimated == 1 { "" } else { "s" }
+            );
+        }
+        out += &header;
+        out += "\n";
+        for todo in &todos {
+            let mut line = format!("  #{} {}", todo.id, todo.title);
+            if !todo.estimate_label().is_empty() {
+                line += &format!(" ({})", todo.estimate_label());
+            }
+            if date::sort_key(&todo.date).is_some_and(|(d, _)| d < today) {
+                line += &format!(" [overdue since {}]", date::display(&todo.date));
+            }
+            out += &line;
+            out += "\n";
+        }
+    }
+    if !over.is_empty() {
+        let days: Vec<String> = over.iter().map(|d| label(*d)).collect();
+        out += &format!("\nOver capacity: {}\n", days.join(", "));
+    }
+    out
+}
+
+/// `day` with its weekday, like `Thu 2026-10-15`.
+fn label(day: Date) -> String {
+    format!(
+        "{} {}",
+        DAYS[day.weekday() as usize],
+        date::display(&day.to_string())
+    )
+}
+
+fn duration(minutes: u64) -> String {
+    match minutes {
+        0 => "0m".to_string(),
+        minutes => date::format_duration(minutes * 60),
+    }
+}
@@ -0,0 +1,318 @@
+//! A sheet of the open TODOs to print, for `todo export --format print`:
+//! a box to tick for each, with its checklist below it, grouped by list
+//! with `--all-lists`, and ruled lines for notes at the end.
+//!
+//! The sheet is laid out once as styled lines, so that it reads the same
+//! as plain text and, with the `pdf` feature and `--pdf`, as an A4 PDF in
+//! Helvetica with larger type for the headings. PDF's built-in fonts only
+//! have Latin characters, so others come out as `?`; print the text from
+//! a browser or editor to keep them.
+
+use crate::date::{self, Date};
+use crate::{lists, someday, Todo, Todos};
+use unicode_width::UnicodeWidthStr;
+
+/// Width of the plain text sheet.
+const WIDTH: usize = 72;
+/// Ruled lines for notes at the end.
+const NOTE_LINES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    Title,
+    List,
+    Item,
+    /// A checklist item, ticked or not.
+    Check(bool),
+    Detail,
+    Rule,
+}
+
+struct Line {
+    style: Style,
+    text: String,
+    /// When it is due, shown at the right.
+    due: String,
+}
+
+fn line(style: Style, text: impl Into<String>) -> Line {
+    Line {
+        style,
+        text: text.into(),
+        due: String::new(),
+    }
+}
+
+/// The lines of the sheet, taking the list of each TODO from its
+/// `lists::FIELD` when `grouped`, and naming the one list `list`
+/// otherwise.
+fn layout(todos: &Todos, grouped: bool, list: &str) -> Vec<Line> {
+    let today = date::today();
+    let days = [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ];
+    let mut lines = vec![line(
+        Style::Title,
+        format!("TODO  {} {}", days[today.weekday() as usize], today),
+    )];
+
+    let mut open: Vec<&Todo> = todos
+        .records
+        .iter()
+        .filter(|t| t.done.is_empty() && !someday::is_someday(t))
+        .filter(|t| Date::parse(&t.start).is_none_or(|d| d <= today))
+        .collect();
+    open.sort_by_key(|t| {
+        (
+            date::sort_key(&t.date).is_none(),
+            date::sort_key(&t.date),
+            t.id.parse::<usize>().unwrap_or(0),
+        )
+    });
+    let list_of = |t: &Todo| match grouped {
+        true => crate::fields::get(t, lists::FIELD)
+            .unwrap_or("")
+            .to_string(),
+        false => list.to_string(),
+    };
+    let mut names: Vec<String> = vec![];
+    for name in open.iter().map(|t| list_of(t)) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    if open.is_empty() {
+        lines.push(line(Style::Detail, "Nothing to do"));
+    }
+
+    for name in &names {
+        lines.push(line(Style::List, lists::label(name).0));
+        for todo in open.iter().filter(|t| list_of(t) == *name) {
+            let due = match date::sort_key(&todo.date) {
+                Some((d, _)) if d < today => format!("overdue {}", date::display(&todo.date)),
+                Some((d, time)) if d == today => match time {
+                    Some((h, m)) => format!("today {:02}:{:02}", h, m),
+                    None => "today".to_string(),
+                },
+                Some(_) => date::display(&todo.date),
+                None => String::new(),
+            };
+            lines.push(Line {
+                style: Style::Item,
+                text: format!("#{} {}", todo.id, todo.title),
+                due,
+            });
+            for item in &todo.checklist {
+                let (done, text) = match item.split_once("] ") {
+                    Some((mark, text)) => (mark.contains('x'), text),
+                    None => (false, item.as_str()),
+                };
+                lines.push(line(Style::Check(done), text));
+            }
+            let mut details: Vec<String> = todo.tags.iter().map(|t| format!("#{}", t)).collect();
+            if let Some(first) = todo.notes.lines().find(|l| !l.trim().is_empty()) {
+                details.push(first.trim().to_string());
+            }
+            if !details.is_empty() {
+                lines.push(line(Style::Detail, details.join("  ")));
+            }
+        }
+    }
+
+    lines.push(line(Style::List, "Notes"));
+    for _ in 0..NOTE_LINES {
+        lines.push(line(Style::Rule, ""));
+    }
+    lines
+}
+
+/// The sheet as plain text, with `☐` boxes.
+pub fn text(todos: &Todos, grouped: bool, list: &str) -> String {
+    let mut out = String::new();
+    for l in layout(todos, grouped, list) {
+        match l.style {
+            Style::Title => {
+                out += &format!("{}\n{}\n", l.text, "=".repeat(WIDTH));
+            }
+            Style::List => {
+                out += &format!("\n{}\n{}\n\n", l.text, "-".repeat(l.text.width()));
+            }
+            Style::Item => {
+                let left = format!("☐ {}", l.text);
+                let gap = WIDTH.saturating_sub(left.width() + l.due.width()).max(2);
+                let due = if l.due.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}{}", " ".repeat(gap), l.due)
+                };
+                out += &format!("{}{}\n", left, due);
+            }
+            Style::Check(done) => {
+                out += &format!("    {} {}\n", if done { "☑" } else { "☐" }, l.text);
+            }
+            Style::Detail => out += &format!("    {}\n", l.text),
+            Style::Rule => out += &format!("\n{}\n", "_".repeat(WIDTH)),
+        }
+    }
+    out
+}
+
+/// The sheet as a PDF of A4 pages.
+#[cfg(feature = "pdf")]
+pub fn pdf(todos: &Todos, grouped: bool, list: &str) -> Vec<u8> {
+    const PAGE: (f64, f64) = (595.0, 842.0);
+    const MARGIN: f64 = 56.0;
+
+    let mut pages: Vec<String> = vec![];
+    let mut page = String::new();
+    let mut y = PAGE.1 - MARGIN;
+    for l in layout(todos, grouped, list) {
+        let (size, bold, indent) = match l.style {
+            Style::Title => (20.0, true, 0.0),
+            Style::List => (15.0, true, 0.0),
+            Style::Item => (13.0, false, 22.0),
+            Style::Check(_) => (11.0, false, 44.0),
+            Style::Detail => (10.0, false, 22.0),
+            Style::Rule => (13.0, false, 0.0),
+        };
+        let space = match l.style {
+            Style::List => 2.2,
+            Style::Rule => 2.0,
+            _ => 1.4,
+        };
+        if y - size * space < MARGIN {
+            pages.push(std::mem::take(&mut page));
+            y = PAGE.1 - MARGIN;
+        }
+        y -= size * space;
+        let x = MARGIN + indent;
+        let font = if bold { "F2" } else { "F1" };
+        match l.style {
+            Style::Item | Style::Check(_) => {
+                let side = size * 0.75;
+                page += &format!(
+                    "{:.1} {:.1} {:.1} {:.1} re S\n",
+                    x - side - 8.0,
+                    y,
+                    side,
+                    side
+                );
+                if l.style == Style::Check(true) {
+                    page += &format!(
+                        "{:.1} {:.1} m {:.1} {:.1} l {:.1} {:.1} l S\n",
+                        x - side - 6.0,
+                        y + side / 2.0,
+                        x - side / 2.0 - 8.0,
+                        y + 2.0,
+                        x - 10.0,
+                        y + side - 1.0
+                    );
+                }
+            }
+            Style::Rule => {
+                page += &format!(
+                    "{:.1} {:.1} m {:.1} {:.1} l S\n",
+                    MARGIN,
+                    y,
+                    PAGE.0 - MARGIN,
+                    y
+                );
+                continue;
+            }
+            _ => {}
+        }
+        // Helvetica is about half as wide as high, on average.
+        let fits = ((PAGE.0 - MARGIN - x) / (size * 0.52)) as usize;
+        let text = match l.due.is_empty() {
+            true => l.text.clone(),
+            false => format!("{}  ({})", l.text, l.due),
+        };
+        let text: String = text.chars().take(fits).collect();
+        page += &format!(
+            "BT /{} {:.0} Tf {:.1} {:.1} Td ({}) Tj ET\n",
+            font,
+            size,
+            x,
+            y,
+            pdf_string(&text)
+        );
+    }
+    pages.push(page);
+
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    let mut kids = vec![];
+    for content in &pages {
+        let id = objects.len() + 1;
+        kids.push(format!("{} 0 R", id));
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE.0,
+            PAGE.1,
+            id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages.len()
+    );
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).bytes());
+    }
+    let xref = out.len();
+    out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        out.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    out.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .bytes(),
+    );
+    out
+}
+
+/// `s` as the inside of a PDF string in WinAnsiEncoding, which for
+/// Latin-1 characters is mostly the same.
+#[cfg(feature = "pdf")]
+fn pdf_string(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => out += &format!("\\{:03o}", c as u32),
+            _ => out.push('?'),
+        }
+    }
+    out
+}
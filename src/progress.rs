@@ -0,0 +1,77 @@
+use crate::{checklist, Todo, Todos};
+use seahorse::Command;
+use std::process::exit;
+
+const BAR_WIDTH: usize = 10;
+
+pub fn progress_command() -> Command {
+    Command::new("progress")
+        .description("Set how far along a TODO is, in percent")
+        .usage("todo progress <todo id> <0-100>")
+        .action(|c| {
+            let (id, percent) = if c.args.len() == 2 {
+                (&c.args[0], &c.args[1])
+            } else {
+                eprintln!("Please specify an ID and a percentage");
+                exit(1);
+            };
+            let percent = match percent.trim_end_matches('%').parse::<u32>() {
+                Ok(p) if p <= 100 => p,
+                _ => {
+                    eprintln!("Please specify a percentage between 0 and 100");
+                    exit(1);
+                }
+            };
+
+            let mut todos = Todos::read();
+            let todo = match todos.find_mut(id) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            };
+            if !todo.checklist.is_empty() {
+                eprintln!(
+                    "The progress of #{} follows its checklist ({})",
+                    todo.id,
+                    checklist::progress(todo)
+                );
+                exit(1);
+            }
+
+            todo.progress = percent.to_string();
+            println!("#{} is {}% done: {}", todo.id, percent, todo.title);
+            todos.save().unwrap();
+        })
+}
+
+/// Completion in percent: derived from the checklist when there is one,
+/// otherwise the value set with `todo progress`.
+pub fn percent(todo: &Todo) -> Option<u32> {
+    if !todo.checklist.is_empty() {
+        let checked = todo
+            .checklist
+            .iter()
+            .filter(|i| checklist::is_checked(i))
+            .count();
+        return Some((checked * 100 / todo.checklist.len()) as u32);
+    }
+    todo.progress.parse().ok()
+}
+
+/// A bar like `██████░░░░ 60%`, or empty when there is no progress.
+pub fn bar(todo: &Todo) -> String {
+    match percent(todo) {
+        Some(p) => {
+            let filled = p as usize * BAR_WIDTH / 100;
+            format!(
+                "{}{} {}%",
+                "█".repeat(filled),
+                "░".repeat(BAR_WIDTH - filled),
+                p
+            )
+        }
+        None => "".to_string(),
+    }
+}
@@ -0,0 +1,412 @@
+//! QR codes (ISO/IEC 18004) of text, in byte mode at error correction
+//! level M, for `todo share --qr`. The smallest version the text fits in
+//! is used, with the mask of the lowest penalty.
+
+/// Error correction codewords per block, by version, at level M.
+const ECC_PER_BLOCK: [usize; 41] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+/// Error correction blocks, by version, at level M.
+const BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23,
+    25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+/// The format bits of level M.
+const LEVEL_M: u32 = 0;
+
+/// A QR code as rows of modules, `true` for dark ones.
+pub struct Code {
+    pub size: usize,
+    modules: Vec<Vec<bool>>,
+    function: Vec<Vec<bool>>,
+}
+
+impl Code {
+    /// The code of `data`, or `None` when it is too long for any version.
+    pub fn encode(data: &[u8]) -> Option<Self> {
+        let version = (1..=40).find(|&v| {
+            let count_bits = if v < 10 { 8 } else { 16 };
+            4 + count_bits + data.len() * 8 <= data_codewords(v) * 8
+        })?;
+
+        let mut bits = Bits::default();
+        bits.push(0b0100, 4);
+        bits.push(data.len() as u32, if version < 10 { 8 } else { 16 });
+        for &byte in data {
+            bits.push(byte as u32, 8);
+        }
+        let capacity = data_codewords(version) * 8;
+        bits.push(0, (capacity - bits.0.len()).min(4));
+        bits.push(0, (8 - bits.0.len() % 8) % 8);
+        for pad in [0xEC, 0x11].iter().cycle() {
+            if bits.0.len() >= capacity {
+                break;
+            }
+            bits.push(*pad, 8);
+        }
+        let codewords: Vec<u8> = bits
+            .0
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, &b| acc << 1 | b as u8))
+            .collect();
+
+        let size = version * 4 + 17;
+        let mut code = Code {
+            size,
+            modules: vec![vec![false; size]; size],
+            function: vec![vec![false; size]; size],
+        };
+        code.draw_function_patterns(version);
+        code.draw_codewords(&with_ecc(&codewords, version));
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                code.apply_mask(mask);
+                code.draw_format(mask);
+                let penalty = code.penalty();
+                code.apply_mask(mask);
+                penalty
+            })
+            .unwrap_or(0);
+        code.apply_mask(mask);
+        code.draw_format(mask);
+        Some(code)
+    }
+
+    /// Whether the module at column `x` and row `y` is dark.
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y][x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y][x] = dark;
+        self.function[y][x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        for (x, y) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4i32..=4 {
+                for dx in -4i32..=4 {
+                    let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                    if (0..size as i32).contains(&xx) && (0..size as i32).contains(&yy) {
+                        let distance = dx.abs().max(dy.abs());
+                        self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                    }
+                }
+            }
+        }
+        let positions = alignment_positions(version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // Not where the finder patterns are.
+                if (i == 0 && (j == 0 || j == last)) || (i == last && j == 0) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        let (xx, yy) = ((x as i32 + dx) as usize, (y as i32 + dy) as usize);
+                        self.set_function(xx, yy, dx.abs().max(dy.abs()) != 1);
+                    }
+                }
+            }
+        }
+        // Reserved for now, drawn with the mask.
+        self.draw_format(0);
+        if version >= 7 {
+            let mut rem = version as u32;
+            for _ in 0..12 {
+                rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+            }
+            let bits = (version as u32) << 12 | rem;
+            for i in 0..18 {
+                let dark = bit(bits, i);
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                self.set_function(a, b, dark);
+                self.set_function(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format(&mut self, mask: u32) {
+        let bits = format_bits(mask);
+        let size = self.size;
+        for i in 0..=5 {
+            self.set_function(8, i, bit(bits, i));
+        }
+        self.set_function(8, 7, bit(bits, 6));
+        self.set_function(8, 8, bit(bits, 7));
+        self.set_function(7, 8, bit(bits, 8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(bits, i));
+        }
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(bits, i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(bits, i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    /// Fill the modules that aren't function patterns with `data`, in
+    /// two-column strips zigzagging up and down from the right.
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size as i32 - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..size {
+                for j in 0..2 {
+                    let x = (right - j) as usize;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward {
+                        size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    if !self.function[y][x] && i < data.len() * 8 {
+                        self.modules[y][x] = (data[i / 8] >> (7 - i % 8)) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    /// Flip the data modules of mask pattern `mask`; doing it again
+    /// undoes it.
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let flip = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if flip && !self.function[y][x] {
+                    self.modules[y][x] = !self.modules[y][x];
+                }
+            }
+        }
+    }
+
+    /// How hard the code is to read, by the rules of the standard: runs
+    /// of one color, 2x2 blocks, patterns like the finders, and an
+    /// imbalance of dark and light.
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+        let lines = (0..size).flat_map(|i| {
+            [
+                (0..size).map(|j| self.modules[i][j]).collect::<Vec<bool>>(),
+                (0..size).map(|j| self.modules[j][i]).collect::<Vec<bool>>(),
+            ]
+        });
+        let finder = [true, false, true, true, true, false, true];
+        for line in lines {
+            let mut run = 1;
+            for j in 1..=size {
+                if j < size && line[j] == line[j - 1] {
+                    run += 1;
+                    continue;
+                }
+                if run >= 5 {
+                    penalty += run - 2;
+                }
+                run = 1;
+            }
+            for j in 0..=size - 7 {
+                if line[j..j + 7] != finder {
+                    continue;
+                }
+                let light = |range: std::ops::Range<usize>| range.clone().all(|k| !line[k]);
+                let before = j >= 4 && light(j - 4..j);
+                let after = j + 11 <= size && light(j + 7..j + 11);
+                if before || after {
+                    penalty += 40;
+                }
+            }
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let dark = self.modules[y][x];
+                if self.modules[y][x + 1] == dark
+                    && self.modules[y + 1][x] == dark
+                    && self.modules[y + 1][x + 1] == dark
+                {
+                    penalty += 3;
+                }
+            }
+        }
+        let dark = self.modules.iter().flatten().filter(|&&m| m).count();
+        let total = size * size;
+        let percent = dark * 100 / total;
+        penalty + percent.abs_diff(50) / 5 * 10
+    }
+}
+
+#[derive(Default)]
+struct Bits(Vec<bool>);
+
+impl Bits {
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            self.0.push((value >> i) & 1 == 1);
+        }
+    }
+}
+
+fn bit(value: u32, i: usize) -> bool {
+    (value >> i) & 1 == 1
+}
+
+/// The level and mask with their BCH error correction.
+fn format_bits(mask: u32) -> u32 {
+    let data = LEVEL_M << 3 | mask;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    (data << 10 | rem) ^ 0x5412
+}
+
+/// Modules left for data and error correction in a code of `version`.
+fn raw_modules(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        modules -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+fn data_codewords(version: usize) -> usize {
+    raw_modules(version) / 8 - ECC_PER_BLOCK[version] * BLOCKS[version]
+}
+
+/// Where the alignment patterns are centered, on both axes.
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return vec![];
+    }
+    let alignments = version / 7 + 2;
+    let step = (version * 8 + alignments * 3 + 5) / (alignments * 4 - 4) * 2;
+    let size = version * 4 + 17;
+    let mut positions: Vec<usize> = (0..alignments - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+/// `data` split into blocks, each followed by its error correction, and
+/// interleaved.
+fn with_ecc(data: &[u8], version: usize) -> Vec<u8> {
+    let blocks = BLOCKS[version];
+    let ecc_len = ECC_PER_BLOCK[version];
+    let raw = raw_modules(version) / 8;
+    let short_blocks = blocks - raw % blocks;
+    let short_len = raw / blocks;
+    let divisor = rs_divisor(ecc_len);
+
+    let mut split = vec![];
+    let mut k = 0;
+    for i in 0..blocks {
+        let len = short_len - ecc_len + usize::from(i >= short_blocks);
+        let mut block = data[k..k + len].to_vec();
+        k += len;
+        let ecc = rs_remainder(&block, &divisor);
+        if i < short_blocks {
+            block.push(0);
+        }
+        block.extend(ecc);
+        split.push(block);
+    }
+    let mut out = vec![];
+    for i in 0..split[0].len() {
+        for (j, block) in split.iter().enumerate() {
+            // Short blocks have no data codeword here, only the padding.
+            if i != short_len - ecc_len || j >= short_blocks {
+                out.push(block[i]);
+            }
+        }
+    }
+    out
+}
+
+fn rs_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn rs_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (r, &d) in result.iter_mut().zip(divisor) {
+            *r ^= gf_multiply(d, factor);
+        }
+    }
+    result
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((y as u32 >> i) & 1) * x as u32;
+    }
+    z as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_correction_and_format() {
+        // The 1-M example of "HELLO WORLD" in alphanumeric mode.
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        assert_eq!(
+            rs_remainder(&data, &rs_divisor(10)),
+            vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+        assert_eq!(format_bits(0), 0b101010000010010);
+        assert_eq!(format_bits(5), 0b100000011001110);
+        assert_eq!(alignment_positions(7), vec![6, 22, 38]);
+        assert_eq!(Code::encode(&[b'a'; 14]).unwrap().size, 21);
+        assert_eq!(Code::encode(&[b'a'; 15]).unwrap().size, 25);
+    }
+}
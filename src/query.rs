@@ -0,0 +1,168 @@
+//! Filters like `--where 'tag:work sprint>=40 date<=friday'`.
+//!
+//! A query is a list of terms that must all match. Each term compares a
+//! field with a value: `:` and `=` test equality, and `!=`, `<`, `<=`,
+//! `>` and `>=` do what they look like. Values are compared as numbers
+//! when both sides are numbers, as dates when both are dates (the value
+//! may be an expression like `+1w`), and otherwise as case-insensitive
+//! text. Double quotes allow spaces in a value: `customer="acme corp"`.
+//!
+//! Fields are the built-in ones in `BUILTIN` and any custom field set
+//! with `todo set`. Enum fields declared in the config compare in the
+//! order of their values. A TODO without the field matches only `!=`.
+
+use crate::date::{self, Date};
+use crate::{fields, Todo};
+use anyhow::bail;
+use std::cmp::Ordering;
+
+/// Fields that are not custom ones. `tag` matches any of the tags.
+const BUILTIN: &[&str] = &[
+    "id", "date", "title", "tag", "assignee", "status", "done", "start", "goal", "repeat",
+    "estimate",
+];
+
+pub fn is_builtin(key: &str) -> bool {
+    BUILTIN.contains(&key)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    key: String,
+    op: Op,
+    value: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+impl Query {
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let mut terms = vec![];
+        for word in split(s) {
+            let Some(at) = word.find([':', '=', '!', '<', '>']) else {
+                bail!("Invalid filter: {}", word);
+            };
+            let (key, rest) = word.split_at(at);
+            let (op, value) = [
+                ("!=", Op::Ne),
+                ("<=", Op::Le),
+                (">=", Op::Ge),
+                ("<", Op::Lt),
+                (">", Op::Gt),
+                (":", Op::Eq),
+                ("=", Op::Eq),
+            ]
+            .iter()
+            .find_map(|(s, op)| rest.strip_prefix(s).map(|v| (*op, v)))
+            .unwrap_or((Op::Eq, ""));
+            if key.is_empty() || (op == Op::Eq && rest.starts_with('!')) {
+                bail!("Invalid filter: {}", word);
+            }
+            let key = key.to_lowercase();
+            let value = match key.as_str() {
+                "tag" => value.trim_start_matches('#'),
+                _ => value,
+            };
+            terms.push(Term {
+                key,
+                op,
+                value: value.to_string(),
+            });
+        }
+        Ok(Query { terms })
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        self.terms.iter().all(|term| {
+            let hit = |field: &String| {
+                let ordering = compare(&term.key, field, &term.value);
+                match term.op {
+                    Op::Eq | Op::Ne => ordering == Ordering::Equal,
+                    Op::Lt => ordering == Ordering::Less,
+                    Op::Le => ordering != Ordering::Greater,
+                    Op::Gt => ordering == Ordering::Greater,
+                    Op::Ge => ordering != Ordering::Less,
+                }
+            };
+            let any = values(todo, &term.key).iter().any(hit);
+            if term.op == Op::Ne {
+                !any
+            } else {
+                any
+            }
+        })
+    }
+}
+
+/// Split on whitespace outside double quotes, dropping the quotes.
+fn split(s: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in s.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// The values of a field, empty ones left out.
+pub fn values(todo: &Todo, key: &str) -> Vec<String> {
+    let value = match key {
+        "id" => todo.id.clone(),
+        "date" => todo.date.clone(),
+        "title" => todo.title.clone(),
+        "tag" => return todo.tags.clone(),
+        "assignee" => todo.assignee.clone(),
+        "status" => todo.status.clone(),
+        "done" => if todo.done.is_empty() { "no" } else { "yes" }.to_string(),
+        "start" => todo.start.clone(),
+        "goal" => todo.goal.clone(),
+        "repeat" => todo.repeat.clone(),
+        "estimate" => todo.estimate.clone(),
+        key => fields::get(todo, key).unwrap_or("").to_string(),
+    };
+    if value.is_empty() {
+        vec![]
+    } else {
+        vec![value]
+    }
+}
+
+fn compare(key: &str, field: &str, value: &str) -> Ordering {
+    if let Some(declared) = fields::find_declared(key) {
+        if let (Some(a), Some(b)) = (declared.rank(field), declared.rank(value)) {
+            return a.cmp(&b);
+        }
+    }
+    if let (Ok(a), Ok(b)) = (field.parse::<f64>(), value.parse::<f64>()) {
+        return a.total_cmp(&b);
+    }
+    if let (Some(a), Some(b)) = (Date::parse(field), Date::parse_expr(value, date::today())) {
+        return a.cmp(&b);
+    }
+    field.to_lowercase().cmp(&value.to_lowercase())
+}
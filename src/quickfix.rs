@@ -0,0 +1,51 @@
+//! `todo list --format quickfix`: one line per TODO pointing at its row
+//! in the data file, for Vim's quickfix list:
+//!
+//! ```text
+//! /home/me/.todo:4:1: #2 Call the bank (2024-05-01) #home
+//! ```
+//!
+//! The lines match Vim's default `errorformat`, and `%f:%l:%c: %m` when
+//! it was changed, so `:cexpr system('todo list --format quickfix')`
+//! lists them. In an encrypted list every TODO points at the first line.
+
+use crate::{crypt, format, icons, Todo, Todos};
+use std::collections::HashMap;
+use std::fs;
+
+pub fn print(records: &[&Todo]) {
+    let path = Todos::todo_path();
+    let lines = lines(&path);
+    for todo in records {
+        let line = lines.get(todo.id.as_str()).copied().unwrap_or(1);
+        println!("{}:{}:1: {}", path, line, text(todo));
+    }
+}
+
+/// The line each ID's row starts on, as the file is on disk.
+fn lines(path: &str) -> HashMap<String, usize> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    if crypt::is_encrypted(&content) {
+        return HashMap::new();
+    }
+    format::parse(&content)
+        .rows
+        .into_iter()
+        .filter(|r| !r.is_empty())
+        .map(|r| (r.fields[0].clone(), r.line))
+        .collect()
+}
+
+fn text(todo: &Todo) -> String {
+    let mut text = format!("#{} {}", todo.id, todo.title.replace('\n', " "));
+    if !todo.date.is_empty() {
+        text += &format!(" ({})", todo.date);
+    }
+    for tag in &todo.tags {
+        text += &format!(" #{}", tag);
+    }
+    if !todo.done.is_empty() {
+        text += &format!(" {}", icons::done(todo));
+    }
+    text
+}
@@ -0,0 +1,249 @@
+//! `todo serve --relay`: the relay for `todo sync relay`, which keeps the
+//! latest upload on each channel and hands it out to the devices, without
+//! knowing what is in it. The protocol is plain HTTP on `/v1/<channel>`:
+//!
+//! - `GET` answers `<version>\n<upload>`, version 0 and nothing for a new
+//!   channel. With `?after=<version>` it waits for a newer version and
+//!   answers 204 if none came within `WAIT`.
+//! - `PUT` with `If-Match: <version>` replaces the upload if that version
+//!   is still the latest and answers the new one, or 412 if it is not.
+//!
+//! Uploads are kept in `~/.todo-relay`, or the directory of `--dir`, to
+//! survive restarts. There is no TLS; for `https://`, put the relay
+//! behind a proxy that has it.
+
+use crate::{config, log};
+use seahorse::{Command, Context, Flag, FlagType};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8787";
+/// How long a `GET` with `after` waits for a newer version.
+const WAIT: Duration = Duration::from_secs(25);
+const MAX_UPLOAD: usize = 16 << 20;
+
+pub fn serve_command() -> Command {
+    Command::new("serve")
+        .description("Serve a relay for todo sync relay")
+        .usage("todo serve --relay [--listen <address>] [--dir <path>]")
+        .flag(Flag::new("relay", FlagType::Bool).description("Serve a relay"))
+        .flag(
+            Flag::new("listen", FlagType::String)
+                .description("The address to listen on, 127.0.0.1:8787 by default"),
+        )
+        .flag(
+            Flag::new("dir", FlagType::String)
+                .description("Where to keep uploads, ~/.todo-relay by default"),
+        )
+        .action(serve_action)
+}
+
+fn serve_action(c: &Context) {
+    if !c.bool_flag("relay") {
+        eprintln!("Please specify --relay, the only thing todo serves");
+        exit(1);
+    }
+    let address = c
+        .string_flag("listen")
+        .unwrap_or_else(|_| DEFAULT_ADDRESS.to_string());
+    let dir = config::expand_home(
+        &c.string_flag("dir")
+            .unwrap_or_else(|_| "~/.todo-relay".to_string()),
+    );
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("Failed to create {}: {}", dir, e);
+        exit(1);
+    }
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen on {}: {}", address, e);
+            exit(1);
+        }
+    };
+    println!("Relaying on {}, keeping uploads in {}", address, dir);
+
+    let relay = Arc::new(Relay {
+        dir: PathBuf::from(dir),
+        channels: Mutex::new(HashMap::new()),
+        uploaded: Condvar::new(),
+    });
+    for stream in listener.incoming().flatten() {
+        let relay = relay.clone();
+        thread::spawn(move || {
+            if let Err(e) = handle(&relay, stream) {
+                log::warn("http", || {
+                    format!("relay request failed error={:?}", e.to_string())
+                });
+                eprintln!("{}", e);
+            }
+        });
+    }
+}
+
+struct Relay {
+    dir: PathBuf,
+    /// The version and upload by channel, as far as they were asked for.
+    channels: Mutex<HashMap<String, (u64, String)>>,
+    uploaded: Condvar,
+}
+
+impl Relay {
+    fn load<'a>(
+        &self,
+        channels: &'a mut HashMap<String, (u64, String)>,
+        channel: &str,
+    ) -> &'a (u64, String) {
+        channels.entry(channel.to_string()).or_insert_with(|| {
+            let content = fs::read_to_string(self.dir.join(channel)).unwrap_or_default();
+            match content.split_once('\n') {
+                Some((version, upload)) => (version.parse().unwrap_or(0), upload.to_string()),
+                None => (0, "".to_string()),
+            }
+        })
+    }
+
+    /// The latest version and upload, `None` if there was none newer than
+    /// `after` within `WAIT`.
+    fn get(&self, channel: &str, after: Option<u64>) -> Option<(u64, String)> {
+        let deadline = Instant::now() + WAIT;
+        let mut channels = self.channels.lock().unwrap();
+        loop {
+            let latest = self.load(&mut channels, channel);
+            if after != Some(latest.0) {
+                return Some(latest.clone());
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return None;
+            }
+            channels = self.uploaded.wait_timeout(channels, left).unwrap().0;
+        }
+    }
+
+    /// Replace `version` with `upload`, answering the new version, or
+    /// `None` if `version` is not the latest.
+    fn put(&self, channel: &str, version: u64, upload: String) -> io::Result<Option<u64>> {
+        let mut channels = self.channels.lock().unwrap();
+        if self.load(&mut channels, channel).0 != version {
+            return Ok(None);
+        }
+        let version = version + 1;
+        let path = self.dir.join(channel);
+        let temporary = self.dir.join(format!(".{}", channel));
+        fs::write(&temporary, format!("{}\n{}", version, upload))?;
+        fs::rename(&temporary, &path)?;
+        channels.insert(channel.to_string(), (version, upload));
+        self.uploaded.notify_all();
+        Ok(Some(version))
+    }
+}
+
+fn handle(relay: &Relay, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    log::debug("http", || {
+        let peer = stream
+            .peer_addr()
+            .map(|a| a.to_string())
+            .unwrap_or_default();
+        format!("relay {} peer={}", method, peer)
+    });
+
+    let (mut length, mut expected, mut continues) = (0, None, false);
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => length = value.parse().unwrap_or(0),
+            "if-match" => expected = value.trim_matches('"').parse().ok(),
+            "expect" => continues = value.eq_ignore_ascii_case("100-continue"),
+            _ => {}
+        }
+    }
+    if length > MAX_UPLOAD {
+        return respond(&mut stream, 413, "Too large\n");
+    }
+    if continues && length > 0 {
+        stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let Some(channel) = path
+        .strip_prefix("/v1/")
+        .filter(|c| c.len() == 64 && c.bytes().all(|b| b.is_ascii_hexdigit()))
+    else {
+        return respond(&mut stream, 404, "Not found\n");
+    };
+    match method {
+        "GET" => {
+            let after = query
+                .split('&')
+                .find_map(|p| p.strip_prefix("after="))
+                .and_then(|v| v.parse().ok());
+            match relay.get(channel, after) {
+                Some((version, upload)) => {
+                    respond(&mut stream, 200, &format!("{}\n{}", version, upload))
+                }
+                None => respond(&mut stream, 204, ""),
+            }
+        }
+        "PUT" => {
+            let Some(expected) = expected else {
+                return respond(&mut stream, 428, "Please send If-Match\n");
+            };
+            let Ok(upload) = String::from_utf8(body) else {
+                return respond(&mut stream, 400, "Not text\n");
+            };
+            match relay.put(channel, expected, upload)? {
+                Some(version) => respond(&mut stream, 200, &format!("{}\n", version)),
+                None => respond(&mut stream, 412, "Another device uploaded first\n"),
+            }
+        }
+        _ => respond(&mut stream, 405, "Method not allowed\n"),
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: u16, body: &str) -> io::Result<()> {
+    log::debug("http", || {
+        format!("relay answered status={} bytes={}", status, body.len())
+    });
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        412 => "Precondition Failed",
+        413 => "Payload Too Large",
+        428 => "Precondition Required",
+        _ => "",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )
+}
@@ -0,0 +1,112 @@
+use crate::{batch, crdt, dry_run, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+
+pub fn renumber_command() -> Command {
+    Command::new("renumber")
+        .description("Give the TODOs sequential IDs from 1, closing the gaps left by deletes")
+        .usage("todo renumber [--force]")
+        .flag(
+            Flag::new("force", FlagType::Bool)
+                .alias("f")
+                .description("Renumber instead of only showing what would change"),
+        )
+        .action(|c| {
+            let mut todos = Todos::read();
+            let changes = renumber(&mut todos.records);
+            if changes.is_empty() {
+                println!("The IDs are already sequential");
+                return;
+            }
+            for (old, new, todo) in &changes {
+                println!("#{} -> #{}: {}", old, new, todo);
+            }
+            if !c.bool_flag("force") {
+                eprintln!(
+                    "IDs you remember or use in scripts will change; run again with --force to renumber"
+                );
+                exit(1);
+            }
+
+            follow_sync(&changes);
+            for todo in todos.records.iter_mut() {
+                move_attachments(todo, &changes);
+            }
+            todos.save().unwrap();
+            println!("Renumbered {} TODOs", changes.len());
+        })
+}
+
+/// Number `records` from 1 in the order of their IDs, keeping everything
+/// else, links to other services included. Returns the old and new IDs
+/// and the title of each one that changed.
+fn renumber(records: &mut [Todo]) -> Vec<(String, String, String)> {
+    // IDs that are not numbers, as in hand-edited files, go after the rest.
+    records.sort_by_key(|r| r.id.parse::<usize>().unwrap_or(usize::MAX));
+    let mut changes = vec![];
+    for (i, todo) in records.iter_mut().enumerate() {
+        let id = (i + 1).to_string();
+        if todo.id != id {
+            changes.push((todo.id.clone(), id.clone(), todo.title.clone()));
+            todo.id = id;
+        }
+    }
+    changes
+}
+
+/// Give the new IDs to the replica of a synced list, which knows the
+/// TODOs by their local IDs, before the list is saved and seen with them.
+/// The IDs are local, so other devices keep theirs. A batch could still
+/// be rolled back after that, so synced lists are not renumbered in one.
+fn follow_sync(changes: &[(String, String, String)]) {
+    let path = Todos::todo_path();
+    let mut replica = match crdt::Replica::load(&path) {
+        Ok(Some(replica)) => replica,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("Failed to read the sync state of {}: {}", path, e);
+            exit(1);
+        }
+    };
+    if batch::is_active() {
+        eprintln!("A synced list can't be renumbered in a batch");
+        exit(1);
+    }
+    if dry_run::is_enabled() {
+        return;
+    }
+    replica.renumber(changes);
+    if let Err(e) = replica.save(&path) {
+        eprintln!("Failed to save the sync state of {}: {}", path, e);
+        exit(1);
+    }
+}
+
+/// Move files attached under the old ID of `todo` to the directory of
+/// its new one, so TODOs that later get the old ID do not share it.
+/// Lower IDs are moved first and new IDs are never higher than old ones,
+/// so the directory is free unless a deleted TODO still uses it, which
+/// leaves the files where they are.
+fn move_attachments(todo: &mut Todo, changes: &[(String, String, String)]) {
+    let Some((old, new, _)) = changes.iter().find(|(_, new, _)| *new == todo.id) else {
+        return;
+    };
+    let dir = |id: &str| format!("{}.attachments/{}", Todos::todo_path(), id);
+    let (from, to) = (dir(old), dir(new));
+    if !Path::new(&from).is_dir() || Path::new(&to).exists() {
+        return;
+    }
+    if dry_run::is_enabled() {
+        println!("Dry run, {} would be moved to {}", from, to);
+    } else if let Err(e) = fs::rename(&from, &to) {
+        eprintln!("Failed to move {} to {}: {}", from, to, e);
+        return;
+    }
+    for path in todo.attachments.iter_mut() {
+        if let Some(rest) = path.strip_prefix(&from) {
+            *path = format!("{}{}", to, rest);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::date::{self, Date};
+use crate::Todo;
+
+/// How often a recurring TODO comes back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interval {
+    Days(i64),
+    Months(i64),
+}
+
+impl Interval {
+    /// `daily`, `weekly`, `monthly`, `yearly`, or a count with a unit such
+    /// as `3d`, `2w` or `6m`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        match s.as_str() {
+            "daily" => return Some(Interval::Days(1)),
+            "weekly" => return Some(Interval::Days(7)),
+            "monthly" => return Some(Interval::Months(1)),
+            "yearly" => return Some(Interval::Months(12)),
+            _ => {}
+        }
+        let (n, unit) = s.split_at(s.len().checked_sub(1)?);
+        let n: i64 = n.parse().ok().filter(|n| *n > 0)?;
+        match unit {
+            "d" => Some(Interval::Days(n)),
+            "w" => Some(Interval::Days(n * 7)),
+            "m" => Some(Interval::Months(n)),
+            "y" => Some(Interval::Months(n * 12)),
+            _ => None,
+        }
+    }
+
+    fn after(self, d: Date) -> Date {
+        match self {
+            Interval::Days(n) => d.add_days(n),
+            Interval::Months(n) => d.add_months(n),
+        }
+    }
+}
+
+pub fn is_recurring(todo: &Todo) -> bool {
+    Interval::parse(&todo.repeat).is_some()
+}
+
+/// Log a completion of a recurring TODO and move its date to the next
+/// occurrence after today, keeping it open.
+pub fn complete(todo: &mut Todo) {
+    let Some(interval) = Interval::parse(&todo.repeat) else {
+        return;
+    };
+    let today = date::today();
+    let due = Date::parse(&todo.date).unwrap_or(today);
+    todo.completions
+        .push(format!("{} {} {}", date::now(), due, crate::current_user()));
+
+    let mut next = interval.after(due);
+    while next <= today {
+        next = interval.after(next);
+    }
+    todo.date = date::format_due(next, date::time_of(&todo.date));
+}
+
+/// Timestamps of the logged completions.
+pub fn completed_at(todo: &Todo) -> impl Iterator<Item = u64> + '_ {
+    todo.completions
+        .iter()
+        .filter_map(|c| c.split(' ').next()?.parse().ok())
+}
+
+/// Current and best run of completions made on or before their date.
+/// The current streak is broken while the TODO is overdue.
+pub fn streaks(todo: &Todo) -> (usize, usize) {
+    let mut current = 0;
+    let mut best = 0;
+    for entry in &todo.completions {
+        let mut parts = entry.split(' ');
+        let on_time = parts.next().zip(parts.next()).and_then(|(ts, due)| {
+            Some(Date::from_timestamp(ts.parse().ok()?) <= Date::parse(due)?)
+        });
+        if on_time == Some(true) {
+            current += 1;
+            best = best.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    if Date::parse(&todo.date).is_some_and(|d| d < date::today()) {
+        current = 0;
+    }
+    (current, best)
+}
+
+/// Streaks as shown next to recurring TODOs, e.g. `3 (best 5)`.
+pub fn streak_label(todo: &Todo) -> String {
+    if !is_recurring(todo) {
+        return "".to_string();
+    }
+    let (current, best) = streaks(todo);
+    format!("{} (best {})", current, best)
+}
@@ -0,0 +1,131 @@
+use crate::date::{self, Date};
+use crate::{focus, repeat, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::process::exit;
+
+/// Heading of TODOs without tags.
+const UNTAGGED: &str = "Untagged";
+
+pub fn report_command() -> Command {
+    Command::new("report")
+        .description("Summarize what was done and added over a period")
+        .usage("todo report [--done] [--since monday] [--format text|md]")
+        .flag(Flag::new("done", FlagType::Bool).description("Only report completed TODOs"))
+        .flag(
+            Flag::new("since", FlagType::String)
+                .description("First day of the period (default: monday)"),
+        )
+        .flag(Flag::new("format", FlagType::String).description("text (default) or md"))
+        .action(|c| {
+            let today = date::today();
+            let since = c.string_flag("since").unwrap_or("monday".to_string());
+            let since = match Date::parse_since(&since, today) {
+                Some(d) => d,
+                None => {
+                    eprintln!("Invalid date: {}", since);
+                    exit(1);
+                }
+            };
+            let markdown = match c.string_flag("format").as_deref() {
+                Err(_) | Ok("text") => false,
+                Ok("md") | Ok("markdown") => true,
+                Ok(f) => {
+                    eprintln!("Unknown format: {}", f);
+                    exit(1);
+                }
+            };
+
+            let todos = Todos::read();
+            let from = since.start_timestamp();
+            let done: Vec<&Todo> = todos
+                .records
+                .iter()
+                .filter(|t| completions_since(t, from) > 0)
+                .collect();
+            let mut sections = vec![(format!("Done since {}", since), done, true)];
+            if !c.bool_flag("done") {
+                let added = todos
+                    .records
+                    .iter()
+                    .filter(|t| t.created_at.parse::<u64>().is_ok_and(|ts| ts >= from))
+                    .collect();
+                sections.push((format!("Added since {}", since), added, false));
+            }
+
+            let mut out = String::new();
+            for (title, todos, done) in sections {
+                out += &render(&title, &todos, from, done, markdown);
+            }
+            print!("{}", out.trim_start());
+        })
+}
+
+/// How many times a TODO was completed at or after `since`.
+fn completions_since(todo: &Todo, since: u64) -> usize {
+    if repeat::is_recurring(todo) {
+        return repeat::completed_at(todo).filter(|ts| *ts >= since).count();
+    }
+    let done = !todo.done.is_empty();
+    usize::from(done && todo.completed_at.parse::<u64>().is_ok_and(|ts| ts >= since))
+}
+
+/// One section of the report, grouped by the first tag of each TODO,
+/// with the time tracked since `since`. `done` sections count repeated
+/// completions of recurring TODOs.
+fn render(title: &str, todos: &[&Todo], since: u64, done: bool, markdown: bool) -> String {
+    let mut groups: Vec<(&str, Vec<&Todo>)> = vec![];
+    for todo in todos {
+        let tag = todo.tags.first().map_or(UNTAGGED, |t| t.as_str());
+        match groups.iter_mut().find(|(t, _)| *t == tag) {
+            Some((_, todos)) => todos.push(todo),
+            None => groups.push((tag, vec![todo])),
+        }
+    }
+    groups.sort_by_key(|(tag, _)| (*tag == UNTAGGED, tag.to_lowercase()));
+
+    let tracked =
+        |todos: &[&Todo]| -> u64 { todos.iter().map(|t| focus::tracked_since(t, since)).sum() };
+    let with_time = |text: String, secs: u64| {
+        if secs > 0 {
+            format!("{} ({})", text, date::format_duration(secs))
+        } else {
+            text
+        }
+    };
+
+    let mut out = if markdown {
+        format!("\n# {}\n", title)
+    } else {
+        format!("\n{}\n", title)
+    };
+    if todos.is_empty() {
+        out += "\nNothing\n";
+        return out;
+    }
+    for (tag, todos) in &groups {
+        let heading = with_time(tag.to_string(), tracked(todos));
+        if markdown {
+            out += &format!("\n## {}\n\n", heading);
+        } else {
+            out += &format!("\n{}\n", heading);
+        }
+        for todo in todos {
+            let mut text = format!("#{} {}", todo.id, todo.title);
+            let times = completions_since(todo, since);
+            if done && times > 1 {
+                text += &format!(" x{}", times);
+            }
+            let line = with_time(text, focus::tracked_since(todo, since));
+            if markdown {
+                out += &format!("- {}\n", line);
+            } else {
+                out += &format!("  {}\n", line);
+            }
+        }
+    }
+    out += &format!(
+        "\n{}\n",
+        with_time(format!("{} TODO(s)", todos.len()), tracked(todos))
+    );
+    out
+}
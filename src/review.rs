@@ -0,0 +1,90 @@
+use crate::{date, prompt, someday, term, trash, Todos};
+use seahorse::Command;
+
+pub fn review_command() -> Command {
+    Command::new("review")
+        .description("Go through every open TODO and decide what to do with it")
+        .usage("todo review")
+        .action(|_| {
+            let todos = Todos::read();
+            let mut queue: Vec<(String, u64)> = todos
+                .records
+                .iter()
+                .filter(|r| r.done.is_empty())
+                .map(|r| (r.id.clone(), r.reviewed_at.parse().unwrap_or(0)))
+                .collect();
+            // Least recently reviewed first, so stale TODOs come up early.
+            queue.sort_by_key(|(_, reviewed_at)| *reviewed_at);
+
+            if queue.is_empty() {
+                println!("Nothing to review");
+                return;
+            }
+
+            let total = queue.len();
+            let mut reviewed = 0;
+            for (i, (id, reviewed_at)) in queue.into_iter().enumerate() {
+                let mut todos = Todos::read();
+                let todo = match todos.records.iter().find(|r| r.id == id) {
+                    Some(t) => t,
+                    None => continue,
+                };
+
+                println!("\n[{}/{}]", i + 1, total);
+                todo.print_details();
+                if reviewed_at > 0 {
+                    let days = (date::now() - reviewed_at) / 86400;
+                    println!("Last reviewed {} day(s) ago", days);
+                } else {
+                    println!("Never reviewed");
+                }
+
+                let parked = someday::is_someday(todo);
+                let choices = if parked {
+                    "[k]eep [a]ctivate [r]eschedule [g] delegate [x] delete [d]one [s]kip [q]uit: "
+                } else {
+                    "[k]eep [m] someday [r]eschedule [g] delegate [x] delete [d]one [s]kip [q]uit: "
+                };
+                let key = loop {
+                    match term::read_key(choices) {
+                        Some('a') if parked => break Some('a'),
+                        Some('m') if !parked => break Some('m'),
+                        Some(k) if "krgxdsq".contains(k) => break Some(k),
+                        Some(_) => {}
+                        None => break None,
+                    }
+                };
+                let todo = todos.find_mut(&id).unwrap();
+                match key {
+                    Some('k') => {}
+                    Some('a') => todo.status = "".to_string(),
+                    Some('m') => todo.status = someday::SOMEDAY.to_string(),
+                    Some('r') => todo.date = prompt("New date: "),
+                    Some('g') => todo.assignee = prompt("Delegate to: "),
+                    Some('x') => {
+                        let todo = todos.delete(id.clone()).unwrap();
+                        todos.save().unwrap();
+                        println!("Deleted #{}: {}", todo.id, todo.title);
+                        let mut trash = trash::Trash::read();
+                        trash.push(todo);
+                        trash.save().unwrap();
+                        reviewed += 1;
+                        continue;
+                    }
+                    Some('d') => {
+                        todos.done(id.clone()).unwrap();
+                        println!("Completed #{}", id);
+                    }
+                    Some('s') => continue,
+                    _ => break,
+                }
+
+                let todo = todos.find_mut(&id).unwrap();
+                todo.reviewed_at = date::now().to_string();
+                todos.save().unwrap();
+                reviewed += 1;
+            }
+
+            println!("\nReviewed {} of {} TODOs", reviewed, total);
+        })
+}
@@ -0,0 +1,66 @@
+//! Rolling over overdue TODOs, as bullet journals migrate what wasn't
+//! done: `todo rollover` moves the due date of every open TODO that is
+//! past it to today, keeping its time, and counts how often it was moved
+//! in the `rollovers` field. Repeating TODOs are left alone, as moving
+//! one would move all the times after it.
+//!
+//! ```toml
+//! [rollover]
+//! auto = true          # also on todo tick, so each morning with cron
+//! tag = "rolled-over"  # to tag what was rolled over
+//! ```
+
+use crate::date::{self, Date};
+use crate::{config, fields, someday, tag_name, Todos};
+use seahorse::Command;
+
+/// The custom field counting the rollovers of a TODO.
+const FIELD: &str = "rollovers";
+
+pub fn rollover_command() -> Command {
+    Command::new("rollover")
+        .description("Move the due date of overdue TODOs to today")
+        .usage("todo rollover")
+        .action(|_| {
+            let mut todos = Todos::read();
+            if roll_over(&mut todos) == 0 {
+                println!("Nothing overdue");
+                return;
+            }
+            todos.save().unwrap();
+        })
+}
+
+/// Whether `todo tick` rolls over too.
+pub fn is_auto() -> bool {
+    config::get().string("rollover.auto") == Some("true")
+}
+
+/// Move overdue TODOs of `todos` to today and tell about each, returning
+/// how many there were.
+pub fn roll_over(todos: &mut Todos) -> usize {
+    let today = date::today();
+    let tag = config::get().string("rollover.tag").map(tag_name);
+    let mut rolled = 0;
+    for todo in todos.records.iter_mut() {
+        if !todo.done.is_empty() || !todo.repeat.is_empty() || someday::is_someday(todo) {
+            continue;
+        }
+        let Some(due) = Date::parse(&todo.date).filter(|d| *d < today) else {
+            continue;
+        };
+        todo.date = date::format_due(today, date::time_of(&todo.date));
+        let count = fields::get(todo, FIELD).and_then(|n| n.parse::<u64>().ok());
+        fields::set(todo, FIELD, &(count.unwrap_or(0) + 1).to_string());
+        if let Some(tag) = tag.as_ref().filter(|t| !t.is_empty()) {
+            if !todo.tags.contains(tag) {
+                todo.tags.push(tag.clone());
+            }
+        }
+        let change = format!("{} date {}", date::now(), crate::current_user());
+        todo.changes.push(change.trim_end().to_string());
+        println!("Rolled over #{} from {}: {}", todo.id, due, todo.title);
+        rolled += 1;
+    }
+    rolled
+}
@@ -0,0 +1,242 @@
+//! `todo rpc`: JSON-RPC 2.0 on stdin and stdout, for editor plugins.
+//!
+//! Each request is one line of JSON and gets one line back, except
+//! notifications (requests without an `id`), which get none:
+//!
+//! ```text
+//! > {"jsonrpc": "2.0", "id": 1, "method": "add", "params": {"title": "Fix #12", "tags": ["work"]}}
+//! < {"jsonrpc":"2.0","id":1,"result":{"id":4,"title":"Fix #12",...}}
+//! ```
+//!
+//! Methods, with their params:
+//!
+//! - `list` `{"all"?: bool, "where"?: string}`: the TODOs, open ones only
+//!   unless `all`, matching the `--where` filter if given.
+//! - `show` `{"id"}`: one TODO.
+//! - `add` `{"title", "date"?, "tags"?: [string], "urls"?: [string]}`:
+//!   the added TODO. Config rules apply as for `todo add`.
+//! - `edit` `{"id", "title"?, "date"?, "tags"?: [string]}`: the edited
+//!   TODO, `tags` replacing the old ones.
+//! - `done` `{"id"}`: the completed TODO.
+//! - `delete` `{"id"}`: the TODO, moved to the trash.
+//!
+//! TODOs are the objects of `--json`. IDs may be given as numbers or
+//! strings. Failures are errors with the standard codes, and -32000 when
+//! the request was valid but could not be carried out, as for an unknown
+//! ID. The list is locked only while a request is handled.
+
+use crate::json::Value;
+use crate::{json, lock, query, resolve_date, rules, tag_name, trash, Todo, Todos};
+use seahorse::Command;
+use std::io::{BufRead, Write};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const FAILED: i64 = -32000;
+
+struct Error {
+    code: i64,
+    message: String,
+}
+
+fn error(code: i64, message: impl Into<String>) -> Error {
+    Error {
+        code,
+        message: message.into(),
+    }
+}
+
+pub fn rpc_command() -> Command {
+    Command::new("rpc")
+        .description("Answer JSON-RPC requests on stdin, one per line, for editor plugins")
+        .usage("todo rpc")
+        .action(|_| {
+            let stdin = std::io::stdin();
+            let mut stdout = std::io::stdout();
+            for line in stdin.lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if let Some(response) = respond(&line) {
+                    writeln!(stdout, "{}", response).unwrap();
+                    stdout.flush().unwrap();
+                }
+                lock::release();
+            }
+        })
+}
+
+/// The response to one request line, `None` for a notification.
+fn respond(line: &str) -> Option<Value> {
+    let request = match json::parse(line) {
+        Ok(r) => r,
+        Err(e) => {
+            return Some(response(
+                Value::Null,
+                Err(error(PARSE_ERROR, e.to_string())),
+            ))
+        }
+    };
+    let id = request.get("id").clone();
+    let result = match request.get("method").as_str() {
+        Some(method) => call(method, request.get("params")),
+        None => Err(error(INVALID_REQUEST, "Missing method")),
+    };
+    match id {
+        Value::Null => None,
+        id => Some(response(id, result)),
+    }
+}
+
+fn response(id: Value, result: Result<Value, Error>) -> Value {
+    let outcome = match result {
+        Ok(result) => ("result", result),
+        Err(e) => (
+            "error",
+            Value::object(vec![
+                ("code", Value::Number(e.code as f64)),
+                ("message", Value::String(e.message)),
+            ]),
+        ),
+    };
+    Value::object(vec![("jsonrpc", "2.0".into()), ("id", id), outcome])
+}
+
+fn call(method: &str, params: &Value) -> Result<Value, Error> {
+    match method {
+        "list" => list(params),
+        "show" => {
+            let todos = Todos::read();
+            let id = id_param(params)?;
+            match todos.records.iter().find(|r| r.id == id) {
+                Some(todo) => Ok(todo.to_json()),
+                None => Err(error(FAILED, "The specified ID does not exist")),
+            }
+        }
+        "add" => add(params),
+        "edit" => change(params, edit),
+        "done" => change(params, |todos, id, _| {
+            todos.done(id).map_err(|e| error(FAILED, e.to_string()))
+        }),
+        "delete" => {
+            let id = id_param(params)?;
+            let mut todos = Todos::read();
+            let todo = todos.delete(id).map_err(|e| error(FAILED, e.to_string()))?;
+            let mut trash = trash::Trash::read();
+            trash.push(todo.clone());
+            trash.save().map_err(|e| error(FAILED, e.to_string()))?;
+            save(&mut todos)?;
+            Ok(todo.to_json())
+        }
+        _ => Err(error(
+            METHOD_NOT_FOUND,
+            format!("Unknown method: {}", method),
+        )),
+    }
+}
+
+fn list(params: &Value) -> Result<Value, Error> {
+    let mut todos = Todos::read();
+    if params.get("all") != &Value::Bool(true) {
+        todos.records.retain(|r| r.done.is_empty());
+    }
+    if let Some(filter) = params.get("where").as_str() {
+        let query =
+            query::Query::parse(filter).map_err(|e| error(INVALID_PARAMS, e.to_string()))?;
+        todos.records.retain(|r| query.matches(r));
+    }
+    Ok(Value::Array(
+        todos.records.iter().map(Todo::to_json).collect(),
+    ))
+}
+
+fn add(params: &Value) -> Result<Value, Error> {
+    let Some(title) = params.get("title").as_str().filter(|t| !t.is_empty()) else {
+        return Err(error(INVALID_PARAMS, "Missing title"));
+    };
+    let date = params
+        .get("date")
+        .as_str()
+        .map(resolve_date)
+        .unwrap_or_default();
+    let mut todo = Todo::new(
+        "".to_string(),
+        date,
+        title.to_string(),
+        strings(params.get("urls"))?,
+        "".to_string(),
+    );
+    todo.tags = strings(params.get("tags"))?
+        .iter()
+        .map(|t| tag_name(t))
+        .collect();
+    rules::apply(&mut todo);
+    let mut todos = Todos::read();
+    let todo = todos.add(todo).map_err(|e| error(FAILED, e.to_string()))?;
+    save(&mut todos)?;
+    Ok(todo.to_json())
+}
+
+fn edit(todos: &mut Todos, id: String, params: &Value) -> Result<Todo, Error> {
+    let tags = match params.get("tags") {
+        Value::Null => None,
+        tags => Some(strings(tags)?),
+    };
+    let todo = todos
+        .find_mut(&id)
+        .map_err(|e| error(FAILED, e.to_string()))?;
+    if let Some(title) = params.get("title").as_str() {
+        todo.title = title.to_string();
+    }
+    if let Some(date) = params.get("date").as_str() {
+        todo.date = resolve_date(date);
+    }
+    if let Some(tags) = tags {
+        todo.tags = tags.iter().map(|t| tag_name(t)).collect();
+    }
+    Ok(todo.clone())
+}
+
+/// Change the TODO of the `id` param with `f` and save.
+fn change(
+    params: &Value,
+    f: impl FnOnce(&mut Todos, String, &Value) -> Result<Todo, Error>,
+) -> Result<Value, Error> {
+    let id = id_param(params)?;
+    let mut todos = Todos::read();
+    let todo = f(&mut todos, id, params)?;
+    save(&mut todos)?;
+    Ok(todo.to_json())
+}
+
+fn save(todos: &mut Todos) -> Result<(), Error> {
+    todos
+        .save()
+        .map_err(|e| error(FAILED, format!("Failed to save: {}", e)))
+}
+
+fn id_param(params: &Value) -> Result<String, Error> {
+    match params.get("id") {
+        Value::Number(n) if n.fract() == 0.0 => Ok((*n as i64).to_string()),
+        Value::String(s) => Ok(s.clone()),
+        _ => Err(error(INVALID_PARAMS, "Missing id")),
+    }
+}
+
+/// An array of strings, empty when not given.
+fn strings(value: &Value) -> Result<Vec<String>, Error> {
+    match value {
+        Value::Null => Ok(vec![]),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| v.as_str().map(|s| s.to_string()))
+            .collect::<Option<Vec<String>>>()
+            .ok_or_else(|| error(INVALID_PARAMS, "Expected an array of strings")),
+        _ => Err(error(INVALID_PARAMS, "Expected an array of strings")),
+    }
+}
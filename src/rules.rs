@@ -0,0 +1,355 @@
+//! Rules that categorize TODOs as they are added or imported:
+//!
+//! ```toml
+//! [rules.code-review]
+//! title = "review|PR"                          # a pattern, see `pattern`
+//! set = ["tag:code-review", "priority:high"]
+//!
+//! [rules.github]
+//! url = "github\\.com/.+/pull/"
+//! ignore_case = true
+//! set = "tag:code-review"
+//! ```
+//!
+//! Every key besides `set` and `ignore_case` names a field whose value
+//! must match the pattern: `title`, `url`, `notes` or any field `--where`
+//! knows. `set` adds tags with `tag:<name>`, and sets the assignee or a
+//! custom field with `<field>:<value>` unless the TODO already has one.
+//!
+//! Tags can have defaults too, for the fields `todo set` takes:
+//!
+//! ```toml
+//! [tags.bill]
+//! priority = "high"
+//! date = "+3d"
+//!
+//! [tags.errand]
+//! context = "@out"
+//! ```
+//!
+//! They are applied after the rules, so that tags set by a rule count,
+//! and like them only to fields still empty: flags given to `todo add`,
+//! values of the imported TODO and what a rule set are kept.
+
+use crate::config::{self, Config};
+use crate::pattern::Pattern;
+use crate::{date, fields, query, Todo};
+use anyhow::bail;
+use std::sync::OnceLock;
+
+static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+static TAG_DEFAULTS: OnceLock<Vec<TagDefaults>> = OnceLock::new();
+
+#[derive(Debug)]
+pub struct Rule {
+    conditions: Vec<(String, Pattern)>,
+    actions: Vec<(String, String)>,
+}
+
+impl Rule {
+    fn load(config: &Config, name: &str) -> anyhow::Result<Self> {
+        let key = |k: &str| format!("rules.{}.{}", name, k);
+        let ignore_case = config.string(&key("ignore_case")) == Some("true");
+        let mut conditions = vec![];
+        for field in config.keys(&format!("rules.{}", name)) {
+            if field == "set" || field == "ignore_case" {
+                continue;
+            }
+            let Some(pattern) = config.string(&key(field)) else {
+                bail!("{} should be a pattern", field);
+            };
+            conditions.push((field.to_string(), Pattern::parse(pattern, ignore_case)?));
+        }
+        if conditions.is_empty() {
+            bail!("nothing to match");
+        }
+        let mut actions = vec![];
+        for action in config.list(&key("set")) {
+            let Some((field, value)) = action.split_once(':') else {
+                bail!("invalid action {}, expected <field>:<value>", action);
+            };
+            let field = field.trim().to_lowercase();
+            if field != "tag" && field != "assignee" && query::is_builtin(&field) {
+                bail!("{} cannot be set by a rule", field);
+            }
+            let value = match field.as_str() {
+                "tag" => crate::tag_name(value.trim()),
+                _ => value.trim().to_string(),
+            };
+            actions.push((field, value));
+        }
+        if actions.is_empty() {
+            bail!("nothing to set");
+        }
+        Ok(Rule {
+            conditions,
+            actions,
+        })
+    }
+
+    fn matches(&self, todo: &Todo) -> bool {
+        self.conditions.iter().all(|(field, pattern)| {
+            let values = match field.as_str() {
+                "url" => todo.urls.clone(),
+                "notes" => vec![todo.notes.clone()],
+                field => query::values(todo, field),
+            };
+            values.iter().any(|v| pattern.is_match(v))
+        })
+    }
+
+    fn apply(&self, todo: &mut Todo) {
+        for (field, value) in &self.actions {
+            match field.as_str() {
+                "tag" => {
+                    if !todo.tags.contains(value) {
+                        todo.tags.push(value.clone());
+                    }
+                }
+                "assignee" => {
+                    if todo.assignee.is_empty() {
+                        todo.assignee = value.clone();
+                    }
+                }
+                field => {
+                    if fields::get(todo, field).is_none() {
+                        fields::set(todo, field, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The rules of `config`, in name order. Broken ones are reported and
+/// left out.
+pub fn load(config: &Config) -> Vec<Rule> {
+    config
+        .tables("rules")
+        .into_iter()
+        .filter_map(|name| match Rule::load(config, name) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                eprintln!("Ignoring rule {}: {}", name, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// The fields to fill in on TODOs with a tag, checked as by `todo set`.
+#[derive(Debug)]
+pub struct TagDefaults {
+    tag: String,
+    values: Vec<(String, String)>,
+}
+
+/// The tag defaults of `config`, in tag order. Broken values are
+/// reported and left out.
+pub fn load_tag_defaults(config: &Config) -> Vec<TagDefaults> {
+    config
+        .tables("tags")
+        .into_iter()
+        .map(|tag| {
+            let section = format!("tags.{}", tag);
+            let mut values = vec![];
+            for field in config.keys(&section) {
+                let checked = match config.string(&format!("{}.{}", section, field)) {
+                    Some(value) => fields::check(field, value),
+                    None => Err(format!("{} should be a string", field)),
+                };
+                match checked {
+                    Ok(value) => values.push(value),
+                    Err(e) => eprintln!("Ignoring default of tag {}: {}", tag, e),
+                }
+            }
+            TagDefaults {
+                tag: crate::tag_name(tag),
+                values,
+            }
+        })
+        .collect()
+}
+
+/// Apply every configured rule that matches `todo`, and then the
+/// defaults of its tags.
+pub fn apply(todo: &mut Todo) {
+    apply_rules(RULES.get_or_init(|| load(config::get())), todo);
+    let defaults = TAG_DEFAULTS.get_or_init(|| load_tag_defaults(config::get()));
+    apply_tag_defaults(defaults, todo);
+}
+
+/// Give a TODO `todo add` adds what it was not given: today's date when
+/// it repeats, the tags of `defaults.tag` when it has none, the rules and
+/// tag defaults, and `defaults.assignee` unless it was `assigned`, even
+/// to no one.
+pub fn apply_to_new(todo: &mut Todo, assigned: bool) {
+    if !todo.repeat.is_empty() && todo.date.is_empty() {
+        todo.date = date::today().to_string();
+    }
+    let config = config::get();
+    if todo.tags.is_empty() {
+        todo.tags = config
+            .list("defaults.tag")
+            .iter()
+            .map(|t| crate::tag_name(t))
+            .filter(|t| !t.is_empty())
+            .collect();
+    }
+    apply(todo);
+    // After the rules, so that those and tag defaults win over it.
+    if !assigned && todo.assignee.is_empty() {
+        todo.assignee = config.string("defaults.assignee").unwrap_or("").to_string();
+    }
+}
+
+fn apply_tag_defaults(defaults: &[TagDefaults], todo: &mut Todo) {
+    for defaults in defaults {
+        if !todo.tags.contains(&defaults.tag) {
+            continue;
+        }
+        for (field, value) in &defaults.values {
+            if fields::raw(todo, field).is_none_or(|v| v.is_empty()) {
+                fields::assign(todo, field, value.clone());
+            }
+        }
+    }
+}
+
+fn apply_rules(rules: &[Rule], todo: &mut Todo) {
+    for rule in rules {
+        if rule.matches(todo) {
+            rule.apply(todo);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(title: &str) -> Todo {
+        Todo::new(
+            "".to_string(),
+            "".to_string(),
+            title.to_string(),
+            vec![],
+            "".to_string(),
+        )
+    }
+
+    fn rules(toml: &str) -> Vec<Rule> {
+        load(&Config::parse(toml).unwrap())
+    }
+
+    #[test]
+    fn matching_title_sets_tag_and_field() {
+        let rules = rules(
+            r#"
+            [rules.code-review]
+            title = "review|PR"
+            set = ["tag:#code-review", "priority:high"]
+            "#,
+        );
+        let mut t = todo("Review PR 12");
+        apply_rules(&rules, &mut t);
+        assert_eq!(t.tags, vec!["code-review"]);
+        assert_eq!(fields::get(&t, "priority"), Some("high"));
+
+        let mut t = todo("Write docs");
+        apply_rules(&rules, &mut t);
+        assert!(t.tags.is_empty());
+        assert!(t.fields.is_empty());
+    }
+
+    #[test]
+    fn all_conditions_must_match() {
+        let rules = rules(
+            r#"
+            [rules.pulls]
+            title = "^Fix"
+            url = "github\\.com/.+/pull/"
+            ignore_case = true
+            set = "tag:code-review"
+            "#,
+        );
+        let mut t = todo("fix the build");
+        apply_rules(&rules, &mut t);
+        assert!(t.tags.is_empty());
+
+        t.urls.push("https://GitHub.com/o/r/pull/3".to_string());
+        apply_rules(&rules, &mut t);
+        assert_eq!(t.tags, vec!["code-review"]);
+    }
+
+    #[test]
+    fn existing_values_are_kept() {
+        let rules = rules(
+            r#"
+            [rules.ops]
+            title = "deploy"
+            set = ["assignee:ops", "priority:high", "tag:ops"]
+            "#,
+        );
+        let mut t = todo("deploy v2");
+        t.assignee = "kim".to_string();
+        t.tags.push("ops".to_string());
+        fields::set(&mut t, "priority", "low");
+        apply_rules(&rules, &mut t);
+        assert_eq!(t.assignee, "kim");
+        assert_eq!(fields::get(&t, "priority"), Some("low"));
+        assert_eq!(t.tags, vec!["ops"]);
+    }
+
+    #[test]
+    fn tag_defaults_fill_empty_fields() {
+        let defaults = load_tag_defaults(
+            &Config::parse(
+                r#"
+                [tags.bill]
+                priority = "high"
+                repeat = "monthly"
+                done = "yes"
+
+                [tags.errand]
+                context = "@out"
+                "#,
+            )
+            .unwrap(),
+        );
+        let mut t = todo("Pay rent");
+        t.tags.push("bill".to_string());
+        fields::set(&mut t, "priority", "low");
+        apply_tag_defaults(&defaults, &mut t);
+        assert_eq!(fields::get(&t, "priority"), Some("low"));
+        assert_eq!(t.repeat, "monthly");
+        assert!(t.done.is_empty());
+        assert_eq!(fields::get(&t, "context"), None);
+    }
+
+    #[test]
+    fn broken_rules_are_left_out() {
+        let rules = rules(
+            r#"
+            [rules.no-match]
+            set = "tag:x"
+
+            [rules.no-action]
+            title = "x"
+
+            [rules.bad-pattern]
+            title = "(x"
+            set = "tag:x"
+
+            [rules.builtin]
+            title = "x"
+            set = "done:yes"
+
+            [rules.good]
+            title = "x"
+            set = "tag:x"
+            "#,
+        );
+        assert_eq!(rules.len(), 1);
+    }
+}
@@ -0,0 +1,81 @@
+//! The questions asked on the first run, before anything is written.
+
+use crate::{config, term, Todos};
+use std::path::Path;
+use std::process::exit;
+
+const DATE_FORMATS: &[&str] = &["YYYY-MM-DD", "DD/MM/YYYY", "MM/DD/YYYY", "DD.MM.YYYY"];
+const COLORS: &[&str] = &["auto", "always", "never"];
+
+/// Ask for the settings and write the config file when there is neither a
+/// config nor a TODO file yet, and someone is at the terminal to answer.
+/// Must run before the config is first read.
+pub fn run_if_first() {
+    if Path::new(&config::Config::path()).exists()
+        || Path::new(&Todos::default_path()).exists()
+        || !term::is_tty()
+        || !term::is_stdout_tty()
+    {
+        return;
+    }
+
+    println!("Welcome to todo! A few questions to set it up, Enter keeps the [default].");
+    let path = ask("Where should TODOs be stored?", "~/.todo");
+    let tag = ask("Tag for new TODOs, like a default list (none)?", "");
+    println!("Date formats:");
+    for (i, f) in DATE_FORMATS.iter().enumerate() {
+        println!("  {}. {}", i + 1, f);
+    }
+    let date_format = choose("Which date format?", DATE_FORMATS);
+    let color = choose("Use colors (auto, always, never)?", COLORS);
+
+    let mut settings = vec![
+        ("display", "date_format", date_format),
+        ("display", "color", color),
+    ];
+    if path != "~/.todo" {
+        settings.push(("storage", "path", path.as_str()));
+    }
+    if !tag.is_empty() {
+        settings.push(("defaults", "tag", tag.as_str()));
+    }
+    for (section, key, value) in settings {
+        if let Err(e) = config::write_key(section, key, Some(value)) {
+            eprintln!("Failed to write {}: {}", config::Config::path(), e);
+            exit(1);
+        }
+    }
+    println!(
+        "Saved the settings to {}, edit it to change them.\n",
+        config::Config::path()
+    );
+}
+
+fn ask(question: &str, default: &str) -> String {
+    let hint = if default.is_empty() {
+        "".to_string()
+    } else {
+        format!(" [{}]", default)
+    };
+    let answer = crate::prompt(&format!("{}{} ", question, hint));
+    if answer.is_empty() {
+        default.to_string()
+    } else {
+        answer
+    }
+}
+
+/// One of `choices`, by number or name. The first is the default.
+fn choose<'a>(question: &str, choices: &[&'a str]) -> &'a str {
+    loop {
+        let answer = ask(question, choices[0]);
+        let by_number = answer
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| choices.get(n.checked_sub(1)?));
+        match by_number.or(choices.iter().find(|c| c.eq_ignore_ascii_case(&answer))) {
+            Some(choice) => return choice,
+            None => println!("Please answer with one of {}", choices.join(", ")),
+        }
+    }
+}
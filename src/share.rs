@@ -0,0 +1,100 @@
+//! `todo share`: a TODO as text to hand to someone or something else,
+//! the title, due date and URLs on lines of their own, or as JSON. With
+//! `--qr` it is drawn as a QR code in the terminal instead, to scan with
+//! a phone's camera without any sync set up.
+
+use crate::json::Value;
+use crate::{date, qr, term, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::process::exit;
+
+/// Light modules around the code, as readers need.
+const QUIET_ZONE: usize = 4;
+
+pub fn share_command() -> Command {
+    Command::new("share")
+        .description("Print a TODO to pass on, as text, JSON or a QR code")
+        .usage("todo share <todo id> [--qr] [--json]")
+        .flag(Flag::new("qr", FlagType::Bool).description("Draw it as a QR code"))
+        .flag(Flag::new("json", FlagType::Bool).description("As JSON, for apps to read"))
+        .action(|c| {
+            let [id] = c.args.as_slice() else {
+                eprintln!("Please specify one ID");
+                exit(1);
+            };
+            let todos = Todos::read();
+            let Some(todo) = todos.records.iter().find(|r| r.id == *id) else {
+                eprintln!("The specified ID does not exist");
+                exit(1);
+            };
+            let text = if c.bool_flag("json") {
+                json(todo)
+            } else {
+                text(todo)
+            };
+            if !c.bool_flag("qr") {
+                println!("{}", text);
+                return;
+            }
+            match qr::Code::encode(text.as_bytes()) {
+                Some(code) => print!("{}", render(&code)),
+                None => {
+                    eprintln!("#{} is too long for a QR code", todo.id);
+                    exit(1);
+                }
+            }
+        })
+}
+
+fn text(todo: &Todo) -> String {
+    let mut lines = vec![todo.title.clone()];
+    if !todo.date.is_empty() {
+        lines.push(format!("Due {}", date::display(&todo.date)));
+    }
+    lines.extend(todo.urls.iter().cloned());
+    lines.join("\n")
+}
+
+fn json(todo: &Todo) -> String {
+    Value::object(vec![
+        ("title", todo.title.as_str().into()),
+        ("date", todo.date.as_str().into()),
+        (
+            "urls",
+            Value::Array(todo.urls.iter().map(|u| u.as_str().into()).collect()),
+        ),
+    ])
+    .to_string()
+}
+
+/// The code with two rows of modules to a line of half blocks. In color
+/// it is drawn dark on light, as printed; without, the light modules are
+/// drawn, which reads right on the usual dark terminal.
+fn render(code: &qr::Code) -> String {
+    let color = term::is_stdout_tty() && std::env::var_os("NO_COLOR").is_none();
+    let side = code.size + QUIET_ZONE * 2;
+    let dark = |x: usize, y: usize| {
+        let inside = |i: usize| (QUIET_ZONE..QUIET_ZONE + code.size).contains(&i);
+        let dark = inside(x) && inside(y) && code.is_dark(x - QUIET_ZONE, y - QUIET_ZONE);
+        dark == color
+    };
+    let mut out = String::new();
+    for y in (0..side).step_by(2) {
+        if color {
+            out += "\x1b[30;107m";
+        }
+        for x in 0..side {
+            out.push(match (dark(x, y), y + 1 < side && dark(x, y + 1)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        if color {
+            out += "\x1b[0m";
+        }
+        out += "\n";
+    }
+    out
+}
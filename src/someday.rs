@@ -0,0 +1,72 @@
+use crate::{Todo, Todos};
+use seahorse::{Command, Context};
+use std::process::exit;
+
+/// `status` of TODOs parked for someday/maybe.
+pub const SOMEDAY: &str = "someday";
+
+pub fn someday_command() -> Command {
+    Command::new("someday")
+        .description("Park a TODO in the someday/maybe bucket")
+        .usage("todo someday [<todo id> | list | activate <todo id>]")
+        .action(|c| {
+            let id = match c.args.as_slice() {
+                [] => return list_action(c),
+                [id] => id,
+                _ => {
+                    eprintln!("Please specify one ID");
+                    exit(1);
+                }
+            };
+            set_status(id, SOMEDAY);
+        })
+        .command(
+            Command::new("list")
+                .description("Show the someday/maybe bucket")
+                .usage("todo someday list")
+                .alias("ls")
+                .action(list_action),
+        )
+        .command(
+            Command::new("activate")
+                .description("Move a TODO out of the someday/maybe bucket")
+                .usage("todo someday activate <todo id>")
+                .action(|c| {
+                    let id = if c.args.len() == 1 {
+                        &c.args[0]
+                    } else {
+                        eprintln!("Please specify one ID");
+                        exit(1);
+                    };
+                    set_status(id, "");
+                }),
+        )
+}
+
+fn list_action(_: &Context) {
+    let mut todos = Todos::read();
+    todos.records.retain(is_someday);
+    todos.print_list(false);
+}
+
+fn set_status(id: &str, status: &str) {
+    let mut todos = Todos::read();
+    let todo = match todos.find_mut(id) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    todo.status = status.to_string();
+    if status == SOMEDAY {
+        println!("Moved #{} to someday: {}", todo.id, todo.title);
+    } else {
+        println!("Activated #{}: {}", todo.id, todo.title);
+    }
+    todos.save().unwrap();
+}
+
+pub fn is_someday(todo: &Todo) -> bool {
+    todo.status == SOMEDAY
+}
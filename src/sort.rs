@@ -0,0 +1,198 @@
+//! Orders of TODOs by one or more fields, for `--sort`:
+//!
+//! ```sh
+//! todo list --sort priority:desc,due,title
+//! ```
+//!
+//! Each key is a field `todo get` knows, with `:asc` (the default) or
+//! `:desc`, and later keys only break the ties of earlier ones. Enum
+//! fields sort in their declared order, numbers by value, dates by date
+//! and text as `collate` has it. TODOs without a value go last either
+//! way, and the sort is stable, so ties keep the order of the file.
+
+use crate::date::{self, Date, Time};
+use crate::{collate, fields, Todo, Todos};
+use anyhow::bail;
+use std::cmp::Ordering;
+
+#[derive(Debug)]
+struct Key {
+    field: String,
+    descending: bool,
+}
+
+#[derive(Debug)]
+pub struct Sort(Vec<Key>);
+
+/// A value to compare, worked out once per TODO and key.
+enum Value {
+    Rank(usize),
+    Number(f64),
+    Date(Date, Option<Time>),
+    Text(String),
+}
+
+impl Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Rank(a), Value::Rank(b)) => a.cmp(b),
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            (Value::Date(a, at), Value::Date(b, bt)) => (a, at).cmp(&(b, bt)),
+            (Value::Text(a), Value::Text(b)) => collate::compare(a, b),
+            // Mixed values of a custom field, kept apart by kind.
+            (a, b) => a.kind().cmp(&b.kind()),
+        }
+    }
+
+    fn kind(&self) -> u8 {
+        match self {
+            Value::Rank(_) => 0,
+            Value::Number(_) => 1,
+            Value::Date(..) => 2,
+            Value::Text(_) => 3,
+        }
+    }
+}
+
+impl Sort {
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let mut keys = vec![];
+        for key in s.split(',').map(str::trim) {
+            let (field, direction) = key.split_once(':').unwrap_or((key, "asc"));
+            let descending = match direction.trim() {
+                "asc" => false,
+                "desc" => true,
+                other => bail!(
+                    "Unknown direction {} for {}, expected asc or desc",
+                    other,
+                    field
+                ),
+            };
+            let field = field.trim();
+            if field.is_empty() {
+                bail!("Missing field in sort {}", s);
+            }
+            let field = match field {
+                "created" => "created_at",
+                "due" => "date",
+                field => field,
+            };
+            keys.push(Key {
+                field: field.to_string(),
+                descending,
+            });
+        }
+        Ok(Sort(keys))
+    }
+
+    /// Orders `records`, failing for fields none of them has and that are
+    /// neither a column nor declared, which are most likely typos.
+    pub fn apply(&self, records: &mut [Todo]) -> anyhow::Result<()> {
+        let headers = Todos::default_headers();
+        for key in &self.0 {
+            let known = headers.contains(&key.field)
+                || ["tag", "url", "urls"].contains(&key.field.as_str())
+                || fields::find_declared(&key.field).is_some()
+                || records.iter().any(|r| fields::raw(r, &key.field).is_some());
+            if !known {
+                bail!("Cannot sort by {}, no TODO has it", key.field);
+            }
+        }
+        let mut keyed: Vec<(Vec<Option<Value>>, Todo)> = records
+            .iter()
+            .map(|r| {
+                let values = self.0.iter().map(|k| value(r, &k.field)).collect();
+                (values, r.clone())
+            })
+            .collect();
+        keyed.sort_by(|(a, _), (b, _)| {
+            for (key, (a, b)) in self.0.iter().zip(a.iter().zip(b)) {
+                let order = match (a, b) {
+                    (Some(a), Some(b)) if key.descending => b.cmp(a),
+                    (Some(a), Some(b)) => a.cmp(b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                };
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+            Ordering::Equal
+        });
+        for (record, (_, todo)) in records.iter_mut().zip(keyed) {
+            *record = todo;
+        }
+        Ok(())
+    }
+}
+
+fn value(todo: &Todo, field: &str) -> Option<Value> {
+    match field {
+        "title" => return Some(Value::Text(todo.title.clone())),
+        "date" => return date::sort_key(&todo.date).map(|(d, t)| Value::Date(d, t)),
+        _ => {}
+    }
+    let raw = fields::raw(todo, field).filter(|v| !v.is_empty())?;
+    if let Some(rank) = fields::find_declared(field).and_then(|d| d.rank(&raw)) {
+        return Some(Value::Rank(rank));
+    }
+    if let Ok(n) = raw.parse::<f64>() {
+        return Some(Value::Number(n));
+    }
+    if let Some(day) = Date::parse(raw.split(' ').next().unwrap_or("")) {
+        return Some(Value::Date(day, date::time_of(&raw)));
+    }
+    Some(Value::Text(raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: &str, date: &str, title: &str) -> Todo {
+        Todo::new(
+            id.to_string(),
+            date.to_string(),
+            title.to_string(),
+            vec![],
+            "".to_string(),
+        )
+    }
+
+    fn ids(records: &[Todo]) -> Vec<&str> {
+        records.iter().map(|r| r.id.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_by_several_keys_with_empty_values_last() {
+        let mut records = vec![
+            todo("1", "", "b"),
+            todo("2", "2026-10-16", "b"),
+            todo("3", "2026-10-15 09:00", "a"),
+            todo("4", "2026-10-16", "a"),
+            todo("10", "2026-10-15", "a"),
+        ];
+        Sort::parse("due,title")
+            .unwrap()
+            .apply(&mut records)
+            .unwrap();
+        assert_eq!(ids(&records), ["10", "3", "4", "2", "1"]);
+        Sort::parse("due:desc, id:desc")
+            .unwrap()
+            .apply(&mut records)
+            .unwrap();
+        assert_eq!(ids(&records), ["4", "2", "3", "10", "1"]);
+        // Ties keep their order.
+        Sort::parse("title").unwrap().apply(&mut records).unwrap();
+        assert_eq!(ids(&records), ["4", "3", "10", "2", "1"]);
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_directions() {
+        let mut records = vec![todo("1", "", "a")];
+        assert!(Sort::parse("title:up").is_err());
+        assert!(Sort::parse("title,").is_err());
+        assert!(Sort::parse("titel").unwrap().apply(&mut records).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::date::{self, Date};
+use crate::{focus, repeat, someday, Todo, Todos};
+use seahorse::{Command, Flag, FlagType};
+use std::collections::HashMap;
+
+/// Weeks shown by `--heatmap`, ending with the current one.
+const HEATMAP_WEEKS: i64 = 53;
+/// Heatmap cells from no completions to the busiest days.
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+pub fn stats_command() -> Command {
+    Command::new("stats")
+        .description("Show statistics about your TODOs")
+        .usage("todo stats [--heatmap]")
+        .flag(
+            Flag::new("heatmap", FlagType::Bool)
+                .description("Show completions per day over the last year"),
+        )
+        .action(|c| {
+            let todos = Todos::read();
+            let today = date::today();
+            if c.bool_flag("heatmap") {
+                print!("{}", heatmap(&todos.records, today));
+                return;
+            }
+
+            let records = &todos.records;
+            let done = records.iter().filter(|r| !r.done.is_empty()).count();
+            let open: Vec<&Todo> = records
+                .iter()
+                .filter(|r| r.done.is_empty() && !someday::is_someday(r))
+                .collect();
+            let overdue = open.iter().filter(|r| date::is_past(&r.date)).count();
+            let week_ago = today.add_days(-6);
+            let this_week = completed_on(records)
+                .iter()
+                .filter(|d| **d >= week_ago)
+                .count();
+            let tracked: u64 = records.iter().map(focus::tracked_seconds).sum();
+
+            for (label, value) in [
+                ("Total", records.len().to_string()),
+                ("Open", open.len().to_string()),
+                ("Someday", (records.len() - done - open.len()).to_string()),
+                ("Done", done.to_string()),
+                ("Overdue", overdue.to_string()),
+                ("Done in the last 7 days", this_week.to_string()),
+                ("Tracked", date::format_duration(tracked)),
+            ] {
+                println!("{:<25}{}", format!("{}:", label), value);
+            }
+
+            let habits: Vec<&Todo> = records.iter().filter(|r| repeat::is_recurring(r)).collect();
+            if !habits.is_empty() {
+                println!("\nStreaks:");
+                for habit in habits {
+                    println!(
+                        "  #{} {} ({}): {}",
+                        habit.id,
+                        habit.title,
+                        habit.repeat,
+                        repeat::streak_label(habit)
+                    );
+                }
+            }
+        })
+}
+
+/// Local dates of every recorded completion, including each one of
+/// recurring TODOs.
+fn completed_on(todos: &[Todo]) -> Vec<Date> {
+    todos
+        .iter()
+        .filter(|t| !t.done.is_empty())
+        .filter_map(|t| t.completed_at.parse().ok())
+        .chain(todos.iter().flat_map(repeat::completed_at))
+        .map(Date::from_timestamp)
+        .collect()
+}
+
+/// A GitHub-style grid of completions, one column per week and one row
+/// per weekday.
+fn heatmap(todos: &[Todo], today: Date) -> String {
+    let mut counts: HashMap<Date, usize> = HashMap::new();
+    for d in completed_on(todos) {
+        *counts.entry(d).or_default() += 1;
+    }
+    let monday = today.add_days(-(today.weekday() as i64));
+    let first = monday.add_days(-7 * (HEATMAP_WEEKS - 1));
+    let max = counts
+        .iter()
+        .filter(|(d, _)| **d >= first && **d <= today)
+        .map(|(_, n)| *n)
+        .max()
+        .unwrap_or(0);
+
+    let mut out = "    ".to_string();
+    let mut last_month = 0;
+    for week in 0..HEATMAP_WEEKS {
+        let d = first.add_days(week * 7);
+        if d.month != last_month && out.len() <= 4 + week as usize {
+            out += &format!("{:<4}", MONTHS[d.month as usize - 1]);
+            last_month = d.month;
+        } else if out.len() <= 4 + week as usize {
+            out.push(' ');
+        }
+    }
+    out = out.trim_end().to_string() + "\n";
+
+    for (weekday, name) in ["Mon", "", "Wed", "", "Fri", "", "Sun"].iter().enumerate() {
+        out += &format!("{:<4}", name);
+        for week in 0..HEATMAP_WEEKS {
+            let d = first.add_days(week * 7 + weekday as i64);
+            if d > today {
+                break;
+            }
+            out.push(shade(counts.get(&d).copied().unwrap_or(0), max));
+        }
+        out += "\n";
+    }
+
+    let total: usize = counts
+        .iter()
+        .filter(|(d, _)| **d >= first && **d <= today)
+        .map(|(_, n)| *n)
+        .sum();
+    out += &format!(
+        "\n{} completed since {}   Less {} More\n",
+        total,
+        first,
+        SHADES.iter().collect::<String>()
+    );
+    out
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+fn shade(count: usize, max: usize) -> char {
+    if count == 0 || max == 0 {
+        return SHADES[0];
+    }
+    let level = (count * (SHADES.len() - 1)).div_ceil(max);
+    SHADES[level]
+}
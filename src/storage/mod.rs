@@ -0,0 +1,241 @@
+//! Keeping the list on remote storage, set with `storage.remote`:
+//!
+//! ```toml
+//! [storage]
+//! remote = "webdavs://cloud.example.com/remote.php/dav/files/me/todo"
+//! # or, on S3 or S3-compatible storage like minio, see `s3`
+//! remote = "s3://bucket/todo"
+//! ```
+//!
+//! The file at `storage.path` is then a cache of the remote one. Reading
+//! the list fetches the remote file if it changed, and saving uploads it,
+//! if it is still the one read, both told by its ETag, which is kept in
+//! `<list>.remote` with the checksum of what was synced. Without a
+//! connection, the cache is used and changes are uploaded the next time.
+//!
+//! When the remote file changed and so did the cache, the remote one is
+//! kept as `<list>.conflict` and nothing is uploaded while that file is
+//! there; `todo merge` it, then remove it.
+
+mod s3;
+mod webdav;
+
+use crate::{config, crypt, dry_run, integrity, sha256, Todos};
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+
+/// What `Storage::fetch` found.
+pub enum Fetched {
+    /// The file is still at the ETag asked about.
+    Unchanged,
+    Missing,
+    Content {
+        content: String,
+        etag: Option<String>,
+    },
+}
+
+/// What `Storage::store` did.
+pub enum Stored {
+    /// Uploaded, now at this ETag if the storage told.
+    Done(Option<String>),
+    /// The file is no longer at the ETag it was to replace.
+    Conflict,
+}
+
+/// A place the list can be kept in.
+pub trait Storage {
+    /// The storage, for messages.
+    fn name(&self) -> String;
+    /// The file, unless it is still at `etag`.
+    fn fetch(&self, etag: Option<&str>) -> anyhow::Result<Fetched>;
+    /// Replace the file if it is still at `etag`, or create it when
+    /// `etag` is `None` and there is none.
+    fn store(&self, content: &str, etag: Option<&str>) -> anyhow::Result<Stored>;
+}
+
+/// The remote storage of the list at `path`, if it has one.
+fn remote(path: &str) -> Option<Box<dyn Storage>> {
+    let url = config::get().string("storage.remote")?;
+    if path != Todos::global_path() {
+        return None;
+    }
+    let storage: anyhow::Result<Box<dyn Storage>> = match url.split_once("://") {
+        Some(("webdav", rest)) => {
+            webdav::WebDav::new(format!("http://{}", rest)).map(|s| Box::new(s) as _)
+        }
+        Some(("webdavs", rest)) => {
+            webdav::WebDav::new(format!("https://{}", rest)).map(|s| Box::new(s) as _)
+        }
+        Some(("s3", rest)) => s3::S3::new(rest).map(|s| Box::new(s) as _),
+        _ => Err(anyhow::anyhow!(
+            "Unknown storage.remote {}, expected webdav://, webdavs:// or s3://",
+            url
+        )),
+    };
+    match storage {
+        Ok(storage) => Some(storage),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+/// The ETag of the remote file and the checksum of its content when the
+/// list was last synced with it.
+struct Synced {
+    etag: Option<String>,
+    checksum: Option<String>,
+}
+
+impl Synced {
+    fn path(list: &str) -> String {
+        format!("{}.remote", list)
+    }
+
+    fn read(list: &str) -> Self {
+        let content = fs::read_to_string(Self::path(list)).unwrap_or_default();
+        let mut lines = content
+            .lines()
+            .map(|l| Some(l.to_string()).filter(|l| !l.is_empty()));
+        Synced {
+            etag: lines.next().flatten(),
+            checksum: lines.next().flatten(),
+        }
+    }
+
+    fn write(list: &str, etag: Option<&str>, content: &str) {
+        let _ = fs::write(
+            Self::path(list),
+            format!("{}\n{}\n", etag.unwrap_or(""), checksum(content)),
+        );
+    }
+}
+
+fn checksum(content: &str) -> String {
+    sha256::hex(&sha256::digest(content.as_bytes()))
+}
+
+fn conflict_path(list: &str) -> String {
+    format!("{}.conflict", list)
+}
+
+/// Bring the list at `path` up to date with its remote file, before it
+/// is read.
+pub fn pull(path: &str) {
+    let Some(remote) = remote(path) else {
+        return;
+    };
+    if let Err(e) = try_pull(path, remote.as_ref()) {
+        eprintln!(
+            "Warning: failed to fetch the list from {}, using {}: {}",
+            remote.name(),
+            path,
+            e
+        );
+    }
+}
+
+fn try_pull(path: &str, remote: &dyn Storage) -> anyhow::Result<()> {
+    let synced = Synced::read(path);
+    let local = fs::read_to_string(path).ok();
+    let changed = local.as_deref().map(checksum) != synced.checksum && local.is_some();
+    let fetched = remote.fetch(synced.etag.as_deref())?;
+    if dry_run::is_enabled() {
+        report(path, remote, fetched, changed, local.as_deref());
+        return Ok(());
+    }
+    match fetched {
+        Fetched::Unchanged | Fetched::Missing if !changed => {}
+        Fetched::Unchanged => upload(path, remote, &local.unwrap(), synced.etag.as_deref()),
+        Fetched::Missing => upload(path, remote, &local.unwrap(), None),
+        Fetched::Content { content, etag } if !changed || local.as_ref() == Some(&content) => {
+            if local.as_ref() != Some(&content) {
+                integrity::before_write(path);
+                let temporary = crate::temporary_path(path, "remote");
+                fs::write(&temporary, &content)?;
+                fs::rename(&temporary, path)?;
+                integrity::record(path, content.as_bytes());
+            }
+            Synced::write(path, etag.as_deref(), &content);
+        }
+        Fetched::Content { content, etag } => {
+            let conflict = conflict_path(path);
+            fs::write(&conflict, crypt::seal(path, content)?)?;
+            // Compare with theirs from now on, but keep ours unsynced.
+            let _ = fs::write(
+                Synced::path(path),
+                format!(
+                    "{}\n{}\n",
+                    etag.unwrap_or_default(),
+                    synced.checksum.unwrap_or_default()
+                ),
+            );
+            eprintln!(
+                "Warning: the list changed both here and on {}; theirs is in {}, \
+                 todo merge it and remove it to upload again",
+                remote.name(),
+                conflict
+            );
+        }
+    }
+    Ok(())
+}
+
+/// What `try_pull` would do with what it `fetched`, for `--dry-run`.
+fn report(path: &str, remote: &dyn Storage, fetched: Fetched, changed: bool, local: Option<&str>) {
+    match fetched {
+        Fetched::Unchanged | Fetched::Missing if !changed => {}
+        Fetched::Unchanged | Fetched::Missing => {
+            println!("Dry run, {} would be uploaded to {}", path, remote.name())
+        }
+        Fetched::Content { content, .. } if local == Some(content.as_str()) => {}
+        Fetched::Content { .. } if !changed => println!(
+            "Dry run, {} would be replaced with the list on {}",
+            path,
+            remote.name()
+        ),
+        Fetched::Content { .. } => println!(
+            "Dry run, the list on {} would be kept as {}",
+            remote.name(),
+            conflict_path(path)
+        ),
+    }
+}
+
+/// Upload the list at `path`, just saved as `content`, to its remote
+/// file.
+pub fn push(path: &str, content: &str) {
+    let Some(remote) = remote(path) else {
+        return;
+    };
+    let etag = Synced::read(path).etag;
+    upload(path, remote.as_ref(), content, etag.as_deref());
+}
+
+fn upload(path: &str, remote: &dyn Storage, content: &str, etag: Option<&str>) {
+    let conflict = conflict_path(path);
+    if Path::new(&conflict).exists() {
+        eprintln!(
+            "Warning: not uploading to {} until {} is merged and removed",
+            remote.name(),
+            conflict
+        );
+        return;
+    }
+    match remote.store(content, etag) {
+        Ok(Stored::Done(etag)) => Synced::write(path, etag.as_deref(), content),
+        Ok(Stored::Conflict) => eprintln!(
+            "Warning: the list changed on {} since it was read; \
+             the change is kept here for the next run to sort out",
+            remote.name()
+        ),
+        Err(e) => eprintln!(
+            "Warning: failed to upload the list to {}, it will be the next time: {}",
+            remote.name(),
+            e
+        ),
+    }
+}
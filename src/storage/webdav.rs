@@ -0,0 +1,70 @@
+//! WebDAV, as Nextcloud and ownCloud have, with the user in
+//! `webdav.user` and their password, or an app password, in
+//! `$WEBDAV_PASSWORD` or the keyring as `todo-webdav`.
+
+use super::{Fetched, Storage, Stored};
+use crate::{auth, config, http};
+use anyhow::bail;
+
+pub struct WebDav {
+    url: String,
+    auth: Option<String>,
+}
+
+impl WebDav {
+    pub fn new(url: String) -> anyhow::Result<Self> {
+        let Some(user) = config::get().string("webdav.user") else {
+            return Ok(WebDav { url, auth: None });
+        };
+        let Some(password) = auth::secret("webdav", user) else {
+            bail!("Please set $WEBDAV_PASSWORD or store the password with todo auth set webdav");
+        };
+        let auth = Some(http::basic_auth(user, &password));
+        Ok(WebDav { url, auth })
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        headers: Vec<String>,
+        body: Option<&str>,
+    ) -> anyhow::Result<http::Response> {
+        let mut headers = headers;
+        headers.extend(self.auth.clone());
+        http::request(method, &self.url, &headers, body)
+    }
+}
+
+impl Storage for WebDav {
+    fn name(&self) -> String {
+        http::host(&self.url)
+    }
+
+    fn fetch(&self, etag: Option<&str>) -> anyhow::Result<Fetched> {
+        let headers = etag.map(|e| format!("If-None-Match: {}", e));
+        let response = self.send("GET", headers.into_iter().collect(), None)?;
+        match response.status {
+            304 => Ok(Fetched::Unchanged),
+            404 => Ok(Fetched::Missing),
+            _ if response.is_success() => Ok(Fetched::Content {
+                content: response.body,
+                etag: response.etag,
+            }),
+            status => bail!("{} answered {}", self.name(), status),
+        }
+    }
+
+    fn store(&self, content: &str, etag: Option<&str>) -> anyhow::Result<Stored> {
+        let condition = match etag {
+            Some(etag) => format!("If-Match: {}", etag),
+            None => "If-None-Match: *".to_string(),
+        };
+        let headers = vec![condition, "Content-Type: text/plain".to_string()];
+        let response = self.send("PUT", headers, Some(content))?;
+        match response.status {
+            412 => Ok(Stored::Conflict),
+            _ if response.is_success() => Ok(Stored::Done(response.etag)),
+            status => bail!("{} answered {}", self.name(), status),
+        }
+    }
+}
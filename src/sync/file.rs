@@ -0,0 +1,86 @@
+//! Sync between devices through a file they share, such as one in a
+//! Dropbox or Syncthing folder or on a network drive:
+//!
+//! ```sh
+//! todo sync file ~/Dropbox/todo.sync
+//! ```
+//!
+//! The file holds the list as a CRDT, see `crdt`, which each sync merges
+//! with this device's copy before writing it back and updating the list,
+//! so devices can sync in any order without conflicts. Unlike with the
+//! services, all fields are synced, and TODOs deleted on one device are
+//! deleted on the others, unless edited there since.
+
+use crate::crdt::{self, Changes, Replica, State};
+use crate::{dry_run, lists, trash, Todos};
+use seahorse::{Command, Context};
+use std::process::exit;
+
+pub fn command() -> Command {
+    Command::new("file")
+        .description("Sync with other devices through a shared file")
+        .usage("todo sync file <path>")
+        .action(|c| {
+            if let Err(e) = sync(c) {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        })
+}
+
+fn sync(c: &Context) -> anyhow::Result<()> {
+    let path = match c.args.as_slice() {
+        [path] => crate::config::expand_home(path),
+        _ => anyhow::bail!("Please specify the file to sync through"),
+    };
+    let list = Todos::todo_path();
+    let mut todos = Todos::read();
+    let _lock = lists::lock(&path);
+    let mut replica = Replica::load(&list)?.unwrap_or_else(Replica::new);
+    replica.observe(&todos, crdt::now());
+    let shared = State::read(&path).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+    replica.state.join(&shared);
+
+    let changes = replica.apply(&mut todos);
+    if dry_run::is_enabled() {
+        println!(
+            "Would sync {}: {} added, {} updated, {} deleted",
+            path,
+            changes.added,
+            changes.updated,
+            changes.deleted.len()
+        );
+        return Ok(());
+    }
+    replica.state.write(&path, &list)?;
+    keep(&list, &mut todos, &replica, &changes)?;
+    println!(
+        "Synced {}: {} added, {} updated, {} deleted",
+        path,
+        changes.added,
+        changes.updated,
+        changes.deleted.len()
+    );
+    Ok(())
+}
+
+/// Save what a sync did: the list, with the deleted TODOs in the trash,
+/// and the replica.
+pub(super) fn keep(
+    list: &str,
+    todos: &mut Todos,
+    replica: &Replica,
+    changes: &Changes,
+) -> anyhow::Result<()> {
+    if !changes.deleted.is_empty() {
+        let mut trash = trash::Trash::read();
+        for todo in &changes.deleted {
+            trash.push(todo.clone());
+        }
+        trash.save()?;
+    }
+    if changes.added + changes.updated + changes.deleted.len() > 0 {
+        todos.save()?;
+    }
+    replica.save(list)
+}
@@ -0,0 +1,207 @@
+//! Google Tasks, through the Tasks API with an OAuth device flow sign-in.
+//!
+//! ```toml
+//! [gtasks]
+//! client_id = "....apps.googleusercontent.com"   # a "TVs and Limited Input" client
+//! client_secret = "..."
+//! tasklist = "My Tasks"                           # title or ID, default: the first list
+//! ```
+
+use super::{RemoteTask, Snapshot};
+use crate::date::Date;
+use crate::json::Value;
+use crate::{config, http, oauth};
+use anyhow::bail;
+use seahorse::{Command, Flag, FlagType};
+use std::process::exit;
+
+const API: &str = "https://tasks.googleapis.com/tasks/v1";
+
+pub fn command() -> Command {
+    Command::new("gtasks")
+        .description("Two-way sync with Google Tasks")
+        .usage("todo sync gtasks [--logout]")
+        .flag(Flag::new("logout", FlagType::Bool).description("Forget the stored sign-in"))
+        .action(|c| {
+            let provider = provider();
+            let result = if c.bool_flag("logout") {
+                provider.logout()
+            } else {
+                GoogleTasks::connect(&provider)
+                    .map_err(|e| super::unreachable("gtasks", "Google Tasks", e))
+                    .and_then(|remote| super::run(&remote))
+            };
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        })
+}
+
+fn provider() -> oauth::Provider {
+    let config = config::get();
+    let Some(client_id) = config.string("gtasks.client_id") else {
+        eprintln!(
+            "Please set gtasks.client_id and gtasks.client_secret in {}",
+            config::Config::path()
+        );
+        exit(1);
+    };
+    oauth::Provider {
+        name: "gtasks",
+        device_url: "https://oauth2.googleapis.com/device/code".to_string(),
+        token_url: "https://oauth2.googleapis.com/token".to_string(),
+        client_id: client_id.to_string(),
+        client_secret: config.string("gtasks.client_secret").map(|s| s.to_string()),
+        scope: "https://www.googleapis.com/auth/tasks",
+    }
+}
+
+struct GoogleTasks {
+    token: String,
+    list: String,
+    title: String,
+}
+
+impl GoogleTasks {
+    fn connect(provider: &oauth::Provider) -> anyhow::Result<Self> {
+        let mut remote = GoogleTasks {
+            token: provider.access_token()?,
+            list: "".to_string(),
+            title: "".to_string(),
+        };
+        let lists = remote.call("GET", &format!("{}/users/@me/lists", API), None)?;
+        let wanted = config::get().string("gtasks.tasklist");
+        let list = lists.get("items").items().iter().find(|l| match wanted {
+            Some(w) => l.get("id").as_str() == Some(w) || l.get("title").as_str() == Some(w),
+            None => true,
+        });
+        match list.and_then(|l| l.get("id").as_str()) {
+            Some(id) => remote.list = id.to_string(),
+            None => bail!("No such task list: {}", wanted.unwrap_or("(default)")),
+        }
+        remote.title = list
+            .and_then(|l| l.get("title").as_str())
+            .unwrap_or(&remote.list)
+            .to_string();
+        Ok(remote)
+    }
+
+    fn call(&self, method: &str, url: &str, body: Option<&Value>) -> anyhow::Result<Value> {
+        let mut headers = vec![format!("Authorization: Bearer {}", self.token)];
+        if body.is_some() {
+            headers.push("Content-Type: application/json".to_string());
+        }
+        let body = body.map(|b| b.to_string());
+        let response = http::request(method, url, &headers, body.as_deref())?;
+        if !response.is_success() {
+            let reply = response.json().unwrap_or(Value::Null);
+            bail!(
+                "Google Tasks answered {}: {}",
+                response.status,
+                reply.get("error").get("message").as_str().unwrap_or("")
+            );
+        }
+        if response.body.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        response.json()
+    }
+
+    fn tasks_url(&self) -> String {
+        format!("{}/lists/{}/tasks", API, http::encode(&self.list))
+    }
+
+    fn body(task: &Snapshot) -> Value {
+        let mut pairs = vec![
+            ("title", task.title.as_str().into()),
+            (
+                "due",
+                match task.due {
+                    Some(d) => format!("{}T00:00:00.000Z", d).as_str().into(),
+                    None => Value::Null,
+                },
+            ),
+            (
+                "status",
+                if task.done {
+                    "completed"
+                } else {
+                    "needsAction"
+                }
+                .into(),
+            ),
+        ];
+        if !task.done {
+            // Reopening also needs the completion time cleared.
+            pairs.push(("completed", Value::Null));
+        }
+        Value::object(pairs)
+    }
+}
+
+impl super::Remote for GoogleTasks {
+    fn key(&self) -> String {
+        format!("gtasks {}", self.list)
+    }
+
+    fn name(&self) -> String {
+        format!("Google Tasks \"{}\"", self.title)
+    }
+
+    fn list(&self) -> anyhow::Result<Vec<RemoteTask>> {
+        let mut tasks = vec![];
+        let mut page = String::new();
+        loop {
+            let url = format!(
+                "{}?showCompleted=true&showHidden=true&maxResults=100&pageToken={}",
+                self.tasks_url(),
+                http::encode(&page)
+            );
+            let reply = self.call("GET", &url, None)?;
+            for item in reply.get("items").items() {
+                let Some(id) = item.get("id").as_str() else {
+                    continue;
+                };
+                if item.get("deleted") == &Value::Bool(true) {
+                    continue;
+                }
+                tasks.push(RemoteTask {
+                    id: id.to_string(),
+                    task: Snapshot {
+                        title: item.get("title").as_str().unwrap_or("").to_string(),
+                        // Google keeps only the date of `due`.
+                        due: item
+                            .get("due")
+                            .as_str()
+                            .and_then(|d| Date::parse(d.get(..10)?)),
+                        done: item.get("status").as_str() == Some("completed"),
+                    },
+                });
+            }
+            match reply.get("nextPageToken").as_str() {
+                Some(next) => page = next.to_string(),
+                None => return Ok(tasks),
+            }
+        }
+    }
+
+    fn create(&self, task: &Snapshot) -> anyhow::Result<String> {
+        let reply = self.call("POST", &self.tasks_url(), Some(&Self::body(task)))?;
+        match reply.get("id").as_str() {
+            Some(id) => Ok(id.to_string()),
+            None => bail!("Google Tasks did not return an ID"),
+        }
+    }
+
+    fn update(&self, id: &str, task: &Snapshot) -> anyhow::Result<()> {
+        let url = format!("{}/{}", self.tasks_url(), http::encode(id));
+        self.call("PATCH", &url, Some(&Self::body(task)))
+            .map(|_| ())
+    }
+
+    fn delete(&self, id: &str) -> anyhow::Result<()> {
+        let url = format!("{}/{}", self.tasks_url(), http::encode(id));
+        self.call("DELETE", &url, None).map(|_| ())
+    }
+}
@@ -0,0 +1,287 @@
+//! Home Assistant to-do lists, such as the shopping list, through its REST
+//! API with a long-lived access token (made on your profile page). Each
+//! list is synced with the TODOs of a tag:
+//!
+//! ```toml
+//! [homeassistant]
+//! url = "http://homeassistant.local:8123"
+//! entity = "todo.chores"          # for TODOs without a mapped tag, default: none
+//!
+//! [homeassistant.lists]
+//! shopping = "todo.shopping_list" # TODOs tagged shopping sync with this list
+//! ```
+//!
+//! The token is read from `$HA_TOKEN`, or else from the keyring under the
+//! service `todo-homeassistant` and the host of the URL as account.
+//! Reading the items needs Home Assistant 2024.8 or later. Due dates are
+//! only sent to lists that have them, and are not removed there when they
+//! are removed here, as the API has no way to.
+
+use super::{RemoteTask, Snapshot};
+use crate::date::Date;
+use crate::json::Value;
+use crate::{auth, config, http, Todo};
+use anyhow::bail;
+use seahorse::Command;
+use std::process::exit;
+
+/// `TodoListEntityFeature.SET_DUE_DATE_ON_ITEM`.
+const DUE_DATE_FEATURE: u64 = 16;
+
+pub fn command() -> Command {
+    Command::new("homeassistant")
+        .description("Two-way sync with Home Assistant to-do lists")
+        .usage("todo sync homeassistant")
+        .alias("ha")
+        .action(|_| {
+            if let Err(e) = sync() {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        })
+}
+
+/// Sync every mapped list, then the one for the remaining TODOs.
+fn sync() -> anyhow::Result<()> {
+    let api = Api::connect()?;
+    let config = config::get();
+    let mapped = config.section("homeassistant.lists");
+    let tags: Vec<String> = mapped.iter().map(|(tag, _)| tag.to_string()).collect();
+    let mut remotes = vec![];
+    for (tag, entity) in &mapped {
+        remotes.push(
+            HomeAssistant::new(&api, entity, Some(tag.to_string()), vec![])
+                .map_err(|e| super::unreachable("homeassistant", "Home Assistant", e))?,
+        );
+    }
+    if let Some(entity) = config.string("homeassistant.entity") {
+        remotes.push(
+            HomeAssistant::new(&api, entity, None, tags)
+                .map_err(|e| super::unreachable("homeassistant", "Home Assistant", e))?,
+        );
+    }
+    if remotes.is_empty() {
+        bail!(
+            "Please map tags to lists under [homeassistant.lists] or set homeassistant.entity in {}",
+            config::Config::path()
+        );
+    }
+
+    for remote in &remotes {
+        super::run(remote)?;
+    }
+    Ok(())
+}
+
+struct Api {
+    /// The instance, without a trailing slash.
+    url: String,
+    auth: String,
+}
+
+impl Api {
+    fn connect() -> anyhow::Result<Self> {
+        let Some(url) = config::get().string("homeassistant.url") else {
+            bail!("Please set homeassistant.url in {}", config::Config::path());
+        };
+        let Some(token) = auth::secret("homeassistant", &http::host(url)) else {
+            bail!("Please set $HA_TOKEN or store a token with todo auth set homeassistant");
+        };
+        Ok(Api {
+            url: url.trim_end_matches('/').to_string(),
+            auth: format!("Authorization: Bearer {}", token),
+        })
+    }
+
+    fn call(&self, method: &str, path: &str, body: Option<&Value>) -> anyhow::Result<Value> {
+        let mut headers = vec![self.auth.clone()];
+        if body.is_some() {
+            headers.push("Content-Type: application/json".to_string());
+        }
+        let body = body.map(|b| b.to_string());
+        let url = format!("{}/api/{}", self.url, path);
+        let response = http::request(method, &url, &headers, body.as_deref())?;
+        if !response.is_success() {
+            let reply = response.json().unwrap_or(Value::Null);
+            let message = match reply.get("message").as_str() {
+                Some(m) => m.to_string(),
+                None => response.body.trim().to_string(),
+            };
+            bail!("Home Assistant answered {}: {}", response.status, message);
+        }
+        if response.body.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        response.json()
+    }
+
+    /// Call a `todo.*` service with `fields` for `entity`.
+    fn service(
+        &self,
+        service: &str,
+        entity: &str,
+        fields: Vec<(&str, Value)>,
+        response: bool,
+    ) -> anyhow::Result<Value> {
+        let mut body = vec![("entity_id", entity.into())];
+        body.extend(fields);
+        let path = format!(
+            "services/todo/{}{}",
+            service,
+            if response { "?return_response" } else { "" }
+        );
+        self.call("POST", &path, Some(&Value::object(body)))
+    }
+}
+
+struct HomeAssistant<'a> {
+    api: &'a Api,
+    entity: String,
+    title: String,
+    due_dates: bool,
+    /// Tag of the TODOs this list is for.
+    tag: Option<String>,
+    /// For the unmapped list: tags whose TODOs go to other lists.
+    other_tags: Vec<String>,
+}
+
+impl<'a> HomeAssistant<'a> {
+    fn new(
+        api: &'a Api,
+        entity: &str,
+        tag: Option<String>,
+        other_tags: Vec<String>,
+    ) -> anyhow::Result<Self> {
+        let state = api
+            .call("GET", &format!("states/{}", entity), None)
+            .map_err(|e| anyhow::anyhow!("No such to-do list: {} ({})", entity, e))?;
+        let attributes = state.get("attributes");
+        let features = attributes.get("supported_features").as_f64().unwrap_or(0.0) as u64;
+        Ok(HomeAssistant {
+            api,
+            entity: entity.to_string(),
+            title: attributes
+                .get("friendly_name")
+                .as_str()
+                .unwrap_or(entity)
+                .to_string(),
+            due_dates: features & DUE_DATE_FEATURE != 0,
+            tag,
+            other_tags,
+        })
+    }
+
+    fn items(&self) -> anyhow::Result<Vec<Value>> {
+        let reply = self.api.service("get_items", &self.entity, vec![], true)?;
+        Ok(reply
+            .get("service_response")
+            .get(&self.entity)
+            .get("items")
+            .items()
+            .to_vec())
+    }
+
+    /// The fields of `task` besides its title.
+    fn fields(&self, task: &Snapshot) -> Vec<(&'static str, Value)> {
+        let mut fields = vec![];
+        if let Some(due) = task.due.filter(|_| self.due_dates) {
+            fields.push(("due_date", due.to_string().as_str().into()));
+        }
+        fields
+    }
+}
+
+impl super::Remote for HomeAssistant<'_> {
+    fn key(&self) -> String {
+        format!("homeassistant {}", self.entity)
+    }
+
+    fn name(&self) -> String {
+        format!("Home Assistant \"{}\"", self.title)
+    }
+
+    fn list(&self) -> anyhow::Result<Vec<RemoteTask>> {
+        Ok(self
+            .items()?
+            .iter()
+            .filter_map(|item| {
+                Some(RemoteTask {
+                    id: item.get("uid").as_str()?.to_string(),
+                    task: Snapshot {
+                        title: item.get("summary").as_str().unwrap_or("").to_string(),
+                        due: item
+                            .get("due")
+                            .as_str()
+                            .and_then(|d| Date::parse(d.get(..10)?)),
+                        done: item.get("status").as_str() == Some("completed"),
+                    },
+                })
+            })
+            .collect())
+    }
+
+    /// The service does not return the new item, so it is told apart
+    /// from the others by its UID.
+    fn create(&self, task: &Snapshot) -> anyhow::Result<String> {
+        let uid = |item: &Value| item.get("uid").as_str().map(|u| u.to_string());
+        let before: Vec<String> = self.items()?.iter().filter_map(uid).collect();
+        let mut fields = vec![("item", task.title.as_str().into())];
+        fields.extend(self.fields(task));
+        self.api.service("add_item", &self.entity, fields, false)?;
+        let created = self
+            .items()?
+            .iter()
+            .filter(|i| i.get("summary").as_str() == Some(task.title.as_str()))
+            .filter_map(uid)
+            .find(|u| !before.contains(u));
+        let Some(id) = created else {
+            bail!("Home Assistant did not add {}", task.title);
+        };
+        if task.done {
+            self.update(&id, task)?;
+        }
+        Ok(id)
+    }
+
+    fn update(&self, id: &str, task: &Snapshot) -> anyhow::Result<()> {
+        let mut fields = vec![
+            ("item", id.into()),
+            ("rename", task.title.as_str().into()),
+            (
+                "status",
+                if task.done {
+                    "completed"
+                } else {
+                    "needs_action"
+                }
+                .into(),
+            ),
+        ];
+        fields.extend(self.fields(task));
+        self.api
+            .service("update_item", &self.entity, fields, false)
+            .map(|_| ())
+    }
+
+    fn delete(&self, id: &str) -> anyhow::Result<()> {
+        self.api
+            .service(
+                "remove_item",
+                &self.entity,
+                vec![("item", id.into())],
+                false,
+            )
+            .map(|_| ())
+    }
+
+    fn owns(&self, todo: &Todo) -> bool {
+        match &self.tag {
+            Some(tag) => todo.tags.contains(tag),
+            None => !todo.tags.iter().any(|t| self.other_tags.contains(t)),
+        }
+    }
+
+    fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+}
@@ -0,0 +1,342 @@
+//! Two-way sync with task services, and between devices, see `file` and `relay`.
+//!
+//! Each linked TODO keeps a `Link` in its `remote_ids` with a hash of the
+//! fields as they were at the last sync. On the next sync, comparing both
+//! sides with that hash tells which one changed. When both did, the local
+//! TODO wins.
+
+mod file;
+mod gtasks;
+mod homeassistant;
+mod mstodo;
+mod queue;
+mod relay;
+#[cfg(all(feature = "reminders", target_os = "macos"))]
+mod reminders;
+
+use crate::date::Date;
+use crate::{dry_run, someday, trash, Todo, Todos};
+use seahorse::Command;
+use std::collections::HashSet;
+
+/// The synced fields of a task, in either place.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub title: String,
+    pub due: Option<Date>,
+    pub done: bool,
+}
+
+impl Snapshot {
+    fn of(todo: &Todo) -> Self {
+        Snapshot {
+            title: todo.title.clone(),
+            due: Date::parse(&todo.date),
+            done: !todo.done.is_empty(),
+        }
+    }
+
+    /// FNV-1a, which unlike `DefaultHasher` is stable across releases.
+    fn hash(&self) -> String {
+        let due = self.due.map(|d| d.to_string()).unwrap_or_default();
+        let text = format!("{}\u{1f}{}\u{1f}{}", self.title, due, self.done);
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for b in text.bytes() {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        format!("{:016x}", hash)
+    }
+
+    fn apply(&self, todo: &mut Todo) {
+        todo.title = self.title.clone();
+        match self.due {
+            // The services only have days; keep a local time on the same one.
+            Some(d) if Date::parse(&todo.date) == Some(d) => {}
+            Some(d) => todo.date = d.to_string(),
+            // A free-form local date has no remote counterpart; keep it.
+            None if Date::parse(&todo.date).is_some() => todo.date = "".to_string(),
+            None => {}
+        }
+        if self.done && todo.done.is_empty() {
+            todo.done = "✓".to_string();
+            todo.completed_at = crate::date::now().to_string();
+        } else if !self.done && !todo.done.is_empty() {
+            todo.done = "".to_string();
+            todo.completed_at = "".to_string();
+            todo.completed_by = "".to_string();
+        }
+    }
+}
+
+pub struct RemoteTask {
+    pub id: String,
+    pub task: Snapshot,
+}
+
+/// A task list in some service.
+pub trait Remote {
+    /// `<service> <list id>`, as stored in links.
+    fn key(&self) -> String;
+    /// The list, for messages.
+    fn name(&self) -> String;
+    fn list(&self) -> anyhow::Result<Vec<RemoteTask>>;
+    /// Create a task and return its ID.
+    fn create(&self, task: &Snapshot) -> anyhow::Result<String>;
+    fn update(&self, id: &str, task: &Snapshot) -> anyhow::Result<()>;
+    fn delete(&self, id: &str) -> anyhow::Result<()>;
+
+    /// Whether an unlinked TODO should be created in this list.
+    fn owns(&self, _todo: &Todo) -> bool {
+        true
+    }
+
+    /// Tag given to TODOs added from this list.
+    fn tag(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// A `remote_ids` entry: `<service> <list id> <remote id> <hash>`.
+struct Link<'a> {
+    key: String,
+    id: &'a str,
+    hash: &'a str,
+}
+
+impl<'a> Link<'a> {
+    fn parse(s: &'a str) -> Option<Self> {
+        let mut parts = s.rsplitn(3, ' ');
+        let hash = parts.next()?;
+        let id = parts.next()?;
+        let key = parts.next()?.to_string();
+        Some(Link { key, id, hash })
+    }
+}
+
+fn link_of<'a>(todo: &'a Todo, key: &str) -> Option<(usize, Link<'a>)> {
+    todo.remote_ids
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| Link::parse(s).map(|l| (i, l)))
+        .find(|(_, l)| l.key == key)
+}
+
+fn set_link(todo: &mut Todo, key: &str, id: &str, task: &Snapshot) {
+    let entry = format!("{} {} {}", key, id, task.hash());
+    match link_of(todo, key).map(|(i, _)| i) {
+        Some(i) => todo.remote_ids[i] = entry,
+        None => todo.remote_ids.push(entry),
+    }
+}
+
+pub fn sync_command() -> Command {
+    let command = Command::new("sync")
+        .description("Sync TODOs with a task service")
+        .usage("todo sync [gtasks | mstodo | homeassistant | file <path> | relay | status]")
+        .action(|c| c.help())
+        .command(gtasks::command())
+        .command(mstodo::command())
+        .command(homeassistant::command())
+        .command(file::command())
+        .command(relay::command())
+        .command(queue::status_command());
+    #[cfg(all(feature = "reminders", target_os = "macos"))]
+    let command = command.command(reminders::command());
+    command
+}
+
+#[derive(Default)]
+struct Counts {
+    pushed: usize,
+    pulled: usize,
+    unlinked: usize,
+}
+
+/// Sync the TODOs of `remote`'s list.
+///
+/// - Open unlinked TODOs it `owns` are created remotely, and open unlinked remote
+///   tasks are added locally.
+/// - Linked ones are updated on the side that did not change.
+/// - TODOs deleted here are deleted remotely.
+/// - Tasks deleted remotely are only unlinked here.
+///
+/// Requests that fail are queued, see `queue`, and what went through is
+/// saved all the same.
+pub fn run(remote: &dyn Remote) -> anyhow::Result<()> {
+    let key = remote.key();
+    let mut requests = queue::Requests::new(&key, &remote.name());
+    let Ok(tasks) = requests.call(|| remote.list()) else {
+        return offline(&mut requests, &key, &|todo| remote.owns(todo));
+    };
+    let mut counts = Counts::default();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    let mut trash = trash::Trash::read();
+    for todo in trash.todos_mut() {
+        if let Some((i, link)) = link_of(todo, &key) {
+            if tasks.iter().any(|t| t.id == link.id) {
+                seen.insert(link.id.to_string());
+                if dry_run::is_enabled() {
+                    println!("Would delete {} on {}", todo.title, remote.name());
+                } else if requests
+                    .send(format!("delete {}", todo.title), || remote.delete(link.id))
+                    .is_none()
+                {
+                    continue;
+                }
+                counts.pushed += 1;
+            }
+            todo.remote_ids.remove(i);
+        }
+    }
+    trash.save()?;
+
+    let mut todos = Todos::read();
+    for todo in todos.records.iter_mut() {
+        let local = Snapshot::of(todo);
+        let Some((i, link)) = link_of(todo, &key) else {
+            if todo.done.is_empty() && !someday::is_someday(todo) && remote.owns(todo) {
+                if dry_run::is_enabled() {
+                    println!(
+                        "Would create #{} {} on {}",
+                        todo.id,
+                        todo.title,
+                        remote.name()
+                    );
+                } else {
+                    let what = format!("create #{} {}", todo.id, todo.title);
+                    let Some(id) = requests.send(what, || remote.create(&local)) else {
+                        continue;
+                    };
+                    set_link(todo, &key, &id, &local);
+                }
+                counts.pushed += 1;
+            }
+            continue;
+        };
+        let (id, hash) = (link.id.to_string(), link.hash.to_string());
+        seen.insert(id.clone());
+
+        match tasks.iter().find(|t| t.id == id) {
+            None => {
+                todo.remote_ids.remove(i);
+                counts.unlinked += 1;
+            }
+            Some(remote_task) if local.hash() != hash => {
+                if remote_task.task != local && dry_run::is_enabled() {
+                    println!(
+                        "Would update #{} {} on {}",
+                        todo.id,
+                        todo.title,
+                        remote.name()
+                    );
+                    counts.pushed += 1;
+                } else if remote_task.task != local {
+                    let what = format!("update #{} {}", todo.id, todo.title);
+                    if requests.send(what, || remote.update(&id, &local)).is_none() {
+                        continue;
+                    }
+                    counts.pushed += 1;
+                }
+                set_link(todo, &key, &id, &local);
+            }
+            Some(remote_task) if remote_task.task.hash() != hash => {
+                remote_task.task.apply(todo);
+                set_link(todo, &key, &id, &remote_task.task);
+                counts.pulled += 1;
+            }
+            Some(_) => {}
+        }
+    }
+
+    for remote_task in tasks.iter().filter(|t| !seen.contains(&t.id)) {
+        if remote_task.task.done {
+            continue;
+        }
+        let mut todo = Todo::new(
+            "".to_string(),
+            "".to_string(),
+            "".to_string(),
+            vec![],
+            "".to_string(),
+        );
+        remote_task.task.apply(&mut todo);
+        todo.tags.extend(remote.tag().map(|t| t.to_string()));
+        set_link(&mut todo, &key, &remote_task.id, &remote_task.task);
+        todos.add(todo)?;
+        counts.pulled += 1;
+    }
+
+    todos.save()?;
+    if !dry_run::is_enabled() {
+        requests.save()?;
+    }
+    println!(
+        "Synced {}: {} pushed, {} pulled, {} unlinked",
+        remote.name(),
+        counts.pushed,
+        counts.pulled,
+        counts.unlinked
+    );
+    match requests.error() {
+        Some(error) => anyhow::bail!(
+            "{} changes could not be pushed and are queued for the next sync: {}",
+            requests.pending(),
+            error
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Queue what a sync would push to the lists of `key`, a list's or a
+/// whole service's, which could not be reached, and tell.
+fn offline(
+    requests: &mut queue::Requests,
+    key: &str,
+    owns: &dyn Fn(&Todo) -> bool,
+) -> anyhow::Result<()> {
+    let links = |todo: &Todo| -> Vec<String> {
+        todo.remote_ids
+            .iter()
+            .filter_map(|s| Link::parse(s))
+            .filter(|l| l.key == key || l.key.starts_with(&format!("{} ", key)))
+            .map(|l| l.hash.to_string())
+            .collect()
+    };
+    for todo in trash::Trash::read().todos_mut() {
+        if !links(todo).is_empty() {
+            requests.queue(format!("delete {}", todo.title));
+        }
+    }
+    for todo in &Todos::read().records {
+        let hashes = links(todo);
+        if hashes.is_empty() {
+            if todo.done.is_empty() && !someday::is_someday(todo) && owns(todo) {
+                requests.queue(format!("create #{} {}", todo.id, todo.title));
+            }
+        } else if hashes.iter().any(|h| *h != Snapshot::of(todo).hash()) {
+            requests.queue(format!("update #{} {}", todo.id, todo.title));
+        }
+    }
+    if !dry_run::is_enabled() {
+        requests.save()?;
+    }
+    anyhow::bail!(
+        "Could not reach {}, {} changes are queued for the next sync: {}",
+        requests.name(),
+        requests.pending(),
+        requests.error().unwrap_or("")
+    )
+}
+
+/// For a service that could not be connected to with `error`: queue
+/// what a sync would push to any of its lists, see `offline`.
+pub fn unreachable(service: &str, name: &str, error: anyhow::Error) -> anyhow::Error {
+    let mut requests = queue::Requests::new(service, name);
+    requests.fail(&error);
+    match offline(&mut requests, service, &|_| true) {
+        Err(e) => e,
+        Ok(()) => error,
+    }
+}
@@ -0,0 +1,246 @@
+//! Microsoft To Do, through the Graph API with an OAuth device flow
+//! sign-in. Each To Do list can be synced with the TODOs of a tag:
+//!
+//! ```toml
+//! [mstodo]
+//! client_id = "..."       # an app registration that allows public client flows
+//! tenant = "common"       # or your organization's tenant ID
+//! list = "Tasks"          # for TODOs without a mapped tag, default: the default list
+//!
+//! [mstodo.lists]
+//! work = "Work"           # TODOs tagged work sync with the "Work" list
+//! ```
+
+use super::{RemoteTask, Snapshot};
+use crate::date::Date;
+use crate::json::Value;
+use crate::{config, http, oauth, Todo};
+use anyhow::bail;
+use seahorse::{Command, Flag, FlagType};
+use std::process::exit;
+
+const API: &str = "https://graph.microsoft.com/v1.0/me/todo";
+
+pub fn command() -> Command {
+    Command::new("mstodo")
+        .description("Two-way sync with Microsoft To Do")
+        .usage("todo sync mstodo [--logout]")
+        .flag(Flag::new("logout", FlagType::Bool).description("Forget the stored sign-in"))
+        .action(|c| {
+            let provider = provider();
+            let result = if c.bool_flag("logout") {
+                provider.logout()
+            } else {
+                sync(&provider)
+            };
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        })
+}
+
+fn provider() -> oauth::Provider {
+    let config = config::get();
+    let Some(client_id) = config.string("mstodo.client_id") else {
+        eprintln!("Please set mstodo.client_id in {}", config::Config::path());
+        exit(1);
+    };
+    let tenant = config.string("mstodo.tenant").unwrap_or("common");
+    let endpoint = |path: &str| {
+        format!(
+            "https://login.microsoftonline.com/{}/oauth2/v2.0/{}",
+            tenant, path
+        )
+    };
+    oauth::Provider {
+        name: "mstodo",
+        device_url: endpoint("devicecode"),
+        token_url: endpoint("token"),
+        client_id: client_id.to_string(),
+        client_secret: None,
+        scope: "Tasks.ReadWrite offline_access",
+    }
+}
+
+/// Sync every mapped list, then the one for the remaining TODOs.
+fn sync(provider: &oauth::Provider) -> anyhow::Result<()> {
+    let config = config::get();
+    let token = provider
+        .access_token()
+        .map_err(|e| super::unreachable("mstodo", "Microsoft To Do", e))?;
+    let lists = Graph { token: &token }
+        .call("GET", &format!("{}/lists", API), None)
+        .map_err(|e| super::unreachable("mstodo", "Microsoft To Do", e))?;
+    let find = |name: Option<&str>| {
+        lists.get("value").items().iter().find(|l| match name {
+            Some(n) => l.get("displayName").as_str() == Some(n) || l.get("id").as_str() == Some(n),
+            None => l.get("wellknownListName").as_str() == Some("defaultList"),
+        })
+    };
+
+    let mapped = config.section("mstodo.lists");
+    let tags: Vec<String> = mapped.iter().map(|(tag, _)| tag.to_string()).collect();
+    let mut remotes = vec![];
+    for (tag, name) in &mapped {
+        match find(Some(name)) {
+            Some(list) => remotes.push(MsTodo::new(&token, list, Some(tag.to_string()), vec![])),
+            None => bail!("No such To Do list: {}", name),
+        }
+    }
+    let default = config.string("mstodo.list");
+    match find(default) {
+        Some(list) => remotes.push(MsTodo::new(&token, list, None, tags)),
+        None => bail!("No such To Do list: {}", default.unwrap_or("(default)")),
+    }
+
+    for remote in &remotes {
+        super::run(remote)?;
+    }
+    Ok(())
+}
+
+struct Graph<'a> {
+    token: &'a str,
+}
+
+impl Graph<'_> {
+    fn call(&self, method: &str, url: &str, body: Option<&Value>) -> anyhow::Result<Value> {
+        let mut headers = vec![format!("Authorization: Bearer {}", self.token)];
+        if body.is_some() {
+            headers.push("Content-Type: application/json".to_string());
+        }
+        let body = body.map(|b| b.to_string());
+        let response = http::request(method, url, &headers, body.as_deref())?;
+        if !response.is_success() {
+            let reply = response.json().unwrap_or(Value::Null);
+            bail!(
+                "Microsoft Graph answered {}: {}",
+                response.status,
+                reply.get("error").get("message").as_str().unwrap_or("")
+            );
+        }
+        if response.body.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        response.json()
+    }
+}
+
+struct MsTodo<'a> {
+    graph: Graph<'a>,
+    list: String,
+    title: String,
+    /// Tag of the TODOs this list is for.
+    tag: Option<String>,
+    /// For the unmapped list: tags whose TODOs go to other lists.
+    other_tags: Vec<String>,
+}
+
+impl<'a> MsTodo<'a> {
+    fn new(token: &'a str, list: &Value, tag: Option<String>, other_tags: Vec<String>) -> Self {
+        MsTodo {
+            graph: Graph { token },
+            list: list.get("id").as_str().unwrap_or("").to_string(),
+            title: list.get("displayName").as_str().unwrap_or("").to_string(),
+            tag,
+            other_tags,
+        }
+    }
+
+    fn tasks_url(&self) -> String {
+        format!("{}/lists/{}/tasks", API, http::encode(&self.list))
+    }
+
+    fn body(task: &Snapshot) -> Value {
+        Value::object(vec![
+            ("title", task.title.as_str().into()),
+            (
+                "dueDateTime",
+                match task.due {
+                    Some(d) => Value::object(vec![
+                        ("dateTime", format!("{}T00:00:00", d).as_str().into()),
+                        ("timeZone", "UTC".into()),
+                    ]),
+                    None => Value::Null,
+                },
+            ),
+            (
+                "status",
+                if task.done { "completed" } else { "notStarted" }.into(),
+            ),
+        ])
+    }
+}
+
+impl super::Remote for MsTodo<'_> {
+    fn key(&self) -> String {
+        format!("mstodo {}", self.list)
+    }
+
+    fn name(&self) -> String {
+        format!("Microsoft To Do \"{}\"", self.title)
+    }
+
+    fn list(&self) -> anyhow::Result<Vec<RemoteTask>> {
+        let mut tasks = vec![];
+        let mut url = self.tasks_url();
+        loop {
+            let reply = self.graph.call("GET", &url, None)?;
+            for item in reply.get("value").items() {
+                let Some(id) = item.get("id").as_str() else {
+                    continue;
+                };
+                tasks.push(RemoteTask {
+                    id: id.to_string(),
+                    task: Snapshot {
+                        title: item.get("title").as_str().unwrap_or("").to_string(),
+                        due: item
+                            .get("dueDateTime")
+                            .get("dateTime")
+                            .as_str()
+                            .and_then(|d| Date::parse(d.get(..10)?)),
+                        done: item.get("status").as_str() == Some("completed"),
+                    },
+                });
+            }
+            match reply.get("@odata.nextLink").as_str() {
+                Some(next) => url = next.to_string(),
+                None => return Ok(tasks),
+            }
+        }
+    }
+
+    fn create(&self, task: &Snapshot) -> anyhow::Result<String> {
+        let reply = self
+            .graph
+            .call("POST", &self.tasks_url(), Some(&Self::body(task)))?;
+        match reply.get("id").as_str() {
+            Some(id) => Ok(id.to_string()),
+            None => bail!("Microsoft Graph did not return an ID"),
+        }
+    }
+
+    fn update(&self, id: &str, task: &Snapshot) -> anyhow::Result<()> {
+        let url = format!("{}/{}", self.tasks_url(), http::encode(id));
+        self.graph
+            .call("PATCH", &url, Some(&Self::body(task)))
+            .map(|_| ())
+    }
+
+    fn delete(&self, id: &str) -> anyhow::Result<()> {
+        let url = format!("{}/{}", self.tasks_url(), http::encode(id));
+        self.graph.call("DELETE", &url, None).map(|_| ())
+    }
+
+    fn owns(&self, todo: &Todo) -> bool {
+        match &self.tag {
+            Some(tag) => todo.tags.contains(tag),
+            None => !todo.tags.iter().any(|t| self.other_tags.contains(t)),
+        }
+    }
+
+    fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+}
@@ -0,0 +1,197 @@
+//! The requests of a sync to a service, see `run`: paced to
+//! `sync.requests_per_second`, 5 unless set, so as not to run into rate
+//! limits, and retried with exponential backoff when they fail. When
+//! they keep failing, as when offline, the service is taken to be out of
+//! reach, and the changes left to push are queued instead, in
+//! `<list>.sync-queue`, for `todo sync status` to show.
+//!
+//! The next sync picks them up again from the links, as it does all
+//! changes, and empties the queue once nothing failed. What did go
+//! through before is kept, so a sync cut short resumes where it stopped.
+
+use crate::json::{self, Value};
+use crate::{config, date, log, Todos};
+use seahorse::Command;
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const REQUESTS_PER_SECOND: u64 = 5;
+/// How often to try a request before giving up on the service.
+const ATTEMPTS: u32 = 4;
+const FIRST_DELAY: Duration = Duration::from_secs(1);
+
+fn path() -> String {
+    format!("{}.sync-queue", Todos::todo_path())
+}
+
+fn read() -> Vec<(String, Value)> {
+    let entries = fs::read_to_string(path())
+        .ok()
+        .and_then(|s| json::parse(&s).ok());
+    match entries {
+        Some(Value::Object(entries)) => entries,
+        _ => vec![],
+    }
+}
+
+pub struct Requests {
+    key: String,
+    name: String,
+    interval: Duration,
+    last: Option<Instant>,
+    /// Why the service could not be reached, once it could not.
+    error: Option<String>,
+    pending: Vec<String>,
+}
+
+impl Requests {
+    /// The requests for the list of `key`, or all of a service's.
+    pub fn new(key: &str, name: &str) -> Self {
+        let rate = config::get()
+            .string("sync.requests_per_second")
+            .and_then(|r| r.parse().ok())
+            .filter(|&r| r > 0)
+            .unwrap_or(REQUESTS_PER_SECOND);
+        Requests {
+            key: key.to_string(),
+            name: name.to_string(),
+            interval: Duration::from_millis(1000 / rate),
+            last: None,
+            error: None,
+            pending: vec![],
+        }
+    }
+
+    /// The result of `request`, or `None` when it failed, in which case
+    /// `what` is queued.
+    pub fn send<T>(&mut self, what: String, request: impl Fn() -> anyhow::Result<T>) -> Option<T> {
+        let result = match self.error {
+            Some(_) => None,
+            None => self.call(request).ok(),
+        };
+        if result.is_none() {
+            self.pending.push(what);
+        }
+        result
+    }
+
+    /// The result of `request`, tried until it succeeds or it is time to
+    /// give up on the service.
+    pub fn call<T>(&mut self, request: impl Fn() -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let (mut attempt, mut delay) = (1, FIRST_DELAY);
+        loop {
+            if let Some(wait) = self
+                .last
+                .and_then(|l| self.interval.checked_sub(l.elapsed()))
+            {
+                thread::sleep(wait);
+            }
+            self.last = Some(Instant::now());
+            match request() {
+                Ok(result) => return Ok(result),
+                Err(e) if attempt == ATTEMPTS => {
+                    log::warn("sync", || {
+                        format!("unreachable name={:?} error={:?}", self.name, e.to_string())
+                    });
+                    self.error = Some(e.to_string());
+                    return Err(e);
+                }
+                Err(e) => {
+                    log::info("sync", || {
+                        let retry_ms = delay.as_millis();
+                        format!(
+                            "retrying name={:?} retry_ms={} error={:?}",
+                            self.name,
+                            retry_ms,
+                            e.to_string()
+                        )
+                    });
+                    thread::sleep(delay);
+                    (attempt, delay) = (attempt + 1, delay * 2);
+                }
+            }
+        }
+    }
+
+    /// Take the service to be out of reach.
+    pub fn fail(&mut self, error: &anyhow::Error) {
+        self.error = Some(error.to_string());
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Queue `what` without trying, since the service is out of reach.
+    pub fn queue(&mut self, what: String) {
+        self.pending.push(what);
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Record what is still pending, or that nothing is.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let mut entries = read();
+        let since = entries
+            .iter()
+            .find(|(k, _)| *k == self.key)
+            .and_then(|(_, e)| e.get("since").as_f64())
+            .unwrap_or(date::now() as f64);
+        // A list's requests going through also answers for its service.
+        let service = self.key.split(' ').next().unwrap_or_default();
+        entries.retain(|(k, _)| *k != self.key && *k != service);
+        if !self.pending.is_empty() {
+            let entry = Value::object(vec![
+                ("name", self.name.as_str().into()),
+                ("since", Value::Number(since)),
+                ("error", self.error.as_deref().unwrap_or("").into()),
+                (
+                    "pending",
+                    Value::Array(self.pending.iter().map(|p| p.as_str().into()).collect()),
+                ),
+            ]);
+            entries.push((self.key.clone(), entry));
+        }
+        match entries.is_empty() {
+            true => match fs::remove_file(path()) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            },
+            false => Ok(fs::write(path(), Value::Object(entries).to_string())?),
+        }
+    }
+}
+
+pub fn status_command() -> Command {
+    Command::new("status")
+        .description("Show the changes waiting for a service to be reachable")
+        .usage("todo sync status")
+        .action(|_| {
+            let entries = read();
+            if entries.is_empty() {
+                println!("Nothing is waiting to be synced");
+            }
+            for (_, entry) in &entries {
+                let pending = entry.get("pending").items();
+                println!(
+                    "{}: {} pending since {}",
+                    entry.get("name").as_str().unwrap_or(""),
+                    pending.len(),
+                    date::format_timestamp(entry.get("since").as_f64().unwrap_or(0.0) as u64)
+                );
+                if let Some(error) = entry.get("error").as_str().filter(|e| !e.is_empty()) {
+                    println!("  last error: {}", error);
+                }
+                for what in pending {
+                    println!("  {}", what.as_str().unwrap_or(""));
+                }
+            }
+        })
+}
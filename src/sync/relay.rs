@@ -0,0 +1,253 @@
+//! Sync between devices through a relay, a server that passes on what it
+//! is given without being able to read it:
+//!
+//! ```sh
+//! export TODO_RELAY_KEY='correct horse battery staple'
+//! todo sync relay --server https://relay.example.com --watch
+//! ```
+//!
+//! The devices share the CRDT of `file`, encrypted with GnuPG under the
+//! key before it leaves each of them. The relay only sees ciphertext, on
+//! a channel named after a hash of the key, so all devices with the same
+//! key sync with each other. The key comes from `$TODO_RELAY_KEY` or the
+//! keyring, as `todo-relay` for the relay's host.
+//!
+//! With `--watch`, the sync keeps running and syncs again as soon as the
+//! list changes here or another device uploads, which the relay tells by
+//! answering a request it held open. The relay speaks plain HTTP, see
+//! `relay` for `todo serve --relay`; `ws://` and `wss://` URLs are taken
+//! to mean `http://` and `https://`.
+
+use super::file;
+use crate::crdt::{self, Changes, Replica, State};
+use crate::{auth, config, crypt, dry_run, http, lock, sha256, Todos};
+use anyhow::{bail, Context as _};
+use seahorse::{Command, Context, Flag, FlagType};
+use std::fs;
+use std::process::exit;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// How often to look at the list for changes with `--watch`.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How often to start over when other devices keep uploading first.
+const ATTEMPTS: usize = 5;
+
+pub fn command() -> Command {
+    Command::new("relay")
+        .description("Sync with other devices through an end-to-end encrypted relay")
+        .usage("todo sync relay [--server <url>] [--watch]")
+        .flag(
+            Flag::new("server", FlagType::String)
+                .description("The relay, if not relay.server in the config"),
+        )
+        .flag(
+            Flag::new("watch", FlagType::Bool)
+                .description("Keep running, syncing whenever something changed"),
+        )
+        .action(|c| {
+            if let Err(e) = relay_action(c) {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        })
+}
+
+fn relay_action(c: &Context) -> anyhow::Result<()> {
+    let channel = Channel::open(c)?;
+    let (version, changes) = sync(&channel)?;
+    report(&channel, &changes, true);
+    if !c.bool_flag("watch") {
+        return Ok(());
+    }
+
+    let path = Todos::todo_path();
+    let stat = || {
+        let metadata = fs::metadata(&path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    };
+    let uploaded = watch_relay(channel.clone(), version);
+    eprintln!("Watching {} and {} (Ctrl-C to stop)", path, channel.host());
+    loop {
+        // Others must be able to change the list in the meantime.
+        lock::release();
+        let seen = stat();
+        while stat() == seen && uploaded.try_recv().is_err() {
+            thread::sleep(POLL_INTERVAL);
+        }
+        while uploaded.try_recv().is_ok() {}
+        match sync(&channel) {
+            Ok((_, changes)) => report(&channel, &changes, false),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+/// Sync once, answering the version of the state on the relay after.
+fn sync(channel: &Channel) -> anyhow::Result<(u64, Changes)> {
+    let list = Todos::todo_path();
+    let mut todos = Todos::read();
+    let mut replica = Replica::load(&list)?.unwrap_or_else(Replica::new);
+    replica.observe(&todos, crdt::now());
+
+    let mut attempts = 0;
+    let version = loop {
+        let (version, theirs) = channel.state()?;
+        replica.state.join(&theirs);
+        if replica.state == theirs || dry_run::is_enabled() {
+            break version;
+        }
+        if let Some(version) = channel.upload(version, &replica.state)? {
+            break version;
+        }
+        attempts += 1;
+        if attempts == ATTEMPTS {
+            bail!("Other devices kept uploading first, please try again");
+        }
+    };
+
+    let changes = replica.apply(&mut todos);
+    if !dry_run::is_enabled() {
+        file::keep(&list, &mut todos, &replica, &changes)?;
+    }
+    Ok((version, changes))
+}
+
+/// Print what a sync did; with `always` also when it did nothing.
+fn report(channel: &Channel, changes: &Changes, always: bool) {
+    let deleted = changes.deleted.len();
+    if !always && changes.added + changes.updated + deleted == 0 {
+        return;
+    }
+    println!(
+        "{} {}: {} added, {} updated, {} deleted",
+        if dry_run::is_enabled() {
+            "Would sync through"
+        } else {
+            "Synced through"
+        },
+        channel.host(),
+        changes.added,
+        changes.updated,
+        deleted
+    );
+}
+
+/// Wait on the relay for uploads newer than `version`, in the
+/// background, sending on the returned receiver for each.
+fn watch_relay(channel: Channel, mut version: u64) -> mpsc::Receiver<()> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || loop {
+        match channel.download(Some(version)) {
+            Ok(Some((newer, _))) => {
+                version = newer;
+                if sender.send(()).is_err() {
+                    return;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("{}", e);
+                thread::sleep(Duration::from_secs(10));
+            }
+        }
+    });
+    receiver
+}
+
+/// Where on the relay the devices sharing a key meet.
+#[derive(Clone)]
+struct Channel {
+    url: String,
+    key: String,
+}
+
+impl Channel {
+    fn open(c: &Context) -> anyhow::Result<Self> {
+        let server = match c.string_flag("server") {
+            Ok(server) => server,
+            Err(_) => match config::get().string("relay.server") {
+                Some(server) => server.to_string(),
+                None => bail!(
+                    "Please specify --server or set relay.server in {}",
+                    config::Config::path()
+                ),
+            },
+        };
+        let server = match server.split_once("://") {
+            Some(("wss", rest)) => format!("https://{}", rest),
+            Some(("ws", rest)) => format!("http://{}", rest),
+            _ => server,
+        };
+        let Some(key) = auth::secret("relay", &http::host(&server)) else {
+            bail!("Please set $TODO_RELAY_KEY or store a key with todo auth set relay");
+        };
+        let name = sha256::hex(&sha256::hmac(key.as_bytes(), b"todo relay channel"));
+        Ok(Channel {
+            url: format!("{}/v1/{}", server.trim_end_matches('/'), name),
+            key,
+        })
+    }
+
+    fn host(&self) -> String {
+        http::host(&self.url)
+    }
+
+    /// The version on the relay and its upload, encrypted. With `after`,
+    /// the relay waits for a newer one, and `None` is when none came.
+    fn download(&self, after: Option<u64>) -> anyhow::Result<Option<(u64, String)>> {
+        let url = match after {
+            Some(version) => format!("{}?after={}", self.url, version),
+            None => self.url.clone(),
+        };
+        let response = http::request("GET", &url, &[], None)?;
+        match response.status {
+            200 => {}
+            204 => return Ok(None),
+            status => bail!("{} answered {}", self.host(), status),
+        }
+        let (version, upload) = response
+            .body
+            .split_once('\n')
+            .unwrap_or((&response.body, ""));
+        let version = version
+            .parse()
+            .with_context(|| format!("{} is not a todo relay", self.host()))?;
+        Ok(Some((version, upload.to_string())))
+    }
+
+    /// The state on the relay and its version.
+    fn state(&self) -> anyhow::Result<(u64, State)> {
+        let Some((version, upload)) = self.download(None)? else {
+            bail!("{} is not a todo relay", self.host());
+        };
+        if upload.is_empty() {
+            return Ok((version, State::default()));
+        }
+        let content = crypt::decrypt_with_passphrase(&upload, &self.key).with_context(|| {
+            format!(
+                "Failed to decrypt the state on {}, is the key the one of the other devices?",
+                self.host()
+            )
+        })?;
+        Ok((version, State::parse(&content)?))
+    }
+
+    /// Upload `state` in place of `version`, answering the new version,
+    /// or `None` when another device uploaded first.
+    fn upload(&self, version: u64, state: &State) -> anyhow::Result<Option<u64>> {
+        let upload = crypt::encrypt_with_passphrase(&state.content(), &self.key)?;
+        let headers = [format!("If-Match: {}", version)];
+        let response = http::request("PUT", &self.url, &headers, Some(&upload))?;
+        match response.status {
+            412 => Ok(None),
+            _ if response.is_success() => {
+                Ok(Some(response.body.trim().parse().with_context(|| {
+                    format!("{} is not a todo relay", self.host())
+                })?))
+            }
+            status => bail!("{} answered {}", self.host(), status),
+        }
+    }
+}
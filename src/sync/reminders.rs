@@ -0,0 +1,169 @@
+//! Apple Reminders, through JavaScript for Automation run by `osascript`,
+//! so TODOs captured with Siri show up here. Needs the `reminders` feature
+//! and macOS, which asks once to allow the terminal to control Reminders.
+//!
+//! ```toml
+//! [reminders]
+//! list = "Reminders"      # default: the default list
+//! ```
+
+use super::{RemoteTask, Snapshot};
+use crate::config;
+use crate::date::Date;
+use crate::json::{self, Value};
+use anyhow::bail;
+use seahorse::Command;
+use std::process::exit;
+
+/// Each script gets the list name and its input as JSON and prints JSON.
+const PRELUDE: &str = "
+function run(argv) {
+    const app = Application('Reminders');
+    const list = argv[0] ? app.lists.byName(argv[0]) : app.defaultList();
+    const input = JSON.parse(argv[1]);
+    const day = (s) => { const [y, m, d] = s.split('-').map(Number); return new Date(y, m - 1, d); };
+    return JSON.stringify(main(app, list, input, day));
+}
+";
+
+const LIST: &str = "
+function main(app, list) {
+    const pad = (n) => String(n).padStart(2, '0');
+    const date = (d) => d ? d.getFullYear() + '-' + pad(d.getMonth() + 1) + '-' + pad(d.getDate()) : null;
+    const r = list.reminders;
+    const [ids, names, dues, done] = [r.id(), r.name(), r.dueDate(), r.completed()];
+    return ids.map((id, i) => ({ id, title: names[i], due: date(dues[i]), done: done[i] }));
+}
+";
+
+const CREATE: &str = "
+function main(app, list, task, day) {
+    const props = { name: task.title, completed: task.done };
+    if (task.due) props.dueDate = day(task.due);
+    const reminder = app.Reminder(props);
+    list.reminders.push(reminder);
+    return reminder.id();
+}
+";
+
+const UPDATE: &str = "
+function main(app, list, task, day) {
+    const reminder = list.reminders.byId(task.id);
+    reminder.name = task.title;
+    reminder.dueDate = task.due ? day(task.due) : null;
+    reminder.completed = task.done;
+    return null;
+}
+";
+
+const DELETE: &str = "
+function main(app, list, task) {
+    app.delete(list.reminders.byId(task.id));
+    return null;
+}
+";
+
+pub fn command() -> Command {
+    Command::new("reminders")
+        .description("Two-way sync with Apple Reminders")
+        .usage("todo sync reminders")
+        .action(|_| {
+            let list = config::get().string("reminders.list").unwrap_or("");
+            let remote = Reminders {
+                list: list.to_string(),
+            };
+            if let Err(e) = super::run(&remote) {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        })
+}
+
+struct Reminders {
+    /// Empty for the default list.
+    list: String,
+}
+
+impl Reminders {
+    fn input(id: Option<&str>, task: &Snapshot) -> Value {
+        let mut pairs = vec![
+            ("title", task.title.as_str().into()),
+            (
+                "due",
+                match task.due {
+                    Some(d) => d.to_string().as_str().into(),
+                    None => Value::Null,
+                },
+            ),
+            ("done", task.done.into()),
+        ];
+        pairs.extend(id.map(|id| ("id", id.into())));
+        Value::object(pairs)
+    }
+}
+
+/// Run `body` after the prelude and parse what it prints.
+fn script(list: &str, body: &str, input: &Value) -> anyhow::Result<Value> {
+    let output = std::process::Command::new("osascript")
+        .args(["-l", "JavaScript", "-e", &format!("{}{}", PRELUDE, body)])
+        .arg(list)
+        .arg(input.to_string())
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "Reminders failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    json::parse(String::from_utf8_lossy(&output.stdout).trim())
+}
+
+impl super::Remote for Reminders {
+    fn key(&self) -> String {
+        match self.list.as_str() {
+            "" => "reminders default".to_string(),
+            list => format!("reminders {}", list),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self.list.as_str() {
+            "" => "Apple Reminders".to_string(),
+            list => format!("Apple Reminders \"{}\"", list),
+        }
+    }
+
+    fn list(&self) -> anyhow::Result<Vec<RemoteTask>> {
+        let reply = script(&self.list, LIST, &Value::Null)?;
+        Ok(reply
+            .items()
+            .iter()
+            .filter_map(|t| {
+                Some(RemoteTask {
+                    id: t.get("id").as_str()?.to_string(),
+                    task: Snapshot {
+                        title: t.get("title").as_str().unwrap_or("").to_string(),
+                        due: t.get("due").as_str().and_then(Date::parse),
+                        done: t.get("done") == &Value::Bool(true),
+                    },
+                })
+            })
+            .collect())
+    }
+
+    fn create(&self, task: &Snapshot) -> anyhow::Result<String> {
+        match script(&self.list, CREATE, &Self::input(None, task))?.as_str() {
+            Some(id) => Ok(id.to_string()),
+            None => bail!("Reminders did not return an ID"),
+        }
+    }
+
+    fn update(&self, id: &str, task: &Snapshot) -> anyhow::Result<()> {
+        script(&self.list, UPDATE, &Self::input(Some(id), task)).map(|_| ())
+    }
+
+    fn delete(&self, id: &str) -> anyhow::Result<()> {
+        let input = Value::object(vec![("id", Value::from(id))]);
+        script(&self.list, DELETE, &input).map(|_| ())
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{collate, tag_name, Todos};
+use seahorse::{Command, Context};
+use std::collections::HashMap;
+use std::process::exit;
+
+pub fn tag_command() -> Command {
+    Command::new("tag")
+        .description("Show, rename or remove tags across all TODOs")
+        .usage("todo tag [list | rename <old> <new> | rm <tag>]")
+        .action(list_action)
+        .command(
+            Command::new("list")
+                .description("Show the tags with how many TODOs have them")
+                .usage("todo tag list")
+                .alias("ls")
+                .action(list_action),
+        )
+        .command(
+            Command::new("rename")
+                .description("Rename a tag on every TODO, merging it into the new one if it exists")
+                .usage("todo tag rename <old> <new>")
+                .alias("mv")
+                .action(|c| {
+                    let (old, new) = match c.args.as_slice() {
+                        [old, new] => (tag_name(old), tag_name(new)),
+                        _ => {
+                            eprintln!("Please specify the old and the new tag");
+                            exit(1);
+                        }
+                    };
+                    if new.is_empty() {
+                        eprintln!("Please specify the new tag");
+                        exit(1);
+                    }
+                    let changed = retag(&old, Some(&new));
+                    println!("Renamed #{} to #{} on {} TODOs", old, new, changed);
+                }),
+        )
+        .command(
+            Command::new("rm")
+                .description("Remove a tag from every TODO")
+                .usage("todo tag rm <tag>")
+                .alias("remove")
+                .action(|c| {
+                    let tag = match c.args.as_slice() {
+                        [tag] => tag_name(tag),
+                        _ => {
+                            eprintln!("Please specify one tag");
+                            exit(1);
+                        }
+                    };
+                    let changed = retag(&tag, None);
+                    println!("Removed #{} from {} TODOs", tag, changed);
+                }),
+        )
+}
+
+fn list_action(_: &Context) {
+    let todos = Todos::read();
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for todo in &todos.records {
+        for tag in &todo.tags {
+            let (all, open) = counts.entry(tag).or_default();
+            *all += 1;
+            if todo.done.is_empty() {
+                *open += 1;
+            }
+        }
+    }
+    if counts.is_empty() {
+        println!("No tags");
+        return;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(a, (a_all, _)), (b, (b_all, _))| {
+        b_all.cmp(a_all).then_with(|| collate::compare(a, b))
+    });
+    let width = counts.iter().map(|(t, _)| t.len() + 1).max().unwrap_or(0);
+    for (tag, (all, open)) in counts {
+        println!(
+            "{:<width$}  {} ({} open)",
+            format!("#{}", tag),
+            all,
+            open,
+            width = width
+        );
+    }
+}
+
+/// Replace `old` with `new` on every TODO that has it, or remove it when
+/// `new` is `None`. Returns how many TODOs changed.
+fn retag(old: &str, new: Option<&str>) -> usize {
+    let mut todos = Todos::read();
+    let mut changed = 0;
+    for todo in todos.records.iter_mut() {
+        let Some(i) = todo.tags.iter().position(|t| t == old) else {
+            continue;
+        };
+        match new {
+            Some(new) if !todo.tags.iter().any(|t| t == new) => todo.tags[i] = new.to_string(),
+            _ => {
+                todo.tags.remove(i);
+            }
+        }
+        changed += 1;
+    }
+    if changed == 0 {
+        eprintln!("No TODO has the tag #{}", old);
+        exit(1);
+    }
+    todos.save().unwrap();
+    changed
+}
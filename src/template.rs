@@ -0,0 +1,397 @@
+//! Templates for `todo list --format`, one line per TODO:
+//!
+//! ```sh
+//! todo list --format '{id:>3}\t{title:<30.30} {?due:({due:%b %d})} {tags}'
+//! ```
+//!
+//! `{field}` is any field `todo get` knows, with `tags` as `#tag`s and
+//! the URLs on one line, and `{field|text}` has `text` when it is empty.
+//! After a `:`, `<`, `>` or `^` and a width pad it to the left, right or
+//! center, `.` and a width cuts it there, and a spec with `%` formats a
+//! date: `%Y`, `%y`, `%m`, `%d`, `%e`, `%b`, `%B`, `%a`, `%A`, `%H`, `%M`
+//! and `%%`. `{?field:...}` is the template after the `:`, only when the
+//! field has a value. `\t`, `\n`, `{{` and `}}` stand for a tab, a line
+//! break and braces.
+
+use crate::date::{self, Date};
+use crate::{fields, Todo, Todos};
+use anyhow::bail;
+use std::iter::Peekable;
+use std::str::Chars;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Default)]
+struct Spec {
+    align: Option<Align>,
+    width: usize,
+    max: Option<usize>,
+    date: Option<String>,
+}
+
+#[derive(Debug)]
+enum Part {
+    Text(String),
+    Field {
+        name: String,
+        default: String,
+        spec: Spec,
+    },
+    If {
+        name: String,
+        body: Vec<Part>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Template(Vec<Part>);
+
+impl Template {
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let mut chars = s.chars().peekable();
+        let parts = parse_parts(&mut chars, false)?;
+        Ok(Template(parts))
+    }
+
+    /// Fails for fields none of `records` has and that are neither a
+    /// column nor declared, which are most likely typos, like `sort` does.
+    pub fn check(&self, records: &[Todo]) -> anyhow::Result<()> {
+        let headers = Todos::default_headers();
+        for name in names(&self.0) {
+            let known = headers.iter().any(|h| h == name)
+                || ["due", "tag", "tags", "url", "urls"].contains(&name)
+                || fields::find_declared(name).is_some()
+                || records.iter().any(|r| fields::raw(r, name).is_some());
+            if !known {
+                bail!("Unknown field {{{}}} in the format, no TODO has it", name);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn render(&self, todo: &Todo) -> String {
+        render(&self.0, todo)
+    }
+}
+
+/// The fields `parts` use, conditionals included.
+fn names(parts: &[Part]) -> Vec<&str> {
+    parts
+        .iter()
+        .flat_map(|part| match part {
+            Part::Text(_) => vec![],
+            Part::Field { name, .. } => vec![name.as_str()],
+            Part::If { name, body } => {
+                let mut names = names(body);
+                names.insert(0, name.as_str());
+                names
+            }
+        })
+        .collect()
+}
+
+/// The parts up to the end, or up to the `}` closing a conditional when
+/// `nested`, which is taken too.
+fn parse_parts(chars: &mut Peekable<Chars>, nested: bool) -> anyhow::Result<Vec<Part>> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('t') => text.push('\t'),
+                Some('n') => text.push('\n'),
+                Some(other) => text.push(other),
+                None => text.push('\\'),
+            },
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '}' if nested => {
+                if !text.is_empty() {
+                    parts.push(Part::Text(text));
+                }
+                return Ok(parts);
+            }
+            '}' => bail!("Unopened }} in the format, write }}}} for a brace"),
+            '{' => {
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(parse_field(chars)?);
+            }
+            c => text.push(c),
+        }
+    }
+    if nested {
+        bail!("Unclosed {{? in the format");
+    }
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    Ok(parts)
+}
+
+/// What is between `{` and `}`, taking the `}`.
+fn parse_field(chars: &mut Peekable<Chars>) -> anyhow::Result<Part> {
+    if chars.peek() == Some(&'?') {
+        chars.next();
+        let name: String =
+            std::iter::from_fn(|| chars.next_if(|&c| c != ':' && c != '}')).collect();
+        if chars.next() != Some(':') {
+            bail!("Expected {{?{}:...}} in the format", name);
+        }
+        let body = parse_parts(chars, true)?;
+        return Ok(Part::If {
+            name: name.trim().to_string(),
+            body,
+        });
+    }
+    let mut inside = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) => inside.push(c),
+            None => bail!("Unclosed {{ in the format"),
+        }
+    }
+    let (field, spec) = inside.split_once(':').unwrap_or((&inside, ""));
+    let (name, default) = field.split_once('|').unwrap_or((field, ""));
+    if name.trim().is_empty() {
+        bail!("Empty {{}} in the format, write {{{{ for a brace");
+    }
+    Ok(Part::Field {
+        name: name.trim().to_string(),
+        default: default.to_string(),
+        spec: parse_spec(spec)?,
+    })
+}
+
+fn parse_spec(s: &str) -> anyhow::Result<Spec> {
+    let mut spec = Spec::default();
+    if s.contains('%') {
+        spec.date = Some(s.to_string());
+        return Ok(spec);
+    }
+    let mut rest = s;
+    spec.align = match rest.chars().next() {
+        Some('<') => Some(Align::Left),
+        Some('>') => Some(Align::Right),
+        Some('^') => Some(Align::Center),
+        _ => None,
+    };
+    if spec.align.is_some() {
+        rest = &rest[1..];
+    }
+    let (width, max) = rest.split_once('.').unwrap_or((rest, ""));
+    let number = |n: &str| {
+        n.parse::<usize>()
+            .map_err(|_| anyhow::anyhow!("Invalid spec :{} in the format", s))
+    };
+    if !width.is_empty() {
+        spec.width = number(width)?;
+    }
+    if !max.is_empty() {
+        spec.max = Some(number(max)?);
+    }
+    Ok(spec)
+}
+
+fn render(parts: &[Part], todo: &Todo) -> String {
+    let mut out = String::new();
+    for part in parts {
+        match part {
+            Part::Text(text) => out += text,
+            Part::Field {
+                name,
+                default,
+                spec,
+            } => {
+                let value = value(todo, name);
+                let value = if value.is_empty() {
+                    default.clone()
+                } else {
+                    match &spec.date {
+                        Some(format) => format_date(&value, format),
+                        None => value,
+                    }
+                };
+                out += &fit(&value, spec);
+            }
+            Part::If { name, body } => {
+                if !value(todo, name).is_empty() {
+                    out += &render(body, todo);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn value(todo: &Todo, field: &str) -> String {
+    match field {
+        "tags" | "tag" => {
+            let tags: Vec<String> = todo.tags.iter().map(|t| format!("#{}", t)).collect();
+            tags.join(" ")
+        }
+        "url" | "urls" => todo.urls.join(" "),
+        "title" => todo.title.replace('\n', " "),
+        field => fields::raw(todo, field).unwrap_or_default(),
+    }
+}
+
+/// A stored date, or a timestamp like `created_at`, in `format`; other
+/// values as they are.
+fn format_date(value: &str, format: &str) -> String {
+    let value = match value.parse::<u64>() {
+        Ok(ts) => date::format_timestamp(ts),
+        Err(_) => value.to_string(),
+    };
+    let Some(day) = Date::parse(&value) else {
+        return value;
+    };
+    let (hours, minutes) = date::time_of(&value).unwrap_or((0, 0));
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let month = MONTHS[day.month as usize - 1];
+        let weekday = WEEKDAYS[day.weekday() as usize];
+        match chars.next() {
+            Some('Y') => out += &format!("{:04}", day.year),
+            Some('y') => out += &format!("{:02}", day.year % 100),
+            Some('m') => out += &format!("{:02}", day.month),
+            Some('d') => out += &format!("{:02}", day.day),
+            Some('e') => out += &format!("{:>2}", day.day),
+            Some('b') => out += &month[..3],
+            Some('B') => out += month,
+            Some('a') => out += &weekday[..3],
+            Some('A') => out += weekday,
+            Some('H') => out += &format!("{:02}", hours),
+            Some('M') => out += &format!("{:02}", minutes),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+/// `value` cut to the `max` of `spec` and padded to its width, both in
+/// terminal columns.
+fn fit(value: &str, spec: &Spec) -> String {
+    let mut value = value.to_string();
+    if let Some(max) = spec.max {
+        if value.width() > max {
+            let mut cut = String::new();
+            for c in value.chars() {
+                if cut.width() + c.width().unwrap_or(0) > max {
+                    break;
+                }
+                cut.push(c);
+            }
+            value = cut;
+        }
+    }
+    let pad = spec.width.saturating_sub(value.width());
+    match spec.align.unwrap_or(Align::Left) {
+        Align::Left => format!("{}{}", value, " ".repeat(pad)),
+        Align::Right => format!("{}{}", " ".repeat(pad), value),
+        Align::Center => format!(
+            "{}{}{}",
+            " ".repeat(pad / 2),
+            value,
+            " ".repeat(pad - pad / 2)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo() -> Todo {
+        let mut todo = Todo::new(
+            "7".to_string(),
+            "2026-10-16 09:30".to_string(),
+            "Pay rent".to_string(),
+            vec![],
+            "".to_string(),
+        );
+        todo.tags = vec!["bill".to_string(), "home".to_string()];
+        todo
+    }
+
+    fn render(template: &str, todo: &Todo) -> String {
+        Template::parse(template).unwrap().render(todo)
+    }
+
+    #[test]
+    fn renders_fields_with_specs() {
+        let t = todo();
+        assert_eq!(
+            render("{id:>3}\\t{title:<10}|{tags}", &t),
+            "  7\tPay rent  |#bill #home"
+        );
+        assert_eq!(render("{due:%a %b %e, %H:%M}", &t), "Fri Oct 16, 09:30");
+        assert_eq!(render("{title:.3}{{x}}", &t), "Pay{x}");
+        assert_eq!(render("{assignee|nobody} {start:^5}!", &t), "nobody      !");
+    }
+
+    #[test]
+    fn conditionals_need_a_value() {
+        let mut t = todo();
+        assert_eq!(
+            render("{title}{? due: (due {due:%m/%d})}", &t),
+            "Pay rent (due 10/16)"
+        );
+        t.date.clear();
+        assert_eq!(render("{title}{? due: (due {due:%m/%d})}", &t), "Pay rent");
+        assert!(Template::parse("{title").is_err());
+        assert!(Template::parse("{?due: x").is_err());
+        assert!(Template::parse("}").is_err());
+        assert!(Template::parse("{id:x}").is_err());
+        let template = Template::parse("{title} {?nope:{due}}").unwrap();
+        assert!(template.check(&[t]).is_err());
+    }
+}
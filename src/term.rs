@@ -0,0 +1,498 @@
+use crate::config;
+use cli_table::ColorChoice;
+use std::io::{Read, Write};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+pub fn is_tty() -> bool {
+    // SAFETY: isatty only inspects the descriptor.
+    unsafe { libc::isatty(libc::STDIN_FILENO) == 1 }
+}
+
+pub fn is_stdout_tty() -> bool {
+    // SAFETY: isatty only inspects the descriptor.
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+/// Whether tables use color, from `display.color`: `always`, `never`, or
+/// by default `auto`, which leaves it out when output is not a terminal
+/// or `$NO_COLOR` is set.
+pub fn color_choice() -> ColorChoice {
+    match config::get().string("display.color") {
+        Some("always") => ColorChoice::Always,
+        Some("never") => ColorChoice::Never,
+        _ if is_stdout_tty() => ColorChoice::Auto,
+        _ => ColorChoice::Never,
+    }
+}
+
+/// The grapheme clusters of `text`, as far as tables need them: a
+/// character with the marks, variation selectors and skin tones after
+/// it, emoji joined by a zero width joiner, and pairs of regional
+/// indicators, which make a flag.
+pub fn clusters(text: &str) -> Vec<&str> {
+    let mut clusters = vec![];
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    let mut flag = false;
+    for (i, c) in text.char_indices() {
+        let regional = ('\u{1f1e6}'..='\u{1f1ff}').contains(&c);
+        let joins = match previous {
+            None => false,
+            Some('\u{200d}') => true,
+            Some(_) if regional => flag,
+            Some(_) => {
+                c.width() == Some(0)
+                    || ('\u{1f3fb}'..='\u{1f3ff}').contains(&c)
+                    || ('\u{e0020}'..='\u{e007f}').contains(&c)
+            }
+        };
+        if !joins && i > 0 {
+            clusters.push(&text[start..i]);
+            start = i;
+        }
+        flag = regional && !(joins && flag);
+        previous = Some(c);
+    }
+    if start < text.len() {
+        clusters.push(&text[start..]);
+    }
+    clusters
+}
+
+/// Columns a cluster takes: those of its first character, and two for
+/// emoji drawn as pictures and flags.
+pub fn cluster_width(cluster: &str) -> usize {
+    let mut chars = cluster.chars();
+    let first = chars.next().map_or(0, |c| c.width().unwrap_or(0));
+    let picture = cluster.contains('\u{fe0f}')
+        || cluster
+            .chars()
+            .filter(|c| ('\u{1f1e6}'..='\u{1f1ff}').contains(c))
+            .count()
+            == 2;
+    if picture {
+        first.max(2)
+    } else {
+        first
+    }
+}
+
+/// Columns `line` takes, by cluster.
+pub fn line_width(line: &str) -> usize {
+    clusters(line).into_iter().map(cluster_width).sum()
+}
+
+/// Put stand-ins in place of the clusters of `rows` that tables would
+/// size wrong. Tables count the width of each character, so a family
+/// emoji or a skin tone, drawn as one wide character, would count as
+/// several and push the borders out of line. A stand-in is a private use
+/// character padded with spaces to the width of its cluster, and
+/// `restore_clusters` puts the clusters back into the drawn table.
+pub fn hide_clusters(rows: &mut [Vec<String>]) -> Vec<(String, String)> {
+    let mut hidden: Vec<(String, String)> = vec![];
+    for cell in rows.iter_mut().flatten() {
+        if cell.is_ascii() {
+            continue;
+        }
+        let mut out = String::new();
+        for cluster in clusters(cell) {
+            let width = cluster_width(cluster);
+            if cluster.width() == width {
+                out += cluster;
+                continue;
+            }
+            let i = match hidden.iter().position(|(c, _)| c == cluster) {
+                Some(i) => i,
+                None => {
+                    let Some(c) = char::from_u32(0xf0000 + hidden.len() as u32) else {
+                        out += cluster;
+                        continue;
+                    };
+                    let stand_in = format!("{}{}", c, " ".repeat(width.saturating_sub(1)));
+                    hidden.push((cluster.to_string(), stand_in));
+                    hidden.len() - 1
+                }
+            };
+            out += &hidden[i].1;
+        }
+        *cell = out;
+    }
+    hidden
+}
+
+/// `table` with the clusters `hide_clusters` took out back in.
+pub fn restore_clusters(mut table: String, hidden: &[(String, String)]) -> String {
+    for (cluster, stand_in) in hidden {
+        table = table.replace(stand_in.as_str(), cluster);
+    }
+    table
+}
+
+/// Columns that give up width when a table would be wider than the
+/// terminal.
+const FLEXIBLE_COLUMNS: &[&str] = &["title", "url", "tags"];
+/// Narrowest a flexible column is made to fit the terminal.
+const MIN_COLUMN_WIDTH: usize = 12;
+
+/// Columns of the terminal, from `$COLUMNS` or the terminal itself.
+/// `None` when output does not go to a terminal.
+pub fn width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    if !is_stdout_tty() {
+        return None;
+    }
+    // SAFETY: TIOCGWINSZ only fills in the winsize struct we own.
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    (ok && size.ws_col > 0).then_some(size.ws_col as usize)
+}
+
+/// Narrow the cells of table columns, by wrapping or with an ellipsis
+/// as `display.overflow` says, to the widths set under
+/// `[display.max_width]` by column name and so that the table fits the
+/// terminal. `names` are the config names of the columns.
+pub fn fit_columns(rows: &mut [Vec<String>], headers: &[String], names: &[&str]) {
+    let config = config::get();
+    let natural: Vec<usize> = (0..headers.len())
+        .map(|i| {
+            rows.iter()
+                .map(|r| text_width(&r[i]))
+                .chain([text_width(&headers[i])])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let mut widths: Vec<usize> = names
+        .iter()
+        .zip(&natural)
+        .map(|(name, &natural)| {
+            let max = config.string(&format!("display.max_width.{}", name));
+            max.and_then(|m| m.parse().ok())
+                .map_or(natural, |m: usize| natural.min(m))
+        })
+        .collect();
+
+    if let Some(available) = width() {
+        // Each cell has a space on either side and a border after it.
+        let total = |widths: &[usize]| widths.iter().map(|w| w + 3).sum::<usize>() + 1;
+        let flexible: Vec<usize> = FLEXIBLE_COLUMNS
+            .iter()
+            .filter_map(|f| names.iter().position(|n| n == f))
+            .collect();
+        // Take from the widest one at a time, so they end up even.
+        while total(&widths) > available {
+            let widest = flexible
+                .iter()
+                .copied()
+                .filter(|&i| widths[i] > MIN_COLUMN_WIDTH)
+                .max_by_key(|&i| widths[i]);
+            match widest {
+                Some(i) => widths[i] -= 1,
+                None => break,
+            }
+        }
+    }
+
+    let truncate = config.string("display.overflow") == Some("truncate");
+    for (i, &width) in widths.iter().enumerate() {
+        let width = width.max(text_width(&headers[i]));
+        if width >= natural[i] {
+            continue;
+        }
+        for row in rows.iter_mut() {
+            row[i] = row[i]
+                .lines()
+                .map(|line| {
+                    if truncate {
+                        truncate_line(line, width)
+                    } else {
+                        wrap_line(line, width)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+    }
+}
+
+/// Width of the widest line of `text`.
+fn text_width(text: &str) -> usize {
+    text.lines().map(line_width).max().unwrap_or(0)
+}
+
+/// `line` cut to `width` with an ellipsis.
+fn truncate_line(line: &str, width: usize) -> String {
+    if line_width(line) <= width {
+        return line.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for cluster in clusters(line) {
+        let w = cluster_width(cluster);
+        if used + w + 1 > width {
+            break;
+        }
+        out += cluster;
+        used += w;
+    }
+    out.push('…');
+    out
+}
+
+/// `line` wrapped at spaces into lines of at most `width`, breaking words
+/// that are longer.
+fn wrap_line(line: &str, width: usize) -> String {
+    let mut lines: Vec<String> = vec![];
+    let mut current = String::new();
+    for word in line.split(' ') {
+        let candidate = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", current, word)
+        };
+        if line_width(&candidate) <= width {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        for cluster in clusters(word) {
+            if line_width(&current) + cluster_width(cluster) > width {
+                lines.push(std::mem::take(&mut current));
+            }
+            current += cluster;
+        }
+    }
+    lines.push(current);
+    lines.join("\n")
+}
+
+/// Print `message` and read a single key press without waiting for
+/// enter. Falls back to reading a line when stdin is not a terminal.
+/// Returns `None` at end of input.
+pub fn read_key(message: &str) -> Option<char> {
+    print!("{}", message);
+    std::io::stdout().flush().unwrap();
+
+    if !is_tty() {
+        let mut line = String::new();
+        return match std::io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim().chars().next().unwrap_or('\n')),
+        };
+    }
+
+    let original = raw_mode();
+    let key = read_byte().map(|b| b as char);
+    restore_mode(&original);
+    println!("{}", key.filter(|k| k.is_ascii_graphic()).unwrap_or(' '));
+    key
+}
+
+/// Let the user check any of `items`, moving with the arrow keys or `j`
+/// and `k`, toggling with space and confirming with enter. Returns the
+/// indexes of the checked ones, or `None` when cancelled with `q` (or
+/// given a number not in the list). When
+/// stdin is not a terminal, the items are numbered and the numbers read
+/// from a line instead.
+pub fn multi_select(message: &str, items: &[String]) -> Option<Vec<usize>> {
+    if !is_tty() {
+        for (i, item) in items.iter().enumerate() {
+            println!("{:>3}. {}", i + 1, item);
+        }
+        print!("{} (numbers separated by spaces): ", message);
+        std::io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        return line
+            .trim()
+            .split([' ', ','])
+            .filter(|n| !n.is_empty())
+            .map(|n| {
+                n.parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .filter(|&i| i < items.len())
+            })
+            .collect();
+    }
+
+    println!(
+        "{} (space to check, enter to confirm, q to cancel)",
+        message
+    );
+    let mut checked = vec![false; items.len()];
+    let mut cursor = 0;
+    let original = raw_mode();
+    let draw = |checked: &[bool], cursor: usize| {
+        let mut out = std::io::stdout();
+        for (i, item) in items.iter().enumerate() {
+            let pointer = if i == cursor { '>' } else { ' ' };
+            let mark = if checked[i] { 'x' } else { ' ' };
+            write!(out, "\r\x1b[K{} [{}] {}\r\n", pointer, mark, item).unwrap();
+        }
+        out.flush().unwrap();
+    };
+    draw(&checked, cursor);
+    let selected = loop {
+        match read_byte() {
+            Some(b' ') => checked[cursor] = !checked[cursor],
+            Some(b'\r' | b'\n') => break Some(()),
+            Some(b'q' | CTRL_C) | None => break None,
+            Some(b'k') => cursor = cursor.saturating_sub(1),
+            Some(b'j') => cursor = (cursor + 1).min(items.len() - 1),
+            // Arrow keys send ESC [ A and ESC [ B.
+            Some(0x1b) => match (read_byte(), read_byte()) {
+                (Some(b'['), Some(b'A')) => cursor = cursor.saturating_sub(1),
+                (Some(b'['), Some(b'B')) => cursor = (cursor + 1).min(items.len() - 1),
+                _ => {}
+            },
+            _ => continue,
+        }
+        print!("\x1b[{}A", items.len());
+        draw(&checked, cursor);
+    };
+    restore_mode(&original);
+    selected?;
+    Some((0..items.len()).filter(|&i| checked[i]).collect())
+}
+
+/// A line typed after `prompt`, or `None` when it is cancelled with
+/// Escape or Ctrl-C. Editing is kept to what a quick note needs:
+/// backspace, and Ctrl-U to start over.
+pub fn read_line(prompt: &str) -> Option<String> {
+    let mut out = std::io::stdout();
+    if !is_tty() {
+        print!("{}", prompt);
+        out.flush().unwrap();
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        return Some(line.trim_end_matches(['\n', '\r']).to_string());
+    }
+
+    let original = raw_mode();
+    let mut bytes: Vec<u8> = vec![];
+    let line = loop {
+        let text = String::from_utf8_lossy(&bytes);
+        write!(out, "\r\x1b[K{}{}", prompt, text).unwrap();
+        out.flush().unwrap();
+        match read_byte() {
+            Some(b'\r' | b'\n') => break Some(text.into_owned()),
+            Some(0x1b | CTRL_C) | None => break None,
+            // Backspace, and Ctrl-H for terminals that send it instead.
+            Some(0x7f | 0x08) => {
+                let mut text = text.into_owned();
+                text.pop();
+                bytes = text.into_bytes();
+            }
+            Some(0x15) => bytes.clear(),
+            Some(b) if b >= 0x20 || b == b'\t' => bytes.push(b),
+            _ => {}
+        }
+    };
+    restore_mode(&original);
+    write!(out, "\r\n").unwrap();
+    line
+}
+
+/// Like `read_line`, but without showing what is typed, for passwords
+/// and tokens.
+pub fn read_secret(prompt: &str) -> Option<String> {
+    let mut out = std::io::stdout();
+    if !is_tty() {
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        return Some(line.trim_end_matches(['\n', '\r']).to_string());
+    }
+
+    write!(out, "{}", prompt).unwrap();
+    out.flush().unwrap();
+    let original = raw_mode();
+    let mut bytes: Vec<u8> = vec![];
+    let line = loop {
+        match read_byte() {
+            Some(b'\r' | b'\n') => break Some(String::from_utf8_lossy(&bytes).into_owned()),
+            Some(0x1b | CTRL_C) | None => break None,
+            Some(0x7f | 0x08) => {
+                let mut text = String::from_utf8_lossy(&bytes).into_owned();
+                text.pop();
+                bytes = text.into_bytes();
+            }
+            Some(0x15) => bytes.clear(),
+            Some(b) if b >= 0x20 => bytes.push(b),
+            _ => {}
+        }
+    };
+    restore_mode(&original);
+    write!(out, "\r\n").unwrap();
+    line
+}
+
+/// Ctrl-C, which arrives as a byte in raw mode.
+const CTRL_C: u8 = 0x03;
+
+/// Put the terminal into reading key presses without echoing them or
+/// turning Ctrl-C into a signal, which would leave it that way. Returns
+/// the settings to restore afterwards.
+fn raw_mode() -> libc::termios {
+    // SAFETY: tcgetattr/tcsetattr only read and write the termios struct
+    // we own, and callers restore the original settings.
+    unsafe {
+        let mut t: libc::termios = std::mem::zeroed();
+        libc::tcgetattr(libc::STDIN_FILENO, &mut t);
+        let mut raw = t;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw);
+        t
+    }
+}
+
+fn restore_mode(original: &libc::termios) {
+    // SAFETY: as in raw_mode.
+    unsafe {
+        libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original);
+    }
+}
+
+fn read_byte() -> Option<u8> {
+    let mut buf = [0u8; 1];
+    match std::io::stdin().read(&mut buf) {
+        Ok(1) => Some(buf[0]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_emoji_sequences_whole() {
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        let text = format!("a{}b\u{1f44d}\u{1f3fd}\u{1f1ef}\u{1f1f5}e\u{301}", family);
+        let parts = clusters(&text);
+        assert_eq!(parts.len(), 6);
+        assert_eq!(parts[1], family);
+        assert_eq!(line_width(&text), 1 + 2 + 1 + 2 + 2 + 1);
+
+        let mut rows = vec![vec![text.clone()], vec![family.to_string()]];
+        let hidden = hide_clusters(&mut rows);
+        assert_eq!(hidden.len(), 2);
+        assert_eq!(rows[0][0].width(), line_width(&text));
+        assert_eq!(
+            restore_clusters(rows.concat().concat(), &hidden),
+            text + family
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use crate::date::{self, Date};
+use crate::{someday, Todo, Todos};
+use seahorse::{Command, Context, Flag, FlagType};
+use std::process::exit;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Longest range the chart draws, one column per day.
+const MAX_DAYS: i64 = 366;
+/// Width of the `#id title` labels.
+const LABEL_WIDTH: usize = 24;
+
+pub fn timeline_command() -> Command {
+    Command::new("timeline")
+        .description("Show scheduled TODOs as a Gantt chart")
+        .usage("todo timeline [--from today] [--to +30d]")
+        .flag(Flag::new("from", FlagType::String).description("First day (default: today)"))
+        .flag(Flag::new("to", FlagType::String).description("Last day (default: +30d)"))
+        .action(|c| {
+            let today = date::today();
+            let from = range_flag(c, "from", today, today);
+            let to = range_flag(c, "to", today, today.add_days(30));
+            let days = to.to_days() - from.to_days() + 1;
+            if days < 1 {
+                eprintln!("--to must not be before --from");
+                exit(1);
+            }
+            if days > MAX_DAYS {
+                eprintln!("The range must be at most {} days", MAX_DAYS);
+                exit(1);
+            }
+
+            let todos = Todos::read();
+            let mut bars: Vec<(&Todo, Date, Date)> = todos
+                .records
+                .iter()
+                .filter(|t| t.done.is_empty() && !someday::is_someday(t))
+                .filter_map(|t| span(t).map(|(s, e)| (t, s, e)))
+                .filter(|(_, s, e)| *s <= to && *e >= from)
+                .collect();
+            if bars.is_empty() {
+                println!("Nothing scheduled between {} and {}", from, to);
+                return;
+            }
+            bars.sort_by_key(|(t, s, e)| (*s, *e, t.id.parse::<usize>().unwrap_or(0)));
+            print!("{}", render(from, to, today, &bars));
+        })
+}
+
+fn range_flag(c: &Context, name: &str, today: Date, default: Date) -> Date {
+    match c.string_flag(name) {
+        Ok(s) => match Date::parse_expr(&s, today) {
+            Some(d) => d,
+            None => {
+                eprintln!("Invalid date: {}", s);
+                exit(1);
+            }
+        },
+        Err(_) => default,
+    }
+}
+
+/// The days a TODO is scheduled for, from its start to its due date. A
+/// TODO with only one of them takes up that day.
+fn span(todo: &Todo) -> Option<(Date, Date)> {
+    match (Date::parse(&todo.start), Date::parse(&todo.date)) {
+        (Some(s), Some(e)) => Some((s.min(e), s.max(e))),
+        (Some(d), None) | (None, Some(d)) => Some((d, d)),
+        (None, None) => None,
+    }
+}
+
+/// One row per TODO with `#` on its scheduled days, `<` and `>` where it
+/// runs past the range, and a last row counting the TODOs on each day so
+/// collisions stand out.
+fn render(from: Date, to: Date, today: Date, bars: &[(&Todo, Date, Date)]) -> String {
+    let days: Vec<Date> = (from.to_days()..=to.to_days())
+        .map(Date::from_days)
+        .collect();
+    let mut out = String::new();
+
+    // Label every Monday, and the first day so the start is always dated.
+    let mut header = String::new();
+    for (i, d) in days.iter().enumerate() {
+        if i == 0 || (d.weekday() == 0 && i > header.len()) {
+            header += &" ".repeat(i - header.len());
+            header += &format!("{:02}-{:02}", d.month, d.day);
+        }
+    }
+    out += &format!("{}  {}\n", " ".repeat(LABEL_WIDTH), header);
+    let ruler: String = days
+        .iter()
+        .map(|d| match d {
+            d if *d == today => '|',
+            d if d.weekday() == 0 => '+',
+            _ => '-',
+        })
+        .collect();
+    out += &format!("{}  {}\n", " ".repeat(LABEL_WIDTH), ruler);
+
+    for (todo, start, end) in bars {
+        let row: String = days
+            .iter()
+            .enumerate()
+            .map(|(i, d)| {
+                if d < start || d > end {
+                    '.'
+                } else if i == 0 && start < d {
+                    '<'
+                } else if i == days.len() - 1 && end > d {
+                    '>'
+                } else {
+                    '#'
+                }
+            })
+            .collect();
+        let label = truncate(&format!("#{} {}", todo.id, todo.title), LABEL_WIDTH);
+        out += &format!("{}  {}\n", label, row);
+    }
+
+    let load: String = days
+        .iter()
+        .map(
+            |d| match bars.iter().filter(|(_, s, e)| s <= d && d <= e).count() {
+                0 | 1 => ' ',
+                n @ 2..=9 => char::from_digit(n as u32, 10).unwrap(),
+                _ => '+',
+            },
+        )
+        .collect();
+    if !load.trim().is_empty() {
+        out += &format!("{}  {}\n", pad("overlap", LABEL_WIDTH), load.trim_end());
+    }
+    out
+}
+
+/// `s` cut or padded to exactly `width` columns.
+fn truncate(s: &str, width: usize) -> String {
+    if s.width() <= width {
+        return pad(s, width);
+    }
+    let mut out = String::new();
+    for c in s.chars() {
+        if out.width() + c.width().unwrap_or(0) > width - 1 {
+            break;
+        }
+        out.push(c);
+    }
+    pad(&(out + "…"), width)
+}
+
+fn pad(s: &str, width: usize) -> String {
+    format!("{}{}", s, " ".repeat(width.saturating_sub(s.width())))
+}
@@ -0,0 +1,94 @@
+//! `--timings`, accepted by every command: when it is done, how long
+//! loading the list, filtering it, rendering and saving took is printed
+//! to stderr, so that a slow command on a big list can be told apart
+//! from a slow disk or a slow terminal.
+//!
+//! ```text
+//! load     12.41 ms
+//! filter    0.83 ms
+//! render    3.10 ms
+//! total    17.02 ms
+//! ```
+//!
+//! A phase that ran more than once is summed, with how often it ran.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const PHASES: [&str; 4] = ["load", "filter", "render", "save"];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STARTED: OnceLock<Instant> = OnceLock::new();
+/// The time spent in each of `PHASES`, and how often it was entered.
+static SPENT: Mutex<[(Duration, u32); 4]> = Mutex::new([(Duration::ZERO, 0); 4]);
+
+/// Take `--timings` out of `args`, and report on exit when it was given.
+pub fn select(args: &mut Vec<String>) {
+    let end = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    let before = args.len();
+    let mut i = 0;
+    args.retain(|a| {
+        i += 1;
+        i > end || a != "--timings"
+    });
+    if args.len() < before {
+        ENABLED.store(true, Ordering::SeqCst);
+        STARTED.get_or_init(Instant::now);
+        // Also when a command ends in `exit`, as on errors.
+        // SAFETY: on_exit takes no arguments and does not unwind.
+        unsafe { libc::atexit(on_exit) };
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// A phase under way, counted when dropped.
+pub struct Timing {
+    phase: usize,
+    started: Instant,
+}
+
+impl Drop for Timing {
+    fn drop(&mut self) {
+        let mut spent = SPENT.lock().unwrap();
+        spent[self.phase].0 += self.started.elapsed();
+        spent[self.phase].1 += 1;
+    }
+}
+
+/// Start timing `phase`, one of `PHASES`, until the result is dropped.
+pub fn start(phase: &str) -> Option<Timing> {
+    if !is_enabled() {
+        return None;
+    }
+    let phase = PHASES
+        .iter()
+        .position(|p| *p == phase)
+        .expect("a phase in PHASES");
+    Some(Timing {
+        phase,
+        started: Instant::now(),
+    })
+}
+
+extern "C" fn on_exit() {
+    let Some(started) = STARTED.get() else {
+        return;
+    };
+    let spent = *SPENT.lock().unwrap_or_else(|e| e.into_inner());
+    for (phase, (time, count)) in PHASES.iter().zip(spent) {
+        match count {
+            0 => {}
+            1 => eprintln!("{:<7} {:>9}", phase, millis(time)),
+            _ => eprintln!("{:<7} {:>9} ({} times)", phase, millis(time), count),
+        }
+    }
+    eprintln!("{:<7} {:>9}", "total", millis(started.elapsed()));
+}
+
+fn millis(time: Duration) -> String {
+    format!("{:.2} ms", time.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,205 @@
+use crate::date::{self, Date};
+use crate::{batch, columns, config, crypt, dry_run, format, migrations, term, Todo, Todos};
+use anyhow::bail;
+use cli_table::{format::Justify, print_stdout, Cell, Style, Table};
+use seahorse::Command;
+use std::process::exit;
+
+/// Deleted TODOs are kept this long before being purged for good, unless
+/// `trash.retention_days` says otherwise. Entries also tell `sync` and
+/// `merge` what was deleted, so the window has to cover the time between
+/// two of them for deletions to carry over.
+const RETENTION_DAYS: u64 = 30;
+
+pub fn trash_command() -> Command {
+    Command::new("trash")
+        .description("Show or restore deleted TODOs")
+        .usage("todo trash [list | restore <todo id>]")
+        .action(list_action)
+        .command(
+            Command::new("list")
+                .description("Show deleted TODOs")
+                .usage("todo trash list")
+                .alias("ls")
+                .action(list_action),
+        )
+        .command(
+            Command::new("restore")
+                .description("Move a deleted TODO back to the list")
+                .usage("todo trash restore <todo id>")
+                .action(|c| {
+                    let id = if c.args.len() == 1 {
+                        &c.args[0]
+                    } else {
+                        eprintln!("Please specify one ID");
+                        exit(1);
+                    };
+
+                    let mut trash = Trash::read();
+                    let mut todo = match trash.take(id) {
+                        Ok(t) => t,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            exit(1);
+                        }
+                    };
+
+                    let mut todos = Todos::read();
+                    if todos.records.iter().any(|r| r.id == todo.id) {
+                        todo.id = todos.next_id().to_string();
+                    }
+                    println!("Restored #{}: {}", todo.id, todo.title);
+                    todos.records.push(todo);
+                    todos.save().unwrap();
+                    trash.save().unwrap();
+                }),
+        )
+}
+
+fn list_action(_: &seahorse::Context) {
+    let trash = Trash::read();
+    let table = trash
+        .entries
+        .iter()
+        .rev()
+        .map(|e| {
+            vec![
+                e.todo.id.clone().cell().justify(Justify::Center),
+                e.todo.title.clone().cell(),
+                Date::from_timestamp(e.deleted_at)
+                    .to_string()
+                    .cell()
+                    .justify(Justify::Center),
+            ]
+        })
+        .table()
+        .title(vec![
+            "ID".cell().bold(true).justify(Justify::Center),
+            "TITLE".cell().bold(true).justify(Justify::Center),
+            "DELETED".cell().bold(true).justify(Justify::Center),
+        ])
+        .bold(true)
+        .color_choice(term::color_choice());
+    print_stdout(table).unwrap();
+}
+
+struct Entry {
+    deleted_at: u64,
+    todo: Todo,
+}
+
+/// Deleted TODOs, stored next to the data file with a leading
+/// `deleted_at` column in front of the usual fields.
+pub struct Trash {
+    entries: Vec<Entry>,
+}
+
+impl Trash {
+    fn path() -> String {
+        format!("{}.trash", Todos::todo_path())
+    }
+
+    pub fn read() -> Self {
+        Self::read_from(&Self::path())
+    }
+
+    /// The trash of the list at `list`.
+    pub fn of(list: &str) -> Self {
+        Self::read_from(&format!("{}.trash", list))
+    }
+
+    fn read_from(path: &str) -> Self {
+        let content = match crypt::unseal(batch::read(path).unwrap_or_default()) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path, e);
+                exit(1);
+            }
+        };
+        let mut table = format::parse(&content);
+        if table.headers.is_empty() {
+            return Trash { entries: vec![] };
+        }
+
+        // Run the data file migrations over the TODO fields only.
+        table.headers.remove(0);
+        let mut deleted = vec![];
+        for row in table.rows.iter_mut().filter(|r| !r.is_empty()) {
+            deleted.push(row.fields.remove(0).parse().unwrap_or(0));
+        }
+        migrations::migrate(&mut table);
+
+        let columns = columns::map(&table.headers);
+        let entries = table
+            .rows
+            .iter()
+            .filter(|r| !r.is_empty())
+            .zip(deleted)
+            .map(|(r, deleted_at)| Entry {
+                deleted_at,
+                todo: Todo::from_row(&columns, &r.fields),
+            })
+            .collect();
+        Trash { entries }
+    }
+
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        // What moves in and out of the trash shows in the list's changes.
+        if dry_run::is_enabled() {
+            return Ok(());
+        }
+        let days = config::get()
+            .string("trash.retention_days")
+            .and_then(|d| d.parse().ok())
+            .unwrap_or(RETENTION_DAYS);
+        let cutoff = date::now().saturating_sub(days * 86400);
+        self.entries.retain(|e| e.deleted_at >= cutoff);
+
+        let others = self
+            .entries
+            .iter()
+            .flat_map(|e| e.todo.other_columns.iter().map(|(h, _)| h.as_str()));
+        let written = columns::to_write(&[], others);
+        let columns = columns::map(&written);
+        let mut headers = vec!["deleted_at".to_string()];
+        headers.extend(written);
+        let rows: Vec<Vec<String>> = self
+            .entries
+            .iter()
+            .map(|e| {
+                let mut fields = vec![e.deleted_at.to_string()];
+                fields.extend(e.todo.to_row(&columns));
+                fields
+            })
+            .collect();
+        let content = format::write(migrations::CURRENT_VERSION, &headers, &rows);
+        batch::write(&Self::path(), crypt::seal(&Todos::todo_path(), content)?)?;
+        Ok(())
+    }
+
+    pub fn push(&mut self, todo: Todo) {
+        self.push_deleted_at(todo, date::now());
+    }
+
+    pub fn push_deleted_at(&mut self, todo: Todo, deleted_at: u64) {
+        self.entries.push(Entry { deleted_at, todo });
+    }
+
+    /// The deleted TODOs with when they were deleted.
+    pub fn deleted(&self) -> impl Iterator<Item = (u64, &Todo)> {
+        self.entries.iter().map(|e| (e.deleted_at, &e.todo))
+    }
+
+    pub fn todos_mut(&mut self) -> impl Iterator<Item = &mut Todo> {
+        self.entries.iter_mut().map(|e| &mut e.todo)
+    }
+
+    /// Remove the most recently deleted entry with `id` and return it.
+    fn take(&mut self, id: &str) -> anyhow::Result<Todo> {
+        let index = match self.entries.iter().rposition(|e| e.todo.id == id) {
+            Some(i) => i,
+            None => bail!("The specified ID is not in the trash"),
+        };
+        Ok(self.entries.remove(index).todo)
+    }
+}
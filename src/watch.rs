@@ -0,0 +1,44 @@
+use crate::{date, list_flags, lock, ls_action, Todos};
+use seahorse::{Command, Context};
+use std::fs;
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often to look at the file for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub fn watch_command() -> Command {
+    let command = Command::new("watch")
+        .description("Keep the list on screen, showing it again whenever it changes")
+        .usage("todo watch [list flags]");
+    list_flags()
+        .into_iter()
+        .fold(command, |command, flag| command.flag(flag))
+        .action(watch_action)
+}
+
+fn watch_action(c: &Context) {
+    let path = Todos::todo_path();
+    let stat = || {
+        let metadata = fs::metadata(&path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    };
+    loop {
+        let shown: Option<(SystemTime, u64)> = stat();
+        // Clear the screen and go to the top.
+        print!("\x1b[H\x1b[2J");
+        ls_action(c);
+        // Others must be able to change the list while it is watched.
+        lock::release();
+        println!(
+            "\nWatching {}, updated {} (Ctrl-C to stop)",
+            path,
+            date::format_timestamp(date::now())
+        );
+        std::io::stdout().flush().unwrap();
+        while stat() == shown {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
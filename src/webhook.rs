@@ -0,0 +1,145 @@
+//! Outgoing webhooks: whenever the list is saved, each TODO that was
+//! added, completed or deleted is POSTed as JSON to the configured URLs.
+//!
+//! ```toml
+//! [webhooks]
+//! urls = ["https://home.example.com/api/webhook/todo"]
+//! secret = "${TODO_WEBHOOK_SECRET}"     # optional
+//! events = ["add", "done"]              # default: add, done and delete
+//! ```
+//!
+//! The body is `{"event": "add", "at": <timestamp>, "todo": {...}}` with
+//! the TODO as `--json` prints it. With a secret, it is signed like
+//! GitHub's webhooks: `X-Todo-Signature: sha256=<hex HMAC of the body>`.
+//! The secret can also be stored with `todo auth set webhook`, which is
+//! used over the config.
+//! Deliveries are made in the background, so that a slow or unreachable
+//! receiver does not hold up the command, and are retried a few times
+//! with growing pauses before they are given up.
+
+use crate::json::Value;
+use crate::{auth, config, http, lock, repeat, sha256, Todo, Todos};
+use std::thread;
+use std::time::Duration;
+
+const EVENTS: [&str; 3] = ["add", "done", "delete"];
+
+/// Pauses before each retry.
+const BACKOFF: [u64; 3] = [1, 4, 16];
+
+pub fn is_configured() -> bool {
+    !config::get().list("webhooks.urls").is_empty()
+}
+
+/// Send the changes from `before` to `after`.
+pub fn emit(before: &Todos, after: &Todos) {
+    let urls = config::get().list("webhooks.urls");
+    let wanted = config::get().list("webhooks.events");
+    let payloads: Vec<(&str, String)> = events(before, after)
+        .into_iter()
+        .filter(|(event, _)| wanted.is_empty() || wanted.iter().any(|w| w == event))
+        .map(|(event, todo)| {
+            let body = Value::object(vec![
+                ("event", event.into()),
+                ("at", Value::Number(crate::date::now() as f64)),
+                ("todo", todo.to_json()),
+            ]);
+            (event, body.to_string())
+        })
+        .collect();
+    if urls.is_empty() || payloads.is_empty() {
+        return;
+    }
+    let secret = auth::secret("webhook", "webhooks").or_else(|| {
+        config::get()
+            .string("webhooks.secret")
+            .map(|s| s.to_string())
+    });
+    in_background(|| {
+        for url in &urls {
+            for (event, body) in &payloads {
+                deliver(url, event, body, secret.as_deref());
+            }
+        }
+    });
+}
+
+/// What happened to each TODO between `before` and `after`.
+fn events<'a>(before: &'a Todos, after: &'a Todos) -> Vec<(&'static str, &'a Todo)> {
+    let mut events = vec![];
+    for new in &after.records {
+        match before.records.iter().find(|r| r.id == new.id) {
+            None => events.push((EVENTS[0], new)),
+            Some(old) => {
+                let completed = old.done.is_empty() && !new.done.is_empty();
+                let repeated =
+                    repeat::completed_at(new).count() > repeat::completed_at(old).count();
+                if completed || repeated {
+                    events.push((EVENTS[1], new));
+                }
+            }
+        }
+    }
+    for old in &before.records {
+        if !after.records.iter().any(|r| r.id == old.id) {
+            events.push((EVENTS[2], old));
+        }
+    }
+    events
+}
+
+fn deliver(url: &str, event: &str, body: &str, secret: Option<&str>) {
+    let mut headers = vec![
+        "Content-Type: application/json".to_string(),
+        format!("X-Todo-Event: {}", event),
+    ];
+    if let Some(secret) = secret {
+        let signature = sha256::hex(&sha256::hmac(secret.as_bytes(), body.as_bytes()));
+        headers.push(format!("X-Todo-Signature: sha256={}", signature));
+    }
+    for pause in std::iter::once(0).chain(BACKOFF) {
+        thread::sleep(Duration::from_secs(pause));
+        match http::request("POST", url, &headers, Some(body)) {
+            // Client errors will not go away by asking again.
+            Ok(r) if r.is_success() || (400..500).contains(&r.status) => return,
+            _ => {}
+        }
+    }
+}
+
+/// Run `f` in a process that outlives this one, detached from the
+/// terminal and from any pipe reading this one's output. It is forked
+/// twice so that long-running commands, like `todo rpc`, do not collect
+/// zombies.
+#[cfg(unix)]
+fn in_background(f: impl FnOnce()) {
+    // SAFETY: the children only run `f` and exit; commands are single
+    // threaded when they save.
+    unsafe {
+        match libc::fork() {
+            -1 => f(),
+            0 => {
+                if libc::fork() == 0 {
+                    // Closing its copy leaves the lock to the parent.
+                    lock::release();
+                    libc::setsid();
+                    let null = libc::open(c"/dev/null".as_ptr(), libc::O_RDWR);
+                    for fd in 0..3 {
+                        libc::dup2(null, fd);
+                    }
+                    f();
+                }
+                // Skips the parent's exit handlers, such as a batch's.
+                libc::_exit(0)
+            }
+            child => {
+                libc::waitpid(child, std::ptr::null_mut(), 0);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn in_background(f: impl FnOnce()) {
+    f()
+}
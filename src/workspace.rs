@@ -0,0 +1,243 @@
+//! Workspaces, like "work" and "personal", each with a list of its own
+//! and settings that override the global config, integrations included.
+//!
+//! A workspace is a directory under `~/.todo-workspaces`, holding its
+//! list as `todo` and its settings as `config.toml`. The current one is
+//! named in `~/.todo-workspaces/.current`; `--workspace <name>` or
+//! `$TODO_WORKSPACE` picks another for one command. Without any, the
+//! global list and config are used, as the `default` workspace.
+
+use crate::config::{expand_home, Config};
+use crate::dry_run;
+use dirs::home_dir;
+use seahorse::{Command, Context, Flag, FlagType};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::OnceLock;
+
+/// Name of the workspace that is the global list and config.
+const DEFAULT: &str = "default";
+
+static SELECTED: OnceLock<Option<String>> = OnceLock::new();
+
+pub fn workspace_command() -> Command {
+    Command::new("workspace")
+        .description("Create and switch between workspaces")
+        .usage("todo workspace [list | create <name> | switch <name>]")
+        .alias("ws")
+        .action(|_| println!("{}", current().unwrap_or(DEFAULT)))
+        .command(
+            Command::new("list")
+                .description("Show the workspaces, marking the current one")
+                .usage("todo workspace list")
+                .alias("ls")
+                .action(|_| {
+                    let current = current().unwrap_or(DEFAULT);
+                    for name in [DEFAULT.to_string()].into_iter().chain(names()) {
+                        let mark = if name == current { "*" } else { " " };
+                        println!("{} {}", mark, name);
+                    }
+                }),
+        )
+        .command(
+            Command::new("create")
+                .description("Create a workspace")
+                .usage("todo workspace create <name> [--switch]")
+                .flag(
+                    Flag::new("switch", FlagType::Bool)
+                        .alias("s")
+                        .description("Switch to it"),
+                )
+                .action(create_action),
+        )
+        .command(
+            Command::new("switch")
+                .description("Make a workspace the current one")
+                .usage("todo workspace switch <name>")
+                .action(|c| {
+                    let name = one_name(c);
+                    if name != DEFAULT && !dir(name).is_dir() {
+                        eprintln!("No such workspace: {}", name);
+                        exit(1);
+                    }
+                    switch(name);
+                }),
+        )
+}
+
+fn create_action(c: &Context) {
+    let name = one_name(c);
+    if !is_valid_name(name) || name == DEFAULT {
+        eprintln!("Invalid workspace name: {}", name);
+        exit(1);
+    }
+    let dir = dir(name);
+    if dir.exists() {
+        eprintln!("Workspace {} already exists", name);
+        exit(1);
+    }
+    let config = format!(
+        "# Settings for the {} workspace, over those in {}.\n",
+        name,
+        crate::config::Config::path()
+    );
+    if dry_run::is_enabled() {
+        println!(
+            "Dry run, {} would be written",
+            dir.join("config.toml").display()
+        );
+    } else if let Err(e) =
+        fs::create_dir_all(&dir).and_then(|_| fs::write(dir.join("config.toml"), config))
+    {
+        eprintln!("Failed to create {}: {}", dir.display(), e);
+        exit(1);
+    } else {
+        println!("Created workspace {} in {}", name, dir.display());
+    }
+    if c.bool_flag("switch") {
+        switch(name);
+    }
+}
+
+fn one_name(c: &Context) -> &str {
+    match c.args.as_slice() {
+        [name] => name,
+        _ => {
+            eprintln!("Please specify one workspace");
+            exit(1);
+        }
+    }
+}
+
+fn switch(name: &str) {
+    let pointer = root().join(".current");
+    if dry_run::is_enabled() {
+        println!("Dry run, {} would be written", pointer.display());
+        return;
+    }
+    let result = if name == DEFAULT {
+        fs::remove_file(&pointer).or_else(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Ok(()),
+            _ => Err(e),
+        })
+    } else {
+        fs::write(&pointer, format!("{}\n", name))
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to write {}: {}", pointer.display(), e);
+        exit(1);
+    }
+    println!("Switched to workspace {}", name);
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(['-', '.'])
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+fn root() -> PathBuf {
+    home_dir().unwrap().join(".todo-workspaces")
+}
+
+fn dir(name: &str) -> PathBuf {
+    root().join(name)
+}
+
+/// The workspaces other than `default`, sorted.
+fn names() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(root())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|n| !n.starts_with('.'))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Take `--workspace <name>` out of `args`, to use that workspace for
+/// this invocation. Must come before anything reads the config.
+pub fn select(args: &mut Vec<String>) {
+    let mut chosen = None;
+    let mut i = 1;
+    while i < args.len() {
+        if args[i] == "--" {
+            break;
+        }
+        if let Some(name) = args[i].strip_prefix("--workspace=") {
+            chosen = Some(name.to_string());
+            args.remove(i);
+        } else if args[i] == "--workspace" {
+            args.remove(i);
+            if i < args.len() {
+                chosen = Some(args.remove(i));
+            } else {
+                eprintln!("Please specify a workspace for --workspace");
+                exit(1);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    if let Some(name) = chosen {
+        let _ = SELECTED.set(check(name, true));
+    }
+}
+
+/// The workspace in use, `None` for `default`.
+pub fn current() -> Option<&'static str> {
+    SELECTED
+        .get_or_init(|| {
+            if let Ok(name) = env::var("TODO_WORKSPACE") {
+                return check(name, true);
+            }
+            let name = fs::read_to_string(root().join(".current")).ok()?;
+            check(name.trim().to_string(), false)
+        })
+        .as_deref()
+}
+
+/// `name` if it is a workspace. A current workspace that was removed
+/// since falls back to `default`, so that another can be switched to.
+fn check(name: String, strict: bool) -> Option<String> {
+    if name.is_empty() || name == DEFAULT {
+        return None;
+    }
+    if !dir(&name).is_dir() {
+        eprintln!("No such workspace: {}", name);
+        if strict {
+            exit(1);
+        }
+        return None;
+    }
+    Some(name)
+}
+
+/// Where the list of a workspace is, unless its config says otherwise.
+pub fn list_path(name: &str) -> String {
+    dir(name).join("todo").to_string_lossy().into_owned()
+}
+
+pub fn config_path(name: &str) -> PathBuf {
+    dir(name).join("config.toml")
+}
+
+/// Every workspace, `default` first, with where its list is.
+pub fn lists() -> Vec<(String, String)> {
+    let storage = |config: Config| config.string("storage.path").map(expand_home);
+    let global = storage(Config::read(&Config::path())).unwrap_or_else(crate::Todos::default_path);
+    let mut lists = vec![(DEFAULT.to_string(), global)];
+    for name in names() {
+        let path = storage(Config::read(&config_path(&name).to_string_lossy()))
+            .unwrap_or_else(|| list_path(&name));
+        lists.push((name, path));
+    }
+    lists
+}
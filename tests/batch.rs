@@ -0,0 +1,59 @@
+mod common;
+
+use common::Sandbox;
+use std::fs;
+use std::io::Write;
+use std::process::{Output, Stdio};
+
+fn batch(sandbox: &Sandbox, commands: &str) -> Output {
+    let mut child = sandbox
+        .command(&["batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(commands.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[cfg(unix)]
+#[test]
+fn keeps_a_symlinked_list_a_link() {
+    let sandbox = Sandbox::new("batch-symlink");
+    sandbox.todo(&["add", "First"]);
+    fs::create_dir(sandbox.path("real")).unwrap();
+    fs::rename(sandbox.path(".todo"), sandbox.path("real/list")).unwrap();
+    std::os::unix::fs::symlink("real/list", sandbox.path(".todo")).unwrap();
+
+    assert!(batch(&sandbox, "add Second\n").status.success());
+    assert!(fs::symlink_metadata(sandbox.path(".todo"))
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert!(fs::read_to_string(sandbox.path("real/list"))
+        .unwrap()
+        .contains("Second"));
+}
+
+#[test]
+fn writes_nothing_when_a_file_cannot_be_written() {
+    let sandbox = Sandbox::new("batch-all-or-nothing");
+    sandbox.todo(&["add", "First"]);
+    // The trash is written after the list, and can't be replaced.
+    fs::create_dir(sandbox.path(".todo.trash")).unwrap();
+    let before = fs::read_to_string(sandbox.path(".todo")).unwrap();
+
+    let output = batch(&sandbox, "add Second\ndelete 1\n");
+    assert!(!output.status.success());
+    assert_eq!(fs::read_to_string(sandbox.path(".todo")).unwrap(), before);
+    for entry in fs::read_dir(&sandbox.home).unwrap() {
+        let name = entry.unwrap().file_name().to_string_lossy().into_owned();
+        assert!(!name.contains(".batch-"), "{} was left", name);
+    }
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::Sandbox;
+use std::fs;
+
+#[test]
+fn fails_on_tags_that_are_not_known() {
+    let sandbox = Sandbox::new("clean-tags");
+    fs::write(
+        sandbox.path(".todo.toml"),
+        "[tags]\nknown = [\"release\"]\n",
+    )
+    .unwrap();
+    sandbox.todo(&["add", "Tag the release", "--tag", "release"]);
+    sandbox.todo(&["done", "1"]);
+    assert!(sandbox.run(&["check-clean"]).status.success());
+
+    sandbox.todo(&["add", "Update the changelog", "--tag", "relase"]);
+    sandbox.todo(&["done", "2"]);
+    let output = sandbox.run(&["check-clean"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("#2 Update the changelog: relase"),
+        "{}",
+        stdout
+    );
+}
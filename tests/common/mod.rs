@@ -0,0 +1,107 @@
+//! A home directory of its own for each test, with `todo` run in it.
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+pub struct Sandbox {
+    pub home: PathBuf,
+}
+
+impl Sandbox {
+    pub fn new(name: &str) -> Self {
+        let home = std::env::temp_dir().join(format!("todo-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home).unwrap();
+        Sandbox { home }
+    }
+
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.home.join(name)
+    }
+
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_todo"));
+        command
+            .args(args)
+            .current_dir(&self.home)
+            .env("HOME", &self.home)
+            .env("GNUPGHOME", self.path(".gnupg"))
+            .env("NO_COLOR", "1")
+            .env_remove("TODO_WORKSPACE")
+            .env_remove("RUST_LOG");
+        command
+    }
+
+    /// Run `todo` with `args`, failing the test when it fails.
+    pub fn todo(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        assert!(
+            output.status.success(),
+            "todo {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command(args)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .unwrap()
+    }
+
+    /// A GnuPG key without a passphrase, `None` when gpg is missing.
+    pub fn gpg_key(&self) -> Option<&'static str> {
+        let key = "todo-test@example.invalid";
+        let dir = self.path(".gnupg");
+        fs::create_dir_all(&dir).ok()?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).ok()?;
+        }
+        let status = Command::new("gpg")
+            .args(["--batch", "--passphrase", "", "--quick-gen-key", key])
+            .args(["default", "default", "never"])
+            .env("GNUPGHOME", &dir)
+            .output()
+            .ok()?
+            .status;
+        status.success().then_some(key)
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.home);
+    }
+}
+
+/// Answer every HTTP request on a port of its own with `body`, with an
+/// ETag, until the test ends. Returns the address.
+pub fn serve(body: String) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let mut request = [0; 8192];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nETag: \"1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+    address
+}
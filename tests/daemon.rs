@@ -0,0 +1,57 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+use std::fs;
+use std::io::Read;
+use std::process::Stdio;
+use std::time::Duration;
+
+#[test]
+fn runs_commands_with_the_config_of_their_directory() {
+    let sandbox = Sandbox::new("daemon-config");
+    sandbox.todo(&["add", "First"]);
+    let mut daemon = sandbox
+        .command(&["daemon"])
+        .env("RUST_LOG", "debug")
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    for _ in 0..100 {
+        if sandbox.path(".todo.sock").exists() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let project = sandbox.path("project");
+    fs::create_dir(&project).unwrap();
+    fs::write(
+        project.join(".todo.toml"),
+        "[display]\nshow_list = \"true\"\n",
+    )
+    .unwrap();
+
+    let output = sandbox
+        .command(&["--client", "add", "Second"])
+        .current_dir(&project)
+        .output()
+        .unwrap();
+    daemon.kill().unwrap();
+    let mut log = String::new();
+    daemon
+        .stderr
+        .take()
+        .unwrap()
+        .read_to_string(&mut log)
+        .unwrap();
+    daemon.wait().unwrap();
+
+    assert!(log.contains("forked"), "not run by the daemon: {}", log);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("First"),
+        "the list was not shown: {}",
+        stdout
+    );
+}
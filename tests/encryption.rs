@@ -0,0 +1,44 @@
+mod common;
+
+use common::Sandbox;
+use std::fs;
+
+#[test]
+fn keeps_the_todos_of_an_encrypted_list_encrypted_next_to_it() {
+    let sandbox = Sandbox::new("encryption");
+    let Some(key) = sandbox.gpg_key() else {
+        eprintln!("Skipping, gpg is not available");
+        return;
+    };
+    sandbox.todo(&["init", "--encrypt", key]);
+    sandbox.todo(&["add", "Secret deleted title"]);
+    sandbox.todo(&["add", "Secret synced title"]);
+    sandbox.todo(&["delete", "1"]);
+    let shared = sandbox.path("shared.sync");
+    sandbox.todo(&["sync", "file", shared.to_str().unwrap()]);
+
+    // A plain copy on the remote, which the next read keeps as the
+    // conflict since the list changed here too.
+    let other = Sandbox::new("encryption-remote");
+    other.todo(&["add", "Secret remote title"]);
+    let address = common::serve(fs::read_to_string(other.path(".todo")).unwrap());
+    fs::write(
+        sandbox.path(".todo.toml"),
+        format!("[storage]\nremote = \"webdav://{}/todo\"\n", address),
+    )
+    .unwrap();
+    sandbox.todo(&["list"]);
+
+    for sidecar in [
+        ".todo",
+        ".todo.trash",
+        ".todo.crdt",
+        "shared.sync",
+        ".todo.conflict",
+    ] {
+        let content = fs::read_to_string(sandbox.path(sidecar))
+            .unwrap_or_else(|e| panic!("{}: {}", sidecar, e));
+        assert!(!content.contains("Secret"), "{} is not encrypted", sidecar);
+    }
+    assert!(sandbox.todo(&["trash"]).contains("Secret deleted title"));
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::Sandbox;
+use std::fs;
+
+#[test]
+fn dry_run_leaves_the_list_and_remote_alone() {
+    let sandbox = Sandbox::new("storage-dry-run");
+    sandbox.todo(&["add", "Here"]);
+    let other = Sandbox::new("storage-dry-run-remote");
+    other.todo(&["add", "There"]);
+    let address = common::serve(fs::read_to_string(other.path(".todo")).unwrap());
+    fs::write(
+        sandbox.path(".todo.toml"),
+        format!("[storage]\nremote = \"webdav://{}/todo\"\n", address),
+    )
+    .unwrap();
+    let before = fs::read_to_string(sandbox.path(".todo")).unwrap();
+
+    let out = sandbox.todo(&["--dry-run", "list"]);
+    assert!(out.contains("would be kept as"), "{}", out);
+    assert_eq!(fs::read_to_string(sandbox.path(".todo")).unwrap(), before);
+    for file in [".todo.remote", ".todo.conflict"] {
+        assert!(!sandbox.path(file).exists(), "{} was written", file);
+    }
+}